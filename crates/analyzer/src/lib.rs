@@ -1,30 +1,36 @@
-use common::ProgramText;
+use common::{ProgramText, Symbol};
 use parser::{
     parse::{
-        expr::{atom::AtomicValue, Expr, ExprKind},
+        expr::{atom::AtomicValue, Expr, ExprKind, NamedArg, Pattern},
         stmt::{Stmt, StmtKind},
-        AstRef,
+        AstRef, Params, Span,
     },
     utils::error::{ParseError, ParseErrorCause},
 };
 use std::collections::HashMap;
 use vm::gravitas_std::NATIVE_FUNCTIONS;
 
+pub mod semantic_tokens;
+pub mod types;
+
 pub type AnalyzerResult<E> = Result<(), E>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ScopeType {
     Function,
     Loop,
+    Block,
     Global,
 }
 
-type Variables = HashMap<ProgramText, bool>;
+type Variables = HashMap<Symbol, bool>;
 
 #[derive(Debug, Clone)]
 struct Scope {
     scope_type: ScopeType,
-    variables: HashMap<ProgramText, bool>,
+    variables: Variables,
+    // Parameter names of the functions declared in this scope, for named arguments
+    signatures: HashMap<Symbol, Vec<Symbol>>,
 }
 
 impl Scope {
@@ -32,6 +38,7 @@ impl Scope {
         Self {
             scope_type,
             variables: HashMap::new(),
+            signatures: HashMap::new(),
         }
     }
 
@@ -39,6 +46,7 @@ impl Scope {
         Self {
             scope_type: ScopeType::Global,
             variables: global_variables,
+            signatures: HashMap::new(),
         }
     }
 
@@ -53,19 +61,26 @@ impl Scope {
     fn is_loop(&self) -> bool {
         self.scope_type == ScopeType::Loop
     }
+
+    fn is_block(&self) -> bool {
+        self.scope_type == ScopeType::Block
+    }
 }
 
 #[derive(Default)]
 pub struct Analyzer {
     scopes: Vec<Scope>,
+    // Names exported by the modules the program can import, by their paths
+    modules: HashMap<ProgramText, Vec<Symbol>>,
 }
 
 impl Analyzer {
     pub fn new() -> Self {
-        let variables: HashMap<ProgramText, bool> = NATIVE_FUNCTIONS
+        let variables: Variables = NATIVE_FUNCTIONS
             .keys()
             .cloned()
-            .map(|fun| (fun.into(), true))
+            .map(Into::<String>::into)
+            .map(|fun| (Symbol::from(fun), true))
             .collect();
 
         let scopes = vec![Scope::global(variables)];
@@ -76,15 +91,48 @@ impl Analyzer {
         }
     }
 
-    fn declare_var(&mut self, name: &str, initialized: bool) {
-        self.current_scope_mut()
-            .variables
-            .insert(name.to_owned(), initialized);
+    // Functions provided by the application embedding the VM, resolved like the natives
+    pub fn with_host_functions(mut self, names: &[ProgramText]) -> Self {
+        for name in names {
+            self.scopes[0].variables.insert(Symbol::intern(name), true);
+        }
+        self
+    }
+
+    // Top-level variables defined by the programs that ran before, e.g. the previous lines of a REPL
+    pub fn with_globals(mut self, names: &[ProgramText]) -> Self {
+        for name in names {
+            self.scopes[0].variables.insert(Symbol::intern(name), true);
+        }
+        self
     }
 
-    fn find_var(&self, name: &ProgramText) -> Option<&bool> {
+    pub fn with_modules(mut self, modules: HashMap<ProgramText, Vec<Symbol>>) -> Self {
+        self.modules = modules;
+        self
+    }
+
+    fn declare_var(&mut self, name: Symbol, initialized: bool) {
+        self.current_scope_mut().variables.insert(name, initialized);
+    }
+
+    // Names can be reused in nested scopes, but not twice in the same one
+    fn declare_unique(&mut self, name: Symbol, span: Span) -> AnalyzerResult<ParseError> {
+        if self.current_scope().variables.contains_key(&name) {
+            return Err(ParseError {
+                span_start: span.clone(),
+                span_end: span,
+                cause: ParseErrorCause::AlreadyDeclared,
+                found: None,
+            });
+        }
+        self.declare_var(name, false);
+        Ok(())
+    }
+
+    fn find_var(&self, name: Symbol) -> Option<&bool> {
         for scope in self.scopes.iter().rev() {
-            if let Some(var) = scope.variables.get(name) {
+            if let Some(var) = scope.variables.get(&name) {
                 return Some(var);
             }
         }
@@ -92,6 +140,67 @@ impl Analyzer {
         None
     }
 
+    // Parameters of the function the name refers to, unless it's shadowed by a variable
+    fn find_signature(&self, name: Symbol) -> Option<&Vec<Symbol>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.variables.contains_key(&name))
+            .and_then(|scope| scope.signatures.get(&name))
+    }
+
+    // Named arguments have to fill every positional parameter exactly once
+    fn check_named_args(
+        &self,
+        callee: &Expr,
+        args: &[Expr],
+        named_args: &[NamedArg],
+    ) -> AnalyzerResult<ParseError> {
+        let err = |span: &Span, cause: ParseErrorCause| {
+            Err(ParseError {
+                span_start: span.clone(),
+                span_end: span.clone(),
+                cause,
+                found: None,
+            })
+        };
+
+        let params = match &*callee.kind {
+            ExprKind::Atom(AtomicValue::Identifier { name, .. }) => self.find_signature(*name),
+            _ => None,
+        };
+        let params = match params {
+            Some(params) => params,
+            None => return err(&callee.span, ParseErrorCause::NamedArgumentsNeedFunction),
+        };
+
+        let mut passed: Vec<Symbol> = params.iter().take(args.len()).copied().collect();
+        for arg in named_args {
+            let name = arg.name.kind;
+            if !params.contains(&name) {
+                return err(
+                    &arg.name.span,
+                    ParseErrorCause::UnknownArgument(name.to_string()),
+                );
+            }
+            if passed.contains(&name) {
+                return err(
+                    &arg.name.span,
+                    ParseErrorCause::DuplicateArgument(name.to_string()),
+                );
+            }
+            passed.push(name);
+        }
+
+        match params.iter().find(|param| !passed.contains(param)) {
+            Some(missing) => err(
+                &callee.span,
+                ParseErrorCause::MissingArgument(missing.to_string()),
+            ),
+            None => Ok(()),
+        }
+    }
+
     fn enter_scope(&mut self, scope_type: ScopeType) {
         self.scopes.push(Scope::new(scope_type));
     }
@@ -100,6 +209,35 @@ impl Analyzer {
         self.scopes.pop();
     }
 
+    // Blocks are transparent, so break inside an if inside a loop is fine, but a function
+    // boundary isn't
+    fn in_loop(&self) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| !scope.is_block())
+            .is_some_and(|scope| scope.is_loop())
+    }
+
+    fn in_function(&self) -> bool {
+        self.scopes.iter().any(|scope| scope.is_function())
+    }
+
+    fn visit_function(&mut self, params: &Params, body: &Expr) -> AnalyzerResult<ParseError> {
+        self.enter_scope(ScopeType::Function);
+        let result = params
+            .kind
+            .iter()
+            .try_for_each(|param| {
+                self.declare_unique(param.kind, param.span.clone())?;
+                self.declare_var(param.kind, true);
+                Ok(())
+            })
+            .and_then(|_| self.visit_expr(body));
+        self.leave_scope();
+        result
+    }
+
     fn current_scope(&self) -> &Scope {
         self.scopes.last().unwrap()
     }
@@ -118,11 +256,12 @@ impl Analyzer {
                 span_end: span.clone(),
                 span_start: span.clone(),
                 cause,
+                found: None,
             })
         };
 
         match &*expr.kind {
-            Atom(AtomicValue::Identifier { name, .. }) => match self.find_var(name) {
+            Atom(AtomicValue::Identifier { name, .. }) => match self.find_var(*name) {
                 Some(false) => {
                     return err(ParseErrorCause::UsedBeforeInitialization);
                 }
@@ -136,27 +275,80 @@ impl Analyzer {
                 self.visit_expr(rhs)?;
             }
             Block { stmts, return_expr } => {
-                for stmt in stmts {
-                    self.visit_stmt(stmt)?;
-                }
-
-                if let Some(expr) = return_expr {
-                    self.visit_expr(expr)?;
-                }
+                self.enter_scope(ScopeType::Block);
+                let result = stmts
+                    .iter()
+                    .try_for_each(|stmt| self.visit_stmt(stmt))
+                    .and_then(|_| match return_expr {
+                        Some(expr) => self.visit_expr(expr),
+                        None => Ok(()),
+                    });
+                self.leave_scope();
+                result?;
             }
             While { condition, body } => {
                 self.visit_expr(condition)?;
                 self.enter_scope(ScopeType::Loop);
-                self.visit_expr(body)?;
+                let result = self.visit_expr(body);
                 self.leave_scope();
+                result?;
+            }
+            Loop { body } => {
+                self.enter_scope(ScopeType::Loop);
+                let result = self.visit_expr(body);
+                self.leave_scope();
+                result?;
+            }
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                // The initializer is visible in the condition, the step and the body
+                self.enter_scope(ScopeType::Block);
+                let result = init
+                    .iter()
+                    .try_for_each(|init| self.visit_stmt(init))
+                    .and_then(|_| condition.iter().try_for_each(|c| self.visit_expr(c)))
+                    .and_then(|_| step.iter().try_for_each(|step| self.visit_expr(step)))
+                    .and_then(|_| {
+                        self.enter_scope(ScopeType::Loop);
+                        let result = self.visit_expr(body);
+                        self.leave_scope();
+                        result
+                    });
+                self.leave_scope();
+                result?;
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                self.visit_expr(iterable)?;
+                self.enter_scope(ScopeType::Loop);
+                self.declare_var(item.kind, true);
+                let result = self.visit_expr(body);
+                self.leave_scope();
+                result?;
+            }
+            Match { value, arms } => {
+                self.visit_expr(value)?;
+                for arm in arms {
+                    if let Pattern::Value(pattern) = &arm.pattern {
+                        self.visit_expr(pattern)?;
+                    }
+                    self.visit_expr(&arm.body)?;
+                }
             }
             Continue => {
-                if !self.current_scope().is_loop() {
+                if !self.in_loop() {
                     return err(ParseErrorCause::UsedOutsideLoop);
                 }
             }
             Break { return_expr } => {
-                if !self.current_scope().is_loop() {
+                if !self.in_loop() {
                     return err(ParseErrorCause::UsedOutsideLoop);
                 }
 
@@ -165,22 +357,35 @@ impl Analyzer {
                 }
             }
             Return { value } => {
-                if !self.current_scope().is_function() {
+                if !self.in_function() {
                     return err(ParseErrorCause::ReturnUsedOutsideFunction);
                 }
                 if let Some(value) = value {
                     self.visit_expr(value)?;
                 }
             }
-            Call { callee, args } => {
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
                 self.visit_expr(callee)?;
                 for arg in args {
                     self.visit_expr(arg)?;
                 }
+                for arg in named_args {
+                    self.visit_expr(&arg.value)?;
+                }
+                if !named_args.is_empty() {
+                    self.check_named_args(callee, args, named_args)?;
+                }
             }
             Unary { op, rhs } => {
                 self.visit_expr(rhs)?;
             }
+            Spread { value } => {
+                self.visit_expr(value)?;
+            }
             If {
                 condition,
                 body,
@@ -192,7 +397,7 @@ impl Analyzer {
                     self.visit_expr(else_expr)?;
                 }
             }
-            Array { values } => {
+            Array { values } | Interpolation { parts: values } => {
                 for value in values {
                     self.visit_expr(value)?;
                 }
@@ -201,6 +406,14 @@ impl Analyzer {
                 self.visit_expr(target)?;
                 self.visit_expr(position)?;
             }
+            Slice {
+                target, start, end, ..
+            } => {
+                self.visit_expr(target)?;
+                for bound in start.iter().chain(end) {
+                    self.visit_expr(bound)?;
+                }
+            }
             GetProperty {
                 target,
                 is_method_call,
@@ -221,14 +434,17 @@ impl Analyzer {
                     self.visit_expr(value)?;
                 }
             }
+            Map { entries } => {
+                for (_, value) in entries {
+                    self.visit_expr(value)?;
+                }
+            }
             Assignment { target, value } => {
                 self.visit_expr(target)?;
                 self.visit_expr(value)?;
             }
             Closure { params, body } => {
-                self.enter_scope(ScopeType::Function);
-                self.visit_expr(body)?;
-                self.leave_scope();
+                self.visit_function(params, body)?;
             }
             _ => {}
         }
@@ -239,21 +455,67 @@ impl Analyzer {
         use StmtKind::*;
 
         match &*stmt.kind {
-            VariableDeclaration { name, expr } => {
-                self.declare_var(name, false);
+            VariableDeclaration { name, expr, .. } => {
+                self.declare_unique(*name, stmt.span.clone())?;
+                self.visit_expr(expr)?;
+                self.declare_var(*name, true);
+            }
+            MultipleDeclaration { names, exprs } => {
+                for name in names {
+                    self.declare_unique(name.kind, name.span.clone())?;
+                }
+                for expr in exprs {
+                    self.visit_expr(expr)?;
+                }
+                for name in names {
+                    self.declare_var(name.kind, true);
+                }
+            }
+            ParallelAssignment { targets, values } => {
+                for expr in targets.iter().chain(values) {
+                    self.visit_expr(expr)?;
+                }
+            }
+            Destructuring { pattern, expr } => {
+                for name in pattern.names() {
+                    self.declare_unique(name.kind, name.span.clone())?;
+                }
                 self.visit_expr(expr)?;
-                self.declare_var(name, true);
+                for name in pattern.names() {
+                    self.declare_var(name.kind, true);
+                }
             }
 
-            FunctionDeclaration { body, name, .. } => {
-                self.declare_var(name, true);
-                self.enter_scope(ScopeType::Function);
-                self.visit_expr(body)?;
-                self.leave_scope();
+            FunctionDeclaration {
+                body, name, params, ..
+            } => {
+                self.declare_unique(*name, stmt.span.clone())?;
+                // Initialized before the body, so the function can call itself
+                self.declare_var(*name, true);
+                let signature = params.kind.params.iter().map(|param| param.kind);
+                self.current_scope_mut()
+                    .signatures
+                    .insert(*name, signature.collect());
+                self.visit_function(params, body)?;
             }
             Expression { expr } => {
                 self.visit_expr(expr)?;
             }
+            Import { path } => {
+                let names = self.modules.get(&path.kind).cloned().ok_or_else(|| ParseError {
+                    span_start: path.span.clone(),
+                    span_end: path.span.clone(),
+                    cause: ParseErrorCause::UnknownModule(path.kind.clone()),
+                    found: None,
+                })?;
+                for name in names {
+                    self.declare_unique(name, path.span.clone())?;
+                    self.declare_var(name, true);
+                }
+            }
+            Export { declaration } => {
+                self.visit_stmt(declaration)?;
+            }
         }
         Ok(())
     }
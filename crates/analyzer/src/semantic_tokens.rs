@@ -0,0 +1,337 @@
+use common::ProgramText;
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind, Pattern},
+    stmt::{Stmt, StmtKind},
+    AstRef, Params, Span,
+};
+use std::collections::HashMap;
+use vm::gravitas_std::NATIVE_FUNCTIONS;
+
+// What an identifier refers to, which is more than the lexer can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenCategory {
+    Parameter,
+    Local,
+    // Local variable of an enclosing function, captured by a closure
+    Upvalue,
+    Global,
+    Function,
+    StdFunction,
+}
+
+impl TokenCategory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Parameter => "parameter",
+            Self::Local => "local",
+            Self::Upvalue => "upvalue",
+            Self::Global => "global",
+            Self::Function => "function",
+            Self::StdFunction => "std_function",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticToken {
+    pub span: Span,
+    pub category: TokenCategory,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Binding {
+    category: TokenCategory,
+    function_depth: usize,
+}
+
+struct Classifier<'c> {
+    code: &'c str,
+    scopes: Vec<HashMap<ProgramText, Binding>>,
+    function_depth: usize,
+    tokens: Vec<SemanticToken>,
+}
+
+impl<'c> Classifier<'c> {
+    fn new(code: &'c str) -> Self {
+        let natives = NATIVE_FUNCTIONS
+            .keys()
+            .map(|fun| {
+                let binding = Binding {
+                    category: TokenCategory::StdFunction,
+                    function_depth: 0,
+                };
+                (fun.clone().into(), binding)
+            })
+            .collect();
+
+        Self {
+            code,
+            scopes: vec![natives, HashMap::new()],
+            function_depth: 0,
+            tokens: vec![],
+        }
+    }
+
+    fn push(&mut self, span: Span, category: TokenCategory) {
+        self.tokens.push(SemanticToken { span, category });
+    }
+
+    fn declare(&mut self, name: &str, category: TokenCategory) {
+        let binding = Binding {
+            category,
+            function_depth: self.function_depth,
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), binding);
+        }
+    }
+
+    // Declared names aren't nodes, so they're found in the source right after their keyword
+    fn declare_name(
+        &mut self,
+        stmt_span: &Span,
+        keyword: &str,
+        name: &str,
+        category: TokenCategory,
+    ) {
+        let after_keyword = stmt_span.start + keyword.len();
+        if let Some(offset) = self
+            .code
+            .get(after_keyword..stmt_span.end)
+            .and_then(|text| text.find(name))
+        {
+            let start = after_keyword + offset;
+            self.push(start..start + name.len(), category);
+        }
+
+        self.declare(name, category);
+    }
+
+    fn reference(&mut self, name: &str, span: Span) {
+        let binding = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .copied();
+
+        if let Some(binding) = binding {
+            let captured = binding.function_depth < self.function_depth;
+            let category = match binding.category {
+                TokenCategory::Parameter | TokenCategory::Local if captured => {
+                    TokenCategory::Upvalue
+                }
+                category => category,
+            };
+            self.push(span, category);
+        }
+    }
+
+    fn local_category(&self) -> TokenCategory {
+        // The first two scopes hold the natives and the top level of the program
+        if self.scopes.len() <= 2 {
+            TokenCategory::Global
+        } else {
+            TokenCategory::Local
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &*stmt.kind {
+            StmtKind::VariableDeclaration { name, expr, .. } => {
+                self.visit_expr(expr);
+                let category = self.local_category();
+                self.declare_name(&stmt.span, "let", name, category);
+            }
+            StmtKind::Destructuring { pattern, expr } => {
+                self.visit_expr(expr);
+                let category = self.local_category();
+                for name in pattern.names() {
+                    self.push(name.span.clone(), category);
+                    self.declare(&name.kind, category);
+                }
+            }
+            StmtKind::MultipleDeclaration { names, exprs } => {
+                for expr in exprs {
+                    self.visit_expr(expr);
+                }
+                let category = self.local_category();
+                for name in names {
+                    self.push(name.span.clone(), category);
+                    self.declare(&name.kind, category);
+                }
+            }
+            StmtKind::ParallelAssignment { targets, values } => {
+                for expr in targets.iter().chain(values) {
+                    self.visit_expr(expr);
+                }
+            }
+            // Names of the imported module aren't known without its source
+            StmtKind::Import { .. } => {}
+            StmtKind::Export { declaration } => self.visit_stmt(declaration),
+            StmtKind::FunctionDeclaration {
+                name, params, body, ..
+            } => {
+                self.declare_name(&stmt.span, "fn", name, TokenCategory::Function);
+                self.visit_function(params, body);
+            }
+            StmtKind::Expression { expr } => self.visit_expr(expr),
+        }
+    }
+
+    fn visit_function(&mut self, params: &Params, body: &Expr) {
+        self.function_depth += 1;
+        self.scopes.push(HashMap::new());
+
+        for param in params.kind.iter() {
+            self.push(param.span.clone(), TokenCategory::Parameter);
+            self.declare(&param.kind, TokenCategory::Parameter);
+        }
+        self.visit_expr(body);
+
+        self.scopes.pop();
+        self.function_depth -= 1;
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        use ExprKind::*;
+
+        match &*expr.kind {
+            Atom(AtomicValue::Identifier { name, .. }) => self.reference(name, expr.span.clone()),
+            Atom(_) | Continue => {}
+            Binary { lhs, rhs, .. } => {
+                self.visit_expr(lhs);
+                self.visit_expr(rhs);
+            }
+            Unary { rhs, .. } | Spread { value: rhs } => self.visit_expr(rhs),
+            Block { stmts, return_expr } => {
+                self.scopes.push(HashMap::new());
+                for stmt in stmts {
+                    self.visit_stmt(stmt);
+                }
+                if let Some(return_expr) = return_expr {
+                    self.visit_expr(return_expr);
+                }
+                self.scopes.pop();
+            }
+            If {
+                condition,
+                body,
+                else_expr,
+            } => {
+                self.visit_expr(condition);
+                self.visit_expr(body);
+                if let Some(else_expr) = else_expr {
+                    self.visit_expr(else_expr);
+                }
+            }
+            While { condition, body } => {
+                self.visit_expr(condition);
+                self.visit_expr(body);
+            }
+            Loop { body } => self.visit_expr(body),
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                self.scopes.push(HashMap::new());
+                if let Some(init) = init {
+                    self.visit_stmt(init);
+                }
+                for expr in condition.iter().chain(step).chain(Some(body)) {
+                    self.visit_expr(expr);
+                }
+                self.scopes.pop();
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                self.visit_expr(iterable);
+                self.scopes.push(HashMap::new());
+                let category = self.local_category();
+                self.push(item.span.clone(), category);
+                self.declare(&item.kind, category);
+                self.visit_expr(body);
+                self.scopes.pop();
+            }
+            Match { value, arms } => {
+                self.visit_expr(value);
+                for arm in arms {
+                    if let Pattern::Value(pattern) = &arm.pattern {
+                        self.visit_expr(pattern);
+                    }
+                    self.visit_expr(&arm.body);
+                }
+            }
+            Break { return_expr: value } | Return { value } => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+            }
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
+                self.visit_expr(callee);
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+                for arg in named_args {
+                    self.push(arg.name.span.clone(), TokenCategory::Parameter);
+                    self.visit_expr(&arg.value);
+                }
+            }
+            Array { values } | Interpolation { parts: values } => {
+                for value in values {
+                    self.visit_expr(value);
+                }
+            }
+            Index { target, position } => {
+                self.visit_expr(target);
+                self.visit_expr(position);
+            }
+            Slice {
+                target, start, end, ..
+            } => {
+                self.visit_expr(target);
+                for bound in start.iter().chain(end) {
+                    self.visit_expr(bound);
+                }
+            }
+            GetProperty { target, .. } => self.visit_expr(target),
+            SetProperty { target, value, .. } | Assignment { target, value } => {
+                self.visit_expr(target);
+                self.visit_expr(value);
+            }
+            ObjectLiteral { properties } => {
+                for (_, value) in properties {
+                    self.visit_expr(value);
+                }
+            }
+            Map { entries } => {
+                for (_, value) in entries {
+                    self.visit_expr(value);
+                }
+            }
+            Closure { params, body } => self.visit_function(params, body),
+        }
+    }
+}
+
+// Classifies every identifier of the program, sorted by their position in the code.
+// Identifiers that don't resolve to anything are left out.
+pub fn semantic_tokens(code: &str, ast: AstRef) -> Vec<SemanticToken> {
+    let mut classifier = Classifier::new(code);
+    for stmt in ast {
+        classifier.visit_stmt(stmt);
+    }
+
+    let mut tokens = classifier.tokens;
+    tokens.sort_by_key(|token| token.span.start);
+    tokens
+}
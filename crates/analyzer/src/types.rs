@@ -0,0 +1,735 @@
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use common::{CompilerDiagnostic, ProgramText};
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind, Pattern},
+    operator::{BinaryOperator, UnaryOperator},
+    stmt::{Stmt, StmtKind},
+    AstRef, Params, Span, TypeAnnotation,
+};
+use vm::gravitas_std::NATIVE_FUNCTIONS;
+
+use crate::AnalyzerResult;
+
+// Static type of an expression. Unknown values are checked at runtime, like without the checker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Unknown,
+    Null,
+    Number,
+    String,
+    Bool,
+    Object,
+    Function(Rc<FunctionType>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionType {
+    pub params: Vec<Type>,
+    // Accepts any number of arguments after the params
+    pub variadic: bool,
+    pub returns: Type,
+}
+
+impl Type {
+    fn is_known(&self) -> bool {
+        *self != Type::Unknown
+    }
+
+    // Common type of two values that can both end up in the same place
+    fn join(&self, other: &Type) -> Type {
+        if self == other {
+            self.clone()
+        } else {
+            Type::Unknown
+        }
+    }
+
+    // Known types must match, anything goes with an unknown one
+    fn accepts(&self, other: &Type) -> bool {
+        !self.is_known() || !other.is_known() || self == other
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "unknown"),
+            Self::Null => write!(f, "null"),
+            Self::Number => write!(f, "number"),
+            Self::String => write!(f, "string"),
+            Self::Bool => write!(f, "bool"),
+            Self::Object => write!(f, "object"),
+            Self::Function(function) => {
+                let mut params: Vec<String> =
+                    function.params.iter().map(Type::to_string).collect();
+                if function.variadic {
+                    params.push("...".to_owned());
+                }
+                write!(f, "fn({}) -> {}", params.join(", "), function.returns)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeErrorCause {
+    OperandTypes {
+        operator: String,
+        operands: Vec<Type>,
+    },
+    Arity {
+        expected: usize,
+        found: usize,
+    },
+    ArgumentType {
+        expected: Type,
+        found: Type,
+    },
+    NotCallable(Type),
+    AssignmentType {
+        expected: Type,
+        found: Type,
+    },
+    InconsistentReturns {
+        first: Type,
+        other: Type,
+    },
+    // Returned value doesn't match the annotation of the function
+    ReturnType {
+        expected: Type,
+        found: Type,
+    },
+    UnknownType(ProgramText),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub span: Span,
+    pub cause: TypeErrorCause,
+}
+
+impl CompilerDiagnostic for TypeError {
+    fn report(&self, file_id: usize) -> Diagnostic<usize> {
+        use TypeErrorCause::*;
+
+        let (message, label) = match &self.cause {
+            OperandTypes { operator, operands } => {
+                let operands: Vec<String> = operands.iter().map(Type::to_string).collect();
+                (
+                    format!(
+                        "Operator {} can't be used with {}",
+                        operator,
+                        operands.join(" and ")
+                    ),
+                    "in this expression".to_owned(),
+                )
+            }
+            Arity { expected, found } => (
+                format!("Expected {} arguments, but got {}", expected, found),
+                "in this call".to_owned(),
+            ),
+            ArgumentType { expected, found } => (
+                "Argument has the wrong type".to_owned(),
+                format!("expected {}, found {}", expected, found),
+            ),
+            NotCallable(found) => (
+                format!("Value of type {} can't be called", found),
+                "called here".to_owned(),
+            ),
+            AssignmentType { expected, found } => (
+                "Assigned value has a different type than the variable".to_owned(),
+                format!("expected {}, found {}", expected, found),
+            ),
+            InconsistentReturns { first, other } => (
+                "Function returns values of different types".to_owned(),
+                format!("returns {} here, but {} elsewhere", other, first),
+            ),
+            ReturnType { expected, found } => (
+                "Returned value has a different type than the function declares".to_owned(),
+                format!("expected {}, found {}", expected, found),
+            ),
+            UnknownType(name) => (
+                format!("Unknown type {}", name),
+                "expected Number, String, Bool, Null, Object or Any".to_owned(),
+            ),
+        };
+
+        Diagnostic::error()
+            .with_message(message)
+            .with_labels(vec![
+                Label::primary(file_id, self.span.clone()).with_message(label)
+            ])
+    }
+}
+
+// Types of the values a function returns, in the order they were found
+#[derive(Default)]
+struct Returns {
+    types: Vec<(Type, Span)>,
+}
+
+struct Checker {
+    scopes: Vec<HashMap<ProgramText, Type>>,
+    returns: Vec<Returns>,
+    errors: Vec<TypeError>,
+}
+
+fn diverges(stmt: &Stmt) -> bool {
+    match &*stmt.kind {
+        StmtKind::Expression { expr } => matches!(
+            &*expr.kind,
+            ExprKind::Return { .. } | ExprKind::Break { .. } | ExprKind::Continue
+        ),
+        _ => false,
+    }
+}
+
+impl Checker {
+    fn new() -> Self {
+        // Natives take anything, only their arity is known
+        let natives = NATIVE_FUNCTIONS
+            .iter()
+            .map(|(name, native)| {
+                let function = FunctionType {
+                    params: vec![Type::Unknown; native.arity],
+                    variadic: false,
+                    returns: Type::Unknown,
+                };
+                (name.clone().into(), Type::Function(Rc::new(function)))
+            })
+            .collect();
+
+        Self {
+            scopes: vec![natives, HashMap::new()],
+            returns: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn error(&mut self, span: &Span, cause: TypeErrorCause) {
+        self.errors.push(TypeError {
+            span: span.clone(),
+            cause,
+        });
+    }
+
+    fn declare(&mut self, name: &str, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), ty);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .unwrap_or(Type::Unknown)
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match &*stmt.kind {
+            StmtKind::VariableDeclaration {
+                name,
+                annotation,
+                expr,
+                ..
+            } => {
+                let mut ty = self.check_expr(expr);
+                if let Some(annotation) = annotation {
+                    let expected = self.annotation_type(annotation);
+                    if !expected.accepts(&ty) {
+                        let cause = TypeErrorCause::AssignmentType {
+                            expected: expected.clone(),
+                            found: ty,
+                        };
+                        self.error(&expr.span, cause);
+                    }
+                    ty = expected;
+                }
+                self.declare(name, ty);
+            }
+            StmtKind::MultipleDeclaration { names, exprs } => {
+                let types: Vec<Type> = exprs.iter().map(|expr| self.check_expr(expr)).collect();
+                for (name, ty) in names.iter().zip(types) {
+                    self.declare(&name.kind, ty);
+                }
+            }
+            StmtKind::ParallelAssignment { targets, values } => {
+                for (target, value) in targets.iter().zip(values) {
+                    self.check_assignment(target, value);
+                }
+            }
+            StmtKind::Destructuring { pattern, expr } => {
+                // Neither arrays nor objects know the types of what they hold
+                self.check_expr(expr);
+                for name in pattern.names() {
+                    self.declare(&name.kind, Type::Unknown);
+                }
+            }
+            StmtKind::FunctionDeclaration {
+                name,
+                params,
+                returns,
+                body,
+                ..
+            } => {
+                let param_types = self.param_types(params);
+                let returns = returns
+                    .as_ref()
+                    .map(|returns| self.annotation_type(returns));
+                // Declared upfront with the annotated or unknown result, so recursive calls can
+                // be checked
+                let pending = FunctionType {
+                    params: param_types.clone(),
+                    variadic: params.kind.rest.is_some(),
+                    returns: returns.clone().unwrap_or(Type::Unknown),
+                };
+                self.declare(name, Type::Function(Rc::new(pending)));
+                let function = self.check_function(params, param_types, returns, body);
+                self.declare(name, function);
+            }
+            StmtKind::Expression { expr } => {
+                self.check_expr(expr);
+            }
+            // Imported names are unknown, just like undeclared ones
+            StmtKind::Import { .. } => {}
+            StmtKind::Export { declaration } => self.check_stmt(declaration),
+        }
+    }
+
+    fn check_assignment(&mut self, target: &Expr, value: &Expr) -> Type {
+        let value_type = self.check_expr(value);
+        if let ExprKind::Atom(AtomicValue::Identifier { name, .. }) = &*target.kind {
+            let expected = self.lookup(name);
+            if !expected.accepts(&value_type) {
+                let cause = TypeErrorCause::AssignmentType {
+                    expected,
+                    found: value_type.clone(),
+                };
+                self.error(&value.span, cause);
+            }
+        } else {
+            self.check_expr(target);
+        }
+        value_type
+    }
+
+    // Annotations name one of the types, Any leaves the value unchecked
+    fn annotation_type(&mut self, annotation: &TypeAnnotation) -> Type {
+        match annotation.kind.as_str() {
+            "Number" => Type::Number,
+            "String" => Type::String,
+            "Bool" => Type::Bool,
+            "Null" => Type::Null,
+            "Object" => Type::Object,
+            "Any" => Type::Unknown,
+            name => {
+                let cause = TypeErrorCause::UnknownType(name.to_owned());
+                self.error(&annotation.span, cause);
+                Type::Unknown
+            }
+        }
+    }
+
+    // Parameters without annotations can be anything
+    fn param_types(&mut self, params: &Params) -> Vec<Type> {
+        params
+            .kind
+            .types
+            .iter()
+            .map(|annotation| match annotation {
+                Some(annotation) => self.annotation_type(annotation),
+                None => Type::Unknown,
+            })
+            .collect()
+    }
+
+    fn check_function(
+        &mut self,
+        params: &Params,
+        param_types: Vec<Type>,
+        annotated_returns: Option<Type>,
+        body: &Expr,
+    ) -> Type {
+        self.scopes.push(HashMap::new());
+        self.returns.push(Returns::default());
+
+        for (param, ty) in params.kind.params.iter().zip(&param_types) {
+            self.declare(&param.kind, ty.clone());
+        }
+        if let Some(rest) = &params.kind.rest {
+            self.declare(&rest.kind, Type::Unknown);
+        }
+
+        let body_type = self.check_expr(body);
+        let ends_with_return = match &*body.kind {
+            ExprKind::Block {
+                return_expr: Some(return_expr),
+                ..
+            } => matches!(&*return_expr.kind, ExprKind::Return { .. }),
+            ExprKind::Block { stmts, .. } => stmts.last().is_some_and(diverges),
+            _ => false,
+        };
+
+        let mut returns = self.returns.pop().unwrap_or_default();
+        self.scopes.pop();
+        if !ends_with_return {
+            returns.types.push((body_type, body.span.clone()));
+        }
+
+        let returned = match annotated_returns {
+            Some(expected) => {
+                for (ty, span) in &returns.types {
+                    if !expected.accepts(ty) {
+                        let cause = TypeErrorCause::ReturnType {
+                            expected: expected.clone(),
+                            found: ty.clone(),
+                        };
+                        self.error(span, cause);
+                    }
+                }
+                expected
+            }
+            None => self.inferred_returns(&returns),
+        };
+
+        let function = FunctionType {
+            params: param_types,
+            variadic: params.kind.rest.is_some(),
+            returns: returned,
+        };
+        Type::Function(Rc::new(function))
+    }
+
+    fn inferred_returns(&mut self, returns: &Returns) -> Type {
+        let mut returned = returns
+            .types
+            .first()
+            .map(|(ty, _)| ty.clone())
+            .unwrap_or(Type::Null);
+        for (ty, span) in returns.types.iter().skip(1) {
+            if !returned.accepts(ty) {
+                let cause = TypeErrorCause::InconsistentReturns {
+                    first: returned.clone(),
+                    other: ty.clone(),
+                };
+                self.error(span, cause);
+            }
+            returned = returned.join(ty);
+        }
+        returned
+    }
+
+    fn check_binary(&mut self, expr: &Expr, lhs: &Type, op: BinaryOperator, rhs: &Type) -> Type {
+        use BinaryOperator::*;
+
+        // Objects can take over arithmetic and comparisons with op_add, op_lt, etc.
+        let overloadable = !matches!(op, And | Or | Range | RangeInclusive);
+        if overloadable && *lhs == Type::Object {
+            return Type::Unknown;
+        }
+
+        let (operand, result) = match op {
+            Addition | Subtraction | Multiplication | Division | Modulo | Power | BitAnd | BitOr
+            | BitXor | ShiftLeft | ShiftRight => (Type::Number, Type::Number),
+            LesserThan | LesserEquals | GreaterThan | GreaterEquals => (Type::Number, Type::Bool),
+            And | Or => (Type::Bool, Type::Bool),
+            // Ranges have no type of their own yet
+            Range | RangeInclusive => (Type::Number, Type::Unknown),
+            Equals | NotEquals => return Type::Bool,
+        };
+
+        if !operand.accepts(lhs) || !operand.accepts(rhs) {
+            let cause = TypeErrorCause::OperandTypes {
+                operator: op.to_string(),
+                operands: vec![lhs.clone(), rhs.clone()],
+            };
+            self.error(&expr.span, cause);
+            // The operation has already been reported, its result shouldn't be reported again
+            return Type::Unknown;
+        }
+
+        result
+    }
+
+    fn check_call(&mut self, expr: &Expr, callee: &Expr, args: &[Expr]) -> Type {
+        use ExprKind::Spread;
+
+        let callee_type = self.check_expr(callee);
+        let arg_types: Vec<Type> = args.iter().map(|arg| self.check_expr(arg)).collect();
+
+        let function = match callee_type {
+            Type::Function(function) => function,
+            Type::Unknown | Type::Object => return Type::Unknown,
+            other => {
+                self.error(&expr.span, TypeErrorCause::NotCallable(other));
+                return Type::Unknown;
+            }
+        };
+
+        // Spread arrays can hold any number of arguments, their positions are only known up to
+        // the first one
+        let known = args
+            .iter()
+            .position(|arg| matches!(&*arg.kind, Spread { .. }))
+            .unwrap_or(args.len());
+        let missing = args.len() < function.params.len();
+        let spreads = known < args.len();
+        if !spreads && (missing || (!function.variadic && args.len() != function.params.len())) {
+            let cause = TypeErrorCause::Arity {
+                expected: function.params.len(),
+                found: args.len(),
+            };
+            self.error(&expr.span, cause);
+        }
+
+        let args = &args[..known];
+        for ((expected, found), arg) in function.params.iter().zip(arg_types).zip(args) {
+            if !expected.accepts(&found) {
+                let cause = TypeErrorCause::ArgumentType {
+                    expected: expected.clone(),
+                    found,
+                };
+                self.error(&arg.span, cause);
+            }
+        }
+
+        function.returns.clone()
+    }
+
+    fn check_expr(&mut self, expr: &Expr) -> Type {
+        use ExprKind::*;
+
+        match &*expr.kind {
+            Atom(atom) => match atom {
+                AtomicValue::Boolean(_) => Type::Bool,
+                AtomicValue::Number(_) => Type::Number,
+                AtomicValue::Text(_) => Type::String,
+                AtomicValue::Identifier { name, .. } => self.lookup(name),
+            },
+            Binary { lhs, op, rhs } => {
+                let lhs = self.check_expr(lhs);
+                let rhs = self.check_expr(rhs);
+                self.check_binary(expr, &lhs, op.kind, &rhs)
+            }
+            Unary { op, rhs } => {
+                let rhs = self.check_expr(rhs);
+                let expected = match op.kind {
+                    UnaryOperator::Negate | UnaryOperator::BitNot => Type::Number,
+                    UnaryOperator::Not => Type::Bool,
+                };
+                if !expected.accepts(&rhs) {
+                    let cause = TypeErrorCause::OperandTypes {
+                        operator: op.kind.to_string(),
+                        operands: vec![rhs],
+                    };
+                    self.error(&expr.span, cause);
+                    return Type::Unknown;
+                }
+                expected
+            }
+            Block { stmts, return_expr } => {
+                self.scopes.push(HashMap::new());
+                for stmt in stmts {
+                    self.check_stmt(stmt);
+                }
+                let ty = match return_expr {
+                    Some(return_expr) => self.check_expr(return_expr),
+                    None => Type::Null,
+                };
+                self.scopes.pop();
+                ty
+            }
+            If {
+                condition,
+                body,
+                else_expr,
+            } => {
+                self.check_expr(condition);
+                let body = self.check_expr(body);
+                match else_expr {
+                    Some(else_expr) => body.join(&self.check_expr(else_expr)),
+                    None => body.join(&Type::Null),
+                }
+            }
+            While { condition, body } => {
+                self.check_expr(condition);
+                self.check_expr(body);
+                Type::Unknown
+            }
+            Loop { body } => {
+                self.check_expr(body);
+                Type::Unknown
+            }
+            Match { value, arms } => {
+                self.check_expr(value);
+                let mut result: Option<Type> = None;
+                let mut exhaustive = false;
+                for arm in arms {
+                    match &arm.pattern {
+                        Pattern::Value(pattern) => {
+                            self.check_expr(pattern);
+                        }
+                        Pattern::Wildcard => exhaustive = true,
+                    }
+                    let body = self.check_expr(&arm.body);
+                    result = Some(match result {
+                        Some(result) => result.join(&body),
+                        None => body,
+                    });
+                }
+
+                // Without a wildcard the value might not match any of the arms
+                match result {
+                    Some(result) if exhaustive => result,
+                    Some(result) => result.join(&Type::Null),
+                    None => Type::Null,
+                }
+            }
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                self.scopes.push(HashMap::new());
+                if let Some(init) = init {
+                    self.check_stmt(init);
+                }
+                for expr in condition.iter().chain(step).chain(Some(body)) {
+                    self.check_expr(expr);
+                }
+                self.scopes.pop();
+                Type::Unknown
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                self.check_expr(iterable);
+                self.scopes.push(HashMap::new());
+                self.declare(&item.kind, Type::Unknown);
+                self.check_expr(body);
+                self.scopes.pop();
+                Type::Unknown
+            }
+            Break { return_expr } => {
+                if let Some(value) = return_expr {
+                    self.check_expr(value);
+                }
+                Type::Unknown
+            }
+            Continue => Type::Unknown,
+            Return { value } => {
+                let ty = match value {
+                    Some(value) => self.check_expr(value),
+                    None => Type::Null,
+                };
+                if let Some(returns) = self.returns.last_mut() {
+                    returns.types.push((ty, expr.span.clone()));
+                }
+                Type::Unknown
+            }
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
+                // Named arguments are matched with the parameters during resolution
+                for arg in named_args {
+                    self.check_expr(&arg.value);
+                }
+                if named_args.is_empty() {
+                    return self.check_call(expr, callee, args);
+                }
+                for arg in args {
+                    self.check_expr(arg);
+                }
+                match self.check_expr(callee) {
+                    Type::Function(function) => function.returns.clone(),
+                    _ => Type::Unknown,
+                }
+            }
+            Array { values } => {
+                for value in values {
+                    self.check_expr(value);
+                }
+                Type::Unknown
+            }
+            Spread { value } => {
+                self.check_expr(value);
+                Type::Unknown
+            }
+            Map { entries } => {
+                for (_, value) in entries {
+                    self.check_expr(value);
+                }
+                Type::Unknown
+            }
+            Interpolation { parts } => {
+                for part in parts {
+                    self.check_expr(part);
+                }
+                Type::String
+            }
+            Index { target, position } => {
+                self.check_expr(target);
+                self.check_expr(position);
+                Type::Unknown
+            }
+            Slice {
+                target, start, end, ..
+            } => {
+                let target = self.check_expr(target);
+                for bound in start.iter().chain(end) {
+                    self.check_expr(bound);
+                }
+                // Slices of strings are strings, arrays have no type of their own yet
+                match target {
+                    Type::String => Type::String,
+                    _ => Type::Unknown,
+                }
+            }
+            GetProperty { target, .. } => {
+                self.check_expr(target);
+                Type::Unknown
+            }
+            SetProperty { target, value, .. } => {
+                self.check_expr(target);
+                self.check_expr(value)
+            }
+            Assignment { target, value } => self.check_assignment(target, value),
+            ObjectLiteral { properties } => {
+                for (_, value) in properties {
+                    self.check_expr(value);
+                }
+                Type::Object
+            }
+            Closure { params, body } => {
+                let param_types = self.param_types(params);
+                self.check_function(params, param_types, None, body)
+            }
+        }
+    }
+}
+
+// Opt-in pass reporting operations that would fail with mismatched types at runtime.
+// Types of variables are inferred from their initializers unless they're annotated, everything
+// else stays unknown.
+pub fn typecheck(ast: AstRef) -> AnalyzerResult<Vec<TypeError>> {
+    let mut checker = Checker::new();
+    for stmt in ast {
+        checker.check_stmt(stmt);
+    }
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
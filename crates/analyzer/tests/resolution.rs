@@ -0,0 +1,104 @@
+use analyzer::analyze;
+use parser::{parse, utils::error::ParseErrorCause};
+
+fn causes(code: &str) -> Vec<ParseErrorCause> {
+    let ast = parse(code).expect("Program should parse");
+    analyze(&ast)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|error| error.cause)
+        .collect()
+}
+
+#[test]
+fn resolves_parameters_and_nested_scopes() {
+    let code = "fn add(a, b) { a + b } let inc = |x| => add(x, 1); \
+                fn first(n) { while true { if n > 0 { return n }; break; }; 0 } \
+                { let x = 1; }; { let x = 2; };";
+    assert_eq!(causes(code), vec![]);
+    // Parameters stay inside their function
+    assert_eq!(
+        causes("fn f(a) { a } a;"),
+        vec![ParseErrorCause::NotDefined]
+    );
+}
+
+#[test]
+fn rejects_duplicate_declarations() {
+    use ParseErrorCause::*;
+    assert_eq!(causes("let a = 1; let a = 2;"), vec![AlreadyDeclared]);
+    assert_eq!(causes("fn f() { 1 } fn f() { 2 }"), vec![AlreadyDeclared]);
+    assert_eq!(causes("fn f(a, a) { a }"), vec![AlreadyDeclared]);
+    assert_eq!(causes("let [a, a] = [1, 2];"), vec![AlreadyDeclared]);
+    assert_eq!(causes("let a = 1; let {b, a} = c;"), vec![AlreadyDeclared]);
+    // Nested scopes may reuse a name
+    assert_eq!(causes("let a = 1; fn f(a) { let a = 2; a }"), vec![]);
+}
+
+#[test]
+fn rejects_control_flow_in_wrong_places() {
+    use ParseErrorCause::*;
+    assert_eq!(causes("break;"), vec![UsedOutsideLoop]);
+    assert_eq!(causes("if true { continue; };"), vec![UsedOutsideLoop]);
+    // A function body is not part of the loop around it
+    assert_eq!(
+        causes("while true { fn f() { break; } break; };"),
+        vec![UsedOutsideLoop]
+    );
+    assert_eq!(causes("{ return 1 };"), vec![ReturnUsedOutsideFunction]);
+    assert_eq!(causes("x + 1;"), vec![NotDefined]);
+    assert_eq!(causes("let x = x;"), vec![UsedBeforeInitialization]);
+}
+
+#[test]
+fn scopes_for_loops() {
+    use ParseErrorCause::*;
+    let code =
+        "fn f() { for (let i = 0; i < 3; i = i + 1) { if i == 1 { continue; }; break; }; 0 }";
+    assert_eq!(causes(code), vec![]);
+    // The initializer only lives as long as the loop
+    assert_eq!(
+        causes("for (let i = 0; i < 3; i = i + 1) { i; }; i;"),
+        vec![NotDefined]
+    );
+    assert_eq!(
+        causes("for (;;) { let a = 1; }; break;"),
+        vec![UsedOutsideLoop]
+    );
+    assert_eq!(
+        causes("for x in [1, 2] { if x == 1 { continue; }; }; x;"),
+        vec![NotDefined]
+    );
+}
+
+#[test]
+fn matches_named_arguments_with_parameters() {
+    use ParseErrorCause::*;
+    let draw = "fn draw(x, y) { x + y }";
+    assert_eq!(
+        causes(&format!("{} draw(y: 1, x: 2); draw(1, y: 2);", draw)),
+        vec![]
+    );
+    assert_eq!(
+        causes(&format!("{} draw(x: 1, z: 2);", draw)),
+        vec![UnknownArgument("z".to_owned())]
+    );
+    assert_eq!(
+        causes(&format!("{} draw(1, x: 2);", draw)),
+        vec![DuplicateArgument("x".to_owned())]
+    );
+    assert_eq!(
+        causes(&format!("{} draw(y: 2);", draw)),
+        vec![MissingArgument("x".to_owned())]
+    );
+    // Only declared functions know their parameters
+    assert_eq!(
+        causes("let f = |x| x; f(x: 1);"),
+        vec![NamedArgumentsNeedFunction]
+    );
+    assert_eq!(
+        causes(&format!("{} fn f(draw) {{ draw(x: 1, y: 2) }}", draw)),
+        vec![NamedArgumentsNeedFunction]
+    );
+}
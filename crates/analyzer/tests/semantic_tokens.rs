@@ -0,0 +1,52 @@
+use analyzer::semantic_tokens::{semantic_tokens, TokenCategory};
+use parser::parse;
+
+fn categories(code: &str) -> Vec<(&str, TokenCategory)> {
+    let ast = parse(code).expect("Program should parse");
+    semantic_tokens(code, &ast)
+        .into_iter()
+        .map(|token| (&code[token.span], token.category))
+        .collect()
+}
+
+#[test]
+fn classifies_declarations_and_references() {
+    use TokenCategory::*;
+
+    assert_eq!(
+        categories("let a = 1; fn f(x) { let b = x; print(a, b) }"),
+        vec![
+            ("a", Global),
+            ("f", Function),
+            ("x", Parameter),
+            ("b", Local),
+            ("x", Parameter),
+            ("print", StdFunction),
+            ("a", Global),
+            ("b", Local),
+        ]
+    );
+}
+
+#[test]
+fn locals_of_enclosing_functions_are_upvalues() {
+    use TokenCategory::*;
+
+    assert_eq!(
+        categories("fn outer(x) { let y = 1; fn inner() => x + y inner }"),
+        vec![
+            ("outer", Function),
+            ("x", Parameter),
+            ("y", Local),
+            ("inner", Function),
+            ("x", Upvalue),
+            ("y", Upvalue),
+            ("inner", Function),
+        ]
+    );
+}
+
+#[test]
+fn unresolved_identifiers_are_left_out() {
+    assert_eq!(categories("missing + 1;"), vec![]);
+}
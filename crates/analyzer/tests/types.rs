@@ -0,0 +1,153 @@
+use analyzer::types::{typecheck, Type, TypeErrorCause};
+use parser::parse;
+
+fn causes(code: &str) -> Vec<TypeErrorCause> {
+    let ast = parse(code).expect("Program should parse");
+    typecheck(&ast)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|error| error.cause)
+        .collect()
+}
+
+#[test]
+fn accepts_well_typed_programs() {
+    let code = "let a = 1; let b = a * 2 > 1 and true; fn id(x) { x } id(\"text\"); print(b);";
+    assert_eq!(causes(code), vec![]);
+    // one() is known to return a number, which and doesn't accept
+    assert_eq!(causes("fn one() { return 1 } one() and true;").len(), 1);
+}
+
+#[test]
+fn reports_wrong_operand_types() {
+    assert_eq!(
+        causes("let name = \"a\"; name - 1; !5;"),
+        vec![
+            TypeErrorCause::OperandTypes {
+                operator: "-".to_owned(),
+                operands: vec![Type::String, Type::Number],
+            },
+            TypeErrorCause::OperandTypes {
+                operator: "!".to_owned(),
+                operands: vec![Type::Number],
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_a_wrong_operand_only_once() {
+    assert_eq!(
+        causes("let s: String = \"a\" + \"b\";"),
+        vec![TypeErrorCause::OperandTypes {
+            operator: "+".to_owned(),
+            operands: vec![Type::String, Type::String],
+        }]
+    );
+    assert_eq!(causes("let n: Number = -\"a\"; n + 1;").len(), 1);
+}
+
+#[test]
+fn reports_calls_and_assignments() {
+    assert_eq!(
+        causes("fn add(a, b) => a + b let sum = add(1); sum = \"a\"; 5();"),
+        vec![
+            TypeErrorCause::Arity {
+                expected: 2,
+                found: 1
+            },
+            TypeErrorCause::AssignmentType {
+                expected: Type::Number,
+                found: Type::String
+            },
+            TypeErrorCause::NotCallable(Type::Number),
+        ]
+    );
+}
+
+#[test]
+fn reports_inconsistent_returns() {
+    let code = "fn pick(flag) { if flag { return 1 }; \"none\" }";
+    assert_eq!(
+        causes(code),
+        vec![TypeErrorCause::InconsistentReturns {
+            first: Type::Number,
+            other: Type::String
+        }]
+    );
+}
+
+#[test]
+fn joins_types_of_match_arms() {
+    assert_eq!(causes("let n = match 1 { 1 => 2, _ => 3 }; n - 1;"), vec![]);
+    assert_eq!(
+        causes("let s = match 1 { 1 => \"a\", _ => \"b\" }; s - 1;"),
+        vec![TypeErrorCause::OperandTypes {
+            operator: "-".to_owned(),
+            operands: vec![Type::String, Type::Number],
+        }]
+    );
+}
+
+#[test]
+fn accepts_any_amount_of_rest_arguments() {
+    let code = "fn log(prefix, ...args) { prefix } log(1); log(1, 2, 3);";
+    assert_eq!(causes(code), vec![]);
+    assert_eq!(
+        causes("fn log(prefix, ...args) { prefix } log();"),
+        vec![TypeErrorCause::Arity {
+            expected: 1,
+            found: 0
+        }]
+    );
+}
+
+#[test]
+fn spread_arguments_skip_the_arity_check() {
+    let code = "fn add(a, b) { a + b } let both = [1, 2]; add(...both); add(1, ...[2]);";
+    assert_eq!(causes(code), vec![]);
+}
+
+#[test]
+fn checks_annotated_declarations() {
+    assert_eq!(
+        causes("let a: Number = 1; let b: Any = \"text\"; let c: String = 2;"),
+        vec![TypeErrorCause::AssignmentType {
+            expected: Type::String,
+            found: Type::Number,
+        }]
+    );
+    // The annotation wins over the type of the initializer
+    assert_eq!(causes("let a: Any = 1; a and true;"), vec![]);
+    assert_eq!(
+        causes("let a: Numbr = 1;"),
+        vec![TypeErrorCause::UnknownType("Numbr".to_owned())]
+    );
+}
+
+#[test]
+fn checks_annotated_functions() {
+    assert_eq!(
+        causes("fn add(a: Number, b: Number) -> Number { a + b } add(1, \"2\");"),
+        vec![TypeErrorCause::ArgumentType {
+            expected: Type::Number,
+            found: Type::String,
+        }]
+    );
+    assert_eq!(
+        causes("fn name(a: String) -> String { a - 1 }"),
+        vec![TypeErrorCause::OperandTypes {
+            operator: "-".to_owned(),
+            operands: vec![Type::String, Type::Number],
+        }]
+    );
+    assert_eq!(causes("let f = |a: Bool| !a; f(true);"), vec![]);
+}
+
+#[test]
+fn objects_can_overload_operators() {
+    let code = "fn add(other) { other } let v = new { op_add: add }; v + 1; v < 2;";
+    assert_eq!(causes(code), vec![]);
+    assert_eq!(causes("let v = new { a: 1 }; v and true;").len(), 1);
+}
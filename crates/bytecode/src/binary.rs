@@ -0,0 +1,541 @@
+// Compiled programs as bytes, so they can be saved to .gvbc files and run later without being
+// parsed and compiled again. The file starts with a header: the magic bytes, the version of the
+// format and the checksum of the rest of the file. Numbers are little endian, lengths are u64.
+use std::{convert::TryFrom, fmt::Display, str::FromStr};
+
+use common::{BuiltInFunction, Symbol};
+
+use crate::{
+    callables::Function,
+    chunk::{Chunk, Constant, JumpTable, SpanEntry, TableKey},
+    stmt::GlobalItem,
+    MemoryAddress, Opcode, ProgramBytecode,
+};
+
+pub const MAGIC: &[u8; 4] = b"GVBC";
+// Bump whenever the encoding changes, older files are rejected instead of being misread
+pub const FORMAT_VERSION: u16 = 2;
+pub const FILE_EXTENSION: &str = "gvbc";
+
+const HEADER_LEN: usize = MAGIC.len() + 2 + 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    // Doesn't start with the magic bytes
+    NotBytecode,
+    UnsupportedVersion(u16),
+    // The file got corrupted after it was written
+    ChecksumMismatch,
+    // Bytes that no encoded program consists of, with what was being read
+    Malformed(&'static str),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::NotBytecode => write!(f, "Not a compiled program"),
+            DecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "Compiled with bytecode format {}, but only {} is supported",
+                version, FORMAT_VERSION
+            ),
+            DecodeError::ChecksumMismatch => write!(f, "Compiled program is corrupted"),
+            DecodeError::Malformed(what) => write!(f, "Malformed {} in compiled program", what),
+        }
+    }
+}
+
+pub type DecodeResult<T> = Result<T, DecodeError>;
+
+// CRC-32 (IEEE), computed bit by bit as the files are small
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// Opcodes without operands, encoded as their position
+const SIMPLE_OPCODES: [Opcode; 33] = {
+    use Opcode::*;
+    [
+        Not,
+        Neg,
+        Add,
+        Sub,
+        Div,
+        Mul,
+        Pow,
+        Mod,
+        Eq,
+        Ne,
+        Lt,
+        Le,
+        Gt,
+        Ge,
+        Or,
+        And,
+        Range,
+        RangeInclusive,
+        BitAnd,
+        BitOr,
+        BitXor,
+        BitNot,
+        Shl,
+        Shr,
+        Dup,
+        Get,
+        Asg,
+        Return,
+        Null,
+        Index,
+        SetIndex,
+        Spread,
+        Iter,
+    ]
+};
+// Tags of the opcodes with operands start after them
+const OPERAND_OPCODES: u8 = 100;
+
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn isize(&mut self, value: isize) {
+        self.i64(value as i64);
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn text(&mut self, text: &str) {
+        self.usize(text.len());
+        self.bytes.extend_from_slice(text.as_bytes());
+    }
+
+    fn global(&mut self, global: &GlobalItem) {
+        match global {
+            GlobalItem::Function(function) => {
+                self.u8(0);
+                self.function(function);
+            }
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.text(&function.name);
+        self.usize(function.arity);
+        self.bool(function.variadic);
+        self.usize(function.max_stack);
+        self.chunk(&function.chunk);
+    }
+
+    fn chunk(&mut self, chunk: &Chunk) {
+        self.code(chunk);
+        self.usize(chunk.spans.len());
+        for entry in &chunk.spans {
+            self.usize(entry.start);
+            self.usize(entry.span.start);
+            self.usize(entry.span.end);
+        }
+    }
+
+    // What the chunk runs, without where it came from
+    fn code(&mut self, chunk: &Chunk) {
+        self.usize(chunk.opcodes.len());
+        for opcode in &chunk.opcodes {
+            self.opcode(opcode);
+        }
+        self.usize(chunk.constants.len());
+        for constant in &chunk.constants {
+            self.constant(constant);
+        }
+    }
+
+    fn opcode(&mut self, opcode: &Opcode) {
+        use Opcode::*;
+        if let Some(tag) = SIMPLE_OPCODES.iter().position(|simple| simple == opcode) {
+            return self.u8(tag as u8);
+        }
+
+        let (tag, operand) = match *opcode {
+            Constant(index) => (0, index as isize),
+            Jif(distance) => (1, distance),
+            Jp(distance) => (2, distance),
+            JumpTable(index) => (3, index as isize),
+            JifEq(distance) => (4, distance),
+            JifNe(distance) => (5, distance),
+            JifLt(distance) => (6, distance),
+            JifLe(distance) => (7, distance),
+            JifGt(distance) => (8, distance),
+            JifGe(distance) => (9, distance),
+            Pop(amount) => (10, amount as isize),
+            DefineGlobal(index) => (11, index as isize),
+            GetGlobal(index) => (12, index as isize),
+            SetGlobal(index) => (13, index as isize),
+            GetProperty { bind_method } => (14, bind_method as isize),
+            SetProperty(amount) => (15, amount as isize),
+            Call(args_count) => (16, args_count as isize),
+            CallSpread(groups) => (17, groups as isize),
+            Block(amount) => (18, amount as isize),
+            Break(distance) => (19, distance),
+            CreateClosure(amount) => (20, amount as isize),
+            CloseUpvalue(slot) => (21, slot as isize),
+            CreateObject(amount) => (22, amount as isize),
+            Slice { inclusive } => (23, inclusive as isize),
+            CreateArray(amount) => (24, amount as isize),
+            CreateSpreadArray(groups) => (25, groups as isize),
+            CreateMap(amount) => (26, amount as isize),
+            IterNext(distance) => (27, distance),
+            Concat(amount) => (28, amount as isize),
+            _ => unreachable!("Opcode {} has no operands", opcode),
+        };
+        self.u8(OPERAND_OPCODES + tag);
+        self.isize(operand);
+    }
+
+    fn constant(&mut self, constant: &Constant) {
+        match constant {
+            Constant::MemoryAddress(address) => {
+                self.u8(0);
+                self.address(address);
+            }
+            Constant::Number(number) => {
+                self.u8(1);
+                self.f64(*number);
+            }
+            Constant::String(text) => {
+                self.u8(2);
+                self.text(text);
+            }
+            Constant::Bool(bool) => {
+                self.u8(3);
+                self.bool(*bool);
+            }
+            Constant::GlobalPointer(ptr) => {
+                self.u8(4);
+                self.usize(*ptr);
+            }
+            Constant::JumpTable(table) => {
+                self.u8(5);
+                self.usize(table.arms.len());
+                for (key, distance) in &table.arms {
+                    match key {
+                        TableKey::Integer(integer) => {
+                            self.u8(0);
+                            self.i64(*integer);
+                        }
+                        TableKey::String(text) => {
+                            self.u8(1);
+                            self.text(text);
+                        }
+                    }
+                    self.isize(*distance);
+                }
+                self.isize(table.default);
+            }
+        }
+    }
+
+    fn address(&mut self, address: &MemoryAddress) {
+        match address {
+            MemoryAddress::Local(index) => {
+                self.u8(0);
+                self.usize(*index);
+            }
+            MemoryAddress::Upvalue { index, is_ref } => {
+                self.u8(1);
+                self.usize(*index);
+                self.bool(*is_ref);
+            }
+            // By name, so reordering the built-in functions doesn't break the files
+            MemoryAddress::BuiltInFunction(function) => {
+                self.u8(2);
+                let name: String = function.clone().into();
+                self.text(&name);
+            }
+            MemoryAddress::HostFunction(index) => {
+                self.u8(3);
+                self.usize(*index);
+            }
+        }
+    }
+}
+
+struct Decoder<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Decoder<'b> {
+    fn take(&mut self, len: usize, what: &'static str) -> DecodeResult<&'b [u8]> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::Malformed(what));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self, what: &'static str) -> DecodeResult<[u8; N]> {
+        let bytes = self.take(N, what)?;
+        Ok(<[u8; N]>::try_from(bytes).expect("Took exactly N bytes"))
+    }
+
+    fn u8(&mut self, what: &'static str) -> DecodeResult<u8> {
+        Ok(self.take(1, what)?[0])
+    }
+
+    fn bool(&mut self, what: &'static str) -> DecodeResult<bool> {
+        match self.u8(what)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Malformed(what)),
+        }
+    }
+
+    fn usize(&mut self, what: &'static str) -> DecodeResult<usize> {
+        let value = u64::from_le_bytes(self.array(what)?);
+        usize::try_from(value).map_err(|_| DecodeError::Malformed(what))
+    }
+
+    fn i64(&mut self, what: &'static str) -> DecodeResult<i64> {
+        Ok(i64::from_le_bytes(self.array(what)?))
+    }
+
+    fn isize(&mut self, what: &'static str) -> DecodeResult<isize> {
+        isize::try_from(self.i64(what)?).map_err(|_| DecodeError::Malformed(what))
+    }
+
+    fn f64(&mut self, what: &'static str) -> DecodeResult<f64> {
+        Ok(f64::from_le_bytes(self.array(what)?))
+    }
+
+    fn text(&mut self, what: &'static str) -> DecodeResult<String> {
+        let len = self.usize(what)?;
+        let bytes = self.take(len, what)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::Malformed(what))
+    }
+
+    // Nothing is allocated up front, a corrupted length runs out of bytes instead
+    fn list<T>(
+        &mut self,
+        what: &'static str,
+        mut item: impl FnMut(&mut Self) -> DecodeResult<T>,
+    ) -> DecodeResult<Vec<T>> {
+        let len = self.usize(what)?;
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn global(&mut self) -> DecodeResult<GlobalItem> {
+        match self.u8("global")? {
+            0 => Ok(GlobalItem::Function(self.function()?)),
+            _ => Err(DecodeError::Malformed("global")),
+        }
+    }
+
+    fn function(&mut self) -> DecodeResult<Function> {
+        Ok(Function {
+            name: Symbol::intern(&self.text("function name")?),
+            arity: self.usize("function arity")?,
+            variadic: self.bool("function")?,
+            max_stack: self.usize("function stack size")?,
+            chunk: self.chunk()?,
+        })
+    }
+
+    fn chunk(&mut self) -> DecodeResult<Chunk> {
+        Ok(Chunk {
+            opcodes: self.list("opcodes", Self::opcode)?,
+            constants: self.list("constants", Self::constant)?,
+            spans: self.list("spans", |decoder| {
+                Ok(SpanEntry {
+                    start: decoder.usize("spans")?,
+                    span: decoder.usize("spans")?..decoder.usize("spans")?,
+                })
+            })?,
+        })
+    }
+
+    fn opcode(&mut self) -> DecodeResult<Opcode> {
+        use Opcode::*;
+        let tag = self.u8("opcode")?;
+        if let Some(opcode) = SIMPLE_OPCODES.get(tag as usize) {
+            return Ok(*opcode);
+        }
+
+        let operand = self.isize("opcode")?;
+        let unsigned = usize::try_from(operand).map_err(|_| DecodeError::Malformed("opcode"));
+        let flag = match operand {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Malformed("opcode")),
+        };
+        Ok(match tag.wrapping_sub(OPERAND_OPCODES) {
+            0 => Constant(unsigned?),
+            1 => Jif(operand),
+            2 => Jp(operand),
+            3 => JumpTable(unsigned?),
+            4 => JifEq(operand),
+            5 => JifNe(operand),
+            6 => JifLt(operand),
+            7 => JifLe(operand),
+            8 => JifGt(operand),
+            9 => JifGe(operand),
+            10 => Pop(unsigned?),
+            11 => DefineGlobal(unsigned?),
+            12 => GetGlobal(unsigned?),
+            13 => SetGlobal(unsigned?),
+            14 => GetProperty { bind_method: flag? },
+            15 => SetProperty(unsigned?),
+            16 => Call(unsigned?),
+            17 => CallSpread(unsigned?),
+            18 => Block(unsigned?),
+            19 => Break(operand),
+            20 => CreateClosure(unsigned?),
+            21 => CloseUpvalue(unsigned?),
+            22 => CreateObject(unsigned?),
+            23 => Slice { inclusive: flag? },
+            24 => CreateArray(unsigned?),
+            25 => CreateSpreadArray(unsigned?),
+            26 => CreateMap(unsigned?),
+            27 => IterNext(operand),
+            28 => Concat(unsigned?),
+            _ => return Err(DecodeError::Malformed("opcode")),
+        })
+    }
+
+    fn constant(&mut self) -> DecodeResult<Constant> {
+        Ok(match self.u8("constant")? {
+            0 => Constant::MemoryAddress(self.address()?),
+            1 => Constant::Number(self.f64("number")?),
+            2 => Constant::String(self.text("string")?),
+            3 => Constant::Bool(self.bool("bool")?),
+            4 => Constant::GlobalPointer(self.usize("global pointer")?),
+            5 => {
+                let arms = self.list("jump table", |decoder| {
+                    let key = match decoder.u8("jump table")? {
+                        0 => TableKey::Integer(decoder.i64("jump table")?),
+                        1 => TableKey::String(decoder.text("jump table")?),
+                        _ => return Err(DecodeError::Malformed("jump table")),
+                    };
+                    Ok((key, decoder.isize("jump table")?))
+                })?;
+                Constant::JumpTable(JumpTable {
+                    arms: arms.into_iter().collect(),
+                    default: self.isize("jump table")?,
+                })
+            }
+            _ => return Err(DecodeError::Malformed("constant")),
+        })
+    }
+
+    fn address(&mut self) -> DecodeResult<MemoryAddress> {
+        Ok(match self.u8("memory address")? {
+            0 => MemoryAddress::Local(self.usize("memory address")?),
+            1 => MemoryAddress::Upvalue {
+                index: self.usize("memory address")?,
+                is_ref: self.bool("memory address")?,
+            },
+            2 => {
+                let name = self.text("built-in function")?;
+                let function = BuiltInFunction::from_str(&name)
+                    .map_err(|_| DecodeError::Malformed("built-in function"))?;
+                MemoryAddress::BuiltInFunction(function)
+            }
+            3 => MemoryAddress::HostFunction(self.usize("memory address")?),
+            _ => return Err(DecodeError::Malformed("memory address")),
+        })
+    }
+}
+
+// FNV-1a, which unlike the hasher of the standard library is the same on every platform and release
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl Chunk {
+    // Hash of the opcodes and constants, which stays the same across runs, so it can be stored
+    // e.g. next to cached compilation results. The spans aren't hashed, moving code around
+    // without changing it keeps the hash.
+    pub fn hash(&self) -> u64 {
+        let mut code = Encoder::default();
+        code.code(self);
+        fnv1a(&code.bytes)
+    }
+}
+
+impl ProgramBytecode {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = Encoder::default();
+        body.usize(self.global_fn_ptr);
+        body.usize(self.globals.len());
+        for global in &self.globals {
+            body.global(global);
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.bytes.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&checksum(&body.bytes).to_le_bytes());
+        bytes.extend_from_slice(&body.bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> DecodeResult<Self> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(DecodeError::NotBytecode);
+        }
+        let mut header = Decoder {
+            bytes: &bytes[MAGIC.len()..],
+        };
+        let version = u16::from_le_bytes(header.array("header")?);
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let expected = u32::from_le_bytes(header.array("header")?);
+        if checksum(header.bytes) != expected {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        let mut body = header;
+        let global_fn_ptr = body.usize("program")?;
+        let globals = body.list("globals", Decoder::global)?;
+        if !body.bytes.is_empty() {
+            return Err(DecodeError::Malformed("program"));
+        }
+        Ok(ProgramBytecode {
+            global_fn_ptr,
+            globals,
+        })
+    }
+}
@@ -1,33 +1,27 @@
 use std::{collections::HashMap, fmt::Display};
 
-use common::ProgramText;
-use prettytable::Table;
+use common::Symbol;
 
 use crate::{
-    chunk::{chunk_into_rows, Chunk},
+    chunk::Chunk,
+    disassemble,
     stmt::GlobalPointer,
 };
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Function {
     pub arity: usize,
+    // Takes any number of arguments past the arity and gets them as an array
+    pub variadic: bool,
     pub chunk: Chunk,
-    pub name: ProgramText,
+    pub name: Symbol,
+    // Most values the function has on the stack at once, counting the arguments, the function
+    // itself and `this`. The VM makes room for them when the function is called.
+    pub max_stack: usize,
 }
 
 impl Display for Function {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut table = Table::new();
-
-        table.add_row(row!["Name", "Arity"]);
-        table.add_row(row![self.name, self.arity]);
-
-        for row in chunk_into_rows(self.chunk.clone()) {
-            table.add_row(row);
-        }
-
-        table.fmt(f)?;
-
-        Ok(())
+        write!(f, "{}", disassemble(self))
     }
 }
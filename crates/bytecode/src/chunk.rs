@@ -1,16 +1,51 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
 use crate::{stmt::GlobalPointer, MemoryAddress, Opcode};
 use common::{Number, ProgramText};
-use prettytable::Row;
+use parser::parse::Span;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constant {
     MemoryAddress(MemoryAddress),
     Number(Number),
     String(ProgramText),
     Bool(bool),
     GlobalPointer(GlobalPointer),
+    JumpTable(JumpTable),
+}
+
+// Literal a match arm compares the value with, numbers only when they are integers
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TableKey {
+    Integer(i64),
+    String(ProgramText),
+}
+
+impl TableKey {
+    pub fn from_number(number: Number) -> Option<Self> {
+        let integer = number as i64;
+        (integer as Number == number).then(|| Self::Integer(integer))
+    }
+}
+
+// Arms of a match by the literal they match. Both the arms and the default are jump distances
+// from the JumpTable opcode, like the distances of the other jumps. The arms are ordered,
+// so the same program always ends up with the same table.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JumpTable {
+    pub arms: BTreeMap<TableKey, isize>,
+    pub default: isize,
+}
+
+impl JumpTable {
+    pub fn distance(&self, key: Option<&TableKey>) -> isize {
+        key.and_then(|key| self.arms.get(key))
+            .copied()
+            .unwrap_or(self.default)
+    }
 }
 
 impl Display for Constant {
@@ -21,6 +56,7 @@ impl Display for Constant {
             Self::String(str) => str.clone(),
             Self::Bool(bool) => bool.to_string(),
             Self::GlobalPointer(ptr) => format!("global_ptr::{}", ptr),
+            Self::JumpTable(table) => format!("jump_table::{}", table.arms.len()),
         };
 
         write!(f, "{}", str)?;
@@ -31,39 +67,38 @@ impl Display for Constant {
 pub type ConstantIndex = usize;
 pub type OpcodeIndex = usize;
 
+// Source of the opcodes from `start` up to the start of the next entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanEntry {
+    pub start: OpcodeIndex,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct Chunk {
     pub opcodes: Vec<Opcode>,
     pub constants: Vec<Constant>,
+    // Sorted by the start, consecutive opcodes of the same node share one entry
+    pub spans: Vec<SpanEntry>,
 }
 
-pub(crate) fn chunk_into_rows(chunk: Chunk) -> Vec<Row> {
-    let mut rows = vec![];
-
-    rows.push(row!["OPCODE", "CONSTANT INDEX", "CONSTANT VALUE"]);
-
-    let opcodes = chunk.opcodes.iter();
-    let constants = chunk
-        .constants
-        .iter()
-        .enumerate()
-        .map(|(i, c)| (i.to_string(), c.to_string()))
-        .chain(std::iter::repeat(("-".to_owned(), "-".to_owned())));
-
-    for (opcode, (constant_index, constant_value)) in opcodes.zip(constants) {
-        rows.push(row![
-            opcode.to_string(),
-            constant_index,
-            constant_value.to_string()
-        ]);
+// An entry that doesn't cover any opcode is replaced, one with the same span as the last is merged
+pub(crate) fn push_span(spans: &mut Vec<SpanEntry>, start: OpcodeIndex, span: Span) {
+    if matches!(spans.last(), Some(last) if last.start == start) {
+        spans.pop();
+    }
+    if !matches!(spans.last(), Some(last) if last.span == span) {
+        spans.push(SpanEntry { start, span });
     }
-
-    rows
 }
 
 impl Chunk {
     pub fn new(opcodes: Vec<Opcode>, constants: Vec<Constant>) -> Self {
-        Self { opcodes, constants }
+        Self {
+            opcodes,
+            constants,
+            spans: vec![],
+        }
     }
 
     pub fn read(&self, index: ConstantIndex) -> Constant {
@@ -74,14 +109,18 @@ impl Chunk {
     }
 
     pub fn write_constant(&mut self, constant: Constant) -> ConstantIndex {
-        let constant_index = self.constants.len();
-
-        self.constants.push(constant);
+        let constant_index = self.add_constant(constant);
         self.write_opcode(Opcode::Constant(constant_index));
 
         constant_index
     }
 
+    // Stores the constant without pushing it, for opcodes that read it on their own
+    pub fn add_constant(&mut self, constant: Constant) -> ConstantIndex {
+        self.constants.push(constant);
+        self.constants.len() - 1
+    }
+
     pub fn write_opcode(&mut self, opcode: Opcode) -> OpcodeIndex {
         let length = self.opcodes_len();
         self.opcodes.push(opcode);
@@ -92,6 +131,20 @@ impl Chunk {
         self.opcodes[index]
     }
 
+    // Marks the opcodes written from now on as generated from the span
+    pub fn set_span(&mut self, span: Span) {
+        let start = self.opcodes_len();
+        push_span(&mut self.spans, start, span);
+    }
+
+    // Source the opcode was generated from, None for the chunks built by hand
+    pub fn span(&self, index: OpcodeIndex) -> Option<&Span> {
+        let entries = self.spans.partition_point(|entry| entry.start <= index);
+        entries
+            .checked_sub(1)
+            .map(|entry| &self.spans[entry].span)
+    }
+
     pub fn opcodes_len(&self) -> usize {
         self.opcodes.len()
     }
@@ -105,6 +158,7 @@ mod test {
     fn read_from_chunk() {
         let chunk = Chunk {
             opcodes: vec![],
+            spans: vec![],
             constants: vec![
                 Constant::Number(10.0),
                 Constant::Bool(false),
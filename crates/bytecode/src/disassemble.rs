@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    callables::Function,
+    chunk::{Chunk, Constant, ConstantIndex, OpcodeIndex, TableKey},
+    peephole::{jump_distance, jump_target},
+    Opcode,
+};
+
+// Where the opcode can jump to, the arms of a jump table in the order they appear in the code
+fn targets(chunk: &Chunk, index: OpcodeIndex) -> Vec<OpcodeIndex> {
+    let opcode = &chunk.opcodes[index];
+    if let Some(distance) = jump_distance(opcode) {
+        return vec![jump_target(index, distance)];
+    }
+    match table_arms(chunk, opcode) {
+        Some(arms) => arms
+            .into_iter()
+            .map(|(_, distance)| jump_target(index, distance))
+            .collect(),
+        None => vec![],
+    }
+}
+
+// Arms of the table the opcode reads, None for the default one
+fn table_arms<'c>(chunk: &'c Chunk, opcode: &Opcode) -> Option<Vec<(Option<&'c TableKey>, isize)>> {
+    let table = match opcode {
+        Opcode::JumpTable(index) => match chunk.constants.get(*index) {
+            Some(Constant::JumpTable(table)) => table,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let mut arms: Vec<_> = table
+        .arms
+        .iter()
+        .map(|(key, distance)| (Some(key), *distance))
+        .collect();
+    arms.sort_by_key(|(_, distance)| *distance);
+    arms.push((None, table.default));
+    Some(arms)
+}
+
+fn describe_constant(chunk: &Chunk, index: ConstantIndex) -> String {
+    match chunk.constants.get(index) {
+        Some(Constant::String(text)) => format!("{:?}", text),
+        Some(constant) => constant.to_string(),
+        None => "<missing constant>".to_owned(),
+    }
+}
+
+fn describe_key(key: Option<&TableKey>) -> String {
+    match key {
+        Some(TableKey::Integer(integer)) => integer.to_string(),
+        Some(TableKey::String(text)) => format!("{:?}", text),
+        None => "_".to_owned(),
+    }
+}
+
+// Listing of the function's opcodes with their offsets. Constants the opcodes read are shown
+// next to them and jumps point at labels placed before their targets.
+pub fn disassemble(function: &Function) -> String {
+    let chunk = &function.chunk;
+    let labels: BTreeMap<OpcodeIndex, usize> = {
+        let mut jumped_to: Vec<_> = (0..chunk.opcodes.len())
+            .flat_map(|index| targets(chunk, index))
+            .collect();
+        jumped_to.sort_unstable();
+        jumped_to.dedup();
+        jumped_to
+            .into_iter()
+            .enumerate()
+            .map(|(label, target)| (target, label))
+            .collect()
+    };
+    let label = |target: OpcodeIndex| match labels.get(&target) {
+        Some(label) => format!("L{}", label),
+        None => format!("@{}", target),
+    };
+
+    let mut listing = format!("fn {} (arity {}", function.name, function.arity);
+    if function.variadic {
+        listing.push_str(", variadic");
+    }
+    listing.push_str(")\n");
+
+    for (index, opcode) in chunk.opcodes.iter().enumerate() {
+        if labels.contains_key(&index) {
+            let _ = writeln!(listing, "{}:", label(index));
+        }
+
+        let operand = match opcode {
+            Opcode::Constant(constant)
+            | Opcode::DefineGlobal(constant)
+            | Opcode::GetGlobal(constant)
+            | Opcode::SetGlobal(constant) => describe_constant(chunk, *constant),
+            Opcode::JumpTable(_) => match table_arms(chunk, opcode) {
+                Some(arms) => arms
+                    .into_iter()
+                    .map(|(key, distance)| {
+                        let target = jump_target(index, distance);
+                        format!("{} -> {}", describe_key(key), label(target))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => "<missing table>".to_owned(),
+            },
+            _ => match jump_distance(opcode) {
+                Some(distance) => format!("-> {}", label(jump_target(index, distance))),
+                None => String::new(),
+            },
+        };
+
+        let line = format!("{:>5}  {:<24}{}", index, opcode.to_string(), operand);
+        let _ = writeln!(listing, "{}", line.trim_end());
+    }
+
+    // Jumps out of a loop at the end of the function land past the last opcode
+    if labels.contains_key(&chunk.opcodes.len()) {
+        let _ = writeln!(listing, "{}:", label(chunk.opcodes.len()));
+    }
+
+    listing
+}
@@ -0,0 +1,74 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use common::{CompilerDiagnostic, ProgramText};
+use parser::parse::Span;
+
+// The analyzer rejects most of these first, the generator reports them when it runs on its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationError {
+    pub span: Span,
+    pub cause: GenerationErrorCause,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerationErrorCause {
+    UndefinedVariable(ProgramText),
+    // break or continue outside of a loop
+    InvalidBreak,
+    // import of a path that wasn't linked before the program
+    UnknownModule(ProgramText),
+    // the linked modules import each other, the path is the import closing the cycle
+    ImportCycle(ProgramText),
+    NamedArgumentsNeedFunction,
+    MissingArgument(ProgramText),
+    // call of a declared function with the wrong number of arguments
+    WrongArgumentCount {
+        expected: usize,
+        variadic: bool,
+        found: usize,
+    },
+}
+
+impl GenerationError {
+    pub fn new(cause: GenerationErrorCause, span: Span) -> Self {
+        Self { span, cause }
+    }
+}
+
+impl CompilerDiagnostic for GenerationError {
+    fn report(&self, file_id: usize) -> Diagnostic<usize> {
+        use GenerationErrorCause::*;
+        let label = Label::primary(file_id, self.span.clone());
+        match &self.cause {
+            UndefinedVariable(name) => Diagnostic::error()
+                .with_message(format!("Variable '{}' is not defined", name))
+                .with_labels(vec![label]),
+            InvalidBreak => Diagnostic::error()
+                .with_message("Break or continue must be used inside loops")
+                .with_labels(vec![label.with_message("...but was used here")]),
+            UnknownModule(path) => Diagnostic::error()
+                .with_message(format!("Module \"{}\" wasn't linked", path))
+                .with_labels(vec![label]),
+            ImportCycle(path) => Diagnostic::error()
+                .with_message(format!("Module \"{}\" is imported in a cycle", path))
+                .with_labels(vec![label]),
+            NamedArgumentsNeedFunction => Diagnostic::error()
+                .with_message("Named arguments can only be passed to declared functions")
+                .with_labels(vec![label.with_message("callee isn't known here")]),
+            MissingArgument(name) => Diagnostic::error()
+                .with_message(format!("Missing argument '{}'", name))
+                .with_labels(vec![label.with_message("in this call")]),
+            WrongArgumentCount {
+                expected,
+                variadic,
+                found,
+            } => Diagnostic::error()
+                .with_message(format!(
+                    "Expected {}{} arguments, found {}",
+                    if *variadic { "at least " } else { "" },
+                    expected,
+                    found
+                ))
+                .with_labels(vec![label.with_message("in this call")]),
+        }
+    }
+}
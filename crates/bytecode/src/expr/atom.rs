@@ -1,9 +1,17 @@
-use parser::parse::expr::atom::AtomicValue;
+use parser::parse::{expr::atom::AtomicValue, Span};
 
-use crate::{chunk::Constant, BytecodeFrom, BytecodeGenerationResult, BytecodeGenerator, Opcode};
+use crate::{
+    chunk::Constant, BytecodeGenerationResult, BytecodeGenerator, GenerationError,
+    GenerationErrorCause, Opcode,
+};
 
-impl BytecodeFrom<AtomicValue> for BytecodeGenerator {
-    fn generate(&mut self, data: AtomicValue) -> BytecodeGenerationResult {
+impl BytecodeGenerator {
+    // Identifiers are reported at the span of the expression they are in
+    pub(crate) fn generate_atom(
+        &mut self,
+        data: AtomicValue,
+        span: Span,
+    ) -> BytecodeGenerationResult {
         match data {
             AtomicValue::Boolean(bool) => {
                 self.write_constant(Constant::Bool(bool));
@@ -18,15 +26,17 @@ impl BytecodeFrom<AtomicValue> for BytecodeGenerator {
                 name,
                 is_assignment,
             } => {
-                let var_address = self
-                    .state
-                    .find_var_address(&name)
-                    .expect("Analyzer takes care of undefined variables");
+                if let Some(var_address) = self.state.find_var_address(name) {
+                    self.write_constant(var_address.into());
 
-                self.write_constant(var_address.into());
-
-                if !is_assignment {
-                    self.write_opcode(Opcode::Get);
+                    if !is_assignment {
+                        self.write_opcode(Opcode::Get);
+                    }
+                } else if self.state.globals.contains(&name) {
+                    self.write_global(Opcode::GetGlobal, name);
+                } else {
+                    let cause = GenerationErrorCause::UndefinedVariable(name.to_string());
+                    return Err(GenerationError::new(cause, span));
                 }
             }
         };
@@ -48,25 +58,25 @@ mod test {
     #[test]
     fn generates_atoms() {
         assert_bytecode_and_constants(
-            AtomicValue::Boolean(true),
+            expr(AtomicValue::Boolean(true)),
             vec![Opcode::Constant(0)],
             vec![Constant::Bool(true)],
         );
 
         assert_bytecode_and_constants(
-            AtomicValue::Boolean(false),
+            expr(AtomicValue::Boolean(false)),
             vec![Opcode::Constant(0)],
             vec![Constant::Bool(false)],
         );
 
         assert_bytecode_and_constants(
-            AtomicValue::Number(0.0),
+            expr(AtomicValue::Number(0.0)),
             vec![Opcode::Constant(0)],
             vec![Constant::Number(0.0)],
         );
 
         assert_bytecode_and_constants(
-            AtomicValue::Text("foo".to_owned()),
+            expr(AtomicValue::Text("foo".to_owned())),
             vec![Opcode::Constant(0)],
             vec![Constant::String("foo".to_owned())],
         );
@@ -1,6 +1,35 @@
-use parser::parse::operator::BinaryOperator;
+use parser::parse::{expr::Expr, operator::BinaryOperator};
 
-use crate::Opcode;
+use crate::{BytecodeFrom, BytecodeGenerationResult, BytecodeGenerator, Opcode};
+
+impl BytecodeGenerator {
+    // The right operand only runs when the left one doesn't decide the result. When it runs,
+    // And / Or still get both operands, so anything but bools fails the same way as before.
+    pub(crate) fn generate_short_circuit(
+        &mut self,
+        lhs: Expr,
+        op: BinaryOperator,
+        rhs: Expr,
+    ) -> BytecodeGenerationResult {
+        self.generate(lhs)?;
+        self.write_opcode(Opcode::Dup);
+        let skip_rhs = match op {
+            // false and ..., the false stays as the result
+            BinaryOperator::And => self.emit_patch(Opcode::Jif(0)),
+            // true or ..., the true stays as the result
+            _ => {
+                let eval_rhs = self.emit_patch(Opcode::Jif(0));
+                let skip_rhs = self.emit_patch(Opcode::Jp(0));
+                self.patch(&eval_rhs);
+                skip_rhs
+            }
+        };
+        self.generate(rhs)?;
+        self.write_opcode(op.into());
+        self.patch(&skip_rhs);
+        Ok(())
+    }
+}
 
 impl From<BinaryOperator> for Opcode {
     fn from(data: BinaryOperator) -> Self {
@@ -21,6 +50,13 @@ impl From<BinaryOperator> for Opcode {
             GreaterEquals => Opcode::Ge,
             Or => Opcode::Or,
             And => Opcode::And,
+            Range => Opcode::Range,
+            RangeInclusive => Opcode::RangeInclusive,
+            BitAnd => Opcode::BitAnd,
+            BitOr => Opcode::BitOr,
+            BitXor => Opcode::BitXor,
+            ShiftLeft => Opcode::Shl,
+            ShiftRight => Opcode::Shr,
         }
     }
 }
@@ -0,0 +1,66 @@
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind},
+    operator::{BinaryOperator, UnaryOperator},
+};
+
+// Value of an operation on literals, computed the same way the VM would do it.
+// Operations the VM would fail on are left for it to report at runtime.
+pub(crate) fn fold(expr: &Expr) -> Option<AtomicValue> {
+    match &*expr.kind {
+        ExprKind::Atom(AtomicValue::Identifier { .. }) => None,
+        ExprKind::Atom(atom) => Some(atom.clone()),
+        ExprKind::Binary { lhs, op, rhs } => binary(fold(lhs)?, op.kind, fold(rhs)?),
+        ExprKind::Unary { op, rhs } => unary(op.kind, fold(rhs)?),
+        _ => None,
+    }
+}
+
+fn binary(lhs: AtomicValue, op: BinaryOperator, rhs: AtomicValue) -> Option<AtomicValue> {
+    use AtomicValue::*;
+    use BinaryOperator::*;
+    // Values of different types are never equal
+    let equals = match (&lhs, &rhs) {
+        (Number(a), Number(b)) => a == b,
+        (Text(a), Text(b)) => a == b,
+        (Boolean(a), Boolean(b)) => a == b,
+        _ => false,
+    };
+    let value = match (lhs, op, rhs) {
+        (_, Equals, _) => Boolean(equals),
+        (_, NotEquals, _) => Boolean(!equals),
+        (Number(a), op, Number(b)) => match op {
+            Addition => Number(a + b),
+            Subtraction => Number(a - b),
+            Multiplication => Number(a * b),
+            Division => Number(a / b),
+            Modulo => Number(a % b),
+            Power => Number(a.powf(b)),
+            LesserThan => Boolean(a < b),
+            LesserEquals => Boolean(a <= b),
+            GreaterThan => Boolean(a > b),
+            GreaterEquals => Boolean(a >= b),
+            BitAnd => Number(((a as i64) & (b as i64)) as f64),
+            BitOr => Number(((a as i64) | (b as i64)) as f64),
+            BitXor => Number(((a as i64) ^ (b as i64)) as f64),
+            ShiftLeft => Number((a as i64).wrapping_shl(b as i64 as u32) as f64),
+            ShiftRight => Number((a as i64).wrapping_shr(b as i64 as u32) as f64),
+            // Ranges have no literal to fold into
+            _ => return None,
+        },
+        (Boolean(a), And, Boolean(b)) => Boolean(a && b),
+        (Boolean(a), Or, Boolean(b)) => Boolean(a || b),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn unary(op: UnaryOperator, rhs: AtomicValue) -> Option<AtomicValue> {
+    Some(match (op, rhs) {
+        (UnaryOperator::Not, AtomicValue::Boolean(value)) => AtomicValue::Boolean(!value),
+        (UnaryOperator::Negate, AtomicValue::Number(value)) => AtomicValue::Number(-value),
+        (UnaryOperator::BitNot, AtomicValue::Number(value)) => {
+            AtomicValue::Number(!(value as i64) as f64)
+        }
+        _ => return None,
+    })
+}
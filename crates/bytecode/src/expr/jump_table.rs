@@ -0,0 +1,85 @@
+use parser::parse::expr::{atom::AtomicValue, Expr, MatchArm, Pattern};
+
+use crate::{
+    chunk::{Constant, JumpTable, TableKey},
+    BytecodeFrom, BytecodeGenerationResult, BytecodeGenerator, Opcode,
+};
+
+use super::fold;
+
+// Below it comparing the value with every arm is about as fast as the lookup
+const MIN_TABLE_ARMS: usize = 4;
+
+fn table_key(pattern: &Expr) -> Option<TableKey> {
+    match fold::fold(pattern)? {
+        AtomicValue::Number(number) => TableKey::from_number(number),
+        AtomicValue::Text(text) => Some(TableKey::String(text)),
+        _ => None,
+    }
+}
+
+// Literals of the arms, when every arm up to the wildcard has one and there's enough of them.
+// Arms after the wildcard are never taken, so they don't count.
+pub(super) fn table_keys(arms: &[MatchArm]) -> Option<Vec<TableKey>> {
+    let keys = arms
+        .iter()
+        .map_while(|arm| match &arm.pattern {
+            Pattern::Wildcard => None,
+            Pattern::Value(pattern) => Some(table_key(pattern)),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (keys.len() >= MIN_TABLE_ARMS).then(|| keys)
+}
+
+impl BytecodeGenerator {
+    // Jumps straight to the arm instead of comparing the value with the arms one by one
+    pub(super) fn generate_jump_table(
+        &mut self,
+        value: Expr,
+        arms: Vec<MatchArm>,
+        keys: Vec<TableKey>,
+    ) -> BytecodeGenerationResult {
+        self.generate(value)?;
+        let index = self
+            .current_chunk()
+            .add_constant(Constant::JumpTable(JumpTable::default()));
+        let jump = self.write_opcode(Opcode::JumpTable(index));
+        let distance = |target: usize| target as isize - jump as isize - 1;
+
+        let mut table = JumpTable::default();
+        let mut keys = keys.into_iter();
+        let mut end_patches = vec![];
+        let mut has_default = false;
+        for arm in arms {
+            let start = distance(self.next_index());
+            match keys.next() {
+                // The first of the arms with the same literal is taken, like in the comparisons
+                Some(key) => {
+                    table.arms.entry(key).or_insert(start);
+                }
+                None => {
+                    table.default = start;
+                    has_default = true;
+                }
+            }
+
+            self.generate(arm.body)?;
+            if has_default {
+                break;
+            }
+            end_patches.push(self.emit_patch(Opcode::Jp(0)));
+        }
+
+        // None of the arms matched
+        if !has_default {
+            table.default = distance(self.next_index());
+            self.write_opcode(Opcode::Null);
+        }
+        for end_patch in &end_patches {
+            self.patch(end_patch);
+        }
+
+        self.current_chunk().constants[index] = Constant::JumpTable(table);
+        Ok(())
+    }
+}
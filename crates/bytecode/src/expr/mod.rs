@@ -1,10 +1,21 @@
-use parser::parse::expr::{Expr, ExprKind};
+use common::{Symbol, LAMBDA_NAME};
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind, NamedArg, Pattern},
+    operator::BinaryOperator,
+    Span,
+};
 
-use crate::{chunk::Constant, state::ScopeType, BytecodeFrom, BytecodeGenerator, Opcode};
+use crate::{
+    chunk::Constant,
+    state::{hidden_name, ScopeType, Signature},
+    BytecodeFrom, BytecodeGenerator, GenerationError, GenerationErrorCause, Opcode, Patch,
+};
 
 mod atom;
 mod binary;
 mod flow_control;
+mod fold;
+mod jump_table;
 mod unary;
 
 impl BytecodeFrom<Vec<Expr>> for BytecodeGenerator {
@@ -16,11 +27,147 @@ impl BytecodeFrom<Vec<Expr>> for BytecodeGenerator {
     }
 }
 
+impl BytecodeGenerator {
+    // Named arguments are moved into the slots of the parameters they name
+    // Signature of the function, when the callee is the name of one
+    fn callee_signature(&self, callee: &Expr) -> Option<&Signature> {
+        match &*callee.kind {
+            ExprKind::Atom(AtomicValue::Identifier { name, .. }) => {
+                self.state.find_signature(*name)
+            }
+            _ => None,
+        }
+    }
+
+    fn order_args(
+        &self,
+        callee: &Expr,
+        mut args: Vec<Expr>,
+        mut named_args: Vec<NamedArg>,
+        span: &Span,
+    ) -> Result<Vec<Expr>, GenerationError> {
+        if named_args.is_empty() {
+            return Ok(args);
+        }
+
+        let signature = self.callee_signature(callee).ok_or_else(|| {
+            GenerationError::new(
+                GenerationErrorCause::NamedArgumentsNeedFunction,
+                callee.span.clone(),
+            )
+        })?;
+
+        for param in signature.params.iter().skip(args.len()) {
+            let position = named_args
+                .iter()
+                .position(|arg| arg.name.kind == *param)
+                .ok_or_else(|| {
+                    let cause = GenerationErrorCause::MissingArgument(param.to_string());
+                    GenerationError::new(cause, span.clone())
+                })?;
+            args.push(named_args.swap_remove(position).value);
+        }
+
+        Ok(args)
+    }
+
+    // Calls of declared functions have to pass as many arguments as there are params,
+    // the VM would take the missing ones from the values below the arguments otherwise
+    fn check_arity(
+        &self,
+        callee: &Expr,
+        args_count: usize,
+        span: &Span,
+    ) -> crate::BytecodeGenerationResult {
+        match self.callee_signature(callee) {
+            Some(signature) if !signature.accepts(args_count) => {
+                let cause = GenerationErrorCause::WrongArgumentCount {
+                    expected: signature.params.len(),
+                    variadic: signature.variadic,
+                    found: args_count,
+                };
+                Err(GenerationError::new(cause, span.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Values are pushed in groups followed by their size, because spread arrays are only
+    // measured at runtime. Returns the number of groups.
+    fn generate_groups(&mut self, values: Vec<Expr>) -> Result<usize, GenerationError> {
+        let mut groups = 0;
+        let mut pending = 0;
+        for value in values {
+            if matches!(&*value.kind, ExprKind::Spread { .. }) {
+                if pending > 0 {
+                    self.write_constant(Constant::Number(pending as f64));
+                    groups += 1;
+                    pending = 0;
+                }
+                self.generate(value)?;
+                groups += 1;
+            } else {
+                self.generate(value)?;
+                pending += 1;
+            }
+        }
+        if pending > 0 {
+            self.write_constant(Constant::Number(pending as f64));
+            groups += 1;
+        }
+        Ok(groups)
+    }
+
+    // Variables break and continue have to drop, they can only appear inside loops
+    fn loop_declared(&self, span: &Span) -> Result<usize, GenerationError> {
+        self.state
+            .declared_in_loop()
+            .ok_or_else(|| GenerationError::new(GenerationErrorCause::InvalidBreak, span.clone()))
+    }
+
+    // Name of the global the assignment target refers to, when it's a top-level variable
+    pub(crate) fn global_target(&mut self, target: &Expr) -> Option<Symbol> {
+        match &*target.kind {
+            ExprKind::Atom(AtomicValue::Identifier { name, .. }) => self.state.find_global(*name),
+            _ => None,
+        }
+    }
+
+    // Break and continue jump past the ends of the blocks they leave, so they close them on their own
+    fn close_loop_upvalues(&mut self, declared: usize) {
+        let slot = self.state.first_slot() + self.state.declared() - declared;
+        self.close_upvalues(slot);
+    }
+}
+
+fn has_spread(values: &[Expr]) -> bool {
+    values
+        .iter()
+        .any(|value| matches!(&*value.kind, ExprKind::Spread { .. }))
+}
+
 impl BytecodeFrom<Expr> for BytecodeGenerator {
     fn generate(&mut self, expr: Expr) -> crate::BytecodeGenerationResult {
+        self.with_span(expr.span.clone(), |generator| generator.generate_expr(expr))
+    }
+}
+
+impl BytecodeGenerator {
+    fn generate_expr(&mut self, expr: Expr) -> crate::BytecodeGenerationResult {
+        if self.options.fold_constants {
+            if let Some(value) = fold::fold(&expr) {
+                return self.generate_atom(value, expr.span);
+            }
+        }
+
         match *expr.kind {
             ExprKind::Atom(atomic_value) => {
-                self.generate(atomic_value)?;
+                self.generate_atom(atomic_value, expr.span)?;
+            }
+            ExprKind::Binary { lhs, op, rhs }
+                if matches!(op.kind, BinaryOperator::And | BinaryOperator::Or) =>
+            {
+                self.generate_short_circuit(lhs, op.kind, rhs)?;
             }
             ExprKind::Binary { lhs, op, rhs } => {
                 self.generate(lhs)?;
@@ -45,28 +192,155 @@ impl BytecodeFrom<Expr> for BytecodeGenerator {
                 let jp_patch = self.emit_patch(Opcode::Jp(0));
                 self.patch(&jif_patch);
 
-                if let Some(else_expr) = else_expr {
-                    self.generate(else_expr)?;
+                // Without else the expression still evaluates to something when the condition fails
+                match else_expr {
+                    Some(else_expr) => self.generate(else_expr)?,
+                    None => {
+                        self.write_opcode(Opcode::Null);
+                    }
                 }
 
                 self.patch(&jp_patch);
             }
             ExprKind::While { condition, body } => {
-                self.enter_scope(ScopeType::Block);
-                let start = self.curr_index();
+                let start = self.next_index();
+                self.enter_loop(start);
                 self.generate(condition)?;
 
                 let jif = self.emit_patch(Opcode::Jif(0));
                 self.generate(body)?;
+                self.write_opcode(Opcode::Pop(1));
+
+                self.jump_to(start);
+                self.patch(&jif);
+                // A loop that runs out evaluates to null, breaks jump over it with their own value
+                self.write_opcode(Opcode::Null);
+                self.leave_scope();
+            }
+            ExprKind::Loop { body } => {
+                let start = self.next_index();
+                self.enter_loop(start);
+                self.generate(body)?;
+                self.write_opcode(Opcode::Pop(1));
+                // Breaks land right after the jump, with their value on the stack
+                self.jump_to(start);
+                self.leave_scope();
+            }
+            ExprKind::For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                // Variables of the initializer live until the end of the loop
+                self.enter_scope(ScopeType::Block);
+                if let Some(init) = init {
+                    self.generate(init)?;
+                }
+
+                // The step sits before the condition, the first iteration jumps over it
+                let step_start = self.next_index();
+                if let Some(step) = step {
+                    let skip_step = self.emit_patch(Opcode::Jp(0));
+                    self.generate(step)?;
+                    self.write_opcode(Opcode::Pop(1));
+                    self.patch(&skip_step);
+                }
+                let condition_start = self.next_index();
+                let continue_target = if step_start == condition_start {
+                    condition_start
+                } else {
+                    step_start + 1
+                };
+
+                self.enter_loop(continue_target);
+                match condition {
+                    Some(condition) => self.generate(condition)?,
+                    None => {
+                        self.write_constant(Constant::Bool(true));
+                    }
+                }
+
+                let jif = self.emit_patch(Opcode::Jif(0));
+                self.generate(body)?;
+                self.write_opcode(Opcode::Pop(1));
 
-                let end = self.curr_index();
-                self.write_opcode(Opcode::Jp(-(end as isize - start as isize)));
+                self.jump_to(continue_target);
                 self.patch(&jif);
-                // TODO: implement breaking from while loops with a value
                 self.write_opcode(Opcode::Null);
                 self.leave_scope();
+
+                self.close_upvalues(self.state.first_slot());
+                self.write_opcode(Opcode::Block(self.state.declared()));
+                self.leave_scope();
+            }
+            ExprKind::ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                // The iterator sits below the locals of the loop until it's exhausted
+                self.enter_scope(ScopeType::Block);
+                self.generate(iterable)?;
+                self.write_opcode(Opcode::Iter);
+                self.state.declare_var(hidden_name("for", "iterator"));
+
+                let start = self.next_index();
+                self.enter_loop(start);
+                let next = self.emit_patch(Opcode::IterNext(0));
+                self.state.declare_var(item.kind);
+                self.generate(body)?;
+                // Every iteration gets its own item, closures capturing it keep the one they saw
+                self.close_upvalues(self.state.first_slot());
+                self.write_opcode(Opcode::Pop(2));
+
+                self.jump_to(start);
+                self.patch(&next);
+                self.write_opcode(Opcode::Null);
+                self.leave_scope();
+
+                self.close_upvalues(self.state.first_slot());
+                self.write_opcode(Opcode::Block(self.state.declared()));
+                self.leave_scope();
+            }
+            ExprKind::Match { value, arms } => {
+                if let Some(keys) = jump_table::table_keys(&arms) {
+                    return self.generate_jump_table(value, arms, keys);
+                }
+
+                // The value stays on the stack until an arm is taken, each pattern compares a copy of it
+                self.generate(value)?;
+
+                let mut end_patches = vec![];
+                for arm in arms {
+                    let next_arm = match arm.pattern {
+                        Pattern::Wildcard => None,
+                        Pattern::Value(pattern) => {
+                            self.write_opcode(Opcode::Dup);
+                            self.generate(pattern)?;
+                            self.write_opcode(Opcode::Eq);
+                            Some(self.emit_patch(Opcode::Jif(0)))
+                        }
+                    };
+
+                    self.write_opcode(Opcode::Pop(1));
+                    self.generate(arm.body)?;
+                    end_patches.push(self.emit_patch(Opcode::Jp(0)));
+                    if let Some(next_arm) = next_arm {
+                        self.patch(&next_arm);
+                    }
+                }
+
+                // None of the arms matched
+                self.write_opcode(Opcode::Pop(1));
+                self.write_opcode(Opcode::Null);
+                for end_patch in &end_patches {
+                    self.patch(end_patch);
+                }
             }
             ExprKind::Block { stmts, return_expr } => {
+                self.enter_scope(ScopeType::Block);
+                let stmts = self.used_locals(stmts, return_expr.as_ref());
                 self.generate(stmts)?;
 
                 if let Some(return_expr) = return_expr {
@@ -75,7 +349,9 @@ impl BytecodeFrom<Expr> for BytecodeGenerator {
                     self.write_opcode(Opcode::Null);
                 }
 
+                self.close_upvalues(self.state.first_slot());
                 self.write_opcode(Opcode::Block(self.state.declared()));
+                self.leave_scope();
             }
             ExprKind::Break { return_expr } => {
                 if let Some(return_expr) = return_expr {
@@ -83,17 +359,44 @@ impl BytecodeFrom<Expr> for BytecodeGenerator {
                 } else {
                     self.write_opcode(Opcode::Null);
                 }
-                self.emit_patch(Opcode::Break(0));
+                let declared = self.loop_declared(&expr.span)?;
+                self.close_loop_upvalues(declared);
+                self.write_opcode(Opcode::Block(declared));
+                let index = self.write_opcode(Opcode::Break(0));
+                if let Some(scope) = self.state.loop_scope_mut() {
+                    scope.patches.insert(Patch { index });
+                }
             }
             ExprKind::Continue => {
-                let ending_index = self.curr_index();
-                let starting_index = self.state.current_scope().starting_index;
-                self.write_opcode(Opcode::Jp(starting_index as isize - ending_index as isize));
+                let declared = self.loop_declared(&expr.span)?;
+                self.close_loop_upvalues(declared);
+                if declared > 0 {
+                    self.write_opcode(Opcode::Pop(declared));
+                }
+                let target = self
+                    .state
+                    .loop_scope_mut()
+                    .map_or(0, |scope| scope.starting_index);
+                self.jump_to(target);
             }
-            ExprKind::Call { callee, args } => {
+            ExprKind::Call {
+                callee,
+                args,
+                named_args,
+            } => {
+                if has_spread(&args) {
+                    let groups = self.generate_groups(args)?;
+                    self.generate(callee)?;
+                    self.write_opcode(Opcode::CallSpread(groups));
+                    return Ok(());
+                }
+
+                let args = self.order_args(&callee, args, named_args, &expr.span)?;
+                let args_count = args.len();
+                self.check_arity(&callee, args_count, &expr.span)?;
                 self.generate(args)?;
                 self.generate(callee)?;
-                self.write_opcode(Opcode::Call);
+                self.write_opcode(Opcode::Call(args_count));
             }
             ExprKind::Return { value } => {
                 if let Some(value) = value {
@@ -103,15 +406,54 @@ impl BytecodeFrom<Expr> for BytecodeGenerator {
                 }
                 self.write_opcode(Opcode::Return);
             }
-            ExprKind::Array { values } => {}
-            ExprKind::Index { target, position } => {}
+            ExprKind::Array { values } if has_spread(&values) => {
+                let groups = self.generate_groups(values)?;
+                self.write_opcode(Opcode::CreateSpreadArray(groups));
+            }
+            // Only valid as one of the groups of an array or arguments
+            ExprKind::Spread { value } => {
+                self.generate(value)?;
+                self.write_opcode(Opcode::Spread);
+            }
+            ExprKind::Array { values } => {
+                let amount = values.len();
+                self.generate(values)?;
+                self.write_opcode(Opcode::CreateArray(amount));
+            }
+            ExprKind::Interpolation { parts } => {
+                let amount = parts.len();
+                self.generate(parts)?;
+                self.write_opcode(Opcode::Concat(amount));
+            }
+            ExprKind::Index { target, position } => {
+                self.generate(target)?;
+                self.generate(position)?;
+                self.write_opcode(Opcode::Index);
+            }
+            ExprKind::Slice {
+                target,
+                start,
+                end,
+                inclusive,
+            } => {
+                self.generate(target)?;
+                for bound in [start, end] {
+                    match bound {
+                        Some(bound) => self.generate(bound)?,
+                        None => {
+                            self.write_opcode(Opcode::Null);
+                        }
+                    }
+                }
+                self.write_opcode(Opcode::Slice { inclusive });
+            }
             ExprKind::GetProperty {
                 target,
                 identifier,
                 is_method_call,
             } => {
                 self.generate(target)?;
-                self.write_constant(Constant::String(identifier.kind.clone()));
+                self.write_constant(Constant::String(identifier.kind.to_string()));
                 self.write_opcode(Opcode::GetProperty {
                     bind_method: is_method_call,
                 });
@@ -122,26 +464,52 @@ impl BytecodeFrom<Expr> for BytecodeGenerator {
                 value,
             } => {
                 self.generate(target)?;
-                self.write_constant(Constant::String(identifier.kind.clone()));
+                self.write_constant(Constant::String(identifier.kind.to_string()));
                 self.generate(value)?;
 
                 self.write_opcode(Opcode::SetProperty(1));
             }
             ExprKind::Assignment { target, value } => {
+                if let ExprKind::Index { target, position } = *target.kind {
+                    self.generate(target)?;
+                    self.generate(position)?;
+                    self.generate(value)?;
+                    self.write_opcode(Opcode::SetIndex);
+                    return Ok(());
+                }
+
+                if let Some(name) = self.global_target(&target) {
+                    self.generate(value)?;
+                    self.write_global(Opcode::SetGlobal, name);
+                    return Ok(());
+                }
+
                 // TODO: If no additional logical will be added to it then it can just as well become a simple binary expression
                 self.generate(target)?;
                 self.generate(value)?;
                 self.write_opcode(Opcode::Asg);
             }
-            ExprKind::Closure { params, body } => {}
+            ExprKind::Closure { params, body } => {
+                let new_fn = self.compile_function(Symbol::intern(LAMBDA_NAME), params, body)?;
+                let fn_ptr = self.add_global(new_fn.into());
+                self.create_closure(fn_ptr);
+            }
             ExprKind::ObjectLiteral { properties } => {
                 let amount = properties.len();
                 for (key, value) in properties {
                     self.generate(value)?;
-                    self.write_constant(Constant::String(key));
+                    self.write_constant(Constant::String(key.to_string()));
                 }
                 self.write_opcode(Opcode::CreateObject(amount));
             }
+            ExprKind::Map { entries } => {
+                let amount = entries.len();
+                for (key, value) in entries {
+                    self.generate(value)?;
+                    self.write_constant(Constant::String(key.kind));
+                }
+                self.write_opcode(Opcode::CreateMap(amount));
+            }
         };
         Ok(())
     }
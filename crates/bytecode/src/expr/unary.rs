@@ -8,6 +8,7 @@ impl From<UnaryOperator> for Opcode {
         match data {
             Not => Opcode::Not,
             Negate => Opcode::Neg,
+            BitNot => Opcode::BitNot,
         }
     }
 }
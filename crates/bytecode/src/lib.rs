@@ -2,18 +2,26 @@ use std::fmt::Display;
 
 use callables::Function;
 use chunk::{Chunk, Constant, ConstantIndex};
-use common::{BuiltInFunction, ProgramText, MAIN_FUNCTION_NAME};
-use parser::parse::{Ast, Program};
+use common::{BuiltInFunction, ProgramText, Symbol, MAIN_FUNCTION_NAME};
+pub use disassemble::disassemble;
+pub use error::{GenerationError, GenerationErrorCause};
+pub use verify::{verify, VerificationError, VerificationErrorCause};
+use parser::parse::{expr::Expr, stmt::Stmt, Ast, Program, Span};
 use state::{GeneratorState, ScopeType};
 use stmt::{GlobalItem, GlobalPointer};
-#[macro_use]
-extern crate prettytable;
 
+pub mod binary;
 pub mod callables;
 pub mod chunk;
+mod disassemble;
+pub mod error;
 pub(crate) mod expr;
+pub mod modules;
+pub(crate) mod peephole;
 pub(crate) mod state;
 pub mod stmt;
+pub(crate) mod unused;
+mod verify;
 
 #[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
 pub struct Patch {
@@ -21,6 +29,7 @@ pub struct Patch {
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MemoryAddress {
     // Local variables, e.g defined inside block or a function.
     // This value is added to the function's stack offset.
@@ -30,6 +39,8 @@ pub enum MemoryAddress {
     // defined by callstack n (second value) jumps above.
     Upvalue { index: usize, is_ref: bool },
     BuiltInFunction(BuiltInFunction),
+    // Index of a function registered by the application embedding the VM
+    HostFunction(usize),
 }
 
 impl Display for MemoryAddress {
@@ -38,6 +49,7 @@ impl Display for MemoryAddress {
             Self::Local(address) => format!("local_address::{}", address),
             Self::Upvalue { index, .. } => format!("upvalue::{}", index),
             Self::BuiltInFunction(function) => format!("built::in::function"),
+            Self::HostFunction(index) => format!("host::function::{}", index),
         };
         write!(f, "{}", str)?;
 
@@ -53,7 +65,7 @@ impl From<MemoryAddress> for Constant {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Variable {
-    pub name: ProgramText,
+    pub name: Symbol,
     pub depth: usize,
     // Calculated index on the stack
     pub index: usize,
@@ -66,7 +78,7 @@ pub struct Upvalue {
     is_local: bool,
     local_index: usize,
     is_ref: bool,
-    name: ProgramText,
+    name: Symbol,
 }
 
 // Each opcode is described with e.g (Address, Number) which means that
@@ -108,22 +120,59 @@ pub enum Opcode {
     Or,
     // and (Bool, Bool)
     And,
+    // .. (Number, Number)
+    Range,
+    // ..= (Number, Number)
+    RangeInclusive,
+    // & (Number, Number), on the integer parts of the numbers like the rest of bitwise operators
+    BitAnd,
+    // | (Number, Number)
+    BitOr,
+    // ^ (Number, Number)
+    BitXor,
+    // ~ (Number)
+    BitNot,
+    // << (Number, Number)
+    Shl,
+    // >> (Number, Number), keeps the sign
+    Shr,
     // jump if false
     Jif(isize),
     // jump (both forwards or backwards)
     Jp(isize),
+    // Pop the value and jump to the arm of the table in the constant at the index that matches it,
+    // or to the default one. Only numbers and strings can match an arm (Any)
+    JumpTable(ConstantIndex),
+    // Comparisons fused with the Jif that follows them by the peephole pass, jump if the
+    // comparison fails and skip the Jif otherwise (Any, Any). The Jif is kept for when
+    // an operator method of the left operand is called instead.
+    JifEq(isize),
+    JifNe(isize),
+    JifLt(isize),
+    JifLe(isize),
+    JifGt(isize),
+    JifGe(isize),
     // pop n values from stack
     Pop(usize),
+    // Push a copy of the value on top of the stack (Any)
+    Dup,
     // Get (Address)
     Get,
+    // Top-level variables are kept by the VM under the name in the constant at the index.
+    // Define pops the value (Any), Set assigns the value on top of the stack and keeps it (Any).
+    DefineGlobal(ConstantIndex),
+    GetGlobal(ConstantIndex),
+    SetGlobal(ConstantIndex),
     // Get object property (n * String)
     GetProperty { bind_method: bool },
     // Set object property (Address, n * String, Value)
     SetProperty(usize),
     // Assign (Address, Any)
     Asg,
-    // Call function or method, (Callable)
-    Call,
+    // Call function or method with the given amount of arguments, (Callable)
+    Call(usize),
+    // Call with the given number of argument groups, each followed by its size (Callable)
+    CallSpread(usize),
     // Return (Any)
     Return,
     Block(usize),
@@ -131,8 +180,30 @@ pub enum Opcode {
     Null,
     // number of upvalue addresses to pop
     CreateClosure(usize),
+    // Move the variables captured from the slot onwards off the stack, the frame is about to drop them
+    CloseUpvalue(usize),
     // number of object properties to evaluate
     CreateObject(usize),
+    // Get element at the index (Indexable, Number)
+    Index,
+    // Set element at the index (Indexable, Number, Any)
+    SetIndex,
+    // Copy of the elements between the bounds, which are null when missing (Array | String, Number, Number)
+    Slice { inclusive: bool },
+    // number of array elements to pop
+    CreateArray(usize),
+    // number of array element groups, each followed by its size
+    CreateSpreadArray(usize),
+    // Replace the array with its elements followed by their count (Array)
+    Spread,
+    // number of map entries to evaluate
+    CreateMap(usize),
+    // Replace the iterable with an iterator over it (Iterable)
+    Iter,
+    // Push the next value of the iterator below, jump when there are none left (Iterator)
+    IterNext(isize),
+    // number of interpolated string parts to join
+    Concat(usize),
 }
 
 impl Display for Opcode {
@@ -155,23 +226,54 @@ impl Display for Opcode {
             Ge => "GE",
             Or => "OR",
             And => "AND",
+            Range => "RANGE",
+            RangeInclusive => "RANGE_INCLUSIVE",
+            BitAnd => "BIT_AND",
+            BitOr => "BIT_OR",
+            BitXor => "BIT_XOR",
+            BitNot => "BIT_NOT",
+            Shl => "SHL",
+            Shr => "SHR",
             Get => "GET",
             Asg => "ASG",
-            Call => "CALL",
             Return => "RET",
             Null => "NULL",
+            Index => "INDEX",
+            SetIndex => "SET_INDEX",
+            Iter => "ITER",
+            Spread => "SPREAD",
+            Dup => "DUP",
             rest => {
                 let str = match rest {
                     Constant(index) => format!("CONSTANT_{}", index),
                     Jif(distance) => format!("JIF_{}", distance),
                     Jp(distance) => format!("JP_{}", distance),
+                    JifEq(distance) => format!("JIF_EQ_{}", distance),
+                    JifNe(distance) => format!("JIF_NE_{}", distance),
+                    JifLt(distance) => format!("JIF_LT_{}", distance),
+                    JifLe(distance) => format!("JIF_LE_{}", distance),
+                    JifGt(distance) => format!("JIF_GT_{}", distance),
+                    JifGe(distance) => format!("JIF_GE_{}", distance),
                     Pop(amount) => format!("POP_{}", amount),
                     Block(amount) => format!("BLC_{}", amount),
                     Break(distance) => format!("BRK_{}", distance),
                     CreateClosure(amount) => format!("CLOSURE_{}", amount),
+                    CloseUpvalue(slot) => format!("CLOSE_UPVALUE_{}", slot),
+                    JumpTable(index) => format!("JUMP_TABLE_{}", index),
+                    DefineGlobal(index) => format!("DEFINE_GLOBAL_{}", index),
+                    GetGlobal(index) => format!("GET_GLOBAL_{}", index),
+                    SetGlobal(index) => format!("SET_GLOBAL_{}", index),
+                    Call(args_count) => format!("CALL_{}", args_count),
+                    CallSpread(groups) => format!("CALL_SPREAD_{}", groups),
                     GetProperty { bind_method } => format!("GET_PROPERTY_BIND_{}", bind_method),
                     SetProperty(amount) => format!("SET_PROPERTY_{}", amount),
                     CreateObject(amount) => format!("CREATE_OBJECT_{}", amount),
+                    CreateArray(amount) => format!("CREATE_ARRAY_{}", amount),
+                    CreateSpreadArray(groups) => format!("CREATE_SPREAD_ARRAY_{}", groups),
+                    CreateMap(amount) => format!("CREATE_MAP_{}", amount),
+                    Slice { inclusive } => format!("SLICE_INCLUSIVE_{}", inclusive),
+                    IterNext(distance) => format!("ITER_NEXT_{}", distance),
+                    Concat(amount) => format!("CONCAT_{}", amount),
                     _ => unreachable!(),
                 };
                 write!(f, "{}", str)?;
@@ -191,20 +293,115 @@ impl Opcode {
             Opcode::Jif(_) => Opcode::Jif(value),
             Opcode::Jp(_) => Opcode::Jp(value),
             Opcode::Break(_) => Opcode::Break(value),
+            Opcode::IterNext(_) => Opcode::IterNext(value),
+            Opcode::JifEq(_) => Opcode::JifEq(value),
+            Opcode::JifNe(_) => Opcode::JifNe(value),
+            Opcode::JifLt(_) => Opcode::JifLt(value),
+            Opcode::JifLe(_) => Opcode::JifLe(value),
+            Opcode::JifGt(_) => Opcode::JifGt(value),
+            Opcode::JifGe(_) => Opcode::JifGe(value),
             _ => unreachable!("Tried to patch invalid opcode"),
         }
     }
+
+    // Jump that runs the comparison, if there's one for the opcode
+    pub fn fuse_comparison(self) -> Option<Self> {
+        Some(match self {
+            Opcode::Eq => Opcode::JifEq(0),
+            Opcode::Ne => Opcode::JifNe(0),
+            Opcode::Lt => Opcode::JifLt(0),
+            Opcode::Le => Opcode::JifLe(0),
+            Opcode::Gt => Opcode::JifGt(0),
+            Opcode::Ge => Opcode::JifGe(0),
+            _ => return None,
+        })
+    }
+
+    // Comparison run by the fused jump
+    pub fn fused_comparison(self) -> Option<Self> {
+        Some(match self {
+            Opcode::JifEq(_) => Opcode::Eq,
+            Opcode::JifNe(_) => Opcode::Ne,
+            Opcode::JifLt(_) => Opcode::Lt,
+            Opcode::JifLe(_) => Opcode::Le,
+            Opcode::JifGt(_) => Opcode::Gt,
+            Opcode::JifGe(_) => Opcode::Ge,
+            _ => return None,
+        })
+    }
 }
 
-pub type BytecodeGenerationResult = Result<(), ()>;
+pub type BytecodeGenerationResult = Result<(), GenerationError>;
+#[derive(Debug, Clone, PartialEq)]
 pub struct ProgramBytecode {
     pub global_fn_ptr: GlobalPointer,
     pub globals: Vec<GlobalItem>,
 }
-pub type GenerationResult = Result<ProgramBytecode, ()>;
+pub type GenerationResult = Result<ProgramBytecode, GenerationError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    // Opcodes are kept the way they were emitted
+    None,
+    // Wasteful opcode sequences are rewritten once the code is generated
+    Basic,
+    // Locals that are never read are left out together with their values, when computing them
+    // has no effects
+    Full,
+}
+
+// Passes the generator runs while it emits the code and what it records about it
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    // Operations on literals are computed during generation, `2 * 3 + 1` becomes `7`.
+    // Can be turned off to get the bytecode of every operator written in the code.
+    pub fold_constants: bool,
+    pub opt_level: OptLevel,
+    // Chunks map their opcodes to the code they were generated from, which runtime errors and
+    // traces point at. Without it the chunks are smaller, but nothing can be located.
+    pub debug_info: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            fold_constants: true,
+            opt_level: OptLevel::Basic,
+            debug_info: true,
+        }
+    }
+}
 
 pub fn generate_bytecode(program: Program) -> GenerationResult {
+    generate_bytecode_with_options(program, Options::default())
+}
+
+pub fn generate_bytecode_with_options(program: Program, options: Options) -> GenerationResult {
     let mut generator = BytecodeGenerator::new();
+    generator.options = options;
+    generator.generate(program)?;
+    Ok(generator.code())
+}
+
+// Same as generate_bytecode, but calls to the given names refer to the functions
+// registered in the VM with VM::register_host_function, in the same order
+pub fn generate_bytecode_with_host_functions(
+    program: Program,
+    host_functions: &[ProgramText],
+) -> GenerationResult {
+    generate_bytecode_with_globals(program, host_functions, &[])
+}
+
+// The program can also use the top-level variables defined by the programs the VM ran before,
+// e.g. the previous lines of a REPL
+pub fn generate_bytecode_with_globals(
+    program: Program,
+    host_functions: &[ProgramText],
+    globals: &[ProgramText],
+) -> GenerationResult {
+    let mut generator = BytecodeGenerator::new();
+    generator.state.host_functions = host_functions.to_vec();
+    generator.state.globals = globals.iter().map(|name| Symbol::intern(name)).collect();
     generator.generate(program)?;
     Ok(generator.code())
 }
@@ -214,6 +411,9 @@ struct BytecodeGenerator {
     state: GeneratorState,
     functions: Vec<Function>,
     globals: Vec<GlobalItem>,
+    options: Options,
+    // Node the written opcodes are generated from
+    span: Span,
 }
 
 impl BytecodeGenerator {
@@ -221,11 +421,40 @@ impl BytecodeGenerator {
         Self {
             state: GeneratorState::new(),
             functions: vec![Function {
-                name: MAIN_FUNCTION_NAME.to_owned(),
+                name: Symbol::intern(MAIN_FUNCTION_NAME),
                 arity: 0,
+                variadic: false,
                 chunk: Chunk::default(),
+                max_stack: 0,
             }],
             globals: vec![],
+            options: Options::default(),
+            span: 0..0,
+        }
+    }
+
+    // Opcodes written by `generate` point at the span, the ones after it at the node around it
+    pub(crate) fn with_span<T>(&mut self, span: Span, generate: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.span, span.clone());
+        self.mark_span(span);
+        let result = generate(self);
+        self.span = outer.clone();
+        self.mark_span(outer);
+        result
+    }
+
+    fn mark_span(&mut self, span: Span) {
+        if self.options.debug_info {
+            self.current_chunk().set_span(span);
+        }
+    }
+
+    // Statements of a block or a function body that are worth generating
+    pub(crate) fn used_locals(&self, stmts: Vec<Stmt>, return_expr: Option<&Expr>) -> Vec<Stmt> {
+        if self.options.opt_level >= OptLevel::Full {
+            unused::remove_unused_locals(stmts, return_expr)
+        } else {
+            stmts
         }
     }
 
@@ -241,17 +470,39 @@ impl BytecodeGenerator {
         self.current_chunk().write_constant(constant)
     }
 
+    // Global opcodes find the variable by its name, which is kept among the constants
+    pub(crate) fn write_global(&mut self, opcode: fn(ConstantIndex) -> Opcode, name: Symbol) {
+        let index = self
+            .current_chunk()
+            .add_constant(Constant::String(name.to_string()));
+        self.write_opcode(opcode(index));
+    }
+
     pub fn code(mut self) -> ProgramBytecode {
         if self.functions.len() > 1 {
             panic!("Tried to own the code before generation finished!");
         }
 
+        // The program evaluates to the last value its statements left on the stack
+        if self.state.declared() == 0 {
+            self.write_opcode(Opcode::Null);
+        }
+
         let global_function = self
             .functions
             .pop()
             .expect("Generator is in invalid state!");
 
         let global_fn_ptr = self.declare_global(global_function.into());
+        if self.options.opt_level >= OptLevel::Basic {
+            for GlobalItem::Function(function) in self.globals.iter_mut() {
+                peephole::optimize(&mut function.chunk);
+            }
+        }
+        for (pointer, GlobalItem::Function(function)) in self.globals.iter_mut().enumerate() {
+            function.max_stack = verify::max_stack(function, pointer == global_fn_ptr)
+                .expect("Generated code should be valid");
+        }
 
         ProgramBytecode {
             globals: self.globals,
@@ -268,6 +519,17 @@ impl BytecodeGenerator {
         }
     }
 
+    // Index the next written opcode will have
+    pub fn next_index(&mut self) -> usize {
+        self.current_chunk().opcodes_len()
+    }
+
+    // Jumps back to an opcode written before, e.g. the start of a loop
+    pub fn jump_to(&mut self, target: usize) {
+        let patch = self.emit_patch(Opcode::Jp(0));
+        self.patch_to(&patch, target);
+    }
+
     pub fn emit_patch(&mut self, opcode: Opcode) -> Patch {
         let index = self.write_opcode(opcode);
         let patch = Patch { index };
@@ -275,24 +537,40 @@ impl BytecodeGenerator {
         patch
     }
 
+    // Points the jump at the opcode that will be written next
     pub fn patch(&mut self, patch: &Patch) {
+        let target = self.next_index();
+        self.patch_to(patch, target);
+    }
+
+    // Points the jump at the target, which can be before the jump as well as after it.
+    // Jumps land one opcode after `index + distance`, because the pointer moves after every opcode.
+    pub fn patch_to(&mut self, patch: &Patch, target: usize) {
         self.state.remove_patch(patch);
-        let current_index = self.curr_index();
+        let len = self.current_chunk().opcodes_len();
+        assert!(
+            target <= len,
+            "Patch tried to jump to {} past the end of the chunk at {}",
+            target,
+            len
+        );
+        let distance = target as isize - patch.index as isize - 1;
         let opcode = self
             .current_chunk()
             .opcodes
             .get_mut(patch.index)
             .expect("Patch tried to access wrong opcode.");
-        let patched_opcode = opcode.patch((current_index - patch.index) as isize);
-
-        let _ = std::mem::replace(opcode, patched_opcode);
+        *opcode = opcode.patch(distance);
     }
 
-    pub fn new_function(&mut self, name: ProgramText, arity: usize) {
+    pub fn new_function(&mut self, name: Symbol, arity: usize, variadic: bool) {
         let new_fn = Function {
             arity,
+            variadic,
             name,
             chunk: Chunk::default(),
+            // Measured once the code is generated
+            max_stack: 0,
         };
 
         self.enter_scope(ScopeType::Function);
@@ -304,6 +582,18 @@ impl BytecodeGenerator {
         self.state.enter_scope(scope_type, starting_index);
     }
 
+    // `continue` jumps back to `continue_target`, `break` past the end of the loop
+    pub fn enter_loop(&mut self, continue_target: usize) {
+        self.state.enter_scope(ScopeType::Loop, continue_target);
+    }
+
+    // Closures keep the captured variables from the slot onwards after they leave the stack
+    pub(crate) fn close_upvalues(&mut self, slot: usize) {
+        if self.state.captured_from(slot) {
+            self.write_opcode(Opcode::CloseUpvalue(slot));
+        }
+    }
+
     pub fn leave_scope(&mut self) {
         let scope = self.state.leave_scope();
         for patch in scope.patches {
@@ -367,7 +657,12 @@ pub(crate) mod test {
 
     pub(crate) fn declare_var(name: String, expr: Expr) -> Stmt {
         Node {
-            kind: Box::new(StmtKind::VariableDeclaration { name, expr }),
+            kind: Box::new(StmtKind::VariableDeclaration {
+                name,
+                annotation: None,
+                expr,
+                doc: None,
+            }),
             span: 0..0,
         }
     }
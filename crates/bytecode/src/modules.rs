@@ -0,0 +1,161 @@
+use common::{ProgramText, Symbol};
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind},
+    stmt::{exports, imports, Stmt, StmtKind},
+    Node, Program,
+};
+
+use crate::{
+    chunk::Constant, state::hidden_name, BytecodeFrom, BytecodeGenerationResult, BytecodeGenerator,
+    GenerationError, GenerationErrorCause, GenerationResult, Opcode,
+};
+
+// File compiled together with the program, named by the path the imports refer to it with
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub path: ProgramText,
+    pub program: Program,
+}
+
+fn module_function(path: &str) -> Symbol {
+    hidden_name("module", path)
+}
+
+fn module_exports(path: &str) -> Symbol {
+    hidden_name("exports", path)
+}
+
+// Nodes of the generated code don't point into any source
+fn node<T>(kind: T) -> Node<T> {
+    Node { kind, span: 0..0 }
+}
+
+fn box_node<T>(kind: T) -> Node<Box<T>> {
+    node(Box::new(kind))
+}
+
+fn identifier(name: Symbol) -> Expr {
+    box_node(ExprKind::Atom(AtomicValue::Identifier {
+        name,
+        is_assignment: false,
+    }))
+}
+
+// Compiles every module into a function of the main program, which calls it once before the
+// program starts and keeps the map of its exports in a hidden variable. Imports read the
+// exported names from that map. The modules can be given in any order, each one is initialized
+// after the ones it imports and otherwise in the order they were given.
+pub fn link_modules(
+    modules: Vec<Module>,
+    program: Program,
+    host_functions: &[ProgramText],
+) -> GenerationResult {
+    let mut generator = BytecodeGenerator::new();
+    generator.state.host_functions = host_functions.to_vec();
+    for module in order_modules(modules)? {
+        generator.generate_module(module)?;
+    }
+    generator.generate(program)?;
+    Ok(generator.code())
+}
+
+// Modules in the order they're initialized in
+struct Ordering {
+    paths: Vec<ProgramText>,
+    // None once the module is placed or being placed
+    modules: Vec<Option<Module>>,
+    // Indexes of the modules whose imports are being placed, to find the cycles
+    placing: Vec<usize>,
+    ordered: Vec<Module>,
+}
+
+impl Ordering {
+    // The modules the one at the index imports are placed before it. Imports of paths that
+    // aren't linked are left for the generator to report.
+    fn place(&mut self, index: usize) -> BytecodeGenerationResult {
+        let module = match self.modules[index].take() {
+            Some(module) => module,
+            None => return Ok(()),
+        };
+        self.placing.push(index);
+        for path in imports(&module.program) {
+            let imported = match self.paths.iter().position(|linked| *linked == path.kind) {
+                Some(imported) => imported,
+                None => continue,
+            };
+            if self.placing.contains(&imported) {
+                let cause = GenerationErrorCause::ImportCycle(path.kind.clone());
+                return Err(GenerationError::new(cause, path.span.clone()));
+            }
+            self.place(imported)?;
+        }
+        self.placing.pop();
+        self.ordered.push(module);
+        Ok(())
+    }
+}
+
+fn order_modules(modules: Vec<Module>) -> Result<Vec<Module>, GenerationError> {
+    let mut ordering = Ordering {
+        paths: modules.iter().map(|module| module.path.clone()).collect(),
+        placing: vec![],
+        ordered: Vec::with_capacity(modules.len()),
+        modules: modules.into_iter().map(Some).collect(),
+    };
+    for index in 0..ordering.modules.len() {
+        ordering.place(index)?;
+    }
+    Ok(ordering.ordered)
+}
+
+impl BytecodeGenerator {
+    fn generate_module(&mut self, module: Module) -> BytecodeGenerationResult {
+        let Module { path, program } = module;
+        let names = exports(&program);
+        let entries = names
+            .iter()
+            .map(|name| (node(name.to_string()), identifier(*name)))
+            .collect();
+        let body = box_node(ExprKind::Block {
+            stmts: program,
+            return_expr: Some(box_node(ExprKind::Map { entries })),
+        });
+
+        let function = module_function(&path);
+        let declaration: Stmt = box_node(StmtKind::FunctionDeclaration {
+            name: function,
+            params: node(vec![].into()),
+            returns: None,
+            body,
+            doc: None,
+        });
+        self.generate(declaration)?;
+        let call: Stmt = box_node(StmtKind::VariableDeclaration {
+            name: module_exports(&path),
+            annotation: None,
+            expr: box_node(ExprKind::Call {
+                callee: identifier(function),
+                args: vec![],
+                named_args: vec![],
+            }),
+            doc: None,
+        });
+        self.generate(call)?;
+        self.state.modules.insert(path, names);
+        Ok(())
+    }
+
+    pub(crate) fn generate_import(&mut self, path: Node<ProgramText>) -> BytecodeGenerationResult {
+        let names = self.state.modules.get(&path.kind).cloned().ok_or_else(|| {
+            let cause = GenerationErrorCause::UnknownModule(path.kind.clone());
+            GenerationError::new(cause, path.span.clone())
+        })?;
+        for name in names {
+            self.generate(identifier(module_exports(&path.kind)))?;
+            self.write_constant(Constant::String(name.to_string()));
+            self.write_opcode(Opcode::Index);
+            self.define_variable(name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashSet, iter::once};
+
+use crate::{
+    chunk::{push_span, Chunk, Constant, JumpTable},
+    Opcode,
+};
+
+pub(crate) fn jump_distance(opcode: &Opcode) -> Option<isize> {
+    match opcode {
+        Opcode::Jif(distance)
+        | Opcode::Jp(distance)
+        | Opcode::Break(distance)
+        | Opcode::IterNext(distance)
+        | Opcode::JifEq(distance)
+        | Opcode::JifNe(distance)
+        | Opcode::JifLt(distance)
+        | Opcode::JifLe(distance)
+        | Opcode::JifGt(distance)
+        | Opcode::JifGe(distance) => Some(*distance),
+        _ => None,
+    }
+}
+
+// Jumps land one opcode after `index + distance`, the end of the chunk is a valid target too
+pub(crate) fn jump_target(index: usize, distance: isize) -> usize {
+    (index as isize + distance + 1) as usize
+}
+
+// Jump distances of the table the opcode reads, none when it's not a JumpTable
+fn table_distances<'c>(
+    constants: &'c mut [Constant],
+    opcode: &Opcode,
+) -> impl Iterator<Item = &'c mut isize> {
+    let table = match opcode {
+        Opcode::JumpTable(index) => match &mut constants[*index] {
+            Constant::JumpTable(table) => Some(table),
+            _ => None,
+        },
+        _ => None,
+    };
+    table
+        .into_iter()
+        .flat_map(|JumpTable { arms, default }| arms.values_mut().chain(once(default)))
+}
+
+// Opcodes that always leave a bool on the stack, so `Not` after them can't fail
+fn produces_bool(opcode: &Opcode) -> bool {
+    use Opcode::*;
+    matches!(opcode, Eq | Ne | Lt | Le | Gt | Ge | Not | And | Or)
+}
+
+// Rewrites wasteful opcode sequences until none are left
+pub(crate) fn optimize(chunk: &mut Chunk) {
+    while rewrite(chunk) {}
+}
+
+// Returns whether anything changed. The opcodes are matched against the chunk as it was before the
+// pass and sequences that something jumps into the middle of are left alone.
+fn rewrite(chunk: &mut Chunk) -> bool {
+    let opcodes = &chunk.opcodes;
+    let mut jumped_to: HashSet<usize> = opcodes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, opcode)| Some(jump_target(index, jump_distance(opcode)?)))
+        .collect();
+    for (index, opcode) in opcodes.iter().enumerate() {
+        let distances = table_distances(&mut chunk.constants, opcode);
+        jumped_to.extend(distances.map(|distance| jump_target(index, *distance)));
+    }
+
+    // None removes the opcode
+    let mut rewritten: Vec<Option<Opcode>> = opcodes.iter().copied().map(Some).collect();
+    // Targets of the jumps, as indexes of the opcodes before the pass
+    let mut targets: Vec<Option<usize>> = opcodes
+        .iter()
+        .enumerate()
+        .map(|(index, opcode)| Some(jump_target(index, jump_distance(opcode)?)))
+        .collect();
+    let mut changed = false;
+
+    let mut index = 0;
+    while index < opcodes.len() {
+        let pair = opcodes
+            .get(index + 1)
+            .filter(|_| !jumped_to.contains(&(index + 1)));
+        match (&opcodes[index], pair) {
+            // The comparison jumps to the target of the Jif itself, the Jif stays after it
+            (comparison, _) if comparison.fuse_comparison().is_some() => {
+                if let Some(Opcode::Jif(_)) = opcodes.get(index + 1) {
+                    rewritten[index] = comparison.fuse_comparison();
+                    targets[index] = targets[index + 1];
+                    changed = true;
+                }
+            }
+            // The condition is known, so the jump either always or never happens
+            (Opcode::Constant(constant), Some(Opcode::Jif(_))) => {
+                match chunk.constants[*constant] {
+                    Constant::Bool(true) => rewritten[index + 1] = None,
+                    Constant::Bool(false) => rewritten[index + 1] = Some(Opcode::Jp(0)),
+                    _ => {
+                        index += 1;
+                        continue;
+                    }
+                }
+                rewritten[index] = None;
+                changed = true;
+                index += 2;
+                continue;
+            }
+            // `!!x` gives back x only when it's a bool, the VM fails on anything else
+            (Opcode::Not, Some(Opcode::Not))
+                if index > 0
+                    && !jumped_to.contains(&index)
+                    && produces_bool(&opcodes[index - 1]) =>
+            {
+                rewritten[index] = None;
+                rewritten[index + 1] = None;
+                changed = true;
+                index += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(target) = targets[index] {
+            // Jumping to a jump is the same as jumping to where it goes, cycles are left as they are
+            let mut threaded = target;
+            let mut hops = 0;
+            while let Some(Opcode::Jp(distance)) = opcodes.get(threaded) {
+                if hops == opcodes.len() {
+                    threaded = target;
+                    break;
+                }
+                threaded = jump_target(threaded, *distance);
+                hops += 1;
+            }
+            if threaded != target {
+                targets[index] = Some(threaded);
+                changed = true;
+            }
+            // A jump to the next opcode does nothing
+            if matches!(opcodes[index], Opcode::Jp(_)) && threaded == index + 1 {
+                rewritten[index] = None;
+                changed = true;
+            }
+        }
+        index += 1;
+    }
+
+    if !changed {
+        return false;
+    }
+
+    // New index of every old one, removed opcodes map to the first opcode kept after them
+    let mut moved = Vec::with_capacity(opcodes.len() + 1);
+    let mut kept = 0;
+    for opcode in &rewritten {
+        moved.push(kept);
+        if opcode.is_some() {
+            kept += 1;
+        }
+    }
+    moved.push(kept);
+
+    // Tables keep jumping to the same arms
+    for (index, opcode) in opcodes.iter().enumerate() {
+        for distance in table_distances(&mut chunk.constants, opcode) {
+            let target = jump_target(index, *distance);
+            *distance = moved[target] as isize - moved[index] as isize - 1;
+        }
+    }
+
+    for entry in std::mem::take(&mut chunk.spans) {
+        push_span(&mut chunk.spans, moved[entry.start], entry.span);
+    }
+
+    chunk.opcodes = rewritten
+        .into_iter()
+        .zip(targets)
+        .enumerate()
+        .filter_map(|(index, (opcode, target))| {
+            let opcode = opcode?;
+            Some(match target {
+                // The Jif replaced with a jump keeps the target of the Jif
+                Some(target) if jump_distance(&opcode).is_some() => {
+                    opcode.patch(moved[target] as isize - moved[index] as isize - 1)
+                }
+                _ => opcode,
+            })
+        })
+        .collect();
+    true
+}
@@ -1,6 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
-use common::{find_std_function, ProgramText};
+use common::{find_std_function, ProgramText, Symbol};
 
 use crate::{MemoryAddress, Patch, Upvalue, Variable};
 
@@ -8,9 +11,18 @@ use crate::{MemoryAddress, Patch, Upvalue, Variable};
 pub enum ScopeType {
     Function,
     Block,
+    // Body of a loop, `break` and `continue` jump relative to it
+    Loop,
     Global,
 }
 
+impl ScopeType {
+    // Blocks and loops share the stack frame of the function around them
+    pub fn is_block(&self) -> bool {
+        matches!(self, ScopeType::Block | ScopeType::Loop)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Scope {
     pub scope_type: ScopeType,
@@ -19,6 +31,25 @@ pub struct Scope {
     pub patches: HashSet<Patch>,
     pub starting_index: usize,
     pub upvalues: Vec<Upvalue>,
+    // Signatures of the functions declared in this scope, used to check and order the arguments
+    pub signatures: HashMap<Symbol, Signature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Symbol>,
+    // The rest parameter takes any number of the arguments after the params
+    pub variadic: bool,
+}
+
+impl Signature {
+    pub fn accepts(&self, args_count: usize) -> bool {
+        if self.variadic {
+            args_count >= self.params.len()
+        } else {
+            args_count == self.params.len()
+        }
+    }
 }
 
 impl Scope {
@@ -30,43 +61,29 @@ impl Scope {
             returned: false,
             starting_index,
             upvalues: vec![],
+            signatures: HashMap::new(),
         }
     }
 
-    pub fn make_enclosed_upvalue(&mut self, upvalue_index: usize, name: ProgramText) -> Upvalue {
-        let upvalue = Upvalue {
-            upvalue_index,
-            is_local: false,
-            is_ref: true,
-            local_index: 0,
-            name,
-        };
-
-        self.upvalues.push(upvalue.clone());
-
-        upvalue
-    }
-
-    pub fn close_variable(&mut self, index: usize) -> Upvalue {
-        let var = self.variables.get_mut(index).unwrap();
-
-        let upvalues_len = self.upvalues.len();
-
-        let upvalue_index = if upvalues_len == 0 {
-            0
-        } else {
-            upvalues_len - 1
-        };
-
-        let upvalue = Upvalue {
-            local_index: var.index,
-            upvalue_index,
-            is_local: true,
-            is_ref: false,
-            name: var.name.clone(),
-        };
-
-        upvalue
+    // Position of the captured variable among the upvalues of the closures created in this scope,
+    // every variable is captured once so all references to it share the same value
+    pub fn capture(&mut self, is_local: bool, index: usize, name: Symbol) -> usize {
+        let (local_index, upvalue_index) = if is_local { (index, 0) } else { (0, index) };
+        let existing = self.upvalues.iter().position(|upvalue| {
+            upvalue.is_local == is_local
+                && upvalue.local_index == local_index
+                && upvalue.upvalue_index == upvalue_index
+        });
+        existing.unwrap_or_else(|| {
+            self.upvalues.push(Upvalue {
+                upvalue_index,
+                is_local,
+                local_index,
+                is_ref: false,
+                name,
+            });
+            self.upvalues.len() - 1
+        })
     }
 }
 
@@ -74,9 +91,21 @@ impl Scope {
 #[derive(Debug, Default, Clone)]
 pub struct GeneratorState {
     pub scopes: Vec<Scope>,
+    // Names of the host functions, in the order they are registered in the VM
+    pub host_functions: Vec<ProgramText>,
+    // Names exported by the modules linked so far, by their paths
+    pub modules: HashMap<ProgramText, Vec<Symbol>>,
+    // Top-level variables, they are kept by the VM instead of the stack
+    pub globals: Vec<Symbol>,
+}
+
+// Name of a variable the generator declares for its own bookkeeping, like the iterator of a
+// `for ... in` loop. Spaces can't appear in identifiers, so it never shadows one of the program.
+pub fn hidden_name(tag: &str, detail: impl Display) -> Symbol {
+    Symbol::intern(&format!("{} {}", tag, detail))
 }
 
-fn search_var(scope: &Scope, name: &str) -> Option<(Variable, usize)> {
+fn search_var(scope: &Scope, name: Symbol) -> Option<(Variable, usize)> {
     for (index, var) in scope.variables.iter().enumerate() {
         if var.name == name {
             return Some((var.clone(), index));
@@ -122,112 +151,164 @@ impl GeneratorState {
         // -1 because we don't count the local scope which is 0
         self.scopes
             .iter()
-            .filter(|s| s.scope_type != ScopeType::Block)
+            .filter(|s| !s.scope_type.is_block())
             .count()
             - 1
     }
 
-    pub fn declare_var(&mut self, name: ProgramText) {
+    // Returns the slot of the variable on the stack
+    pub fn declare_var(&mut self, name: Symbol) -> usize {
         let depth = self.depth();
         // If we are in closure or function then offset equals to 0, otherwise we need to calculate blocks
         // above the current scope, because they don't reset the stack counter to
         // the beginning of the stack frame.
-        let stack_offset: usize = if &self.current_scope().scope_type == &ScopeType::Function {
-            0
-        } else {
-            self.scopes
-                .iter()
-                .rev()
-                .skip(1)
-                .take_while(|s| [ScopeType::Block, ScopeType::Global].contains(&s.scope_type))
-                .map(|s| s.variables.len())
-                .sum()
-        };
-
+        let index = self.first_slot() + self.declared();
         let scope = self.current_scope_mut();
 
         scope.variables.push(Variable {
-            name: name.to_owned(),
+            name,
             depth,
-            index: stack_offset + scope.variables.len(),
+            index,
             upvalue_index: None,
-        })
+        });
+        index
     }
 
-    // This can't fail because it's either an upvalue or it's not defined and analyzer prevents the latter.
-    pub fn search_upvalue_var(&mut self, name: &str) -> Option<Upvalue> {
-        // We skip the first scope because it's the local scope
-        // that we already checked and didn't find the variable there so we assumed it's an upvalue
-        let scopes = self
-            .scopes
-            .iter_mut()
-            .rev()
-            .skip(1)
-            .filter(|scope| scope.scope_type != ScopeType::Block);
+    // Slot of the first variable of the current scope in the stack frame
+    pub fn first_slot(&self) -> usize {
+        self.frame_scopes().skip(1).map(|s| s.variables.len()).sum()
+    }
 
-        let mut scopes_to_close: Vec<&mut Scope> = vec![];
+    // Index of the upvalue the current function reads the variable through. The variable is captured
+    // by the function scope it lives in, and every function between it and the current one passes it on.
+    pub fn search_upvalue_var(&mut self, name: Symbol) -> Option<usize> {
+        let functions: Vec<usize> = (0..self.scopes.len())
+            .filter(|&index| !self.scopes[index].scope_type.is_block())
+            .collect();
+        let enclosing = functions.len().checked_sub(1)?;
+
+        let (owner, variable) = (0..enclosing).rev().find_map(|function| {
+            let end = functions[function + 1];
+            self.scopes[functions[function]..end]
+                .iter()
+                .rev()
+                .find_map(|scope| search_var(scope, name))
+                .map(|(variable, _)| (function, variable))
+        })?;
 
-        let mut upvalue = None;
+        let mut position = self.scopes[functions[owner]].capture(true, variable.index, name);
+        for &function in &functions[owner + 1..enclosing] {
+            position = self.scopes[function].capture(false, position, name);
+        }
+        Some(position)
+    }
+
+    // Scopes sharing the current stack frame, from the innermost one up to the function or global one
+    fn frame_scopes(&self) -> impl Iterator<Item = &Scope> {
+        let blocks = self
+            .scopes
+            .iter()
+            .rev()
+            .take_while(|s| s.scope_type.is_block())
+            .count();
+        self.scopes.iter().rev().take(blocks + 1)
+    }
 
-        for scope in scopes {
-            if let Some((_, index)) = search_var(scope, name) {
-                if let Some(existing_upvalue) = scope.upvalues.get(index) {
-                    upvalue = Some(existing_upvalue.clone());
-                } else {
-                    let new_upvalue = scope.close_variable(index);
+    pub fn declare_signature(&mut self, name: Symbol, signature: Signature) {
+        self.current_scope_mut().signatures.insert(name, signature);
+    }
 
-                    scope.upvalues.push(new_upvalue.clone());
-                    upvalue = Some(new_upvalue);
-                }
-                break;
-            }
+    // Signature of the function the name refers to, unless it's shadowed by a variable
+    pub fn find_signature(&self, name: Symbol) -> Option<&Signature> {
+        let global = self.globals.contains(&name);
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| {
+                search_var(scope, name).is_some() || global && scope.scope_type == ScopeType::Global
+            })
+            .and_then(|scope| scope.signatures.get(&name))
+    }
 
-            scopes_to_close.push(scope);
-        }
+    pub fn search_local_var(&self, name: Symbol) -> Option<Variable> {
+        self.frame_scopes()
+            .find_map(|scope| search_var(scope, name))
+            .map(|(var, _)| var)
+    }
 
-        if let Some(mut upvalue) = upvalue {
-            for scope in scopes_to_close.into_iter().rev() {
-                upvalue = scope.make_enclosed_upvalue(upvalue.upvalue_index, name.to_owned());
+    // Variables declared from the innermost scope up to the enclosing loop, `None` outside of loops
+    pub fn declared_in_loop(&self) -> Option<usize> {
+        let mut declared = 0;
+        for scope in self.frame_scopes() {
+            declared += scope.variables.len();
+            if scope.scope_type == ScopeType::Loop {
+                return Some(declared);
             }
-            return Some(upvalue);
-        } else {
-            return None;
         }
+        None
     }
 
-    pub fn search_local_var(&self, name: &str) -> Option<Variable> {
-        // there's always some scope
-        let current_scope = self.scopes.last().unwrap();
-        search_var(current_scope, name).map(|(var, _)| var)
+    pub fn loop_scope_mut(&mut self) -> Option<&mut Scope> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .take_while(|s| s.scope_type.is_block())
+            .find(|s| s.scope_type == ScopeType::Loop)
     }
 
-    pub fn find_var_address(&mut self, name: &str) -> Option<MemoryAddress> {
+    pub fn find_var_address(&mut self, name: Symbol) -> Option<MemoryAddress> {
         if let Some(local_variable) = self.search_local_var(name) {
             return Some(MemoryAddress::Local(local_variable.index));
         }
 
-        if let Some(built_in_function) = find_std_function(name) {
+        if let Some(built_in_function) = find_std_function(&name) {
             return Some(MemoryAddress::BuiltInFunction(built_in_function));
         }
 
-        if let Some(upvalue) = self.search_upvalue_var(name) {
-            let Upvalue {
-                upvalue_index,
-                is_ref,
-                ..
-            } = upvalue;
+        if let Some(index) = self.host_functions.iter().position(|host| *host == *name) {
+            return Some(MemoryAddress::HostFunction(index));
+        }
 
+        if let Some(index) = self.search_upvalue_var(name) {
             return Some(MemoryAddress::Upvalue {
-                index: upvalue_index,
-                is_ref,
+                index,
+                is_ref: false,
             });
         }
 
         return None;
     }
 
+    // Top-level declarations define globals, everything declared below them stays on the stack
+    pub fn is_global_scope(&self) -> bool {
+        self.current_scope().scope_type == ScopeType::Global
+    }
+
+    pub fn declare_global(&mut self, name: Symbol) {
+        if !self.globals.contains(&name) {
+            self.globals.push(name);
+        }
+    }
+
+    // Names declared in the functions and blocks shadow the globals, so they are looked up first
+    pub fn find_global(&mut self, name: Symbol) -> Option<Symbol> {
+        let shadowed = self.find_var_address(name).is_some();
+        (!shadowed && self.globals.contains(&name)).then(|| name)
+    }
+
+    // Variables captured from the current stack frame, closures get all of them in this order
     pub fn scope_upvalues(&self) -> Vec<&Upvalue> {
-        self.current_scope().upvalues.iter().collect()
+        self.frame_scopes()
+            .last()
+            .map(|scope| scope.upvalues.iter().collect())
+            .unwrap_or_default()
+    }
+
+    // Whether closures captured any variable of the current stack frame from the slot onwards
+    pub fn captured_from(&self, slot: usize) -> bool {
+        self.scope_upvalues()
+            .iter()
+            .any(|upvalue| upvalue.is_local && upvalue.local_index >= slot)
     }
 
     pub(crate) fn add_patch(&mut self, patch: Patch) {
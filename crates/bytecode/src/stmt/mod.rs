@@ -1,13 +1,16 @@
 use std::fmt::Display;
 
 use crate::{
-    callables::Function, chunk::Constant, BytecodeFrom, BytecodeGenerationResult,
-    BytecodeGenerator, MemoryAddress, Opcode,
+    callables::Function,
+    chunk::Constant,
+    state::{hidden_name, Signature},
+    BytecodeFrom, BytecodeGenerationResult, BytecodeGenerator, GenerationError, MemoryAddress,
+    Opcode,
 };
-use common::ProgramText;
+use common::Symbol;
 use parser::parse::{
     expr::ExprKind,
-    stmt::{Stmt, StmtKind},
+    stmt::{DestructuringPattern, Stmt, StmtKind},
     FunctionBody, Params,
 };
 
@@ -15,15 +18,22 @@ mod var;
 
 pub type GlobalPointer = usize;
 
-#[derive(Debug, Clone)]
+fn signature(params: &Params) -> Signature {
+    Signature {
+        params: params.kind.params.iter().map(|param| param.kind).collect(),
+        variadic: params.kind.rest.is_some(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum GlobalItem {
     Function(Function),
 }
 
 impl GlobalItem {
-    pub fn name(&self) -> &String {
+    pub fn name(&self) -> Symbol {
         match self {
-            GlobalItem::Function(function) => &function.name,
+            GlobalItem::Function(function) => function.name,
         }
     }
 }
@@ -53,21 +63,24 @@ impl Display for GlobalItem {
 impl BytecodeGenerator {
     pub(crate) fn compile_function(
         &mut self,
-        name: String,
+        name: Symbol,
         params: Params,
         body: FunctionBody,
-    ) -> Result<Function, ()> {
-        self.new_function(name.clone(), params.kind.len());
+    ) -> Result<Function, GenerationError> {
+        self.new_function(name, params.kind.params.len(), params.kind.rest.is_some());
+        let signature = signature(&params);
 
-        for param in params.kind {
+        for param in params.kind.iter().cloned() {
             self.state.declare_var(param.kind);
         }
 
-        self.state.declare_var(name.clone());
-        self.state.declare_var("this".to_owned());
+        self.state.declare_var(name);
+        self.state.declare_signature(name, signature);
+        self.state.declare_var(Symbol::intern("this"));
 
         match *body.kind {
             ExprKind::Block { stmts, return_expr } => {
+                let stmts = self.used_locals(stmts, return_expr.as_ref());
                 self.generate(stmts)?;
 
                 match return_expr {
@@ -95,56 +108,184 @@ impl BytecodeGenerator {
         return Ok(new_fn);
     }
 
+    // Top-level variables become globals of the VM, the value on top of the stack is popped into
+    // them. The rest of the variables take the slot of the value.
+    pub(crate) fn define_variable(&mut self, name: Symbol) {
+        if self.state.is_global_scope() {
+            self.write_global(Opcode::DefineGlobal, name);
+            self.state.declare_global(name);
+        } else {
+            self.state.declare_var(name);
+        }
+    }
+
     pub fn declare_global(&mut self, item: GlobalItem) -> GlobalPointer {
-        self.state.declare_var(item.name().clone());
+        self.state.declare_var(item.name());
+        self.add_global(item)
+    }
+
+    // Closures are values of expressions, so they don't take a variable slot like declared functions
+    pub fn add_global(&mut self, item: GlobalItem) -> GlobalPointer {
         self.globals.push(item);
         self.globals.len() - 1
     }
+
+    // Puts the compiled function on the stack together with the variables it captured
+    pub(crate) fn create_closure(&mut self, fn_ptr: GlobalPointer) {
+        let (upvalues_addresses, upvalues_count) = {
+            let upvalues = self.state.scope_upvalues();
+            let count = upvalues.len();
+            let addresses: Vec<Constant> = upvalues
+                .iter()
+                .map(|upvalue| {
+                    // It's still on the stack because depth 1 means that it's the function in which closure is declared
+                    if upvalue.is_local {
+                        Constant::MemoryAddress(MemoryAddress::Local(upvalue.local_index))
+                    } else {
+                        Constant::MemoryAddress(MemoryAddress::Upvalue {
+                            index: upvalue.upvalue_index,
+                            is_ref: upvalue.is_ref,
+                        })
+                    }
+                })
+                .collect();
+
+            (addresses, count)
+        };
+
+        self.write_constant(Constant::GlobalPointer(fn_ptr));
+
+        for upvalue_address in upvalues_addresses {
+            self.write_constant(upvalue_address);
+        }
+
+        self.write_opcode(Opcode::CreateClosure(upvalues_count));
+    }
 }
 
 impl BytecodeFrom<Stmt> for BytecodeGenerator {
     fn generate(&mut self, stmt: Stmt) -> BytecodeGenerationResult {
+        self.with_span(stmt.span.clone(), |generator| generator.generate_stmt(stmt))
+    }
+}
+
+impl BytecodeGenerator {
+    fn generate_stmt(&mut self, stmt: Stmt) -> BytecodeGenerationResult {
         match *stmt.kind {
             StmtKind::Expression { expr } => {
                 self.generate(expr)?;
+                // Blocks expect only their variables below the result, the global scope keeps
+                // the values so the last one becomes the result of the program
+                if self.state.is_global_scope() {
+                    self.state.declare_var(hidden_name("statement", "result"));
+                } else {
+                    self.write_opcode(Opcode::Pop(1));
+                }
             }
-            StmtKind::VariableDeclaration { name, expr } => {
+            // Annotations are only read by the type checker
+            StmtKind::VariableDeclaration { name, expr, .. } => {
                 self.generate(expr)?;
-                self.state.declare_var(name);
+                self.define_variable(name);
             }
-            StmtKind::FunctionDeclaration { name, params, body } => {
-                let new_fn = self.compile_function(name.clone(), params, body)?;
-                let fn_ptr = self.declare_global(new_fn.into());
-
-                let (upvalues_addresses, upvalues_count) = {
-                    let upvalues = self.state.scope_upvalues();
-                    let count = upvalues.len();
-                    let addresses: Vec<Constant> = upvalues
-                        .iter()
-                        .map(|upvalue| {
-                            // It's still on the stack because depth 1 means that it's the function in which closure is declared
-                            if upvalue.is_local {
-                                Constant::MemoryAddress(MemoryAddress::Local(upvalue.local_index))
-                            } else {
-                                Constant::MemoryAddress(MemoryAddress::Upvalue {
-                                    index: upvalue.upvalue_index,
-                                    is_ref: upvalue.is_ref,
-                                })
-                            }
-                        })
-                        .collect();
-
-                    (addresses, count)
-                };
-
-                self.write_constant(Constant::GlobalPointer(fn_ptr));
+            StmtKind::MultipleDeclaration { names, exprs } => {
+                self.generate(exprs)?;
+                // Globals are popped starting from the last value
+                if self.state.is_global_scope() {
+                    for name in names.into_iter().rev() {
+                        self.define_variable(name.kind);
+                    }
+                } else {
+                    for name in names {
+                        self.define_variable(name.kind);
+                    }
+                }
+            }
+            StmtKind::ParallelAssignment { targets, values } => {
+                // Every value is stored in a temporary slot before the first assignment,
+                // otherwise `a, b = b, a` would read the already overwritten `a`
+                let mut slots = vec![];
+                for value in values {
+                    self.generate(value)?;
+                    let slot = hidden_name("assigned value", self.state.declared());
+                    slots.push(self.state.declare_var(slot));
+                }
 
-                for upvalue_address in upvalues_addresses {
-                    self.write_constant(upvalue_address);
+                for (target, slot) in targets.into_iter().zip(slots) {
+                    let value = Constant::MemoryAddress(MemoryAddress::Local(slot));
+                    match *target.kind {
+                        ExprKind::Index { target, position } => {
+                            self.generate(target)?;
+                            self.generate(position)?;
+                            self.write_constant(value);
+                            self.write_opcode(Opcode::Get);
+                            self.write_opcode(Opcode::SetIndex);
+                        }
+                        ExprKind::GetProperty {
+                            target, identifier, ..
+                        } => {
+                            self.generate(target)?;
+                            self.write_constant(Constant::String(identifier.kind.to_string()));
+                            self.write_constant(value);
+                            self.write_opcode(Opcode::Get);
+                            self.write_opcode(Opcode::SetProperty(1));
+                        }
+                        _ => match self.global_target(&target) {
+                            Some(name) => {
+                                self.write_constant(value);
+                                self.write_opcode(Opcode::Get);
+                                self.write_global(Opcode::SetGlobal, name);
+                            }
+                            None => {
+                                self.generate(target)?;
+                                self.write_constant(value);
+                                self.write_opcode(Opcode::Get);
+                                self.write_opcode(Opcode::Asg);
+                            }
+                        },
+                    }
+                    self.write_opcode(Opcode::Pop(1));
                 }
+            }
+            StmtKind::Destructuring { pattern, expr } => {
+                self.generate(expr)?;
+                // Read by its slot, because another destructuring in the same scope reuses the name
+                let source = self.state.declare_var(hidden_name("destructured", "value"));
+                let source = Constant::MemoryAddress(MemoryAddress::Local(source));
 
-                self.write_opcode(Opcode::CreateClosure(upvalues_count));
+                match pattern {
+                    DestructuringPattern::Object(names) => {
+                        for name in names {
+                            self.write_constant(source.clone());
+                            self.write_opcode(Opcode::Get);
+                            self.write_constant(Constant::String(name.kind.to_string()));
+                            self.write_opcode(Opcode::GetProperty { bind_method: false });
+                            self.define_variable(name.kind);
+                        }
+                    }
+                    DestructuringPattern::Array(names) => {
+                        for (index, name) in names.into_iter().enumerate() {
+                            self.write_constant(source.clone());
+                            self.write_opcode(Opcode::Get);
+                            self.write_constant(Constant::Number(index as f64));
+                            self.write_opcode(Opcode::Index);
+                            self.define_variable(name.kind);
+                        }
+                    }
+                }
+            }
+            StmtKind::FunctionDeclaration {
+                name, params, body, ..
+            } => {
+                let signature = signature(&params);
+                let new_fn = self.compile_function(name, params, body)?;
+                let fn_ptr = self.add_global(new_fn.into());
+                self.create_closure(fn_ptr);
+                self.define_variable(name);
+                self.state.declare_signature(name, signature);
             }
+            StmtKind::Import { path } => self.generate_import(path)?,
+            // Exported names are collected when the module gets linked
+            StmtKind::Export { declaration } => self.generate(declaration)?,
         }
         Ok(())
     }
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use common::Symbol;
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind},
+    stmt::{Stmt, StmtKind},
+    visit::{walk_expr, Visit},
+};
+
+// Every name the code reads or assigns to, nested functions included
+#[derive(Default)]
+struct References {
+    names: HashSet<Symbol>,
+}
+
+impl Visit for References {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Atom(AtomicValue::Identifier { name, .. }) = &*expr.kind {
+            self.names.insert(*name);
+        }
+        walk_expr(self, expr);
+    }
+}
+
+// Values that can be computed without running any code of the program or failing.
+// Operators are left out as they may call methods of the operands.
+fn is_pure(expr: &Expr) -> bool {
+    match &*expr.kind {
+        ExprKind::Atom(AtomicValue::Identifier { .. }) => false,
+        ExprKind::Atom(_) => true,
+        ExprKind::Array { values } => values.iter().all(is_pure),
+        ExprKind::Map { entries } => entries.iter().all(|(_, value)| is_pure(value)),
+        ExprKind::ObjectLiteral { properties } => {
+            properties.iter().all(|(_, value)| is_pure(value))
+        }
+        _ => false,
+    }
+}
+
+// Drops the local declarations of a block that nothing reads, as long as computing their value
+// can't be observed. The slots of the remaining locals are given out as if they were never there.
+pub(crate) fn remove_unused_locals(stmts: Vec<Stmt>, return_expr: Option<&Expr>) -> Vec<Stmt> {
+    let mut references = References::default();
+    for stmt in &stmts {
+        references.visit_stmt(stmt);
+    }
+    if let Some(return_expr) = return_expr {
+        references.visit_expr(return_expr);
+    }
+
+    stmts
+        .into_iter()
+        .filter(|stmt| match &*stmt.kind {
+            StmtKind::VariableDeclaration { name, expr, .. } => {
+                references.names.contains(name) || !is_pure(expr)
+            }
+            _ => true,
+        })
+        .collect()
+}
@@ -0,0 +1,482 @@
+// Checks the VM runs before a program, so bytecode that didn't come straight from the generator,
+// e.g loaded from a file or written by hand, is rejected instead of making the VM panic.
+// Besides the operands of every opcode it follows all paths through the functions and checks
+// that they agree on how many values are on the stack. The generator measures the stack
+// the functions need the same way.
+use crate::{
+    callables::Function,
+    chunk::{Constant, ConstantIndex, OpcodeIndex},
+    peephole::jump_distance,
+    stmt::{GlobalItem, GlobalPointer},
+    MemoryAddress, Opcode, ProgramBytecode,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerificationError {
+    // Function with the opcode that failed the check
+    pub function: GlobalPointer,
+    pub index: OpcodeIndex,
+    pub cause: VerificationErrorCause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerificationErrorCause {
+    InvalidGlobalPointer(GlobalPointer),
+    // Jumps can only land on an opcode of the function or right after the last one
+    JumpOutOfRange(isize),
+    InvalidConstant(ConstantIndex),
+    // The constant exists, but the opcode expects a different kind of it
+    UnexpectedConstant(ConstantIndex),
+    // The opcode reads a constant from the stack, but the value there isn't one of its kind,
+    // e.g. Get without the address of a variable
+    UnexpectedOperand,
+    // Slot of the frame that isn't on the stack yet
+    InvalidLocal(usize),
+    StackUnderflow,
+    // Paths that meet at the opcode leave different values on the stack
+    StackMismatch,
+    // Spread values are read in groups, each followed by its size
+    MissingGroupSize,
+    // The function can reach its end without returning
+    MissingReturn,
+    ReturnOutsideFunction,
+    // The main function has to leave the result of the program on the stack
+    MissingResult,
+    // The function needs more room on the stack than its max_stack, which is the value
+    StackSizeTooSmall(usize),
+}
+
+// What the verifier knows about a value on the stack
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slot {
+    Value,
+    // Number constant, which can be the size of a group
+    Size(usize),
+    // Elements of a spread array followed by their count, of unknown length
+    Spread,
+    // Constants that opcodes read from the stack and can't do without
+    Address,
+    FunctionPointer,
+    String,
+}
+
+type Stack = Vec<Slot>;
+
+#[derive(Debug, Clone)]
+struct State {
+    slots: Stack,
+    // Whether every path leaves exactly these values, see `merge`
+    exact: bool,
+}
+type Check<T = ()> = Result<T, VerificationErrorCause>;
+
+fn pop(stack: &mut Stack, amount: usize) -> Check {
+    let len = stack
+        .len()
+        .checked_sub(amount)
+        .ok_or(VerificationErrorCause::StackUnderflow)?;
+    stack.truncate(len);
+    Ok(())
+}
+
+fn pop_groups(stack: &mut Stack, groups: usize) -> Check {
+    for _ in 0..groups {
+        match stack.pop() {
+            Some(Slot::Spread) => {}
+            Some(Slot::Size(size)) => pop(stack, size)?,
+            Some(_) => return Err(VerificationErrorCause::MissingGroupSize),
+            None => return Err(VerificationErrorCause::StackUnderflow),
+        }
+    }
+    Ok(())
+}
+
+fn pop_constant(stack: &mut Stack, kind: Slot) -> Check {
+    match stack.pop() {
+        Some(slot) if slot == kind => Ok(()),
+        Some(_) => Err(VerificationErrorCause::UnexpectedOperand),
+        None => Err(VerificationErrorCause::StackUnderflow),
+    }
+}
+
+struct FunctionVerifier<'p> {
+    function: &'p Function,
+    globals: usize,
+    is_main: bool,
+    // Stack before every opcode, once a path reached it. The extra one is the end of the function.
+    stacks: Vec<Option<State>>,
+    pending: Vec<OpcodeIndex>,
+    // Most values on the stack before any of the opcodes
+    peak: usize,
+}
+
+impl<'p> FunctionVerifier<'p> {
+    fn constant(&self, index: ConstantIndex) -> Check<&'p Constant> {
+        self.function
+            .chunk
+            .constants
+            .get(index)
+            .ok_or(VerificationErrorCause::InvalidConstant(index))
+    }
+
+    fn target(&self, index: OpcodeIndex, distance: isize) -> Check<OpcodeIndex> {
+        let target = index as isize + distance + 1;
+        if target < 0 || target > self.function.chunk.opcodes.len() as isize {
+            return Err(VerificationErrorCause::JumpOutOfRange(target));
+        }
+        Ok(target as OpcodeIndex)
+    }
+
+    fn table_targets(&self, index: OpcodeIndex, table: ConstantIndex) -> Check<Vec<OpcodeIndex>> {
+        match self.constant(table)? {
+            Constant::JumpTable(table) => table
+                .arms
+                .values()
+                .chain(Some(&table.default))
+                .map(|distance| self.target(index, *distance))
+                .collect(),
+            _ => Err(VerificationErrorCause::UnexpectedConstant(table)),
+        }
+    }
+
+    // Operands that don't depend on the path the opcode was reached by
+    fn check_operands(&self, index: OpcodeIndex) -> Check {
+        let opcode = &self.function.chunk.opcodes[index];
+        if let Some(distance) = jump_distance(opcode) {
+            self.target(index, distance)?;
+        }
+        // The fused comparison skips the Jif after it when the comparison holds
+        if opcode.fused_comparison().is_some() {
+            self.target(index, 1)?;
+        }
+
+        match *opcode {
+            Opcode::Constant(constant) => match self.constant(constant)? {
+                Constant::GlobalPointer(ptr) if *ptr >= self.globals => {
+                    Err(VerificationErrorCause::InvalidGlobalPointer(*ptr))
+                }
+                _ => Ok(()),
+            },
+            Opcode::JumpTable(table) => self.table_targets(index, table).map(|_| ()),
+            Opcode::DefineGlobal(name) | Opcode::GetGlobal(name) | Opcode::SetGlobal(name) => {
+                match self.constant(name)? {
+                    Constant::String(_) => Ok(()),
+                    _ => Err(VerificationErrorCause::UnexpectedConstant(name)),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn constant_slot(&self, index: ConstantIndex, depth: usize) -> Check<Slot> {
+        Ok(match self.constant(index)? {
+            Constant::Number(number) if *number >= 0.0 && number.fract() == 0.0 => {
+                Slot::Size(*number as usize)
+            }
+            Constant::MemoryAddress(MemoryAddress::Local(local)) if *local >= depth => {
+                return Err(VerificationErrorCause::InvalidLocal(*local))
+            }
+            Constant::MemoryAddress(_) => Slot::Address,
+            Constant::GlobalPointer(_) => Slot::FunctionPointer,
+            Constant::String(_) => Slot::String,
+            _ => Slot::Value,
+        })
+    }
+
+    // Stack after the opcode for every opcode that can run next
+    fn successors(&self, index: OpcodeIndex, mut stack: Stack) -> Check<Vec<(OpcodeIndex, Stack)>> {
+        use Opcode::*;
+        let next = index + 1;
+        let opcode = self.function.chunk.opcodes[index];
+        let pushed = |mut stack: Stack, slot: Slot| {
+            stack.push(slot);
+            vec![(next, stack)]
+        };
+
+        Ok(match opcode {
+            Constant(constant) => {
+                let slot = self.constant_slot(constant, stack.len())?;
+                pushed(stack, slot)
+            }
+            Null | GetGlobal(_) => pushed(stack, Slot::Value),
+            Not | Neg | BitNot | Iter | SetGlobal(_) => {
+                pop(&mut stack, 1)?;
+                pushed(stack, Slot::Value)
+            }
+            Get => {
+                pop_constant(&mut stack, Slot::Address)?;
+                pushed(stack, Slot::Value)
+            }
+            Asg => {
+                pop(&mut stack, 1)?;
+                pop_constant(&mut stack, Slot::Address)?;
+                pushed(stack, Slot::Value)
+            }
+            GetProperty { .. } => {
+                pop_constant(&mut stack, Slot::String)?;
+                pop(&mut stack, 1)?;
+                pushed(stack, Slot::Value)
+            }
+            SetProperty(_) => {
+                pop(&mut stack, 1)?;
+                pop_constant(&mut stack, Slot::String)?;
+                pop(&mut stack, 1)?;
+                pushed(stack, Slot::Value)
+            }
+            Add | Sub | Div | Mul | Pow | Mod | Eq | Ne | Lt | Le | Gt | Ge | Or | And | Range
+            | RangeInclusive | BitAnd | BitOr | BitXor | Shl | Shr | Index => {
+                pop(&mut stack, 2)?;
+                pushed(stack, Slot::Value)
+            }
+            SetIndex | Slice { .. } => {
+                pop(&mut stack, 3)?;
+                pushed(stack, Slot::Value)
+            }
+            Dup => {
+                let top = *stack.last().ok_or(VerificationErrorCause::StackUnderflow)?;
+                pushed(stack, top)
+            }
+            Spread => {
+                pop(&mut stack, 1)?;
+                pushed(stack, Slot::Spread)
+            }
+            Pop(amount) => {
+                pop(&mut stack, amount)?;
+                vec![(next, stack)]
+            }
+            DefineGlobal(_) => {
+                pop(&mut stack, 1)?;
+                vec![(next, stack)]
+            }
+            Block(amount) => {
+                pop(&mut stack, 1 + amount)?;
+                pushed(stack, Slot::Value)
+            }
+            Call(args_count) => {
+                pop(&mut stack, 1 + args_count)?;
+                pushed(stack, Slot::Value)
+            }
+            CallSpread(groups) => {
+                pop(&mut stack, 1)?;
+                pop_groups(&mut stack, groups)?;
+                pushed(stack, Slot::Value)
+            }
+            CreateClosure(upvalues) => {
+                for _ in 0..upvalues {
+                    pop_constant(&mut stack, Slot::Address)?;
+                }
+                pop_constant(&mut stack, Slot::FunctionPointer)?;
+                pushed(stack, Slot::Value)
+            }
+            // Every value is followed by its key
+            CreateObject(amount) | CreateMap(amount) => {
+                for _ in 0..amount {
+                    pop_constant(&mut stack, Slot::String)?;
+                    pop(&mut stack, 1)?;
+                }
+                pushed(stack, Slot::Value)
+            }
+            CreateArray(amount) | Concat(amount) => {
+                pop(&mut stack, amount)?;
+                pushed(stack, Slot::Value)
+            }
+            CreateSpreadArray(groups) => {
+                pop_groups(&mut stack, groups)?;
+                pushed(stack, Slot::Value)
+            }
+            CloseUpvalue(slot) => {
+                if slot > stack.len() {
+                    return Err(VerificationErrorCause::InvalidLocal(slot));
+                }
+                vec![(next, stack)]
+            }
+            Return => {
+                if self.is_main {
+                    return Err(VerificationErrorCause::ReturnOutsideFunction);
+                }
+                pop(&mut stack, 1)?;
+                vec![]
+            }
+            Jp(distance) | Break(distance) => vec![(self.target(index, distance)?, stack)],
+            Jif(distance) => {
+                pop(&mut stack, 1)?;
+                vec![
+                    (next, stack.clone()),
+                    (self.target(index, distance)?, stack),
+                ]
+            }
+            JifEq(distance) | JifNe(distance) | JifLt(distance) | JifLe(distance)
+            | JifGt(distance) | JifGe(distance) => {
+                pop(&mut stack, 2)?;
+                // An operator method leaves its result for the Jif after the comparison
+                let mut called = stack.clone();
+                called.push(Slot::Value);
+                vec![
+                    (next, called),
+                    (next + 1, stack.clone()),
+                    (self.target(index, distance)?, stack),
+                ]
+            }
+            JumpTable(table) => {
+                pop(&mut stack, 1)?;
+                self.table_targets(index, table)?
+                    .into_iter()
+                    .map(|target| (target, stack.clone()))
+                    .collect()
+            }
+            // The iterator stays on the stack, the next value is pushed above it
+            IterNext(distance) => {
+                if stack.is_empty() {
+                    return Err(VerificationErrorCause::StackUnderflow);
+                }
+                let target = self.target(index, distance)?;
+                let mut advanced = stack.clone();
+                advanced.push(Slot::Value);
+                vec![(next, advanced), (target, stack)]
+            }
+        })
+    }
+
+    // Paths meeting at the opcode have to leave the same amount of values, sizes that
+    // differ between them aren't known anymore. Break and continue leave the values of the
+    // expression they're in on the stack, so after them only the values every path has are known.
+    fn merge(&mut self, index: OpcodeIndex, mut stack: Stack, exact: bool) -> Check {
+        let known = match &mut self.stacks[index] {
+            Some(known) => known,
+            None => {
+                self.peak = self.peak.max(stack.len());
+                self.stacks[index] = Some(State {
+                    slots: stack,
+                    exact,
+                });
+                self.pending.push(index);
+                return Ok(());
+            }
+        };
+
+        let mut changed = false;
+        if known.slots.len() != stack.len() {
+            if known.exact && exact {
+                return Err(VerificationErrorCause::StackMismatch);
+            }
+            let len = known.slots.len().min(stack.len());
+            stack.truncate(len);
+            changed = known.slots.len() != len;
+            known.slots.truncate(len);
+        }
+        if known.exact && !exact {
+            known.exact = false;
+            changed = true;
+        }
+
+        for (known, slot) in known.slots.iter_mut().zip(stack) {
+            if *known == slot || *known == Slot::Value {
+                continue;
+            }
+            if *known == Slot::Spread || slot == Slot::Spread {
+                return Err(VerificationErrorCause::StackMismatch);
+            }
+            *known = Slot::Value;
+            changed = true;
+        }
+        if changed {
+            self.pending.push(index);
+        }
+        Ok(())
+    }
+
+    // Returns the most values the function has on the stack at once, counting its frame.
+    // Values left behind by break and continue aren't counted, they're only known at runtime.
+    fn verify(mut self) -> Result<usize, (OpcodeIndex, VerificationErrorCause)> {
+        let len = self.function.chunk.opcodes.len();
+        for index in 0..len {
+            self.check_operands(index).map_err(|cause| (index, cause))?;
+        }
+
+        // Arguments, the function itself and `this`, variadic functions get the rest as an array
+        let frame = match self.is_main {
+            true => 0,
+            false => self.function.arity + self.function.variadic as usize + 2,
+        };
+        self.merge(0, vec![Slot::Value; frame], true)
+            .map_err(|cause| (0, cause))?;
+
+        while let Some(index) = self.pending.pop() {
+            let State {
+                slots: stack,
+                exact,
+            } = self.stacks[index]
+                .clone()
+                .expect("Merged before it was queued");
+            if index == len {
+                if !self.is_main {
+                    return Err((index, VerificationErrorCause::MissingReturn));
+                }
+                if stack.is_empty() {
+                    return Err((index, VerificationErrorCause::MissingResult));
+                }
+                continue;
+            }
+
+            let exact = exact && !matches!(self.function.chunk.opcodes[index], Opcode::Break(_));
+            let successors = self
+                .successors(index, stack)
+                .map_err(|cause| (index, cause))?;
+            for (target, stack) in successors {
+                self.merge(target, stack, exact)
+                    .map_err(|cause| (target, cause))?;
+            }
+        }
+        Ok(self.peak)
+    }
+}
+
+fn function_verifier(function: &Function, globals: usize, is_main: bool) -> FunctionVerifier<'_> {
+    FunctionVerifier {
+        function,
+        globals,
+        is_main,
+        stacks: vec![None; function.chunk.opcodes.len() + 1],
+        pending: vec![],
+        peak: 0,
+    }
+}
+
+// Stack the generated function needs, its global pointers aren't checked
+pub(crate) fn max_stack(
+    function: &Function,
+    is_main: bool,
+) -> Result<usize, VerificationErrorCause> {
+    function_verifier(function, usize::MAX, is_main)
+        .verify()
+        .map_err(|(_, cause)| cause)
+}
+
+pub fn verify(program: &ProgramBytecode) -> Result<(), VerificationError> {
+    let globals = program.globals.len();
+    if program.global_fn_ptr >= globals {
+        return Err(VerificationError {
+            function: program.global_fn_ptr,
+            index: 0,
+            cause: VerificationErrorCause::InvalidGlobalPointer(program.global_fn_ptr),
+        });
+    }
+
+    for (pointer, global) in program.globals.iter().enumerate() {
+        let GlobalItem::Function(function) = global;
+        let error = |(index, cause)| VerificationError {
+            function: pointer,
+            index,
+            cause,
+        };
+        let is_main = pointer == program.global_fn_ptr;
+        let needed = function_verifier(function, globals, is_main)
+            .verify()
+            .map_err(error)?;
+        if needed > function.max_stack {
+            let cause = VerificationErrorCause::StackSizeTooSmall(needed);
+            return Err(error((0, cause)));
+        }
+    }
+    Ok(())
+}
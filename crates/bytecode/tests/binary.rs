@@ -0,0 +1,174 @@
+use bytecode::{
+    binary::{DecodeError, FORMAT_VERSION, MAGIC},
+    callables::Function,
+    chunk::{Chunk, Constant, JumpTable, TableKey},
+    generate_bytecode,
+    stmt::GlobalItem,
+    MemoryAddress, Opcode, ProgramBytecode,
+};
+use common::BuiltInFunction;
+use parser::parse;
+
+fn compile(code: &str) -> ProgramBytecode {
+    generate_bytecode(parse(code).unwrap()).expect("Program should compile")
+}
+
+#[test]
+fn decodes_the_encoded_program() {
+    let program = compile(
+        "let n = 3;
+        fn make_adder(x) { fn add(y) => x + y add }
+        let label = match n { 1 => \"one\", 2 => \"two\", 3 => \"three\", 4 => \"four\", _ => \"many\" };
+        print(label);
+        make_adder(n)(1.5);",
+    );
+    let bytes = program.to_bytes();
+    assert!(bytes.starts_with(MAGIC));
+    assert_eq!(ProgramBytecode::from_bytes(&bytes), Ok(program.clone()));
+    // The same program is always encoded the same way
+    assert_eq!(program.to_bytes(), bytes);
+}
+
+fn every_opcode() -> Vec<Opcode> {
+    use Opcode::*;
+    vec![
+        Constant(0),
+        Not,
+        Neg,
+        Add,
+        Sub,
+        Div,
+        Mul,
+        Pow,
+        Mod,
+        Eq,
+        Ne,
+        Lt,
+        Le,
+        Gt,
+        Ge,
+        Or,
+        And,
+        Range,
+        RangeInclusive,
+        BitAnd,
+        BitOr,
+        BitXor,
+        BitNot,
+        Shl,
+        Shr,
+        Jif(-3),
+        Jp(7),
+        JumpTable(1),
+        JifEq(1),
+        JifNe(2),
+        JifLt(3),
+        JifLe(4),
+        JifGt(5),
+        JifGe(6),
+        Pop(2),
+        Dup,
+        Get,
+        DefineGlobal(2),
+        GetGlobal(2),
+        SetGlobal(2),
+        GetProperty { bind_method: true },
+        SetProperty(1),
+        Asg,
+        Call(3),
+        CallSpread(2),
+        Return,
+        Block(4),
+        Break(-8),
+        Null,
+        CreateClosure(1),
+        CloseUpvalue(2),
+        CreateObject(2),
+        Index,
+        SetIndex,
+        Slice { inclusive: false },
+        CreateArray(3),
+        CreateSpreadArray(1),
+        Spread,
+        CreateMap(2),
+        Iter,
+        IterNext(5),
+        Concat(3),
+    ]
+}
+
+#[test]
+fn decodes_every_opcode() {
+    let table = JumpTable {
+        arms: vec![
+            (TableKey::Integer(-4), 2),
+            (TableKey::String("a".to_owned()), 5),
+        ]
+        .into_iter()
+        .collect(),
+        default: 9,
+    };
+    let constants = vec![
+        Constant::Number(-0.25),
+        Constant::JumpTable(table),
+        Constant::String("name".to_owned()),
+        Constant::Bool(true),
+        Constant::GlobalPointer(0),
+        MemoryAddress::Local(3).into(),
+        MemoryAddress::Upvalue {
+            index: 1,
+            is_ref: true,
+        }
+        .into(),
+        MemoryAddress::BuiltInFunction(BuiltInFunction::Print).into(),
+        MemoryAddress::HostFunction(2).into(),
+    ];
+    let function = Function {
+        arity: 2,
+        variadic: true,
+        chunk: Chunk::new(every_opcode(), constants),
+        name: "everything".into(),
+        max_stack: 0,
+    };
+    let program = ProgramBytecode {
+        global_fn_ptr: 0,
+        globals: vec![GlobalItem::Function(function)],
+    };
+
+    assert_eq!(
+        ProgramBytecode::from_bytes(&program.to_bytes()),
+        Ok(program)
+    );
+}
+
+#[test]
+fn rejects_bytes_that_are_not_a_program() {
+    let bytes = compile("1 + 2;").to_bytes();
+
+    assert_eq!(
+        ProgramBytecode::from_bytes(b"let a = 1;"),
+        Err(DecodeError::NotBytecode)
+    );
+
+    let mut newer = bytes.clone();
+    newer[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        ProgramBytecode::from_bytes(&newer),
+        Err(DecodeError::UnsupportedVersion(FORMAT_VERSION + 1))
+    );
+
+    let mut corrupted = bytes.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert_eq!(
+        ProgramBytecode::from_bytes(&corrupted),
+        Err(DecodeError::ChecksumMismatch)
+    );
+    assert_eq!(
+        ProgramBytecode::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(DecodeError::ChecksumMismatch)
+    );
+    assert_eq!(
+        ProgramBytecode::from_bytes(&bytes[..MAGIC.len() + 1]),
+        Err(DecodeError::Malformed("header"))
+    );
+}
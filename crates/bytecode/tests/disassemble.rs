@@ -0,0 +1,32 @@
+use bytecode::{disassemble, generate_bytecode, stmt::GlobalItem};
+use parser::parse;
+
+fn main_listing(code: &str) -> String {
+    let bytecode = generate_bytecode(parse(code).unwrap()).expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    disassemble(function)
+}
+
+#[test]
+fn lists_opcodes_with_their_constants() {
+    let listing = main_listing("let greeting = \"hi\"; greeting;");
+    assert!(listing.starts_with("fn main (arity 0)\n"));
+    assert!(listing.contains("    0  CONSTANT_0              \"hi\"\n"));
+    assert!(listing.contains("DEFINE_GLOBAL_1         \"greeting\"\n"));
+}
+
+#[test]
+fn labels_jump_targets() {
+    let listing = main_listing("let n = 1; if n > 0 { 1 } else { 2 };");
+    assert!(listing.contains("-> L0"));
+    assert!(listing.contains("-> L1"));
+    assert!(listing.contains("\nL0:\n"));
+    assert!(listing.contains("\nL1:\n"));
+}
+
+#[test]
+fn lists_arms_of_jump_tables() {
+    let listing =
+        main_listing("let n = 2; match n { 1 => 1, 2 => 2, 3 => 3, \"4\" => 4, _ => 0 };");
+    assert!(listing.contains("1 -> L0, 2 -> L1, 3 -> L2, \"4\" -> L3, _ -> L4"));
+}
@@ -0,0 +1,76 @@
+use bytecode::{
+    generate_bytecode,
+    modules::{link_modules, Module},
+    GenerationError, GenerationErrorCause,
+};
+use common::CompilerDiagnostic;
+use parser::parse;
+
+// The analyzer isn't run, so the generator is the one to find the problems
+fn error(code: &str) -> GenerationError {
+    let ast = parse(code).expect("Program should parse");
+    generate_bytecode(ast).expect_err("Generation should fail")
+}
+
+#[test]
+fn reports_undefined_variables() {
+    let error = error("let a = 1; a + b;");
+    assert_eq!(
+        error.cause,
+        GenerationErrorCause::UndefinedVariable("b".to_owned())
+    );
+    assert_eq!(error.span, 15..16);
+    assert_eq!(error.report(0).message, "Variable 'b' is not defined");
+}
+
+#[test]
+fn reports_break_outside_of_loops() {
+    let error = error("fn f() { break; } f();");
+    assert_eq!(error.cause, GenerationErrorCause::InvalidBreak);
+    assert_eq!(error.span, 9..14);
+}
+
+#[test]
+fn reports_calls_with_wrong_argument_counts() {
+    let missing = error("fn f(a, b) { a + b } f(1);");
+    assert_eq!(
+        missing.cause,
+        GenerationErrorCause::WrongArgumentCount {
+            expected: 2,
+            variadic: false,
+            found: 1
+        }
+    );
+    assert_eq!(missing.span, 21..25);
+    assert_eq!(missing.report(0).message, "Expected 2 arguments, found 1");
+
+    let variadic = error("fn f(a, b, ...rest) { a } f(1);");
+    assert_eq!(
+        variadic.report(0).message,
+        "Expected at least 2 arguments, found 1"
+    );
+
+    // Named arguments count too, the callee of the functions passed around isn't known
+    let code = "fn f(a, b) { a } f(b: 1, a: 2); let g = f; g(1);";
+    let ast = parse(code).expect("Program should parse");
+    assert!(generate_bytecode(ast).is_ok());
+}
+
+#[test]
+fn reports_modules_importing_each_other() {
+    let module = |path: &str, code: &str| Module {
+        path: path.to_owned(),
+        program: parse(code).expect("Module should parse"),
+    };
+    let modules = vec![
+        module("a.gv", r#"import "b.gv"; export let a = 1;"#),
+        module("b.gv", r#"import "a.gv"; export let b = 2;"#),
+    ];
+    let program = parse(r#"import "a.gv"; a;"#).expect("Program should parse");
+    let error = link_modules(modules, program, &[]).expect_err("Linking should fail");
+    assert_eq!(
+        error.cause,
+        GenerationErrorCause::ImportCycle("a.gv".to_owned())
+    );
+    assert_eq!(error.span, 7..13);
+}
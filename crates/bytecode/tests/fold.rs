@@ -0,0 +1,58 @@
+use bytecode::{
+    chunk::{Chunk, Constant},
+    generate_bytecode_with_options,
+    stmt::GlobalItem,
+    Opcode, Options,
+};
+use parser::parse;
+
+fn main_chunk(code: &str, fold_constants: bool) -> Chunk {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode_with_options(
+        ast,
+        Options {
+            fold_constants,
+            ..Options::default()
+        },
+    )
+    .expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    function.chunk.clone()
+}
+
+#[test]
+fn folds_operations_on_literals() {
+    let chunk = main_chunk("2 * 3 + 1;", true);
+    assert_eq!(chunk.constants, vec![Constant::Number(7.0)]);
+    assert_eq!(chunk.opcodes[0], Opcode::Constant(0));
+
+    let chunk = main_chunk("!(1 < 2) or -4 == ~3;", true);
+    assert_eq!(chunk.constants, vec![Constant::Bool(true)]);
+}
+
+#[test]
+fn leaves_operations_the_vm_has_to_run() {
+    // Only the literal part of the expression is folded
+    let chunk = main_chunk("let a = 1; a + 2 * 3;", true);
+    assert!(chunk.constants.contains(&Constant::Number(6.0)));
+    assert!(chunk.opcodes.contains(&Opcode::Add));
+
+    // Mismatched types are reported by the VM
+    let chunk = main_chunk("1 + true;", true);
+    assert!(chunk.opcodes.contains(&Opcode::Add));
+}
+
+#[test]
+fn keeps_every_operator_without_folding() {
+    let chunk = main_chunk("2 * 3 + 1;", false);
+    assert_eq!(
+        chunk.constants,
+        vec![
+            Constant::Number(2.0),
+            Constant::Number(3.0),
+            Constant::Number(1.0)
+        ]
+    );
+    assert!(chunk.opcodes.contains(&Opcode::Mul));
+    assert!(chunk.opcodes.contains(&Opcode::Add));
+}
@@ -0,0 +1,48 @@
+use bytecode::{chunk::Chunk, generate_bytecode, stmt::GlobalItem, ProgramBytecode};
+use parser::parse;
+
+fn compile(code: &str) -> ProgramBytecode {
+    generate_bytecode(parse(code).unwrap()).expect("Program should compile")
+}
+
+fn hashes(program: &ProgramBytecode) -> Vec<u64> {
+    program
+        .globals
+        .iter()
+        .map(|GlobalItem::Function(function)| function.chunk.hash())
+        .collect()
+}
+
+#[test]
+fn same_code_compiles_to_the_same_bytes() {
+    // Matches on literals keep their arms in a table, which has to come out the same too
+    let code = r#"
+        fn name(n) {
+            match n { "a" => 1, "b" => 2, "c" => 3, "d" => 4, "e" => 5, 6 => 6, 7 => 7, _ => 0 }
+        }
+        name("c") + name(7);
+    "#;
+    let first = compile(code);
+    for _ in 0..10 {
+        let again = compile(code);
+        assert_eq!(again.to_bytes(), first.to_bytes());
+        assert_eq!(hashes(&again), hashes(&first));
+    }
+}
+
+#[test]
+fn hash_covers_only_what_the_chunk_runs() {
+    // Spans differ, the code doesn't
+    assert_eq!(
+        hashes(&compile("1 + 2;")),
+        hashes(&compile("\n  1   +   2 ;"))
+    );
+    assert_ne!(hashes(&compile("1 + 2;")), hashes(&compile("1 + 3;")));
+    assert_ne!(hashes(&compile("1 + 2;")), hashes(&compile("1 - 2;")));
+}
+
+#[test]
+fn hash_is_stable() {
+    // Cached hashes stay valid as long as the encoding doesn't change
+    assert_eq!(Chunk::default().hash(), 0x8820_1fb9_60ff_6465);
+}
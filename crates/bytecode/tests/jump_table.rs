@@ -0,0 +1,46 @@
+use bytecode::{
+    chunk::{Chunk, Constant},
+    generate_bytecode,
+    stmt::GlobalItem,
+    Opcode,
+};
+use parser::parse;
+
+fn main_chunk(code: &str) -> Chunk {
+    let bytecode = generate_bytecode(parse(code).unwrap()).expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    function.chunk.clone()
+}
+
+fn table(chunk: &Chunk) -> Option<usize> {
+    chunk.opcodes.iter().find_map(|opcode| match opcode {
+        Opcode::JumpTable(index) => match &chunk.constants[*index] {
+            Constant::JumpTable(table) => Some(table.arms.len()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+#[test]
+fn dispatches_many_literal_arms_through_a_table() {
+    let chunk = main_chunk("let n = 2; match n { 1 => 1, 2 => 2, \"3\" => 3, 4 => 4, _ => 0 };");
+    assert_eq!(table(&chunk), Some(4));
+    assert!(!chunk.opcodes.contains(&Opcode::Eq));
+}
+
+#[test]
+fn compares_arms_one_by_one_otherwise() {
+    // Too few arms
+    assert_eq!(
+        table(&main_chunk(
+            "let n = 2; match n { 1 => 1, 2 => 2, _ => 0 };"
+        )),
+        None
+    );
+    // Arms that aren't integer or string literals
+    let chunk = main_chunk("let n = 2; match n { 1 => 1, 2 => 2, 3 => 3, n => 4 };");
+    assert_eq!(table(&chunk), None);
+    let chunk = main_chunk("let n = 2; match n { 1 => 1, 2 => 2, 3 => 3, 4.5 => 4 };");
+    assert_eq!(table(&chunk), None);
+}
@@ -0,0 +1,48 @@
+use bytecode::{
+    chunk::Chunk, generate_bytecode_with_options, stmt::GlobalItem, Opcode, OptLevel, Options,
+};
+use parser::parse;
+
+fn main_chunk(code: &str) -> Chunk {
+    let ast = parse(code).expect("Program should parse");
+    let options = Options {
+        opt_level: OptLevel::None,
+        ..Options::default()
+    };
+    let bytecode = generate_bytecode_with_options(ast, options).expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    function.chunk.clone()
+}
+
+// Indexes the backward jumps land on, in the order of the jumps
+fn backward_targets(chunk: &Chunk) -> Vec<usize> {
+    chunk
+        .opcodes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, opcode)| match opcode {
+            Opcode::Jp(distance) if *distance < 0 => Some((index as isize + distance + 1) as usize),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn loops_jump_back_to_their_start() {
+    let chunk = main_chunk("let a = 0; while a < 3 { a = a + 1; }; a;");
+    let condition = chunk
+        .opcodes
+        .iter()
+        .position(|opcode| *opcode == Opcode::Lt)
+        .unwrap()
+        - 2;
+    assert_eq!(backward_targets(&chunk), vec![condition]);
+}
+
+#[test]
+fn continue_jumps_to_the_step_of_the_loop() {
+    let chunk = main_chunk("for (let i = 0; i < 3; i = i + 1) { if i == 1 { continue; }; i; };");
+    // The initializer jumps over the step, which comes right after it
+    assert_eq!(chunk.opcodes[1], Opcode::Jp(7));
+    assert_eq!(backward_targets(&chunk), vec![2, 2]);
+}
@@ -0,0 +1,90 @@
+use bytecode::{
+    chunk::Chunk, generate_bytecode_with_options, stmt::GlobalItem, Opcode, OptLevel, Options,
+};
+use parser::parse;
+
+fn main_chunk(code: &str, opt_level: OptLevel) -> Chunk {
+    let ast = parse(code).expect("Program should parse");
+    let options = Options {
+        opt_level,
+        ..Options::default()
+    };
+    let bytecode = generate_bytecode_with_options(ast, options).expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    function.chunk.clone()
+}
+
+fn count(chunk: &Chunk, matches: fn(&Opcode) -> bool) -> usize {
+    chunk
+        .opcodes
+        .iter()
+        .filter(|opcode| matches(opcode))
+        .count()
+}
+
+#[test]
+fn removes_jumps_on_known_conditions() {
+    let code = "let a = 0; if 1 < 2 { a = 1; }; a;";
+    let chunk = main_chunk(code, OptLevel::None);
+    assert_eq!(count(&chunk, |opcode| matches!(opcode, Opcode::Jif(_))), 1);
+
+    let chunk = main_chunk(code, OptLevel::Basic);
+    assert_eq!(count(&chunk, |opcode| matches!(opcode, Opcode::Jif(_))), 0);
+
+    // A condition that never holds jumps straight to the else branch
+    let chunk = main_chunk(
+        "let a = 0; if false { a = 1; } else { a = 2; }; a;",
+        OptLevel::Basic,
+    );
+    assert_eq!(count(&chunk, |opcode| matches!(opcode, Opcode::Jif(_))), 0);
+    assert!(matches!(chunk.opcodes[2], Opcode::Jp(_)));
+}
+
+#[test]
+fn removes_double_negations_of_bools() {
+    let chunk = main_chunk("let a = 1; !!(a < 2);", OptLevel::Basic);
+    assert_eq!(count(&chunk, |opcode| *opcode == Opcode::Not), 0);
+
+    // The VM fails on `!` of anything but a bool, so it has to run
+    let chunk = main_chunk("let a = 1; !!a;", OptLevel::Basic);
+    assert_eq!(count(&chunk, |opcode| *opcode == Opcode::Not), 2);
+}
+
+#[test]
+fn threads_jumps_to_jumps() {
+    let code = "let i = 0; while false { i = i + 1; }; i;";
+    let jumps_back = |chunk: &Chunk| {
+        chunk
+            .opcodes
+            .iter()
+            .any(|opcode| matches!(opcode, Opcode::Jp(distance) if *distance < 0))
+    };
+    assert!(jumps_back(&main_chunk(code, OptLevel::None)));
+
+    // The jump back to the condition lands on the jump that replaced it, which leaves the loop
+    let chunk = main_chunk(code, OptLevel::Basic);
+    assert!(!jumps_back(&chunk));
+    assert_eq!(count(&chunk, |opcode| matches!(opcode, Opcode::Jp(_))), 1);
+}
+
+#[test]
+fn fuses_comparisons_with_jumps() {
+    let code = "let i = 0; while i < 3 { i = i + 1; }; i;";
+    let chunk = main_chunk(code, OptLevel::Basic);
+    let fused = chunk
+        .opcodes
+        .iter()
+        .position(|opcode| matches!(opcode, Opcode::JifLt(_)))
+        .expect("Comparison should be fused");
+    // Both jump past the loop
+    match (chunk.opcodes[fused], chunk.opcodes[fused + 1]) {
+        (Opcode::JifLt(fused_distance), Opcode::Jif(distance)) => {
+            assert_eq!(fused_distance, distance + 1)
+        }
+        other => panic!("Expected the Jif after the comparison, got {:?}", other),
+    }
+    assert_eq!(count(&chunk, |opcode| *opcode == Opcode::Lt), 0);
+
+    let chunk = main_chunk(code, OptLevel::None);
+    assert_eq!(count(&chunk, |opcode| *opcode == Opcode::Lt), 1);
+}
@@ -0,0 +1,74 @@
+use bytecode::{
+    chunk::Chunk, generate_bytecode, generate_bytecode_with_options, stmt::GlobalItem, Opcode,
+    Options, ProgramBytecode,
+};
+use parser::parse;
+
+fn main_chunk(code: &str) -> Chunk {
+    let bytecode = generate_bytecode(parse(code).unwrap()).expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    function.chunk.clone()
+}
+
+fn source<'a>(code: &'a str, chunk: &Chunk, opcode: Opcode) -> &'a str {
+    let index = chunk
+        .opcodes
+        .iter()
+        .position(|written| *written == opcode)
+        .expect("Opcode should be written");
+    let span = chunk
+        .span(index)
+        .expect("Opcode should have a span")
+        .clone();
+    &code[span]
+}
+
+#[test]
+fn maps_opcodes_to_their_nodes() {
+    let code = "let a = 1;\n{ let b = a * 2;\nb + b };";
+    let chunk = main_chunk(code);
+    assert_eq!(source(code, &chunk, Opcode::Mul), "a * 2");
+    assert_eq!(source(code, &chunk, Opcode::Add), "b + b");
+    assert_eq!(source(code, &chunk, Opcode::Constant(0)), "1");
+    // Consecutive opcodes of the same node share an entry
+    assert!(chunk.spans.len() < chunk.opcodes.len());
+}
+
+#[test]
+fn keeps_spans_of_optimized_code() {
+    let code = "let i = 0; while i < 3 { i = i + 1; }; i;";
+    let chunk = main_chunk(code);
+    let fused = chunk
+        .opcodes
+        .iter()
+        .find(|opcode| matches!(opcode, Opcode::JifLt(_)))
+        .copied()
+        .unwrap();
+    assert_eq!(source(code, &chunk, fused), "i < 3");
+    assert_eq!(source(code, &chunk, Opcode::Add), "i + 1");
+}
+
+#[test]
+fn leaves_spans_out_without_debug_info() {
+    let code = "fn f(a) { a * 2 } f(1) + 1;";
+    let options = Options {
+        debug_info: false,
+        ..Options::default()
+    };
+    let bytecode = generate_bytecode_with_options(parse(code).unwrap(), options)
+        .expect("Program should compile");
+    for GlobalItem::Function(function) in &bytecode.globals {
+        assert!(function.chunk.spans.is_empty());
+        assert_eq!(function.chunk.span(0), None);
+    }
+    // The code is the same either way
+    let with_spans = generate_bytecode(parse(code).unwrap()).unwrap();
+    let opcodes = |bytecode: &ProgramBytecode| -> Vec<Vec<Opcode>> {
+        bytecode
+            .globals
+            .iter()
+            .map(|global| global.as_function().chunk.opcodes.clone())
+            .collect()
+    };
+    assert_eq!(opcodes(&bytecode), opcodes(&with_spans));
+}
@@ -0,0 +1,61 @@
+use bytecode::{
+    chunk::Chunk, generate_bytecode_with_options, stmt::GlobalItem, Opcode, OptLevel, Options,
+};
+use parser::parse;
+
+fn main_chunk(code: &str, opt_level: OptLevel) -> Chunk {
+    let ast = parse(code).expect("Program should parse");
+    let options = Options {
+        opt_level,
+        ..Options::default()
+    };
+    let bytecode = generate_bytecode_with_options(ast, options).expect("Program should compile");
+    let GlobalItem::Function(function) = &bytecode.globals[bytecode.global_fn_ptr];
+    function.chunk.clone()
+}
+
+// Amount of locals the outermost block of the program drops
+fn block_locals(chunk: &Chunk) -> usize {
+    chunk
+        .opcodes
+        .iter()
+        .rev()
+        .find_map(|opcode| match opcode {
+            Opcode::Block(declared) => Some(*declared),
+            _ => None,
+        })
+        .expect("Program should have a block")
+}
+
+#[test]
+fn removes_locals_that_are_never_read() {
+    let code = "{ let a = 1; let b = [2, 3]; let c = 4; c; };";
+    assert_eq!(block_locals(&main_chunk(code, OptLevel::Basic)), 3);
+    assert_eq!(block_locals(&main_chunk(code, OptLevel::Full)), 1);
+}
+
+#[test]
+fn keeps_locals_with_effects() {
+    let code = "fn f() { 1; } { let a = f(); let b = 1 + 2; let c = a; 5; };";
+    let chunk = main_chunk(code, OptLevel::Full);
+    assert_eq!(block_locals(&chunk), 3);
+    assert!(chunk.opcodes.contains(&Opcode::Call(0)));
+}
+
+#[test]
+fn keeps_locals_read_by_closures_or_assigned() {
+    let code = "{ let a = 1; let b = 2; let f = || a; b = 3; f; };";
+    assert_eq!(block_locals(&main_chunk(code, OptLevel::Full)), 3);
+}
+
+#[test]
+fn globals_are_kept() {
+    let code = "let a = 1; let b = 2; b;";
+    let chunk = main_chunk(code, OptLevel::Full);
+    let defined = chunk
+        .opcodes
+        .iter()
+        .filter(|opcode| matches!(opcode, Opcode::DefineGlobal(_)))
+        .count();
+    assert_eq!(defined, 2);
+}
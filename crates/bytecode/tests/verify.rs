@@ -0,0 +1,298 @@
+use bytecode::{
+    callables::Function,
+    chunk::{Chunk, Constant, JumpTable},
+    generate_bytecode,
+    stmt::GlobalItem,
+    verify, MemoryAddress, Opcode, ProgramBytecode, VerificationError, VerificationErrorCause,
+};
+use parser::parse;
+
+fn function(
+    name: &str,
+    arity: usize,
+    opcodes: Vec<Opcode>,
+    constants: Vec<Constant>,
+) -> GlobalItem {
+    GlobalItem::Function(Function {
+        arity,
+        variadic: false,
+        chunk: Chunk::new(opcodes, constants),
+        name: name.into(),
+        max_stack: 8,
+    })
+}
+
+fn main_fn(opcodes: Vec<Opcode>, constants: Vec<Constant>) -> ProgramBytecode {
+    ProgramBytecode {
+        global_fn_ptr: 0,
+        globals: vec![function("main", 0, opcodes, constants)],
+    }
+}
+
+fn cause(program: &ProgramBytecode) -> VerificationErrorCause {
+    verify(program)
+        .expect_err("Program should be rejected")
+        .cause
+}
+
+#[test]
+fn accepts_generated_programs() {
+    let programs = [
+        "let a = 1; a + 2;",
+        "fn f(x, ...rest) { if x > 0 { return rest; }; [x, ...rest] } f(1, 2, 3); f(...[0, 1]);",
+        "let total = 0; for i in 0..10 { if i == 3 { continue; }; total = total + i; }; total;",
+        "fn f() { let s = 0; for i in 0..5 { s = s + match i { 0 => 1, 4 => { break s; }, _ => i }; }; s } f();",
+        "fn counter() { let n = 0; fn inc() { n = n + 1; } inc } counter()();",
+        "let n = 2; match n { 1 => \"a\", 2 => \"b\", 3 => \"c\", 4 => \"d\", _ => \"e\" };",
+        "let o = { x: 1 }; o.x = o.x + 1; let m = { \"a\": 1 }; m[\"a\"];",
+    ];
+    for code in programs {
+        let program = generate_bytecode(parse(code).unwrap()).expect("Program should compile");
+        assert_eq!(verify(&program), Ok(()), "{}", code);
+    }
+}
+
+#[test]
+fn rejects_invalid_operands() {
+    assert_eq!(
+        verify(&main_fn(vec![Opcode::Null, Opcode::Jp(5)], vec![])),
+        Err(VerificationError {
+            function: 0,
+            index: 1,
+            cause: VerificationErrorCause::JumpOutOfRange(7),
+        })
+    );
+    assert_eq!(
+        cause(&main_fn(
+            vec![Opcode::Constant(1)],
+            vec![Constant::Bool(true)]
+        )),
+        VerificationErrorCause::InvalidConstant(1)
+    );
+    let number = vec![Constant::Number(1.0)];
+    assert_eq!(
+        cause(&main_fn(
+            vec![Opcode::Null, Opcode::JumpTable(0)],
+            number.clone()
+        )),
+        VerificationErrorCause::UnexpectedConstant(0)
+    );
+    assert_eq!(
+        cause(&main_fn(vec![Opcode::GetGlobal(0)], number)),
+        VerificationErrorCause::UnexpectedConstant(0)
+    );
+    assert_eq!(
+        cause(&main_fn(
+            vec![Opcode::Constant(0), Opcode::CreateClosure(0)],
+            vec![Constant::GlobalPointer(3)]
+        )),
+        VerificationErrorCause::InvalidGlobalPointer(3)
+    );
+    let program = ProgramBytecode {
+        global_fn_ptr: 1,
+        globals: vec![],
+    };
+    assert_eq!(
+        cause(&program),
+        VerificationErrorCause::InvalidGlobalPointer(1)
+    );
+}
+
+#[test]
+fn rejects_operands_of_the_wrong_kind() {
+    let code = "fn counter() { let n = 0; fn inc() { n = n + 1; } inc } counter()();";
+    let program = generate_bytecode(parse(code).unwrap()).expect("Program should compile");
+    let changed = |function: usize, index: usize, opcode: Opcode| {
+        let mut program = program.clone();
+        let GlobalItem::Function(changed) = &mut program.globals[function];
+        changed.chunk.opcodes[index] = opcode;
+        verify(&program)
+    };
+    // `counter` creates the closure from the number 0 instead of the pointer to `inc`
+    assert_eq!(
+        changed(1, 1, Opcode::Constant(0)),
+        Err(VerificationError {
+            function: 1,
+            index: 3,
+            cause: VerificationErrorCause::UnexpectedOperand,
+        })
+    );
+    // The address of `inc` isn't a function
+    assert_eq!(
+        changed(1, 5, Opcode::CreateClosure(0)),
+        Err(VerificationError {
+            function: 1,
+            index: 5,
+            cause: VerificationErrorCause::UnexpectedOperand,
+        })
+    );
+
+    let number = || vec![Constant::Number(1.0)];
+    let pointer_and_number = || vec![Constant::GlobalPointer(0), Constant::Number(1.0)];
+    let programs = [
+        main_fn(vec![Opcode::Constant(0), Opcode::Get], number()),
+        main_fn(
+            vec![Opcode::Constant(0), Opcode::Null, Opcode::Asg],
+            number(),
+        ),
+        main_fn(
+            vec![
+                Opcode::Constant(0),
+                Opcode::Constant(1),
+                Opcode::CreateClosure(1),
+            ],
+            pointer_and_number(),
+        ),
+        main_fn(
+            vec![Opcode::Null, Opcode::Constant(0), Opcode::CreateMap(1)],
+            number(),
+        ),
+        main_fn(
+            vec![Opcode::Null, Opcode::Constant(0), Opcode::CreateObject(1)],
+            number(),
+        ),
+        main_fn(
+            vec![
+                Opcode::Null,
+                Opcode::Constant(0),
+                Opcode::GetProperty { bind_method: false },
+            ],
+            number(),
+        ),
+        main_fn(
+            vec![
+                Opcode::Null,
+                Opcode::Constant(0),
+                Opcode::Null,
+                Opcode::SetProperty(1),
+            ],
+            number(),
+        ),
+    ];
+    for program in &programs {
+        assert_eq!(cause(program), VerificationErrorCause::UnexpectedOperand);
+    }
+}
+
+#[test]
+fn rejects_unbalanced_stacks() {
+    let number = || vec![Constant::Number(1.0)];
+    assert_eq!(
+        cause(&main_fn(vec![Opcode::Constant(0), Opcode::Add], number())),
+        VerificationErrorCause::StackUnderflow
+    );
+    // Only one of the paths pushes a value before they meet
+    assert_eq!(
+        verify(&main_fn(
+            vec![
+                Opcode::Constant(0),
+                Opcode::Constant(0),
+                Opcode::Jif(1),
+                Opcode::Null,
+                Opcode::Null
+            ],
+            vec![Constant::Bool(true)]
+        ))
+        .unwrap_err(),
+        VerificationError {
+            function: 0,
+            index: 4,
+            cause: VerificationErrorCause::StackMismatch,
+        }
+    );
+    // The size of a spread group has to be pushed after its values
+    assert_eq!(
+        cause(&main_fn(
+            vec![Opcode::Null, Opcode::Null, Opcode::CreateSpreadArray(1)],
+            vec![]
+        )),
+        VerificationErrorCause::MissingGroupSize
+    );
+    assert_eq!(
+        cause(&main_fn(
+            vec![
+                Opcode::Null,
+                Opcode::Constant(0),
+                Opcode::CreateSpreadArray(1)
+            ],
+            vec![Constant::Number(2.0)]
+        )),
+        VerificationErrorCause::StackUnderflow
+    );
+    assert_eq!(
+        cause(&main_fn(
+            vec![Opcode::Constant(0), Opcode::Get],
+            vec![MemoryAddress::Local(0).into()]
+        )),
+        VerificationErrorCause::InvalidLocal(0)
+    );
+    let table = JumpTable {
+        arms: Default::default(),
+        default: 1,
+    };
+    assert_eq!(
+        cause(&main_fn(
+            vec![Opcode::Null, Opcode::JumpTable(0), Opcode::Null],
+            vec![Constant::JumpTable(table)]
+        )),
+        VerificationErrorCause::MissingResult
+    );
+}
+
+#[test]
+fn rejects_functions_without_returns() {
+    let program = ProgramBytecode {
+        global_fn_ptr: 1,
+        globals: vec![
+            function("f", 1, vec![Opcode::Null], vec![]),
+            function("main", 0, vec![Opcode::Null], vec![]),
+        ],
+    };
+    assert_eq!(
+        verify(&program),
+        Err(VerificationError {
+            function: 0,
+            index: 1,
+            cause: VerificationErrorCause::MissingReturn,
+        })
+    );
+    assert_eq!(
+        cause(&main_fn(vec![Opcode::Null, Opcode::Return], vec![])),
+        VerificationErrorCause::ReturnOutsideFunction
+    );
+    assert_eq!(
+        cause(&main_fn(vec![], vec![])),
+        VerificationErrorCause::MissingResult
+    );
+}
+
+#[test]
+fn measures_the_stack_functions_need() {
+    let code = "fn add(a, b) { a + b } add(1, 2) * 3;";
+    let program = generate_bytecode(parse(code).unwrap()).expect("Program should compile");
+    let max_stack = |name: &str| {
+        program
+            .globals
+            .iter()
+            .map(GlobalItem::as_function)
+            .find(|function| function.name == name)
+            .unwrap()
+            .max_stack
+    };
+    // The frame, the value of `a` and the address of `b`
+    assert_eq!(max_stack("add"), 6);
+    // The arguments and the function they are passed to
+    assert_eq!(max_stack("main"), 3);
+
+    let mut program = main_fn(vec![Opcode::Null, Opcode::Null, Opcode::Pop(1)], vec![]);
+    let GlobalItem::Function(main) = &mut program.globals[0];
+    main.max_stack = 1;
+    assert_eq!(
+        verify(&program),
+        Err(VerificationError {
+            function: 0,
+            index: 0,
+            cause: VerificationErrorCause::StackSizeTooSmall(2),
+        })
+    );
+}
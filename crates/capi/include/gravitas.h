@@ -0,0 +1,69 @@
+// C interface for embedding the Gravitas VM, implemented by the gravitas-capi crate.
+//
+// Every object returned by a gv_*_new, gv_compile or gv_run call is owned by the caller
+// and must be released with the matching gv_*_free function. Strings returned by the
+// library are borrowed and stay valid as long as the object they came from.
+
+#ifndef GRAVITAS_H
+#define GRAVITAS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct GvVm GvVm;
+typedef struct GvProgram GvProgram;
+typedef struct GvValue GvValue;
+
+typedef enum GvValueType {
+    GV_NULL = 0,
+    GV_NUMBER = 1,
+    GV_BOOL = 2,
+    GV_STRING = 3,
+    // Functions, objects, arrays and buffers, which can't be inspected from C yet
+    GV_OTHER = 4,
+} GvValueType;
+
+// Called with the arguments in the order they were written in the script. The arguments
+// are only valid during the call. Returns a value created with one of the gv_value_new_*
+// functions, which the VM takes ownership of, or NULL to fail the script with an error.
+typedef GvValue *(*GvHostFn)(void *user_data, const GvValue *const *args, size_t argc);
+
+GvVm *gv_vm_new(void);
+void gv_vm_free(GvVm *vm);
+
+// Message of the last failed call made with the vm, or NULL if the last call succeeded
+const char *gv_last_error(const GvVm *vm);
+
+// Makes the function callable by name from every program compiled afterwards.
+// Returns 0 on success and -1 if the name is invalid or already registered.
+int32_t gv_register_fn(GvVm *vm, const char *name, size_t arity, GvHostFn fn, void *user_data);
+
+// Returns NULL if the source doesn't compile, see gv_last_error for the reason
+GvProgram *gv_compile(GvVm *vm, const char *source);
+void gv_program_free(GvProgram *program);
+
+// Runs the program in a fresh machine and returns its result, or NULL on a runtime error
+GvValue *gv_run(GvVm *vm, const GvProgram *program);
+
+GvValueType gv_value_type(const GvValue *value);
+// Return 0, false and NULL respectively when the value has a different type
+double gv_value_number(const GvValue *value);
+bool gv_value_bool(const GvValue *value);
+const char *gv_value_string(const GvValue *value);
+
+GvValue *gv_value_new_null(void);
+GvValue *gv_value_new_number(double number);
+GvValue *gv_value_new_bool(bool value);
+GvValue *gv_value_new_string(const char *text);
+void gv_value_free(GvValue *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
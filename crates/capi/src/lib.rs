@@ -0,0 +1,301 @@
+// The contract of every function is described in include/gravitas.h
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::{c_void, CStr, CString},
+    os::raw::c_char,
+    ptr,
+};
+
+use analyzer::Analyzer;
+use bytecode::{generate_bytecode_with_host_functions, ProgramBytecode};
+use common::{CompilerDiagnostic, ProgramText};
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, VM};
+
+// Diagnostics aren't rendered against a file, so the id is never looked up
+const FILE_ID: usize = 0;
+
+pub type GvHostFn =
+    extern "C" fn(user_data: *mut c_void, args: *const *const GvValue, argc: usize) -> *mut GvValue;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GvValueType {
+    Null = 0,
+    Number = 1,
+    Bool = 2,
+    String = 3,
+    // Functions, objects, arrays and buffers, which can't be inspected from C yet
+    Other = 4,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HostEntry {
+    arity: usize,
+    function: GvHostFn,
+    user_data: *mut c_void,
+}
+
+// Registered host functions and the last error, shared by every program it compiles and runs
+#[derive(Debug, Default)]
+pub struct GvVm {
+    names: Vec<ProgramText>,
+    host_functions: Vec<HostEntry>,
+    last_error: Option<CString>,
+}
+
+#[derive(Debug)]
+pub struct GvProgram {
+    bytecode: ProgramBytecode,
+    // Programs can only run in a VM with the same host functions they were compiled with
+    host_functions: usize,
+}
+
+#[derive(Debug)]
+pub struct GvValue {
+    value: RuntimeValue,
+    // Kept next to the value so the pointer given out by gv_value_string stays valid
+    text: Option<CString>,
+}
+
+impl GvValue {
+    fn new(value: RuntimeValue) -> Self {
+        let text = match &value {
+            RuntimeValue::String(text) => Some(c_string(text)),
+            _ => None,
+        };
+        Self { value, text }
+    }
+
+    fn into_raw(self) -> *mut GvValue {
+        Box::into_raw(Box::new(self))
+    }
+}
+
+// Interior nul bytes can't be represented in C, so the string is cut at the first one
+fn c_string(text: &str) -> CString {
+    let end = text.find('\0').unwrap_or(text.len());
+    CString::new(&text[..end]).expect("Nul bytes were removed")
+}
+
+fn messages(errors: Vec<impl CompilerDiagnostic>) -> String {
+    errors
+        .iter()
+        .map(|error| error.report(FILE_ID).message)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+unsafe fn str_arg<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+impl GvVm {
+    fn fail<T>(&mut self, message: &str) -> *mut T {
+        self.last_error = Some(c_string(message));
+        ptr::null_mut()
+    }
+
+    fn compile(&self, code: &str) -> Result<ProgramBytecode, String> {
+        let ast = parse(code).map_err(messages)?;
+        Analyzer::new()
+            .with_host_functions(&self.names)
+            .analyze(&ast)
+            .map_err(messages)?;
+        generate_bytecode_with_host_functions(ast, &self.names).map_err(|error| messages(vec![error]))
+    }
+
+    fn machine(&self) -> VM {
+        let mut machine = VM::new(VmOptions::default());
+        for (name, entry) in self.names.iter().zip(self.host_functions.iter().copied()) {
+            machine.register_host_function(name, entry.arity, move |args| {
+                let args: Vec<GvValue> = args.into_iter().map(GvValue::new).collect();
+                let arg_ptrs: Vec<*const GvValue> =
+                    args.iter().map(|arg| arg as *const GvValue).collect();
+
+                let result = (entry.function)(entry.user_data, arg_ptrs.as_ptr(), arg_ptrs.len());
+                if result.is_null() {
+                    return Err(RuntimeErrorCause::HostFunctionFailed);
+                }
+                Ok(unsafe { Box::from_raw(result) }.value)
+            });
+        }
+        machine
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn gv_vm_new() -> *mut GvVm {
+    Box::into_raw(Box::new(GvVm::default()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_vm_free(vm: *mut GvVm) {
+    if !vm.is_null() {
+        drop(Box::from_raw(vm));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_last_error(vm: *const GvVm) -> *const c_char {
+    match vm.as_ref().and_then(|vm| vm.last_error.as_ref()) {
+        Some(error) => error.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_register_fn(
+    vm: *mut GvVm,
+    name: *const c_char,
+    arity: usize,
+    function: GvHostFn,
+    user_data: *mut c_void,
+) -> i32 {
+    let vm = match vm.as_mut() {
+        Some(vm) => vm,
+        None => return -1,
+    };
+    let name = match str_arg(name) {
+        Some(name) => name,
+        None => {
+            vm.fail::<()>("Function name must be valid UTF-8");
+            return -1;
+        }
+    };
+    if vm.names.iter().any(|registered| registered == name) {
+        vm.fail::<()>(&format!("Function {} is already registered", name));
+        return -1;
+    }
+
+    vm.names.push(name.to_owned());
+    vm.host_functions.push(HostEntry {
+        arity,
+        function,
+        user_data,
+    });
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_compile(vm: *mut GvVm, source: *const c_char) -> *mut GvProgram {
+    let vm = match vm.as_mut() {
+        Some(vm) => vm,
+        None => return ptr::null_mut(),
+    };
+    let code = match str_arg(source) {
+        Some(code) => code,
+        None => return vm.fail("Source must be valid UTF-8"),
+    };
+
+    match vm.compile(code) {
+        Ok(bytecode) => {
+            vm.last_error = None;
+            let program = GvProgram {
+                bytecode,
+                host_functions: vm.names.len(),
+            };
+            Box::into_raw(Box::new(program))
+        }
+        Err(message) => vm.fail(&message),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_program_free(program: *mut GvProgram) {
+    if !program.is_null() {
+        drop(Box::from_raw(program));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_run(vm: *mut GvVm, program: *const GvProgram) -> *mut GvValue {
+    let vm = match vm.as_mut() {
+        Some(vm) => vm,
+        None => return ptr::null_mut(),
+    };
+    let program = match program.as_ref() {
+        Some(program) => program,
+        None => return vm.fail("Program is null"),
+    };
+    if program.host_functions > vm.names.len() {
+        return vm.fail("Program was compiled with host functions this VM doesn't have");
+    }
+
+    match vm.machine().run(program.bytecode.clone()) {
+        Ok(value) => {
+            vm.last_error = None;
+            GvValue::new(value).into_raw()
+        }
+        Err(error) => vm.fail(&format!("Runtime error: {:?}", error.cause)),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_value_free(value: *mut GvValue) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_value_type(value: *const GvValue) -> GvValueType {
+    match value.as_ref().map(|value| &value.value) {
+        None | Some(RuntimeValue::Null) => GvValueType::Null,
+        Some(RuntimeValue::Number(_)) => GvValueType::Number,
+        Some(RuntimeValue::Bool(_)) => GvValueType::Bool,
+        Some(RuntimeValue::String(_)) => GvValueType::String,
+        Some(_) => GvValueType::Other,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_value_number(value: *const GvValue) -> f64 {
+    match value.as_ref().map(|value| &value.value) {
+        Some(RuntimeValue::Number(number)) => *number,
+        _ => 0.0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_value_bool(value: *const GvValue) -> bool {
+    matches!(
+        value.as_ref().map(|value| &value.value),
+        Some(RuntimeValue::Bool(true))
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_value_string(value: *const GvValue) -> *const c_char {
+    match value.as_ref().and_then(|value| value.text.as_ref()) {
+        Some(text) => text.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn gv_value_new_null() -> *mut GvValue {
+    GvValue::new(RuntimeValue::Null).into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn gv_value_new_number(number: f64) -> *mut GvValue {
+    GvValue::new(RuntimeValue::Number(number)).into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn gv_value_new_bool(value: bool) -> *mut GvValue {
+    GvValue::new(RuntimeValue::Bool(value)).into_raw()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gv_value_new_string(text: *const c_char) -> *mut GvValue {
+    match str_arg(text) {
+        Some(text) => GvValue::new(RuntimeValue::String(text.to_owned())).into_raw(),
+        None => ptr::null_mut(),
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    ffi::{c_void, CStr, CString},
+    ptr, slice,
+};
+
+use gravitas_capi::*;
+
+extern "C" fn add(
+    user_data: *mut c_void,
+    args: *const *const GvValue,
+    argc: usize,
+) -> *mut GvValue {
+    let calls = unsafe { &mut *(user_data as *mut usize) };
+    *calls += 1;
+
+    let args = unsafe { slice::from_raw_parts(args, argc) };
+    let sum = unsafe { gv_value_number(args[0]) + gv_value_number(args[1]) };
+    gv_value_new_number(sum)
+}
+
+extern "C" fn fail(_: *mut c_void, _: *const *const GvValue, _: usize) -> *mut GvValue {
+    ptr::null_mut()
+}
+
+fn source(code: &str) -> CString {
+    CString::new(code).unwrap()
+}
+
+#[test]
+fn runs_programs_calling_host_functions() {
+    let mut calls = 0usize;
+
+    unsafe {
+        let vm = gv_vm_new();
+        let name = source("add");
+        let user_data = &mut calls as *mut usize as *mut c_void;
+        assert_eq!(gv_register_fn(vm, name.as_ptr(), 2, add, user_data), 0);
+        // Names can't be registered twice
+        assert_eq!(gv_register_fn(vm, name.as_ptr(), 2, add, user_data), -1);
+
+        let program = gv_compile(vm, source("add(40, 2);").as_ptr());
+        assert!(!program.is_null());
+
+        let result = gv_run(vm, program);
+        assert_eq!(gv_value_type(result), GvValueType::Number);
+        assert_eq!(gv_value_number(result), 42.0);
+        assert!(gv_last_error(vm).is_null());
+
+        gv_value_free(result);
+        gv_program_free(program);
+        gv_vm_free(vm);
+    }
+
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn reports_errors_through_last_error() {
+    unsafe {
+        let vm = gv_vm_new();
+        let program = gv_compile(vm, source("undefined_fn(1);").as_ptr());
+        assert!(program.is_null());
+        let error = CStr::from_ptr(gv_last_error(vm)).to_str().unwrap();
+        assert_eq!(error, "Variable was used but it's not defined anywhere");
+
+        gv_register_fn(vm, source("fail").as_ptr(), 0, fail, ptr::null_mut());
+        let program = gv_compile(vm, source("fail();").as_ptr());
+        assert!(gv_run(vm, program).is_null());
+        let error = CStr::from_ptr(gv_last_error(vm)).to_str().unwrap();
+        assert_eq!(error, "Runtime error: HostFunctionFailed");
+
+        gv_program_free(program);
+        gv_vm_free(vm);
+    }
+}
+
+#[test]
+fn converts_strings_both_ways() {
+    unsafe {
+        let vm = gv_vm_new();
+        let program = gv_compile(vm, source("\"gravitas\";").as_ptr());
+        let result = gv_run(vm, program);
+
+        assert_eq!(gv_value_type(result), GvValueType::String);
+        assert_eq!(
+            CStr::from_ptr(gv_value_string(result)).to_str(),
+            Ok("gravitas")
+        );
+        assert!(gv_value_string(ptr::null()).is_null());
+
+        let text = gv_value_new_string(source("embedded").as_ptr());
+        assert_eq!(
+            CStr::from_ptr(gv_value_string(text)).to_str(),
+            Ok("embedded")
+        );
+
+        gv_value_free(text);
+        gv_value_free(result);
+        gv_program_free(program);
+        gv_vm_free(vm);
+    }
+}
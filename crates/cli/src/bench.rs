@@ -0,0 +1,280 @@
+use std::{
+    cell::RefCell,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    process::{exit, Command},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use analyzer::analyze;
+use bytecode::{generate_bytecode, Opcode};
+use clap::Args;
+use parser::parse;
+use vm::{hook::ExecutionHook, options::VmOptions, VM};
+
+use crate::compiler::{compile, log_errors};
+
+const PHASES: [&str; 4] = ["parse", "analyze", "codegen", "execute"];
+
+/// Measure how long each compilation phase and the execution of a program take
+#[derive(Debug, Args)]
+pub(crate) struct Bench {
+    /// Runs that aren't measured, so caches are warm before the measured ones
+    #[clap(long, default_value_t = 3)]
+    pub(crate) warmup: usize,
+    #[clap(long, short = 'n', default_value_t = 10)]
+    pub(crate) repetitions: usize,
+    /// Git revision of the file to benchmark instead of the working copy, pass twice to compare two revisions
+    #[clap(long = "rev", num_args = 1, conflicts_with = "compare_with")]
+    pub(crate) revisions: Vec<String>,
+    file_path: PathBuf,
+    /// Second program to compare the first one against
+    compare_with: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counters {
+    instructions: usize,
+    peak_stack: usize,
+}
+
+// Collects the counters during one extra run, so they don't slow down the timed ones
+#[derive(Debug, Clone, Default)]
+struct CountingHook(Rc<RefCell<Counters>>);
+
+impl ExecutionHook for CountingHook {
+    fn before_instruction(&mut self, vm: &VM, _: &Opcode) {
+        let mut counters = self.0.borrow_mut();
+        counters.instructions += 1;
+        counters.peak_stack = counters.peak_stack.max(vm.operands().len());
+    }
+}
+
+struct Report {
+    label: String,
+    // Measurements of every phase, in the order of PHASES
+    timings: [Vec<Duration>; 4],
+    counters: Counters,
+    heap_objects: usize,
+}
+
+impl Report {
+    fn mean(&self, phase: usize) -> Duration {
+        let timings = &self.timings[phase];
+        timings.iter().sum::<Duration>() / timings.len().max(1) as u32
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn time<T>(measure: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = measure();
+    (result, start.elapsed())
+}
+
+fn exit_with_errors(label: &str) -> ! {
+    eprintln!(
+        "{} failed, see above errors to find out what went wrong.",
+        label
+    );
+    exit(1)
+}
+
+// Runs the whole pipeline once and returns how long each phase took
+fn measure(code: &str, label: &str) -> [Duration; 4] {
+    let (ast, parse_time) = time(|| parse(code));
+    let ast = ast.unwrap_or_else(|errors| {
+        log_errors(errors, code);
+        exit_with_errors(label)
+    });
+
+    let (analysis, analyze_time) = time(|| analyze(&ast));
+    if let Err(errors) = analysis {
+        log_errors(errors, code);
+        exit_with_errors(label);
+    }
+
+    let (bytecode, codegen_time) = time(|| generate_bytecode(ast));
+    let bytecode = bytecode.unwrap_or_else(|_| exit_with_errors(label));
+
+    let mut vm = VM::new(VmOptions::default());
+    let (output, execute_time) = time(|| vm.run(bytecode));
+    if let Err(error) = output {
+        eprintln!("Runtime error: {:?}", error.cause);
+        exit_with_errors(label);
+    }
+
+    [parse_time, analyze_time, codegen_time, execute_time]
+}
+
+fn git_show(revision: &str, path: &Path) -> String {
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("{}:./{}", revision, path.display()))
+        .output()
+        .expect("Couldn't run git");
+
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        exit(1);
+    }
+
+    String::from_utf8(output.stdout).expect("File isn't valid UTF-8")
+}
+
+impl Bench {
+    // Labels and code of the programs to benchmark
+    fn sources(&self) -> Vec<(String, String)> {
+        let read = |path: &PathBuf| {
+            let code = read_to_string(path).expect("File not found!");
+            (path.display().to_string(), code)
+        };
+
+        let mut sources: Vec<(String, String)> = self
+            .revisions
+            .iter()
+            .map(|revision| {
+                let label = format!("{}@{}", self.file_path.display(), revision);
+                (label, git_show(revision, &self.file_path))
+            })
+            .collect();
+
+        // A single revision is compared with the working copy
+        if sources.len() < 2 {
+            sources.push(read(&self.file_path));
+        }
+
+        if let Some(path) = &self.compare_with {
+            sources.push(read(path));
+        }
+
+        sources
+    }
+
+    fn bench(&self, label: String, code: &str) -> Report {
+        for _ in 0..self.warmup {
+            measure(code, &label);
+        }
+
+        let mut timings: [Vec<Duration>; 4] = Default::default();
+        for _ in 0..self.repetitions {
+            for (phase, duration) in measure(code, &label).iter().enumerate() {
+                timings[phase].push(*duration);
+            }
+        }
+
+        let bytecode = compile(code).unwrap_or_else(|_| exit_with_errors(&label));
+        let hook = CountingHook::default();
+        let mut vm = VM::new(VmOptions::default()).with_hook(hook.clone());
+        if vm.run(bytecode).is_err() {
+            exit_with_errors(&label);
+        }
+
+        let counters = *hook.0.borrow();
+        Report {
+            label,
+            timings,
+            counters,
+            heap_objects: vm.heap_objects(),
+        }
+    }
+
+    pub(crate) fn run(&self) {
+        if self.revisions.len() > 2 {
+            eprintln!("At most two revisions can be compared");
+            exit(1);
+        }
+
+        let reports: Vec<Report> = self
+            .sources()
+            .into_iter()
+            .map(|(label, code)| self.bench(label, &code))
+            .collect();
+
+        for report in &reports {
+            println!(
+                "{} ({} runs after {} warmups)",
+                report.label, self.repetitions, self.warmup
+            );
+            println!("  {:<10}{:>12}{:>12}{:>12}", "phase", "mean", "min", "max");
+
+            for (phase, name) in PHASES.iter().enumerate() {
+                let timings = &report.timings[phase];
+                let min = timings.iter().min().copied().unwrap_or_default();
+                let max = timings.iter().max().copied().unwrap_or_default();
+                println!(
+                    "  {:<10}{:>12}{:>12}{:>12}",
+                    name,
+                    millis(report.mean(phase)),
+                    millis(min),
+                    millis(max)
+                );
+            }
+
+            println!("  instructions executed: {}", report.counters.instructions);
+            println!("  peak operand stack: {}", report.counters.peak_stack);
+            println!("  heap objects allocated: {}", report.heap_objects);
+            println!();
+        }
+
+        if let [baseline, other] = &reports[..] {
+            println!("{} compared to {}", other.label, baseline.label);
+            for (phase, name) in PHASES.iter().enumerate() {
+                let baseline_mean = baseline.mean(phase).as_secs_f64();
+                let ratio = other.mean(phase).as_secs_f64() / baseline_mean.max(f64::EPSILON);
+                println!("  {:<10}{:>11.2}x", name, ratio);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::{millis, Bench, Counters, Report};
+
+    fn bench(repetitions: usize) -> Bench {
+        Bench {
+            warmup: 1,
+            repetitions,
+            revisions: vec![],
+            file_path: PathBuf::from("bench.gv"),
+            compare_with: None,
+        }
+    }
+
+    #[test]
+    fn averages_the_timings_of_a_phase() {
+        let report = Report {
+            label: "bench.gv".to_owned(),
+            timings: [
+                vec![Duration::from_millis(1), Duration::from_millis(3)],
+                vec![],
+                vec![],
+                vec![],
+            ],
+            counters: Counters::default(),
+            heap_objects: 0,
+        };
+        assert_eq!(report.mean(0), Duration::from_millis(2));
+        // Phases without measurements don't divide by zero
+        assert_eq!(report.mean(1), Duration::ZERO);
+        assert_eq!(millis(Duration::from_micros(1500)), "1.500ms");
+    }
+
+    #[test]
+    fn measures_every_repetition_and_counts_instructions() {
+        let report = bench(3).bench("loop".to_owned(), "let a = 0; while a < 10 { a = a + 1; };");
+        assert!(report.timings.iter().all(|timings| timings.len() == 3));
+        assert!(report.counters.instructions > 10);
+        assert!(report.counters.peak_stack > 0);
+
+        let fewer = bench(3).bench("loop".to_owned(), "let a = 0; while a < 5 { a = a + 1; };");
+        assert!(fewer.counters.instructions < report.counters.instructions);
+    }
+}
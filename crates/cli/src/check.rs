@@ -0,0 +1,35 @@
+use std::{fs::read_to_string, path::PathBuf, process::exit};
+
+use analyzer::types::typecheck;
+use clap::Args;
+
+use crate::compiler::{compile_file, log_errors};
+
+/// Report errors in a program without running it
+#[derive(Debug, Args)]
+pub(crate) struct Check {
+    /// Also report operations on values of the wrong type
+    #[clap(long, action)]
+    pub(crate) types: bool,
+    file_path: PathBuf,
+}
+
+impl Check {
+    pub(crate) fn run(&self) {
+        let code = read_to_string(&self.file_path).expect("File not found!");
+
+        if compile_file(&self.file_path).is_err() {
+            exit(1);
+        }
+
+        if self.types {
+            let ast = parser::parse(&code).expect("The program compiled");
+            if let Err(errors) = typecheck(&ast) {
+                log_errors(errors, &code);
+                exit(1);
+            }
+        }
+
+        println!("{}: no errors found", self.file_path.display());
+    }
+}
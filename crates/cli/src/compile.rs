@@ -0,0 +1,30 @@
+use std::{fs, path::PathBuf, process::exit};
+
+use bytecode::binary::FILE_EXTENSION;
+use clap::Args;
+
+use crate::compiler::compile_file;
+
+/// Compile a program to a .gvbc file, which `run` executes without compiling it again
+#[derive(Debug, Args)]
+pub(crate) struct Compile {
+    file_path: PathBuf,
+    /// Where to write the bytecode, next to the program by default
+    #[clap(long, short)]
+    output: Option<PathBuf>,
+}
+
+impl Compile {
+    pub(crate) fn run(&self) {
+        let bytecode = match compile_file(&self.file_path) {
+            Ok(bytecode) => bytecode,
+            Err(_) => exit(1),
+        };
+
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| self.file_path.with_extension(FILE_EXTENSION));
+        fs::write(&output, bytecode.to_bytes()).expect("Couldn't write the bytecode");
+    }
+}
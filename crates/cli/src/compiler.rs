@@ -1,5 +1,6 @@
-use analyzer::analyze;
-use bytecode::generate_bytecode;
+use analyzer::Analyzer;
+use bytecode::{binary::FILE_EXTENSION, generate_bytecode_with_globals, ProgramBytecode};
+use clap::Args;
 use codespan_reporting::{
     files::SimpleFiles,
     term::{
@@ -7,10 +8,14 @@ use codespan_reporting::{
         termcolor::{ColorChoice, StandardStream},
     },
 };
-use common::CompilerDiagnostic;
-use parser::{parse, parse::Program};
-use std::path::Path;
-use vm::{run, runtime_value::RuntimeValue};
+use common::{diagnostics::SourceDb, CompilerDiagnostic};
+use parser::parse;
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+use vm::{hook::JsonTrace, options::VmOptions, ProgramOutput, VM};
 
 pub(crate) fn log_errors(errors: Vec<impl CompilerDiagnostic>, code: &str) {
     let mut files = SimpleFiles::new();
@@ -23,29 +28,79 @@ pub(crate) fn log_errors(errors: Vec<impl CompilerDiagnostic>, code: &str) {
     }
 }
 
-pub(crate) fn compile(code: &str) -> Program {
-    parse(code)
-        .and_then(|ast| {
-            if let Err(errors) = analyze(&ast) {
-                return Err(errors);
-            }
-            Ok(ast)
-        })
-        .map_err(|errors| log_errors(errors, code))
-        .expect("Compilation failed. See above errors to find out what went wrong.")
+// Runs every compilation stage and reports the errors of the first one that failed
+pub(crate) fn compile(code: &str) -> Result<ProgramBytecode, ()> {
+    compile_with_globals(code, &[])
+}
+
+// The code can use the top-level variables of the programs the VM ran before
+pub(crate) fn compile_with_globals(code: &str, globals: &[String]) -> Result<ProgramBytecode, ()> {
+    let ast = parse(code).map_err(|errors| log_errors(errors, code))?;
+    Analyzer::new()
+        .with_globals(globals)
+        .analyze(&ast)
+        .map_err(|errors| log_errors(errors, code))?;
+    generate_bytecode_with_globals(ast, &[], globals).map_err(|error| log_errors(vec![error], code))
+}
+
+// VM settings shared by the commands that execute programs
+#[derive(Debug, Args)]
+pub(crate) struct VmFlags {
+    #[clap(long, short, action)]
+    pub(crate) debug: bool,
+    #[clap(long, action)]
+    pub(crate) allow_eval: bool,
+    /// Write every executed instruction to the file as JSON Lines
+    #[clap(long)]
+    pub(crate) trace: Option<PathBuf>,
 }
 
-pub(crate) fn compile_and_run(code: &str, debug: bool) -> RuntimeValue {
-    let ast = compile(code);
-    analyze(&ast)
-        .map_err(|errors| log_errors(errors, &code))
-        .expect("Static analysis failed. Investigate above errors to find the cause.");
+pub(crate) fn run(bytecode: ProgramBytecode, flags: &VmFlags) -> ProgramOutput {
+    new_vm(flags).run_event_loop(bytecode)
+}
+
+pub(crate) fn new_vm(flags: &VmFlags) -> VM {
+    let mut options = VmOptions::new();
+
+    if flags.debug {
+        options = options.debug_log().expect("Couldn't create the debug log");
+    }
+
+    if flags.allow_eval {
+        options = options.allow_eval();
+    }
 
-    let bytecode = generate_bytecode(ast.clone())
-        .map_err(|error| println!("TODO: generation errors"))
-        .expect("Bytecode generation failed. Investigate above errors to find the cause.");
+    let mut vm = VM::new(options);
 
-    run(bytecode, debug)
+    if let Some(path) = &flags.trace {
+        let file = File::create(path).expect("Couldn't create the trace file");
+        vm = vm.with_hook(JsonTrace::new(BufWriter::new(file)));
+    }
+
+    vm
 }
 
-pub(crate) fn compile_file<P: AsRef<Path>>(path: P) {}
+// Unlike `compile`, the program can import other files, relative to its directory
+pub(crate) fn compile_file<P: AsRef<Path>>(path: P) -> Result<ProgramBytecode, ()> {
+    let mut sources = SourceDb::new();
+    gravitas::compile_file(path, &mut sources).map_err(|diagnostics| {
+        let writer = StandardStream::stderr(ColorChoice::Always);
+        diagnostics
+            .emit(&mut writer.lock(), &sources)
+            .expect("Couldn't write the errors");
+    })
+}
+
+// Programs compiled by `compile` are loaded as they are, the rest is compiled first
+pub(crate) fn load_file<P: AsRef<Path>>(path: P) -> Result<ProgramBytecode, ()> {
+    let path = path.as_ref();
+    if path
+        .extension()
+        .map_or(false, |extension| extension == FILE_EXTENSION)
+    {
+        let bytes = fs::read(path).map_err(|err| eprintln!("Couldn't read the file: {}", err))?;
+        return ProgramBytecode::from_bytes(&bytes).map_err(|err| eprintln!("{}", err));
+    }
+
+    compile_file(path)
+}
@@ -0,0 +1,521 @@
+use std::{
+    collections::HashSet,
+    fs::read_to_string,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpListener,
+};
+
+use clap::Args;
+use common::ProgramText;
+use serde_json::{json, Value};
+use vm::{options::VmOptions, TickOutcome, VM};
+
+use crate::compiler::compile;
+
+// DAP only requires thread ids to be stable, the VM runs everything on a single one
+const THREAD_ID: i64 = 1;
+
+/// Serve the Debug Adapter Protocol so editors can debug programs
+#[derive(Debug, Args)]
+pub(crate) struct Dap {
+    #[clap(long, default_value_t = 4711)]
+    port: u16,
+}
+
+// Where the execution should pause next
+enum Resume {
+    Breakpoint,
+    Step,
+    // Until the call stack is shallower than the given depth
+    Out(usize),
+}
+
+struct Session<W: Write> {
+    out: W,
+    seq: i64,
+    vm: Option<VM>,
+    function_breakpoints: HashSet<ProgramText>,
+    stop_on_entry: bool,
+    finished: bool,
+}
+
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let content_length = content_length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length"))?;
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl<W: Write> Session<W> {
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+
+        let body = message.to_string();
+        write!(self.out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        self.out.flush()
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    fn fail(&mut self, request: &Value, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) -> io::Result<()> {
+        self.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "text": text }),
+        )
+    }
+
+    fn launch(&mut self, request: &Value) -> io::Result<()> {
+        let arguments = &request["arguments"];
+        let program = match arguments["program"].as_str() {
+            Some(program) => program,
+            None => return self.fail(request, "Missing the program to launch"),
+        };
+
+        let code = match read_to_string(program) {
+            Ok(code) => code,
+            Err(err) => return self.fail(request, &format!("Couldn't read {}: {}", program, err)),
+        };
+
+        let bytecode = match compile(&code) {
+            Ok(bytecode) => bytecode,
+            Err(_) => return self.fail(request, "Compilation failed"),
+        };
+
+        let mut options = VmOptions::new();
+        if arguments["allowEval"].as_bool().unwrap_or(false) {
+            options = options.allow_eval();
+        }
+        let mut vm = VM::new(options);
+        if let Err(error) = vm.load(bytecode) {
+            return self.fail(request, &format!("Invalid bytecode: {:?}", error.cause));
+        }
+
+        self.vm = Some(vm);
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.respond(request, json!({}))
+    }
+
+    fn resume(&mut self, until: Resume) -> io::Result<()> {
+        if self.finished {
+            return self.event("terminated", json!({}));
+        }
+
+        let vm = match self.vm.as_mut() {
+            Some(vm) => vm,
+            None => return Ok(()),
+        };
+
+        loop {
+            match vm.step() {
+                Ok(TickOutcome::FinishProgram) => {
+                    let result = vm.operands().last().map(|value| value.to_string());
+                    self.finished = true;
+                    if let Some(result) = result {
+                        let output = format!("Program finished with {}\n", result);
+                        self.event("output", json!({ "category": "console", "output": output }))?;
+                    }
+                    return self.event("terminated", json!({}));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.finished = true;
+                    return self.stopped("exception", Some(format!("{:?}", err.cause)));
+                }
+            }
+
+            let breakpoints = &self.function_breakpoints;
+            let hit_breakpoint = vm.ip() == 0
+                && vm
+                    .current_function()
+                    .is_some_and(|function| breakpoints.contains(function.name.as_str()));
+
+            match until {
+                Resume::Step => return self.stopped("step", None),
+                Resume::Out(depth) if vm.frames().len() < depth => {
+                    return self.stopped("step", None)
+                }
+                _ if hit_breakpoint => return self.stopped("function breakpoint", None),
+                _ => {}
+            }
+        }
+    }
+
+    fn stack_trace(&self) -> Value {
+        let vm = match &self.vm {
+            Some(vm) => vm,
+            None => return json!({ "stackFrames": [], "totalFrames": 0 }),
+        };
+
+        // Every frame keeps its own position, callers are paused right after their call
+        let frames = vm.frames();
+        let mut stack_frames = vec![];
+
+        for (id, frame) in frames.iter().enumerate().rev() {
+            stack_frames.push(json!({
+                "id": id,
+                "name": frame.name,
+                "line": 0,
+                "column": 0,
+                "instructionPointerReference": frame.ip.to_string(),
+            }));
+        }
+
+        json!({ "stackFrames": stack_frames, "totalFrames": frames.len() })
+    }
+
+    // Variable references are frame ids shifted by one, since zero means "no children"
+    fn variables(&self, reference: usize) -> Value {
+        let vm = match &self.vm {
+            Some(vm) => vm,
+            None => return json!({ "variables": [] }),
+        };
+
+        let frames = vm.frames();
+        let frame_id = reference.saturating_sub(1);
+        let (start, end) = match frames.get(frame_id) {
+            Some(frame) => (
+                frame.stack_start,
+                frames
+                    .get(frame_id + 1)
+                    .map_or(vm.operands().len(), |callee| callee.stack_start),
+            ),
+            None => return json!({ "variables": [] }),
+        };
+
+        let variables: Vec<Value> = vm.operands()[start..end.max(start)]
+            .iter()
+            .enumerate()
+            .map(|(slot, value)| {
+                json!({
+                    "name": format!("local_{}", slot),
+                    "value": value.to_string(),
+                    "variablesReference": 0,
+                })
+            })
+            .collect();
+
+        json!({ "variables": variables })
+    }
+
+    // Returns false once the client asked to end the session
+    fn handle(&mut self, request: &Value) -> io::Result<bool> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+
+        match command {
+            "initialize" => {
+                self.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsFunctionBreakpoints": true,
+                    }),
+                )?;
+                self.event("initialized", json!({}))?;
+            }
+            "launch" => self.launch(request)?,
+            "setBreakpoints" => {
+                let count = arguments["breakpoints"].as_array().map_or(0, Vec::len);
+                let breakpoint = json!({
+                    "verified": false,
+                    "message": "Bytecode has no line information, use function breakpoints",
+                });
+                self.respond(request, json!({ "breakpoints": vec![breakpoint; count] }))?;
+            }
+            "setFunctionBreakpoints" => {
+                self.function_breakpoints = arguments["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|breakpoint| breakpoint["name"].as_str())
+                    .map(ProgramText::from)
+                    .collect();
+
+                let breakpoints: Vec<Value> = self
+                    .function_breakpoints
+                    .iter()
+                    .map(|_| json!({ "verified": true }))
+                    .collect();
+                self.respond(request, json!({ "breakpoints": breakpoints }))?;
+            }
+            "configurationDone" => {
+                self.respond(request, json!({}))?;
+                if self.stop_on_entry {
+                    self.stopped("entry", None)?;
+                } else {
+                    self.resume(Resume::Breakpoint)?;
+                }
+            }
+            "threads" => {
+                let threads = json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] });
+                self.respond(request, threads)?;
+            }
+            "stackTrace" => {
+                let stack_trace = self.stack_trace();
+                self.respond(request, stack_trace)?;
+            }
+            "scopes" => {
+                let frame_id = arguments["frameId"].as_u64().unwrap_or_default();
+                let scopes = json!({ "scopes": [{
+                    "name": "Locals",
+                    "variablesReference": frame_id + 1,
+                    "expensive": false,
+                }]});
+                self.respond(request, scopes)?;
+            }
+            "variables" => {
+                let reference = arguments["variablesReference"].as_u64().unwrap_or_default();
+                let variables = self.variables(reference as usize);
+                self.respond(request, variables)?;
+            }
+            "continue" => {
+                self.respond(request, json!({ "allThreadsContinued": true }))?;
+                self.resume(Resume::Breakpoint)?;
+            }
+            "next" | "stepIn" => {
+                self.respond(request, json!({}))?;
+                self.resume(Resume::Step)?;
+            }
+            "stepOut" => {
+                let depth = self.vm.as_ref().map_or(0, |vm| vm.frames().len());
+                self.respond(request, json!({}))?;
+                self.resume(Resume::Out(depth))?;
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, json!({}))?;
+                return Ok(false);
+            }
+            _ => self.fail(request, &format!("Unsupported request: {}", command))?,
+        }
+
+        Ok(true)
+    }
+}
+
+impl Dap {
+    pub(crate) fn run(&self) {
+        // Serving over TCP keeps the protocol apart from the program's own stdout
+        let listener =
+            TcpListener::bind(("127.0.0.1", self.port)).expect("Couldn't bind the debug port");
+        eprintln!("Debug adapter listening on 127.0.0.1:{}", self.port);
+
+        let (stream, _) = listener.accept().expect("Couldn't accept the client");
+        let mut reader = BufReader::new(stream.try_clone().expect("Couldn't clone the stream"));
+        let mut session = Session {
+            out: stream,
+            seq: 0,
+            vm: None,
+            function_breakpoints: HashSet::new(),
+            stop_on_entry: false,
+            finished: false,
+        };
+
+        loop {
+            let request = match read_message(&mut reader) {
+                Ok(Some(request)) => request,
+                Ok(None) => break,
+                Err(err) => {
+                    eprintln!("Debug adapter error: {}", err);
+                    break;
+                }
+            };
+
+            match session.handle(&request) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    eprintln!("Debug adapter error: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, fs::write, io::Cursor, mem::take};
+
+    use serde_json::{json, Value};
+
+    use super::{read_message, Session};
+
+    fn session() -> Session<Vec<u8>> {
+        Session {
+            out: vec![],
+            seq: 0,
+            vm: None,
+            function_breakpoints: HashSet::new(),
+            stop_on_entry: false,
+            finished: false,
+        }
+    }
+
+    // Messages the session wrote since the last call, read back through the framing
+    fn sent(session: &mut Session<Vec<u8>>) -> Vec<Value> {
+        let mut reader = Cursor::new(take(&mut session.out));
+        let mut messages = vec![];
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn request(session: &mut Session<Vec<u8>>, command: &str, arguments: Value) -> Vec<Value> {
+        let request = json!({
+            "seq": 1,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        assert!(session.handle(&request).unwrap());
+        sent(session)
+    }
+
+    fn launch(session: &mut Session<Vec<u8>>, test: &str, code: &str) {
+        let program = std::env::temp_dir().join(format!("dap-{}-{}.gv", test, std::process::id()));
+        write(&program, code).unwrap();
+
+        let messages = request(session, "launch", json!({ "program": program }));
+        assert_eq!(messages[0]["success"], true);
+    }
+
+    fn events(messages: &[Value]) -> Vec<&str> {
+        messages
+            .iter()
+            .filter_map(|message| message["event"].as_str())
+            .collect()
+    }
+
+    fn frame_names(session: &mut Session<Vec<u8>>) -> Vec<String> {
+        let messages = request(session, "stackTrace", json!({ "threadId": 1 }));
+        messages[0]["body"]["stackFrames"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|frame| frame["name"].as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn frames_messages_with_content_length() {
+        let input = concat!(
+            "Content-Length: 10\r\nContent-Type: application/json\r\n\r\n{\"seq\": 1}",
+            "Content-Length: 2\r\n\r\n{}",
+        );
+        let mut reader = Cursor::new(input);
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(json!({ "seq": 1 }))
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), Some(json!({})));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+
+        let mut reader = Cursor::new("Content-Type: application/json\r\n\r\n{}");
+        assert!(read_message(&mut reader).is_err());
+
+        let mut session = session();
+        session.event("initialized", json!({})).unwrap();
+        session.event("initialized", json!({})).unwrap();
+        let out = String::from_utf8(session.out).unwrap();
+        let body = r#"{"body":{},"event":"initialized","seq":1,"type":"event"}"#;
+        assert!(out.starts_with(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body)));
+        assert!(out.ends_with(r#""seq":2,"type":"event"}"#));
+    }
+
+    #[test]
+    fn runs_the_program_after_the_configuration() {
+        let mut session = session();
+
+        let messages = request(&mut session, "initialize", json!({}));
+        assert_eq!(messages[0]["type"], "response");
+        assert_eq!(messages[0]["body"]["supportsFunctionBreakpoints"], true);
+        assert_eq!(events(&messages), vec!["initialized"]);
+
+        launch(&mut session, "configuration", "let a = 1;");
+        let messages = request(&mut session, "configurationDone", json!({}));
+        assert_eq!(messages[0]["command"], "configurationDone");
+        assert_eq!(events(&messages).last(), Some(&"terminated"));
+    }
+
+    #[test]
+    fn stops_at_function_breakpoints() {
+        let mut session = session();
+        launch(&mut session, "breakpoint", "fn foo() { 1 } foo();");
+
+        let messages = request(
+            &mut session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "foo" }] }),
+        );
+        assert_eq!(messages[0]["body"]["breakpoints"][0]["verified"], true);
+
+        let messages = request(&mut session, "configurationDone", json!({}));
+        assert_eq!(events(&messages), vec!["stopped"]);
+        assert_eq!(messages[1]["body"]["reason"], "function breakpoint");
+        assert_eq!(frame_names(&mut session)[0], "foo");
+    }
+
+    #[test]
+    fn steps_out_of_the_function() {
+        let mut session = session();
+        launch(&mut session, "step_out", "fn foo() { 1 } foo(); let a = 2;");
+        request(
+            &mut session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "foo" }] }),
+        );
+        request(&mut session, "configurationDone", json!({}));
+        let depth = frame_names(&mut session).len();
+
+        let messages = request(&mut session, "stepOut", json!({}));
+        assert_eq!(events(&messages), vec!["stopped"]);
+        assert_eq!(messages[1]["body"]["reason"], "step");
+        assert_eq!(frame_names(&mut session).len(), depth - 1);
+    }
+}
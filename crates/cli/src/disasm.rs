@@ -0,0 +1,25 @@
+use std::{path::PathBuf, process::exit};
+
+use bytecode::disassemble;
+use clap::Args;
+
+use crate::compiler::load_file;
+
+/// Print the bytecode of every function in a program, compiled or read from a .gvbc file
+#[derive(Debug, Args)]
+pub(crate) struct Disasm {
+    file_path: PathBuf,
+}
+
+impl Disasm {
+    pub(crate) fn run(&self) {
+        let program = match load_file(&self.file_path) {
+            Ok(program) => program,
+            Err(_) => exit(1),
+        };
+
+        for global in &program.globals {
+            println!("{}", disassemble(global.as_function()));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs::{read_to_string, write},
+    path::PathBuf,
+    process::exit,
+};
+
+use clap::{Args, ValueEnum};
+use parser::{
+    parse,
+    parse::{
+        expr::ExprKind,
+        stmt::{annotated, Stmt, StmtKind},
+        Params, TypeAnnotation,
+    },
+};
+
+use crate::compiler::log_errors;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum DocFormat {
+    Markdown,
+    Html,
+}
+
+/// Generate documentation from the /// comments of top level functions
+#[derive(Debug, Args)]
+pub(crate) struct Doc {
+    #[clap(long, value_enum, default_value_t = DocFormat::Markdown)]
+    pub(crate) format: DocFormat,
+    /// Write the documentation to a file instead of printing it
+    #[clap(short, long)]
+    pub(crate) output: Option<PathBuf>,
+    #[arg(required = true)]
+    file_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Item {
+    signature: String,
+    doc: Option<String>,
+}
+
+#[derive(Debug)]
+struct Module {
+    name: String,
+    items: Vec<Item>,
+}
+
+fn signature(
+    keyword: &str,
+    name: &str,
+    params: &Params,
+    returns: &Option<TypeAnnotation>,
+) -> String {
+    let mut names: Vec<String> = params
+        .kind
+        .params
+        .iter()
+        .zip(&params.kind.types)
+        .map(|(param, annotation)| format!("{}{}", param.kind, annotated(annotation)))
+        .collect();
+    if let Some(rest) = &params.kind.rest {
+        names.push(format!("...{}", rest.kind));
+    }
+    match keyword {
+        "fn" => {
+            let returns = returns
+                .as_ref()
+                .map(|returns| format!(" -> {}", returns.kind))
+                .unwrap_or_default();
+            format!("fn {}({}){}", name, names.join(", "), returns)
+        }
+        _ => format!("let {} = |{}|", name, names.join(", ")),
+    }
+}
+
+// Functions, and closures bound to a name, are the only things the language can declare
+fn item(stmt: &Stmt) -> Option<Item> {
+    let declaration = match &*stmt.kind {
+        StmtKind::Export { declaration } => declaration,
+        _ => stmt,
+    };
+    let (signature, doc) = match &*declaration.kind {
+        StmtKind::FunctionDeclaration {
+            name,
+            params,
+            returns,
+            doc,
+            ..
+        } => (signature("fn", name, params, returns), doc),
+        StmtKind::VariableDeclaration {
+            name, expr, doc, ..
+        } => match &*expr.kind {
+            ExprKind::Closure { params, .. } => (signature("let", name, params, &None), doc),
+            _ => return None,
+        },
+        StmtKind::Expression { .. }
+        | StmtKind::MultipleDeclaration { .. }
+        | StmtKind::ParallelAssignment { .. }
+        | StmtKind::Destructuring { .. }
+        | StmtKind::Import { .. }
+        | StmtKind::Export { .. } => return None,
+    };
+
+    Some(Item {
+        signature,
+        doc: doc.clone(),
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_markdown(modules: &[Module]) -> String {
+    let mut out = String::new();
+    for module in modules {
+        out.push_str(&format!("# {}\n", module.name));
+        for item in &module.items {
+            out.push_str(&format!("\n## `{}`\n", item.signature));
+            if let Some(doc) = &item.doc {
+                out.push_str(&format!("\n{}\n", doc));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn render_html(modules: &[Module]) -> String {
+    let mut out =
+        String::from("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"></head>\n<body>\n");
+    for module in modules {
+        out.push_str(&format!("<h1>{}</h1>\n", escape_html(&module.name)));
+        for item in &module.items {
+            out.push_str(&format!(
+                "<h2><code>{}</code></h2>\n",
+                escape_html(&item.signature)
+            ));
+            // Blank lines separate paragraphs, like in Markdown
+            let doc = item.doc.as_deref().unwrap_or_default();
+            for paragraph in doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                out.push_str(&format!("<p>{}</p>\n", escape_html(paragraph.trim())));
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+impl Doc {
+    pub(crate) fn run(&self) {
+        let mut modules = vec![];
+
+        for path in &self.file_paths {
+            let code = read_to_string(path).expect("File not found!");
+            let ast = match parse(&code) {
+                Ok(ast) => ast,
+                Err(errors) => {
+                    log_errors(errors, &code);
+                    exit(1);
+                }
+            };
+
+            modules.push(Module {
+                name: path.file_stem().map_or_else(
+                    || path.display().to_string(),
+                    |stem| stem.to_string_lossy().into_owned(),
+                ),
+                items: ast.iter().filter_map(item).collect(),
+            });
+        }
+
+        let documentation = match self.format {
+            DocFormat::Markdown => render_markdown(&modules),
+            DocFormat::Html => render_html(&modules),
+        };
+
+        match &self.output {
+            Some(output) => write(output, documentation).expect("Couldn't write the documentation"),
+            None => print!("{}", documentation),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    fs::{read_to_string, write},
+    path::PathBuf,
+    process::exit,
+};
+
+use clap::Args;
+
+use crate::compiler::log_errors;
+
+/// Format programs in place
+#[derive(Debug, Args)]
+pub(crate) struct Fmt {
+    /// Only report files that aren't formatted instead of rewriting them
+    #[clap(long, action)]
+    pub(crate) check: bool,
+    #[arg(required = true)]
+    file_paths: Vec<PathBuf>,
+}
+
+impl Fmt {
+    pub(crate) fn run(&self) {
+        let mut failed = false;
+
+        for path in &self.file_paths {
+            let code = read_to_string(path).expect("File not found!");
+
+            let formatted = match parser::format(&code) {
+                Ok(formatted) => formatted,
+                Err(errors) => {
+                    log_errors(errors, &code);
+                    failed = true;
+                    continue;
+                }
+            };
+
+            if formatted == code {
+                continue;
+            }
+
+            if self.check {
+                println!("{} is not formatted", path.display());
+                failed = true;
+            } else {
+                write(path, formatted).expect("Couldn't write the formatted file");
+            }
+        }
+
+        if failed {
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::{fs::read_to_string, path::PathBuf, process::exit};
+
+use clap::{Args, ValueEnum};
+use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+use common::diagnostics::SourceDb;
+use lint::{lint, Level, LintConfig, Rule};
+use parser::parse;
+
+use crate::compiler::log_errors;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum LintFormat {
+    Human,
+    Json,
+}
+
+/// Report code that compiles but is likely a mistake
+#[derive(Debug, Args)]
+pub(crate) struct Lint {
+    /// File with one `rule = level` per line, the flags below take precedence over it
+    #[clap(long)]
+    pub(crate) config: Option<PathBuf>,
+    #[clap(long, short = 'A')]
+    pub(crate) allow: Vec<Rule>,
+    #[clap(long, short = 'W')]
+    pub(crate) warn: Vec<Rule>,
+    #[clap(long, short = 'D')]
+    pub(crate) deny: Vec<Rule>,
+    #[clap(long, value_enum, default_value_t = LintFormat::Human)]
+    pub(crate) format: LintFormat,
+    file_path: PathBuf,
+}
+
+impl Lint {
+    fn config(&self) -> LintConfig {
+        let mut config = match &self.config {
+            Some(path) => {
+                let text = read_to_string(path).expect("Config file not found!");
+                text.parse().unwrap_or_else(|error| {
+                    eprintln!("{}: {}", path.display(), error);
+                    exit(1)
+                })
+            }
+            None => LintConfig::default(),
+        };
+
+        let flags = [
+            (&self.allow, Level::Allow),
+            (&self.warn, Level::Warn),
+            (&self.deny, Level::Deny),
+        ];
+        for (rules, level) in flags {
+            for rule in rules {
+                config.set(*rule, level);
+            }
+        }
+
+        config
+    }
+
+    pub(crate) fn run(&self) {
+        let code = read_to_string(&self.file_path).expect("File not found!");
+        let ast = parse(&code).unwrap_or_else(|errors| {
+            log_errors(errors, &code);
+            exit(1)
+        });
+
+        let mut sources = SourceDb::new();
+        let file_id = sources.add(self.file_path.display().to_string(), code.clone());
+        let diagnostics = lint(&code, &ast, file_id, &self.config());
+
+        match self.format {
+            LintFormat::Human => {
+                let writer = StandardStream::stderr(ColorChoice::Auto);
+                diagnostics
+                    .emit(&mut writer.lock(), &sources)
+                    .expect("Couldn't write the diagnostics");
+            }
+            LintFormat::Json => println!("{}", diagnostics.to_json(&sources)),
+        }
+
+        if diagnostics.has_errors() {
+            exit(1);
+        }
+    }
+}
@@ -3,16 +3,39 @@ use options::GravitasAction;
 
 use crate::options::Gravitas;
 
+pub(crate) mod bench;
+pub(crate) mod check;
+pub(crate) mod compile;
 pub(crate) mod compiler;
+pub(crate) mod dap;
+pub(crate) mod disasm;
+pub(crate) mod doc;
+pub(crate) mod fmt;
+pub(crate) mod lint;
 pub(crate) mod options;
 pub(crate) mod repl;
-pub(crate) mod run_file;
+pub(crate) mod run;
+#[cfg(feature = "tui")]
+pub(crate) mod step;
+pub(crate) mod viz;
+pub(crate) mod watch;
 
 fn main() {
     let gravitas = Gravitas::parse();
 
     match gravitas.action {
         GravitasAction::Repl(repl) => repl.run(),
-        GravitasAction::RunFile(run_file) => run_file.run(),
+        GravitasAction::Run(run) => run.run(),
+        GravitasAction::Compile(compile) => compile.run(),
+        GravitasAction::Check(check) => check.run(),
+        GravitasAction::Disasm(disasm) => disasm.run(),
+        GravitasAction::Fmt(fmt) => fmt.run(),
+        GravitasAction::Dap(dap) => dap.run(),
+        GravitasAction::Viz(viz) => viz.run(),
+        GravitasAction::Bench(bench) => bench.run(),
+        GravitasAction::Lint(lint) => lint.run(),
+        GravitasAction::Doc(doc) => doc.run(),
+        #[cfg(feature = "tui")]
+        GravitasAction::Step(step) => step.run(),
     }
 }
@@ -1,7 +1,13 @@
-use crate::{repl::Repl, run_file::RunFile};
+#[cfg(feature = "tui")]
+use crate::step::Step;
+use crate::{
+    bench::Bench, check::Check, compile::Compile, dap::Dap, disasm::Disasm, doc::Doc, fmt::Fmt,
+    lint::Lint, repl::Repl, run::Run, viz::Viz,
+};
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
+#[command(name = "gravitas", version)]
 pub(crate) struct Gravitas {
     #[command(subcommand)]
     pub(crate) action: GravitasAction,
@@ -10,5 +16,65 @@ pub(crate) struct Gravitas {
 #[derive(Subcommand)]
 pub(crate) enum GravitasAction {
     Repl(Repl),
-    RunFile(RunFile),
+    Run(Run),
+    #[command(alias = "build")]
+    Compile(Compile),
+    Check(Check),
+    Disasm(Disasm),
+    Fmt(Fmt),
+    Dap(Dap),
+    Viz(Viz),
+    Bench(Bench),
+    Lint(Lint),
+    Doc(Doc),
+    #[cfg(feature = "tui")]
+    Step(Step),
+}
+
+#[cfg(test)]
+mod test {
+    use clap::{CommandFactory, Parser};
+
+    use super::{Gravitas, GravitasAction};
+
+    fn action(args: &[&str]) -> GravitasAction {
+        let args = ["gravitas"].iter().chain(args);
+        Gravitas::try_parse_from(args).unwrap().action
+    }
+
+    #[test]
+    fn defines_consistent_arguments() {
+        Gravitas::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_the_subcommands() {
+        match action(&["run", "main.gv", "--allow-eval"]) {
+            GravitasAction::Run(run) => {
+                assert!(run.flags.allow_eval);
+                assert!(!run.flags.debug);
+                assert!(!run.watch);
+            }
+            _ => panic!("Expected the run subcommand"),
+        }
+        assert!(matches!(
+            action(&["check", "main.gv"]),
+            GravitasAction::Check(_)
+        ));
+        assert!(matches!(
+            action(&["compile", "main.gv", "-o", "main.gvbc"]),
+            GravitasAction::Compile(_)
+        ));
+        assert!(matches!(
+            action(&["build", "main.gv"]),
+            GravitasAction::Compile(_)
+        ));
+        assert!(matches!(
+            action(&["disasm", "main.gv"]),
+            GravitasAction::Disasm(_)
+        ));
+
+        assert!(Gravitas::try_parse_from(["gravitas", "run"]).is_err());
+        assert!(Gravitas::try_parse_from(["gravitas", "run-file", "main.gv"]).is_err());
+    }
 }
@@ -1,24 +1,30 @@
 use clap::Args;
 use rustyline::{error::ReadlineError, Editor};
 
-use crate::compiler::compile_and_run;
+use crate::compiler::{compile_with_globals, new_vm, VmFlags};
 
+/// Start an interactive session
 #[derive(Debug, Args)]
 pub(crate) struct Repl {
-    #[clap(long, short, action)]
-    pub(crate) debug: bool,
+    #[command(flatten)]
+    pub(crate) flags: VmFlags,
 }
 
 impl Repl {
     pub(crate) fn run(&self) {
         let mut rl = Editor::<()>::new();
+        // Top-level variables of the previous lines stay defined in the VM
+        let mut vm = new_vm(&self.flags);
 
         loop {
             let readline = rl.readline(">> ");
             match readline {
                 Ok(code) => {
                     rl.add_history_entry(code.as_str());
-                    let program_output = compile_and_run(&code, self.debug);
+                    let bytecode = compile_with_globals(&code, &vm.global_names()).expect(
+                        "Compilation failed. See above errors to find out what went wrong.",
+                    );
+                    let program_output = vm.run_event_loop(bytecode).expect("VM went kaboom");
 
                     println!("> {}", program_output);
                 }
@@ -0,0 +1,69 @@
+use std::{fs::read_to_string, path::PathBuf, process::exit};
+
+use bytecode::binary::FILE_EXTENSION;
+use clap::Args;
+use vm::runtime_error::RuntimeError;
+
+use crate::{
+    compiler::{load_file, log_errors, run, VmFlags},
+    watch::watch,
+};
+
+/// Compile and execute a program, or execute one compiled by `compile`
+#[derive(Debug, Args)]
+pub(crate) struct Run {
+    #[command(flatten)]
+    pub(crate) flags: VmFlags,
+    /// Rerun the program whenever the file changes
+    #[clap(long, short, action)]
+    pub(crate) watch: bool,
+    file_path: PathBuf,
+}
+
+impl Run {
+    pub(crate) fn run(&self) {
+        if self.watch {
+            return watch(&self.file_path, &self.flags);
+        }
+
+        let bytecode = match load_file(&self.file_path) {
+            Ok(bytecode) => bytecode,
+            Err(_) => exit(1),
+        };
+        if let Err(error) = run(bytecode, &self.flags) {
+            self.report(error);
+            exit(1);
+        }
+    }
+
+    // Errors are marked in the source of the program, compiled programs and spans of imported
+    // files that don't fit in it only get the backtrace
+    fn report(&self, error: RuntimeError) {
+        let compiled = self
+            .file_path
+            .extension()
+            .map_or(false, |extension| extension == FILE_EXTENSION);
+        let source = if compiled {
+            None
+        } else {
+            read_to_string(&self.file_path).ok()
+        };
+
+        match &source {
+            Some(code) if fits(&error, code) => log_errors(vec![error], code),
+            _ => eprintln!(
+                "Runtime error: {:?}\n{}",
+                error.cause,
+                error.backtrace(source.as_deref())
+            ),
+        }
+    }
+}
+
+fn fits(error: &RuntimeError, code: &str) -> bool {
+    error
+        .trace
+        .iter()
+        .filter_map(|frame| frame.span.as_ref())
+        .all(|span| span.end <= code.len())
+}
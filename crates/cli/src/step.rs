@@ -0,0 +1,343 @@
+use std::{
+    collections::HashSet,
+    fs::read_to_string,
+    io::{self, stdout, Stdout, Write},
+    path::PathBuf,
+    process::exit,
+    time::Duration,
+};
+
+use bytecode::Opcode;
+use clap::Args;
+use common::ProgramText;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode},
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use vm::{options::VmOptions, TickOutcome, VM};
+
+use crate::compiler::compile;
+
+const HELP: &str = "[s]tep [c]ontinue [b]reakpoint [up/down] select [q]uit";
+// How many opcodes to execute before checking if the user wants to interrupt `continue`
+const STEPS_BETWEEN_INTERRUPTS: usize = 10_000;
+
+/// Step through the bytecode of a program in an interactive terminal UI
+#[derive(Debug, Args)]
+pub(crate) struct Step {
+    #[clap(long, action)]
+    pub(crate) allow_eval: bool,
+    file_path: PathBuf,
+}
+
+struct Stepper {
+    vm: VM,
+    // Function name and opcode index
+    breakpoints: HashSet<(ProgramText, usize)>,
+    selected: usize,
+    status: String,
+    finished: bool,
+}
+
+impl Stepper {
+    fn function_name(&self) -> ProgramText {
+        self.vm
+            .current_function()
+            .map(|function| function.name.to_string())
+            .unwrap_or_default()
+    }
+
+    fn location(&self) -> (ProgramText, usize) {
+        (self.function_name(), self.vm.ip())
+    }
+
+    fn step(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        match self.vm.step() {
+            Ok(TickOutcome::FinishProgram) => {
+                self.finished = true;
+                self.status = match self.vm.operands().last() {
+                    Some(result) => format!("Program finished with {}", result),
+                    None => "Program finished".to_owned(),
+                };
+            }
+            Ok(_) => {}
+            Err(error) => {
+                self.finished = true;
+                self.status = format!("Runtime error: {:?}", error.cause);
+            }
+        }
+
+        self.selected = self.vm.ip();
+    }
+
+    fn continue_execution(&mut self) -> io::Result<()> {
+        let mut steps = 0;
+        self.step();
+
+        while !self.finished && !self.breakpoints.contains(&self.location()) {
+            self.step();
+            steps += 1;
+
+            if steps % STEPS_BETWEEN_INTERRUPTS == 0 && event::poll(Duration::ZERO)? {
+                event::read()?;
+                self.status = "Interrupted".to_owned();
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn toggle_breakpoint(&mut self) {
+        let breakpoint = (self.function_name(), self.selected);
+
+        if !self.breakpoints.remove(&breakpoint) {
+            self.breakpoints.insert(breakpoint);
+        }
+    }
+
+    fn disassembly(&self) -> Vec<String> {
+        let function = match self.vm.current_function() {
+            Some(function) => function,
+            None => return vec![],
+        };
+        let mut lines = vec![format!("fn {} (arity {})", function.name, function.arity)];
+
+        for (index, opcode) in function.chunk.opcodes.iter().enumerate() {
+            let cursor = if index == self.vm.ip() { ">" } else { " " };
+            let selected = if index == self.selected { "~" } else { " " };
+            let breakpoint = if self.breakpoints.contains(&(function.name.to_string(), index)) {
+                "*"
+            } else {
+                " "
+            };
+
+            let mut line = format!("{}{}{} {:>4} {}", cursor, selected, breakpoint, index, opcode);
+            if let Opcode::Constant(constant_index) = opcode {
+                line = format!("{} ; {}", line, function.chunk.read(*constant_index));
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    fn state(&self) -> Vec<String> {
+        let mut lines = vec!["Stack (top first)".to_owned()];
+        for value in self.vm.operands().iter().rev() {
+            lines.push(format!("  {}", value));
+        }
+
+        let frames = self.vm.frames();
+        lines.push(String::new());
+        lines.push("Locals".to_owned());
+        if let Some(frame) = frames.last() {
+            let locals = self.vm.operands().iter().skip(frame.stack_start);
+            for (index, value) in locals.enumerate() {
+                lines.push(format!("  local_{} = {}", index, value));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push("Frames (innermost first)".to_owned());
+        for frame in frames.iter().rev() {
+            lines.push(format!("  {} (at {})", frame.name, frame.ip));
+        }
+
+        lines
+    }
+
+    fn draw(&self, out: &mut Stdout) -> io::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let (columns, rows) = (columns as usize, rows as usize);
+        let half = columns / 2;
+        let height = rows.saturating_sub(2);
+
+        queue!(out, Clear(ClearType::All))?;
+
+        // Scroll the listing so the selected opcode stays visible, +1 for the header
+        let disassembly = self.disassembly();
+        let offset = (self.selected + 1).saturating_sub(height.saturating_sub(1));
+        let left = disassembly.iter().take(1).chain(disassembly.iter().skip(offset + 1));
+
+        for (row, line) in left.take(height).enumerate() {
+            queue!(out, MoveTo(0, row as u16), Print(truncate(line, half)))?;
+        }
+
+        for (row, line) in self.state().iter().take(height).enumerate() {
+            queue!(
+                out,
+                MoveTo(half as u16, row as u16),
+                Print(truncate(&format!("| {}", line), columns - half))
+            )?;
+        }
+
+        queue!(
+            out,
+            MoveTo(0, height as u16),
+            Print(truncate(&self.status, columns)),
+            MoveTo(0, height as u16 + 1),
+            Print(truncate(HELP, columns))
+        )?;
+
+        out.flush()
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+impl Step {
+    pub(crate) fn run(&self) {
+        let code = read_to_string(&self.file_path).expect("File not found!");
+        let program = match compile(&code) {
+            Ok(program) => program,
+            Err(_) => exit(1),
+        };
+
+        let mut options = VmOptions::new();
+        if self.allow_eval {
+            options = options.allow_eval();
+        }
+        let mut vm = VM::new(options);
+        if let Err(error) = vm.load(program) {
+            eprintln!("Invalid bytecode: {:?}", error.cause);
+            exit(1);
+        }
+
+        let mut stepper = Stepper {
+            vm,
+            breakpoints: HashSet::new(),
+            selected: 0,
+            status: "Ready".to_owned(),
+            finished: false,
+        };
+
+        let mut out = stdout();
+        terminal::enable_raw_mode().expect("Couldn't enable raw mode");
+        execute!(out, EnterAlternateScreen, Hide).expect("Couldn't prepare the terminal");
+
+        let result = event_loop(&mut stepper, &mut out);
+
+        execute!(out, Show, LeaveAlternateScreen).expect("Couldn't restore the terminal");
+        terminal::disable_raw_mode().expect("Couldn't disable raw mode");
+
+        if let Err(error) = result {
+            eprintln!("Terminal error: {}", error);
+            exit(1);
+        }
+
+        println!("{}", stepper.status);
+    }
+}
+
+fn event_loop(stepper: &mut Stepper, out: &mut Stdout) -> io::Result<()> {
+    loop {
+        stepper.draw(out)?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('s') | KeyCode::Enter => stepper.step(),
+                KeyCode::Char('c') => stepper.continue_execution()?,
+                KeyCode::Char('b') => stepper.toggle_breakpoint(),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    stepper.selected = stepper.selected.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let last = stepper
+                        .vm
+                        .current_function()
+                        .map_or(0, |function| function.chunk.opcodes.len());
+                    stepper.selected = (stepper.selected + 1).min(last.saturating_sub(1));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use common::MAIN_FUNCTION_NAME;
+    use vm::{options::VmOptions, VM};
+
+    use super::{truncate, Stepper};
+    use crate::compiler::compile;
+
+    fn stepper(code: &str) -> Stepper {
+        let mut vm = VM::new(VmOptions::new());
+        vm.load(compile(code).unwrap()).unwrap();
+        Stepper {
+            vm,
+            breakpoints: HashSet::new(),
+            selected: 0,
+            status: "Ready".to_owned(),
+            finished: false,
+        }
+    }
+
+    #[test]
+    fn steps_until_the_program_finishes() {
+        let mut stepper = stepper("let a = 1 + 2;");
+        while !stepper.finished {
+            stepper.step();
+        }
+        assert!(stepper.status.starts_with("Program finished"));
+
+        // Stepping a finished program does nothing
+        let status = stepper.status.clone();
+        stepper.step();
+        assert_eq!(stepper.status, status);
+    }
+
+    #[test]
+    fn toggles_breakpoints_on_the_selected_opcode() {
+        let mut stepper = stepper("let a = 1 + 2;");
+        stepper.selected = 1;
+        stepper.toggle_breakpoint();
+        assert!(stepper
+            .breakpoints
+            .contains(&(MAIN_FUNCTION_NAME.into(), 1)));
+
+        // The header comes first, then the current, selected and breakpoint markers
+        let disassembly = stepper.disassembly();
+        assert!(disassembly[0].starts_with(&format!("fn {}", MAIN_FUNCTION_NAME)));
+        assert!(disassembly[1].starts_with(">  "));
+        assert!(disassembly[2].starts_with(" ~*"));
+
+        stepper.toggle_breakpoint();
+        assert!(stepper.breakpoints.is_empty());
+    }
+
+    #[test]
+    fn continues_to_the_next_breakpoint() {
+        let mut stepper = stepper("fn foo() { 1 } foo(); let a = 2;");
+        stepper.breakpoints.insert(("foo".into(), 0));
+
+        stepper.continue_execution().unwrap();
+        assert!(!stepper.finished);
+        assert_eq!(stepper.location(), ("foo".into(), 0));
+        assert!(stepper.state().contains(&"  foo (at 0)".to_owned()));
+
+        stepper.continue_execution().unwrap();
+        assert!(stepper.finished);
+    }
+
+    #[test]
+    fn truncates_by_characters() {
+        assert_eq!(truncate("żółw", 2), "żó");
+        assert_eq!(truncate("ab", 5), "ab");
+    }
+}
@@ -0,0 +1,39 @@
+use std::{fs::read_to_string, path::PathBuf, process::exit};
+
+use clap::{Args, ValueEnum};
+
+use crate::compiler::log_errors;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum VizFormat {
+    Dot,
+}
+
+/// Render the syntax tree of a program as a graph
+#[derive(Debug, Args)]
+pub(crate) struct Viz {
+    #[clap(long, value_enum, default_value_t = VizFormat::Dot)]
+    pub(crate) format: VizFormat,
+    /// Link identifiers to their declarations and mark the ones captured by closures
+    #[clap(long, action)]
+    pub(crate) scopes: bool,
+    file_path: PathBuf,
+}
+
+impl Viz {
+    pub(crate) fn run(&self) {
+        let code = read_to_string(&self.file_path).expect("File not found!");
+
+        let graph = match self.format {
+            VizFormat::Dot => parser::dot(&code, self.scopes),
+        };
+
+        match graph {
+            Ok(graph) => print!("{}", graph),
+            Err(errors) => {
+                log_errors(errors, &code);
+                exit(1);
+            }
+        }
+    }
+}
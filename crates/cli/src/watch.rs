@@ -0,0 +1,102 @@
+use std::{
+    fs::{metadata, read_to_string},
+    path::Path,
+    thread::sleep,
+    time::{Duration, SystemTime},
+};
+
+use bytecode::ProgramBytecode;
+
+use crate::compiler::{compile, log_errors, run, VmFlags};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Editors often write a file in several steps, so wait until it stops changing
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+// Bytecode of the last source that compiled, so saving an unchanged file doesn't recompile it
+struct CompilationCache {
+    source: String,
+    bytecode: ProgramBytecode,
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn wait_for_change(path: &Path, last_modified: Option<SystemTime>) -> Option<SystemTime> {
+    let mut modified = modified_at(path);
+    while modified == last_modified {
+        sleep(POLL_INTERVAL);
+        modified = modified_at(path);
+    }
+
+    loop {
+        sleep(DEBOUNCE);
+        let settled = modified_at(path);
+        if settled == modified {
+            return settled;
+        }
+        modified = settled;
+    }
+}
+
+fn compile_cached(code: &str, cache: &mut Option<CompilationCache>) -> Option<ProgramBytecode> {
+    if let Some(cached) = cache.as_ref().filter(|cached| cached.source == code) {
+        return Some(cached.bytecode.clone());
+    }
+
+    let bytecode = compile(code).ok()?;
+    *cache = Some(CompilationCache {
+        source: code.to_owned(),
+        bytecode: bytecode.clone(),
+    });
+    Some(bytecode)
+}
+
+// Reruns the program every time the file is saved, until the process is killed
+pub(crate) fn watch(path: &Path, flags: &VmFlags) {
+    let mut cache = None;
+    let mut last_modified = None;
+
+    loop {
+        last_modified = wait_for_change(path, last_modified);
+
+        match read_to_string(path) {
+            Ok(code) => {
+                if let Some(bytecode) = compile_cached(&code, &mut cache) {
+                    if let Err(error) = run(bytecode, flags) {
+                        log_errors(vec![error], &code);
+                    }
+                }
+            }
+            Err(error) => eprintln!("Couldn't read {}: {}", path.display(), error),
+        }
+
+        eprintln!("[watching {} for changes]", path.display());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::compile_cached;
+
+    #[test]
+    fn reuses_the_bytecode_of_unchanged_code() {
+        let mut cache = None;
+        let bytecode = compile_cached("let a = 1;", &mut cache).unwrap();
+        assert_eq!(cache.as_ref().unwrap().source, "let a = 1;");
+        assert_eq!(compile_cached("let a = 1;", &mut cache), Some(bytecode));
+
+        compile_cached("let a = 2;", &mut cache).unwrap();
+        assert_eq!(cache.as_ref().unwrap().source, "let a = 2;");
+    }
+
+    #[test]
+    fn keeps_the_last_compiled_code_after_errors() {
+        let mut cache = None;
+        compile_cached("let a = 1;", &mut cache).unwrap();
+
+        assert!(compile_cached("let a = ;", &mut cache).is_none());
+        assert_eq!(cache.as_ref().unwrap().source, "let a = 1;");
+    }
+}
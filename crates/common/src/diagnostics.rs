@@ -0,0 +1,215 @@
+use std::{fmt, io};
+
+use codespan_reporting::{
+    diagnostic::{Diagnostic, LabelStyle, Severity},
+    files::{Files, SimpleFiles},
+    term::{
+        self,
+        termcolor::{Buffer, WriteColor},
+    },
+};
+use serde_json::{json, Value};
+
+use crate::CompilerDiagnostic;
+
+pub type FileId = usize;
+
+// Sources the diagnostics point into, every file gets the id returned by `add`
+#[derive(Debug, Clone)]
+pub struct SourceDb {
+    files: SimpleFiles<String, String>,
+}
+
+impl SourceDb {
+    pub fn new() -> Self {
+        Self {
+            files: SimpleFiles::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.add(name.into(), source.into())
+    }
+
+    pub fn name(&self, file_id: FileId) -> Option<String> {
+        self.files.name(file_id).ok()
+    }
+
+    pub fn source(&self, file_id: FileId) -> Option<&str> {
+        self.files
+            .get(file_id)
+            .ok()
+            .map(|file| file.source().as_str())
+    }
+}
+
+impl Default for SourceDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Compilation stage or part of the runtime that found the problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Lexer,
+    Parser,
+    Analyzer,
+    Codegen,
+    Runtime,
+    Lint,
+}
+
+impl DiagnosticKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lexer => "lexer",
+            Self::Parser => "parser",
+            Self::Analyzer => "analyzer",
+            Self::Codegen => "codegen",
+            Self::Runtime => "runtime",
+            Self::Lint => "lint",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub kind: DiagnosticKind,
+    pub diagnostic: Diagnostic<FileId>,
+}
+
+// Errors of every stage of the pipeline, rendered the same way
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    entries: Vec<Entry>,
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    }
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, kind: DiagnosticKind, diagnostic: Diagnostic<FileId>) {
+        self.entries.push(Entry { kind, diagnostic });
+    }
+
+    pub fn extend(
+        &mut self,
+        kind: DiagnosticKind,
+        file_id: FileId,
+        errors: impl IntoIterator<Item = impl CompilerDiagnostic>,
+    ) {
+        for error in errors {
+            self.push(kind, error.report(file_id));
+        }
+    }
+
+    // Shorthand for a single stage failing with a list of errors
+    pub fn from_errors(
+        kind: DiagnosticKind,
+        file_id: FileId,
+        errors: impl IntoIterator<Item = impl CompilerDiagnostic>,
+    ) -> Self {
+        let mut diagnostics = Self::new();
+        diagnostics.extend(kind, file_id, errors);
+        diagnostics
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.diagnostic.severity >= Severity::Error)
+    }
+
+    pub fn emit(&self, writer: &mut dyn WriteColor, sources: &SourceDb) -> io::Result<()> {
+        let config = term::Config::default();
+        for entry in &self.entries {
+            term::emit(writer, &config, &sources.files, &entry.diagnostic)
+                .map_err(|error| io::Error::other(error.to_string()))?;
+        }
+        Ok(())
+    }
+
+    // Same output as `emit`, without colors
+    pub fn render(&self, sources: &SourceDb) -> String {
+        let mut buffer = Buffer::no_color();
+        self.emit(&mut buffer, sources)
+            .expect("Writing to a buffer can't fail");
+        String::from_utf8_lossy(buffer.as_slice()).into_owned()
+    }
+
+    // Array of objects with the kind, severity, message, notes and labels of every diagnostic.
+    // Labels have byte ranges and the 1-based line and column of their start.
+    pub fn to_json(&self, sources: &SourceDb) -> Value {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let diagnostic = &entry.diagnostic;
+                let labels: Vec<Value> = diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| {
+                        let location = sources
+                            .files
+                            .location(label.file_id, label.range.start)
+                            .ok();
+                        json!({
+                            "file": sources.name(label.file_id),
+                            "start": label.range.start,
+                            "end": label.range.end,
+                            "line": location.as_ref().map(|location| location.line_number),
+                            "column": location.as_ref().map(|location| location.column_number),
+                            "primary": label.style == LabelStyle::Primary,
+                            "message": label.message,
+                        })
+                    })
+                    .collect();
+
+                json!({
+                    "kind": entry.kind.name(),
+                    "severity": severity_name(diagnostic.severity),
+                    "message": diagnostic.message,
+                    "labels": labels,
+                    "notes": diagnostic.notes,
+                })
+            })
+            .collect();
+
+        Value::Array(entries)
+    }
+}
+
+// Only the messages, for places that don't have the sources at hand
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let severity = severity_name(entry.diagnostic.severity);
+            writeln!(f, "{}: {}", severity, entry.diagnostic.message)?;
+        }
+        Ok(())
+    }
+}
@@ -2,6 +2,11 @@ use std::str::FromStr;
 
 use codespan_reporting::diagnostic::Diagnostic;
 
+pub mod diagnostics;
+pub mod symbol;
+
+pub use symbol::Symbol;
+
 pub trait CompilerDiagnostic: Sized {
     fn report(&self, file_id: usize) -> Diagnostic<usize>;
 }
@@ -16,9 +21,24 @@ pub type ProgramText = String;
 // STD function names
 
 #[derive(Hash, PartialEq, PartialOrd, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BuiltInFunction {
     Clock,
     Print,
+    ReadLine,
+    Eval,
+    Error,
+    SetTimeout,
+    SetInterval,
+    ClearTimer,
+    Bytes,
+    ReadU32,
+    WriteU32,
+    ReadF64,
+    WriteF64,
+    Slice,
+    BytesFromString,
+    BytesToString,
 }
 
 impl Into<String> for BuiltInFunction {
@@ -26,6 +46,20 @@ impl Into<String> for BuiltInFunction {
         match self {
             BuiltInFunction::Clock => "clock".to_string(),
             BuiltInFunction::Print => "print".to_string(),
+            BuiltInFunction::ReadLine => "read_line".to_string(),
+            BuiltInFunction::Eval => "eval".to_string(),
+            BuiltInFunction::Error => "Error".to_string(),
+            BuiltInFunction::SetTimeout => "set_timeout".to_string(),
+            BuiltInFunction::SetInterval => "set_interval".to_string(),
+            BuiltInFunction::ClearTimer => "clear_timer".to_string(),
+            BuiltInFunction::Bytes => "bytes".to_string(),
+            BuiltInFunction::ReadU32 => "read_u32".to_string(),
+            BuiltInFunction::WriteU32 => "write_u32".to_string(),
+            BuiltInFunction::ReadF64 => "read_f64".to_string(),
+            BuiltInFunction::WriteF64 => "write_f64".to_string(),
+            BuiltInFunction::Slice => "slice".to_string(),
+            BuiltInFunction::BytesFromString => "bytes_from_string".to_string(),
+            BuiltInFunction::BytesToString => "bytes_to_string".to_string(),
         }
     }
 }
@@ -37,6 +71,20 @@ impl FromStr for BuiltInFunction {
         Ok(match s {
             "clock" => BuiltInFunction::Clock,
             "print" => BuiltInFunction::Print,
+            "read_line" => BuiltInFunction::ReadLine,
+            "eval" => BuiltInFunction::Eval,
+            "Error" => BuiltInFunction::Error,
+            "set_timeout" => BuiltInFunction::SetTimeout,
+            "set_interval" => BuiltInFunction::SetInterval,
+            "clear_timer" => BuiltInFunction::ClearTimer,
+            "bytes" => BuiltInFunction::Bytes,
+            "read_u32" => BuiltInFunction::ReadU32,
+            "write_u32" => BuiltInFunction::WriteU32,
+            "read_f64" => BuiltInFunction::ReadF64,
+            "write_f64" => BuiltInFunction::WriteF64,
+            "slice" => BuiltInFunction::Slice,
+            "bytes_from_string" => BuiltInFunction::BytesFromString,
+            "bytes_to_string" => BuiltInFunction::BytesToString,
             _ => return Err(()),
         })
     }
@@ -0,0 +1,64 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use common::{
+    diagnostics::{DiagnosticKind, Diagnostics, SourceDb},
+    CompilerDiagnostic,
+};
+
+struct Undefined {
+    start: usize,
+    end: usize,
+}
+
+impl CompilerDiagnostic for Undefined {
+    fn report(&self, file_id: usize) -> Diagnostic<usize> {
+        Diagnostic::error()
+            .with_message("Variable is not defined")
+            .with_labels(vec![Label::primary(file_id, self.start..self.end)])
+    }
+}
+
+fn sources() -> (SourceDb, usize) {
+    let mut sources = SourceDb::new();
+    let file_id = sources.add("main.gv", "let a = 1;\nb;");
+    (sources, file_id)
+}
+
+#[test]
+fn renders_every_kind_against_the_sources() {
+    let (sources, file_id) = sources();
+    let mut diagnostics = Diagnostics::from_errors(
+        DiagnosticKind::Analyzer,
+        file_id,
+        vec![Undefined { start: 11, end: 12 }],
+    );
+    diagnostics.push(
+        DiagnosticKind::Runtime,
+        Diagnostic::error().with_message("Runtime error: StackOverflow"),
+    );
+
+    let rendered = diagnostics.render(&sources);
+    assert!(rendered.contains("error: Variable is not defined"));
+    assert!(rendered.contains("main.gv:2:1"));
+    assert!(rendered.contains("error: Runtime error: StackOverflow"));
+    assert!(diagnostics.has_errors());
+}
+
+#[test]
+fn writes_json_with_locations() {
+    let (sources, file_id) = sources();
+    let diagnostics = Diagnostics::from_errors(
+        DiagnosticKind::Analyzer,
+        file_id,
+        vec![Undefined { start: 11, end: 12 }],
+    );
+
+    let json = diagnostics.to_json(&sources);
+    let diagnostic = &json[0];
+    assert_eq!(diagnostic["kind"], "analyzer");
+    assert_eq!(diagnostic["severity"], "error");
+    assert_eq!(diagnostic["message"], "Variable is not defined");
+    assert_eq!(diagnostic["labels"][0]["file"], "main.gv");
+    assert_eq!(diagnostic["labels"][0]["line"], 2);
+    assert_eq!(diagnostic["labels"][0]["column"], 1);
+    assert_eq!(diagnostic["labels"][0]["start"], 11);
+}
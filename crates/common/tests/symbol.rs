@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+use common::Symbol;
+
+#[test]
+fn interns_equal_texts_once() {
+    let a = Symbol::intern("counter");
+    let b = Symbol::from(String::from("counter"));
+    assert_eq!(a, b);
+    assert!(std::ptr::eq(a.as_str(), b.as_str()));
+    assert_ne!(a, Symbol::intern("count"));
+}
+
+#[test]
+fn compares_and_looks_up_by_text() {
+    let name = Symbol::intern("total");
+    assert_eq!(name, "total");
+    assert_eq!(name.to_string(), "total");
+
+    let mut scope = HashMap::new();
+    scope.insert(name, 1);
+    assert_eq!(scope.get("total"), Some(&1));
+}
@@ -0,0 +1,195 @@
+// One entry point to the whole pipeline: parsing, analysis, bytecode generation and execution
+use std::{fmt, path::Path};
+
+use analyzer::Analyzer;
+use bytecode::{generate_bytecode_with_globals, GenerationError};
+use common::{
+    diagnostics::{DiagnosticKind, FileId},
+    ProgramText,
+};
+use parser::parse;
+use vm::{hook::ExecutionHook, VM};
+
+mod modules;
+
+pub use bytecode::ProgramBytecode;
+pub use common::{
+    diagnostics::{Diagnostics, SourceDb},
+    Number,
+};
+pub use vm::{
+    options::VmOptions,
+    runtime_error::{RuntimeError, RuntimeErrorCause},
+    runtime_value::RuntimeValue as Value,
+    stats::VmStats,
+};
+
+// Programs are compiled from a single file, the first one added to a SourceDb
+const FILE_ID: FileId = 0;
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Compile(Diagnostics),
+    Runtime(RuntimeError),
+}
+
+impl Error {
+    // Runtime errors become diagnostics too, so every error renders the same way
+    pub fn diagnostics(&self) -> Diagnostics {
+        match self {
+            Self::Compile(diagnostics) => diagnostics.clone(),
+            Self::Runtime(error) => {
+                Diagnostics::from_errors(DiagnosticKind::Runtime, FILE_ID, [error.clone()])
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compile(diagnostics) => write!(f, "{}", diagnostics),
+            Self::Runtime(error) => write!(f, "Runtime error: {:?}", error.cause),
+        }
+    }
+}
+
+impl From<Diagnostics> for Error {
+    fn from(diagnostics: Diagnostics) -> Self {
+        Self::Compile(diagnostics)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Self::Runtime(error)
+    }
+}
+
+// The source can use the top-level variables defined by the programs that ran before it
+fn compile_with_globals(
+    source: &str,
+    host_functions: &[ProgramText],
+    globals: &[ProgramText],
+) -> Result<ProgramBytecode, Diagnostics> {
+    let ast = parse(source)
+        .map_err(|errors| Diagnostics::from_errors(DiagnosticKind::Parser, FILE_ID, errors))?;
+    Analyzer::new()
+        .with_host_functions(host_functions)
+        .with_globals(globals)
+        .analyze(&ast)
+        .map_err(|errors| Diagnostics::from_errors(DiagnosticKind::Analyzer, FILE_ID, errors))?;
+    generate_bytecode_with_globals(ast, host_functions, globals)
+        .map_err(|error| codegen_failed(FILE_ID, error))
+}
+
+fn codegen_failed(file_id: FileId, error: GenerationError) -> Diagnostics {
+    Diagnostics::from_errors(DiagnosticKind::Codegen, file_id, [error])
+}
+
+pub fn compile(source: &str) -> Result<ProgramBytecode, Diagnostics> {
+    compile_with_globals(source, &[], &[])
+}
+
+// Unlike `compile`, the program can import other files, see modules.rs
+pub fn compile_file(
+    path: impl AsRef<Path>,
+    sources: &mut SourceDb,
+) -> Result<ProgramBytecode, Diagnostics> {
+    modules::compile_file(path.as_ref(), sources, &[])
+}
+
+pub fn run(source: &str, options: VmOptions) -> Result<Value, Error> {
+    Vm::builder().options(options).build().run(source)
+}
+
+#[derive(Debug)]
+pub struct VmBuilder {
+    // The machine is made before its options are known, so it gets them in build()
+    options: VmOptions,
+    machine: VM,
+    host_functions: Vec<ProgramText>,
+}
+
+impl VmBuilder {
+    // Limits, eval(), the event loop, and where print() and read_line() go, see vm::options
+    pub fn options(mut self, options: VmOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn hook(mut self, hook: impl ExecutionHook + 'static) -> Self {
+        self.machine = self.machine.with_hook(hook);
+        self
+    }
+
+    // Makes the function callable by name from the programs the Vm runs
+    pub fn host_function(
+        mut self,
+        name: &str,
+        arity: usize,
+        body: impl FnMut(Vec<Value>) -> Result<Value, RuntimeErrorCause> + 'static,
+    ) -> Self {
+        self.machine.register_host_function(name, arity, body);
+        self.host_functions.push(name.to_owned());
+        self
+    }
+
+    pub fn build(self) -> Vm {
+        let mut machine = self.machine;
+        machine.set_options(self.options);
+
+        Vm {
+            machine,
+            host_functions: self.host_functions,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Vm {
+    machine: VM,
+    host_functions: Vec<ProgramText>,
+}
+
+impl Vm {
+    pub fn builder() -> VmBuilder {
+        VmBuilder {
+            options: VmOptions::default(),
+            machine: VM::new(VmOptions::default()),
+            host_functions: vec![],
+        }
+    }
+
+    pub fn options(&self) -> &VmOptions {
+        self.machine.options()
+    }
+
+    // Counters of the last program executed by this Vm
+    pub fn stats(&self) -> VmStats {
+        self.machine.stats()
+    }
+
+    // Compiles the source so it can call the host functions of this Vm and use the top-level
+    // variables of the programs it ran before
+    pub fn compile(&self, source: &str) -> Result<ProgramBytecode, Diagnostics> {
+        compile_with_globals(source, &self.host_functions, &self.machine.global_names())
+    }
+
+    pub fn compile_file(
+        &self,
+        path: impl AsRef<Path>,
+        sources: &mut SourceDb,
+    ) -> Result<ProgramBytecode, Diagnostics> {
+        modules::compile_file(path.as_ref(), sources, &self.host_functions)
+    }
+
+    pub fn execute(&mut self, bytecode: ProgramBytecode) -> Result<Value, RuntimeError> {
+        self.machine.execute(bytecode)
+    }
+
+    pub fn run(&mut self, source: &str) -> Result<Value, Error> {
+        let bytecode = self.compile(source)?;
+        Ok(self.execute(bytecode)?)
+    }
+}
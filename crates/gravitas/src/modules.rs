@@ -0,0 +1,158 @@
+// Loads the files a program imports, from the directory of the program
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use analyzer::Analyzer;
+use bytecode::modules::{link_modules, Module};
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use common::{
+    diagnostics::{DiagnosticKind, Diagnostics, FileId, SourceDb},
+    ProgramText, Symbol,
+};
+use parser::{
+    parse,
+    parse::{
+        stmt::{exports, imports},
+        Program,
+    },
+};
+
+use crate::{codegen_failed, ProgramBytecode};
+
+struct LoadedModule {
+    file_id: FileId,
+    module: Module,
+}
+
+struct Loader<'a> {
+    root: &'a Path,
+    sources: &'a mut SourceDb,
+    // Every module comes after the ones it imports
+    loaded: Vec<LoadedModule>,
+    // Paths of the modules whose imports are being loaded, to find the cycles
+    loading: Vec<ProgramText>,
+    diagnostics: Diagnostics,
+}
+
+impl Loader<'_> {
+    fn parse(&mut self, file_id: FileId) -> Option<Program> {
+        let source = self.sources.source(file_id)?;
+        match parse(source) {
+            Ok(program) => Some(program),
+            Err(errors) => {
+                self.diagnostics
+                    .extend(DiagnosticKind::Parser, file_id, errors);
+                None
+            }
+        }
+    }
+
+    fn load_imports(&mut self, program: &Program, file_id: FileId) {
+        for path in imports(program) {
+            let label = || vec![Label::primary(file_id, path.span.clone())];
+
+            if self.loading.contains(&path.kind) {
+                let diagnostic = Diagnostic::error()
+                    .with_message(format!("Module \"{}\" is imported in a cycle", path.kind))
+                    .with_labels(label());
+                self.diagnostics.push(DiagnosticKind::Parser, diagnostic);
+                continue;
+            }
+            if self.loaded.iter().any(|loaded| loaded.module.path == path.kind) {
+                continue;
+            }
+
+            let source = match read_to_string(self.root.join(&path.kind)) {
+                Ok(source) => source,
+                Err(error) => {
+                    let diagnostic = Diagnostic::error()
+                        .with_message(format!("Couldn't read module \"{}\": {}", path.kind, error))
+                        .with_labels(label());
+                    self.diagnostics.push(DiagnosticKind::Parser, diagnostic);
+                    continue;
+                }
+            };
+            let module_id = self.sources.add(path.kind.clone(), source);
+            let module = match self.parse(module_id) {
+                Some(module) => module,
+                None => continue,
+            };
+
+            self.loading.push(path.kind.clone());
+            self.load_imports(&module, module_id);
+            self.loading.pop();
+
+            self.loaded.push(LoadedModule {
+                file_id: module_id,
+                module: Module {
+                    path: path.kind.clone(),
+                    program: module,
+                },
+            });
+        }
+    }
+}
+
+// Compiles the file together with every module it imports. Import paths are relative to the
+// directory of the file, in the imported modules too. Sources of all of them end up in the
+// SourceDb, so the diagnostics can be rendered.
+pub(crate) fn compile_file(
+    path: &Path,
+    sources: &mut SourceDb,
+    host_functions: &[ProgramText],
+) -> Result<ProgramBytecode, Diagnostics> {
+    let source = read_to_string(path).map_err(|error| {
+        let mut diagnostics = Diagnostics::new();
+        let diagnostic = Diagnostic::error()
+            .with_message(format!("Couldn't read {}: {}", path.display(), error));
+        diagnostics.push(DiagnosticKind::Parser, diagnostic);
+        diagnostics
+    })?;
+    let file_id = sources.add(path.display().to_string(), source);
+
+    let mut loader = Loader {
+        root: path.parent().unwrap_or_else(|| Path::new("")),
+        sources,
+        loaded: vec![],
+        loading: vec![],
+        diagnostics: Diagnostics::new(),
+    };
+    let program = loader.parse(file_id);
+    if let Some(program) = &program {
+        loader.load_imports(program, file_id);
+    }
+
+    let Loader {
+        loaded,
+        mut diagnostics,
+        ..
+    } = loader;
+    // Imports of the modules that failed to load would be reported again by the analyzer
+    let program = match program {
+        Some(program) if diagnostics.is_empty() => program,
+        _ => return Err(diagnostics),
+    };
+    let modules: HashMap<ProgramText, Vec<Symbol>> = loaded
+        .iter()
+        .map(|loaded| (loaded.module.path.clone(), exports(&loaded.module.program)))
+        .collect();
+    let files = loaded
+        .iter()
+        .map(|loaded| (loaded.file_id, &loaded.module.program))
+        .chain(std::iter::once((file_id, &program)));
+    for (file_id, program) in files {
+        let analyzed = Analyzer::new()
+            .with_host_functions(host_functions)
+            .with_modules(modules.clone())
+            .analyze(program);
+        if let Err(errors) = analyzed {
+            diagnostics.extend(DiagnosticKind::Analyzer, file_id, errors);
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    let modules = loaded.into_iter().map(|loaded| loaded.module).collect();
+    // The analyzer catches the errors of the modules, so the generator only reports the main file
+    link_modules(modules, program, host_functions).map_err(|error| codegen_failed(file_id, error))
+}
@@ -0,0 +1,30 @@
+use gravitas::{run, Error, RuntimeErrorCause, Value, VmOptions};
+
+fn result(code: &str) -> Result<Value, Error> {
+    run(code, VmOptions::default())
+}
+
+#[test]
+fn indexes_array_literals() {
+    assert!(matches!(result("[1, 2, 3][0];"), Ok(Value::Number(n)) if n == 1.0));
+    assert!(matches!(
+        result("fn f() { let xs = [10, 20]; xs[1] = 5; xs[0] + xs[1] } f();"),
+        Ok(Value::Number(n)) if n == 15.0
+    ));
+    assert!(matches!(
+        result("[[1, 2], [3, 4]][1][0];"),
+        Ok(Value::Number(n)) if n == 3.0
+    ));
+}
+
+#[test]
+fn rejects_positions_outside_of_the_array() {
+    assert!(matches!(
+        result("[1, 2][2];"),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::IndexOutOfBounds
+    ));
+    assert!(matches!(
+        result("[1, 2][0.5];"),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::MismatchedTypes
+    ));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::number;
+use gravitas::{run, VmOptions};
+
+#[test]
+fn declares_multiple_variables() {
+    assert_eq!(number("let a, b = 1, 2; a - b;"), -1.0);
+    assert_eq!(
+        number("fn f() { let x, y, z = 1, 2, 3; x * 100 + y * 10 + z } f();"),
+        123.0
+    );
+}
+
+#[test]
+fn swaps_variables() {
+    assert_eq!(number("let a, b = 1, 2; a, b = b, a; a - b;"), 1.0);
+    let code = "fn f() { let x, y, z = 1, 2, 3; x, y, z = z, x, y; x * 100 + y * 10 + z } f();";
+    assert_eq!(number(code), 312.0);
+}
+
+#[test]
+fn swaps_elements_and_properties() {
+    let code = "fn f() { let arr = [1, 2]; arr[0], arr[1] = arr[1], arr[0]; arr[0] - arr[1] } f();";
+    assert_eq!(number(code), 1.0);
+    let code = "fn f() { let o = new { p: 1, q: 2 }; o.p, o.q = o.q, o.p; o.p - o.q } f();";
+    assert_eq!(number(code), 1.0);
+}
+
+#[test]
+fn keeps_variables_declared_after_the_assignment() {
+    let code =
+        "fn f() { let a, b = 1, 2; a, b = b, a; let c = 10; { a, b = b, a; }; c + a - b } f();";
+    assert_eq!(number(code), 9.0);
+}
+
+#[test]
+fn rejects_mismatched_value_counts() {
+    assert!(run("let a, b = 1;", VmOptions::default()).is_err());
+    assert!(run("let a, b = 1, 2; a, b = 1, 2, 3;", VmOptions::default()).is_err());
+    assert!(run("let a = 1; a, 2 = 1, 2;", VmOptions::default()).is_err());
+}
+
+#[test]
+fn chains_assignments() {
+    assert_eq!(
+        number("let a, b, c = 0, 0, 0; a = b = c = 2; a + b + c;"),
+        6.0
+    );
+    let code = "fn f() { let o = new { x: 0 }; let arr = [0]; let n = 0; o.x = arr[0] = n = 5; \
+                o.x + arr[0] + n } f();";
+    assert_eq!(number(code), 15.0);
+    // The whole chain evaluates to the assigned value
+    assert_eq!(number("let a, b = 0, 0; let c = a = b = 4; c;"), 4.0);
+}
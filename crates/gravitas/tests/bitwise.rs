@@ -0,0 +1,37 @@
+mod common;
+
+use common::number;
+use gravitas::{run, VmOptions};
+
+#[test]
+fn operates_on_integers() {
+    assert_eq!(number("6 & 3;"), 2.0);
+    assert_eq!(number("6 | 3;"), 7.0);
+    assert_eq!(number("6 ^ 3;"), 5.0);
+    assert_eq!(number("~5;"), -6.0);
+    assert_eq!(number("1 | 2 & 3 ^ 4;"), 7.0);
+}
+
+#[test]
+fn shifts_bits() {
+    assert_eq!(number("1 << 4;"), 16.0);
+    assert_eq!(number("256 >> 4;"), 16.0);
+    // Right shift keeps the sign
+    assert_eq!(number("-16 >> 2;"), -4.0);
+    // Shifts bind tighter than other bitwise operators
+    assert_eq!(number("1 | 1 << 3;"), 9.0);
+    // The amount is taken modulo 64
+    assert_eq!(number("1 << 65;"), 2.0);
+}
+
+#[test]
+fn truncates_fractions() {
+    assert_eq!(number("5.9 & 7;"), 5.0);
+    assert_eq!(number("~-1.5;"), 0.0);
+}
+
+#[test]
+fn rejects_other_types() {
+    assert!(run("true & 1;", VmOptions::default()).is_err());
+    assert!(run("~\"text\";", VmOptions::default()).is_err());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::number;
+
+#[test]
+fn calls_closures() {
+    assert_eq!(number("let inc = |a| => a + 1; inc(2);"), 3.0);
+    assert_eq!(number("let mul = |a, b| { a * b }; mul(2, 3);"), 6.0);
+    let code = "fn apply(f, v) { f(v) } fn f() { let m = 7; apply(|v| => v + m, 1) } f();";
+    assert_eq!(number(code), 8.0);
+}
+
+#[test]
+fn captures_variables_of_enclosing_functions() {
+    assert_eq!(
+        number("fn add(k) { |a| => a + k } let add10 = add(10); add10(5);"),
+        15.0
+    );
+    // Each captured variable keeps its own slot
+    assert_eq!(
+        number("fn f() { let a = 1; let b = 2; let g = || => b * 10 + a; g() } f();"),
+        21.0
+    );
+    // Variables declared in blocks can be captured too
+    assert_eq!(
+        number("fn f() { if true { let q = 9; let g = || => q; g() } else { 0 } } f();"),
+        9.0
+    );
+}
+
+#[test]
+fn captures_through_nested_closures() {
+    assert_eq!(
+        number("fn mk(a) { |b| => |c| => a + b * 10 + c * 100 } mk(1)(2)(3);"),
+        321.0
+    );
+    assert_eq!(
+        number("fn f() { let xs = [1, 2]; let g = || => || => xs[1]; g()() } f();"),
+        2.0
+    );
+    assert_eq!(
+        number(
+            "fn outer() { let x = 1; fn mid() { fn inner() { x + 2 } inner() } mid() } outer();"
+        ),
+        3.0
+    );
+}
+
+#[test]
+fn keeps_assignments_to_captured_variables() {
+    assert_eq!(
+        number("fn counter() { let n = 0; |d| { n = n + d; n } } let c = counter(); c(1); c(2);"),
+        3.0
+    );
+    assert_eq!(
+        number("fn f() { let n = 0; fn inc() { n = n + 1; n } inc() + inc() } f();"),
+        3.0
+    );
+}
+
+#[test]
+fn shares_captured_variables_with_their_scope() {
+    // The closure sees assignments made after it was created
+    assert_eq!(
+        number("fn f() { let n = 1; let g = || => n; n = 5; g() } f();"),
+        5.0
+    );
+    // and the scope sees assignments made by the closure
+    assert_eq!(
+        number("fn f() { let n = 1; let inc = || { n = n + 1; }; inc(); inc(); n } f();"),
+        3.0
+    );
+    assert_eq!(
+        number(
+            "fn pair() { let n = 0; [|| { n = n + 1; }, || => n] } let p = pair(); p[0](); p[0](); p[1]();"
+        ),
+        2.0
+    );
+}
+
+#[test]
+fn keeps_captured_variables_after_their_scope_ends() {
+    assert_eq!(
+        number("fn f() { let g = { let q = 4; || => q * 2 }; let w = 100; g() } f();"),
+        8.0
+    );
+    // Every iteration has its own variables
+    assert_eq!(
+        number(
+            "let fs = [0, 0, 0]; let k = 0; for x in [1, 2, 3] { fs[k] = || => x; k = k + 1; }; fs[0]() + fs[2]() * 10;"
+        ),
+        31.0
+    );
+    assert_eq!(
+        number(
+            "let fs = [0, 0, 0]; let i = 0; while i < 3 { let j = i; fs[j] = || => j; i = i + 1; if j == 1 { continue; } }; fs[1]() + fs[2]() * 10;"
+        ),
+        21.0
+    );
+}
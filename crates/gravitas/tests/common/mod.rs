@@ -0,0 +1,23 @@
+// Helpers shared by the test files, each of them uses only some
+#![allow(dead_code)]
+
+use gravitas::{run, Value, VmOptions};
+
+// Value of the last expression of a program that is expected to run
+pub fn value(code: &str) -> Value {
+    run(code, VmOptions::default()).expect("Program should run")
+}
+
+pub fn number(code: &str) -> f64 {
+    match value(code) {
+        Value::Number(number) => number,
+        value => panic!("Expected a number, got {:?}", value),
+    }
+}
+
+pub fn string(code: &str) -> String {
+    match value(code) {
+        Value::String(text) => text,
+        value => panic!("Expected a string, got {:?}", value),
+    }
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use common::number;
+use gravitas::{run, VmOptions};
+
+#[test]
+fn unpacks_arrays_and_objects() {
+    assert_eq!(number("let [x, y] = [1, 2]; x - y;"), -1.0);
+    assert_eq!(number("let {a, b} = new { b: 1, a: 2 }; a - b;"), 1.0);
+    // Elements that aren't named are skipped
+    assert_eq!(number("let [first] = [1, 2, 3]; first;"), 1.0);
+}
+
+#[test]
+fn keeps_separate_values_in_the_same_scope() {
+    let code = "fn f(point) { let {x, y} = point; let [a, b] = [y, x]; { let [c] = [a - b]; c } } \
+                f(new { x: 1, y: 5 });";
+    assert_eq!(number(code), 4.0);
+}
+
+#[test]
+fn fails_on_missing_elements() {
+    assert!(run("let [x, y] = [1];", VmOptions::default()).is_err());
+}
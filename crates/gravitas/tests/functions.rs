@@ -0,0 +1,60 @@
+mod common;
+
+use common::number;
+use gravitas::{run, Error, RuntimeErrorCause, VmOptions};
+
+#[test]
+fn collects_remaining_arguments_into_an_array() {
+    let sum =
+        "fn sum(first, ...rest) { let total = first; for n in rest { total = total + n; }; total }";
+    assert_eq!(number(&format!("{} sum(1, 2, 3, 4);", sum)), 10.0);
+    // Nothing left over gives an empty array
+    assert_eq!(number(&format!("{} sum(5);", sum)), 5.0);
+    assert_eq!(number("fn second(...all) { all[1] } second(1, 2, 3);"), 2.0);
+}
+
+#[test]
+fn requires_positional_arguments() {
+    let code = "fn f(a, ...rest) { a } f();";
+    assert!(matches!(
+        run(code, VmOptions::default()),
+        Err(Error::Compile(_))
+    ));
+    // The callee isn't known until the program runs
+    let code = "fn f(a, ...rest) { a } let g = f; g();";
+    assert!(matches!(
+        run(code, VmOptions::default()),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::MissingArguments
+    ));
+}
+
+#[test]
+fn reorders_named_arguments() {
+    let code = "fn point(x, y, scale) { (x - y) * scale } point(scale: 10, y: 1, x: 3);";
+    assert_eq!(number(code), 20.0);
+    let code = "fn point(x, y, scale) { (x - y) * scale } point(3, scale: 10, y: 1);";
+    assert_eq!(number(code), 20.0);
+    // Works for recursive calls as well
+    let code =
+        "fn down(n, step) { if n <= 0 { n } else { down(step: step, n: n - step) } } down(10, 3);";
+    assert_eq!(number(code), -2.0);
+}
+
+#[test]
+fn checks_argument_counts_of_calls_through_values() {
+    let wrong_count = |code: &str| {
+        matches!(
+            run(code, VmOptions::default()),
+            Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::WrongArgumentCount
+        )
+    };
+    // Too many arguments used to leave the extra ones as the result
+    assert!(wrong_count("fn g(a) { a } let h = g; h(1, 2, 3);"));
+    // Too few used to read the locals of the caller as the missing parameters
+    assert!(wrong_count(
+        "fn g(a, b) { b } let h = g; fn w() { let x = 5; let y = 7; h(1) } w();"
+    ));
+    assert!(wrong_count("let f = |a, b| 1; f(1);"));
+    assert!(wrong_count("let p = print; p(1, 2);"));
+    assert_eq!(number("fn g(a, b) { a + b } let h = g; h(1, 2);"), 3.0);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::number;
+use gravitas::{run, Value, Vm, VmOptions};
+
+#[test]
+fn shares_top_level_variables_with_functions() {
+    assert_eq!(
+        number("let n = 1; fn inc() { n = n + 1; } inc(); inc(); n;"),
+        3.0
+    );
+    assert_eq!(number("let a, b = 1, 2; fn f() => a * 10 + b f();"), 12.0);
+    // Values of the statements between declarations don't move the variables
+    assert_eq!(number("let a = 1; 5; let b = 2; b;"), 2.0);
+    assert_eq!(number("let a = 1; 5; { let b = 3; a + b };"), 4.0);
+}
+
+#[test]
+fn keeps_top_level_variables_between_programs() {
+    let mut vm = Vm::builder().build();
+    vm.run("let n = 20; fn add(x) { x + n }")
+        .expect("Program should run");
+    vm.run("n = n + 1;").expect("Program should run");
+
+    let value = vm.run("add(1);").expect("Program should run");
+    assert!(matches!(value, Value::Number(number) if number == 22.0));
+}
+
+#[test]
+fn evaluated_code_sees_top_level_variables() {
+    let options = VmOptions::new().allow_eval();
+    let value = run("let n = 4; eval(\"n * 2;\");", options).expect("Program should run");
+    assert!(matches!(value, Value::Number(number) if number == 8.0));
+}
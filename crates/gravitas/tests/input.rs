@@ -0,0 +1,11 @@
+use gravitas::{Vm, VmOptions};
+
+#[test]
+fn reads_the_input_of_the_options() {
+    let options = VmOptions::new().input_text("gravitas\n");
+    let mut vm = Vm::builder().options(options).build();
+    let value = vm
+        .run("read_line() == \"gravitas\";")
+        .expect("Program should run");
+    assert_eq!(value.to_string(), "true");
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::number;
+use gravitas::{run, Error, RuntimeErrorCause, Value, VmOptions};
+
+#[test]
+fn runs_for_loops() {
+    assert_eq!(
+        number("let s = 0; for (let i = 0; i < 5; i = i + 1) { s = s + i; }; s;"),
+        10.0
+    );
+    // Every part of the header is optional
+    assert_eq!(
+        number("fn f() { let n = 0; for (;;) { n = n + 1; if n > 3 { break; }; }; n } f();"),
+        4.0
+    );
+    // Locals of the body are fresh on every iteration
+    assert_eq!(
+        number(
+            "fn f() { let s = 0; for (let i = 0; i < 3; i = i + 1) { let d = i * 2; \
+             for (let j = 0; j < 2; j = j + 1) { s = s + d + j; }; }; s } f();"
+        ),
+        15.0
+    );
+}
+
+#[test]
+fn breaks_and_continues_loops() {
+    // continue still runs the step
+    assert_eq!(
+        number(
+            "fn f() { let s = 0; for (let i = 0; i < 10; i = i + 1) { let k = i; \
+             if k == 3 { continue; }; if k == 6 { break; }; s = s + k; }; s } f();"
+        ),
+        12.0
+    );
+    assert_eq!(
+        number(
+            "fn f() { let s = 0; let i = 0; while i < 10 { i = i + 1; \
+             if i % 2 == 0 { continue; }; if i > 7 { break; }; s = s + i; }; s } f();"
+        ),
+        16.0
+    );
+    // The value of break becomes the value of the loop
+    assert_eq!(
+        number("fn f() { for (let i = 0; ; i = i + 1) { if i == 4 { break i * 10; }; } } f();"),
+        40.0
+    );
+    assert_eq!(
+        number(
+            "fn f() { let i = 0; while true { i = i + 1; let d = i * 3; \
+             if d > 10 { break d; }; } } f();"
+        ),
+        12.0
+    );
+    // A loop that runs out without breaking evaluates to null
+    assert!(matches!(
+        run("let i = 0; while i < 3 { i = i + 1; };", VmOptions::default()),
+        Ok(Value::Null)
+    ));
+    assert!(matches!(
+        run("let i = 0; let r = while i < 3 { i = i + 1; }; r;", VmOptions::default()),
+        Ok(Value::Null)
+    ));
+}
+
+#[test]
+fn iterates_over_collections() {
+    assert_eq!(
+        number("let s = 0; for x in [1, 2, 3] { s = s + x; }; s;"),
+        6.0
+    );
+    assert_eq!(
+        number(
+            "fn f() { let xs = [10, 20]; xs[1] = 5; let s = 0; \
+             for a in xs { for b in [1, 2] { let t = a * b; s = s + t; }; }; s } f();"
+        ),
+        45.0
+    );
+    // Strings are iterated by characters
+    assert_eq!(
+        number("fn f() { let n = 0; for c in \"héllo\" { n = n + 1; }; n } f();"),
+        5.0
+    );
+    assert_eq!(
+        number("fn f() { for x in [1, 2, 3] { if x == 2 { break x * 100; }; } } f();"),
+        200.0
+    );
+    assert!(matches!(
+        run("for x in 5 { x; };", VmOptions::default()),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::NotIterable
+    ));
+}
+
+#[test]
+fn iterates_over_ranges() {
+    assert_eq!(number("let s = 0; for i in 0..5 { s = s + i; }; s;"), 10.0);
+    assert_eq!(number("let s = 0; for i in 1..=4 { s = s + i; }; s;"), 10.0);
+    assert_eq!(number("(2..10)[3];"), 5.0);
+    assert!(matches!(
+        run("0..3 == 0..3;", VmOptions::default()),
+        Ok(Value::Bool(true))
+    ));
+    assert!(matches!(
+        run("0..3 == 0..=3;", VmOptions::default()),
+        Ok(Value::Bool(false))
+    ));
+    assert!(matches!(
+        run("(0..3)[3];", VmOptions::default()),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::IndexOutOfBounds
+    ));
+}
+
+#[test]
+fn runs_infinite_loops_until_break() {
+    assert_eq!(
+        number("let i = 0; let found = loop { i = i + 1; if i * i > 50 { break i }; }; found;"),
+        8.0
+    );
+    assert_eq!(
+        number("fn f(limit) { let n = 1; loop { if n > limit { break n }; n = n * 2; } } f(100);"),
+        128.0
+    );
+    // continue goes back to the start of the body
+    assert_eq!(
+        number("let i = 0; loop { i = i + 1; if i < 3 { continue; }; break; }; i;"),
+        3.0
+    );
+}
+
+#[test]
+fn runs_loops_with_known_conditions() {
+    assert_eq!(number("let i = 0; while false { i = i + 1; }; i;"), 0.0);
+    assert_eq!(
+        number("let i = 0; while 1 < 2 { i = i + 1; if !!(i > 2) { break; }; }; i;"),
+        3.0
+    );
+}
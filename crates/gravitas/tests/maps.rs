@@ -0,0 +1,44 @@
+mod common;
+
+use common::{number, value};
+use gravitas::{run, Value, VmOptions};
+
+#[test]
+fn reads_and_writes_entries() {
+    let code =
+        r#"let m = { "key": 1, other: 2 }; m["third"] = 3; m["key"] + m["other"] + m["third"];"#;
+    assert_eq!(number(code), 6.0);
+    // Missing keys read as null, like missing properties
+    assert!(matches!(value(r#"let m = { a: 1 }; m["b"];"#), Value::Null));
+}
+
+#[test]
+fn later_entries_replace_earlier_ones() {
+    assert_eq!(number(r#"let m = { a: 1, "a": 2 }; m["a"];"#), 2.0);
+}
+
+#[test]
+fn copies_share_the_entries() {
+    let code = r#"fn f() { let m = { a: 1 }; let n = m; n["a"] = 5; m["a"] } f();"#;
+    assert_eq!(number(code), 5.0);
+}
+
+#[test]
+fn braces_without_keys_are_still_blocks() {
+    assert_eq!(number("let x = 2; { x + 1 };"), 3.0);
+}
+
+#[test]
+fn keys_must_be_strings() {
+    assert!(run("let m = { a: 1 }; m[0];", VmOptions::default()).is_err());
+}
+
+#[test]
+fn reads_and_writes_entries_as_properties() {
+    let code = r#"fn f() { let m = { a: 1 }; m.b = 2; m.a + m["b"] } f();"#;
+    assert_eq!(number(code), 3.0);
+    assert!(matches!(value("let m = { a: 1 }; m.b;"), Value::Null));
+    // Only objects and maps have properties
+    assert!(run("let n = 1; n.a;", VmOptions::default()).is_err());
+    assert!(run("let xs = [1]; xs.a = 2;", VmOptions::default()).is_err());
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::value;
+use gravitas::Value;
+
+#[test]
+fn takes_the_first_matching_arm() {
+    let code = "fn name(n) { match n { 1 => \"one\", 2 => \"two\", _ => \"many\" } } \
+                \"${name(1)} ${name(2)} ${name(3)}\";";
+    assert!(matches!(value(code), Value::String(text) if text == "one two many"));
+    // Patterns are expressions compared with ==
+    let code = "let limit = 3; match 2 + 1 { limit - 1 => 0, limit => 1, _ => 2 };";
+    assert!(matches!(value(code), Value::Number(number) if number == 1.0));
+}
+
+#[test]
+fn evaluates_to_null_when_nothing_matches() {
+    assert!(matches!(value("match 5 { 1 => 1 };"), Value::Null));
+}
+
+#[test]
+fn keeps_the_stack_balanced_inside_expressions() {
+    let code = "fn f() { let s = 0; for i in 0..5 { \
+                s = s + match i { 0 => 100, 4 => { break s; }, _ => i }; }; s } f();";
+    assert!(matches!(value(code), Value::Number(number) if number == 106.0));
+}
+
+#[test]
+fn matches_many_literal_arms() {
+    let code =
+        "fn name(n) { match n { 1 => \"one\", 2 => \"two\", 3 => \"three\", 2 => \"again\", \
+                \"four\" => \"4\", _ => \"many\" } } \
+                \"${name(1)} ${name(2)} ${name(3)} ${name(\"four\")} ${name(2.5)} ${name(true)}\";";
+    assert!(matches!(value(code), Value::String(text) if text == "one two three 4 many many"));
+    assert!(matches!(
+        value("match 9 { 1 => 1, 2 => 2, 3 => 3, 4 => 4 };"),
+        Value::Null
+    ));
+    let code = "fn f() { let s = 0; for i in 0..6 { \
+                s = s + match i { 0 => 100, 1 => 10, 2 => 1, 4 => { break s; }, _ => 0 }; }; s } f();";
+    assert!(matches!(value(code), Value::Number(number) if number == 111.0));
+}
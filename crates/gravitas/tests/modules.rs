@@ -0,0 +1,87 @@
+use std::{fs, path::PathBuf};
+
+use gravitas::{compile_file, Diagnostics, SourceDb, Value, Vm};
+
+// Writes the files into a fresh directory and returns the path of the first one
+fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gravitas-{}-{}", test, std::process::id()));
+    for (name, code) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, code).unwrap();
+    }
+    dir.join(files[0].0)
+}
+
+fn compile(test: &str, files: &[(&str, &str)]) -> Result<gravitas::ProgramBytecode, String> {
+    let mut sources = SourceDb::new();
+    compile_file(write_files(test, files), &mut sources)
+        .map_err(|diagnostics: Diagnostics| diagnostics.render(&sources))
+}
+
+fn number(test: &str, files: &[(&str, &str)]) -> f64 {
+    let bytecode = compile(test, files).expect("Program should compile");
+    match Vm::builder().build().execute(bytecode) {
+        Ok(Value::Number(number)) => number,
+        result => panic!("Expected a number, got {:?}", result),
+    }
+}
+
+#[test]
+fn imports_exported_names() {
+    let files = [
+        ("main.gv", r#"import "lib/math.gv"; square(2) + three;"#),
+        (
+            "lib/math.gv",
+            "export fn square(x) { x * x } export let three = 3; let hidden = 1;",
+        ),
+    ];
+    assert_eq!(number("exported", &files), 7.0);
+}
+
+#[test]
+fn modules_import_other_modules() {
+    let files = [
+        ("main.gv", r#"import "b.gv"; fn f() { b() } f();"#),
+        ("b.gv", r#"import "a.gv"; export fn b() { a() + 1 }"#),
+        ("a.gv", "export fn a() { 1 }"),
+    ];
+    assert_eq!(number("nested", &files), 2.0);
+}
+
+#[test]
+fn modules_run_once() {
+    let files = [
+        (
+            "main.gv",
+            r#"import "counter.gv"; import "a.gv"; import "b.gv"; count[0];"#,
+        ),
+        ("a.gv", r#"import "counter.gv"; count[0] = count[0] + 1;"#),
+        ("b.gv", r#"import "counter.gv"; count[0] = count[0] + 1;"#),
+        ("counter.gv", "export let count = [0];"),
+    ];
+    assert_eq!(number("once", &files), 2.0);
+}
+
+#[test]
+fn only_exported_names_are_visible() {
+    let files = [
+        ("main.gv", r#"import "lib.gv"; hidden;"#),
+        ("lib.gv", "let hidden = 1;"),
+    ];
+    assert!(compile("hidden", &files).is_err());
+}
+
+#[test]
+fn reports_missing_modules_and_cycles() {
+    let missing = compile("missing", &[("main.gv", r#"import "nope.gv";"#)]).unwrap_err();
+    assert!(missing.contains("Couldn't read module \"nope.gv\""));
+
+    let files = [
+        ("main.gv", r#"import "a.gv";"#),
+        ("a.gv", r#"import "b.gv";"#),
+        ("b.gv", r#"import "a.gv";"#),
+    ];
+    let cycle = compile("cycle", &files).unwrap_err();
+    assert!(cycle.contains("Module \"a.gv\" is imported in a cycle"));
+}
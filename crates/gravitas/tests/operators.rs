@@ -0,0 +1,84 @@
+mod common;
+
+use common::value;
+use gravitas::{run, Error, RuntimeErrorCause, Value, VmOptions};
+
+#[test]
+fn calls_operator_methods_of_objects() {
+    let code = "fn add(other) { other + 1 } let v = new { op_add: add }; v + 41;";
+    assert!(matches!(value(code), Value::Number(number) if number == 42.0));
+    let code = "fn positive(other) { other > 0 } let v = new { op_lt: positive }; v < 3;";
+    assert!(matches!(value(code), Value::Bool(true)));
+    let code = "fn always(other) { true } let v = new { op_eq: always }; v == 5;";
+    assert!(matches!(value(code), Value::Bool(true)));
+}
+
+#[test]
+fn rejects_operator_methods_with_other_parameters() {
+    for method in ["|a, b| 42", "|| 42"] {
+        let code = format!("let v = new {{ op_add: {} }}; v + 1;", method);
+        assert!(matches!(
+            run(&code, VmOptions::default()),
+            Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::WrongArgumentCount
+        ));
+    }
+    // Rest parameters collect the right operand
+    let code = "fn add(...rest) { rest[0] + 1 } let v = new { op_add: add }; v + 1;";
+    assert!(matches!(value(code), Value::Number(number) if number == 2.0));
+}
+
+#[test]
+fn falls_back_without_operator_methods() {
+    // Only the left operand is asked
+    let code = "fn add(other) { other } let v = new { op_add: add }; 1 + v;";
+    assert!(run(code, VmOptions::default()).is_err());
+    assert!(run("let v = new { a: 1 }; v * 2;", VmOptions::default()).is_err());
+    assert!(matches!(
+        value("let v = new { a: 1 }; v == 1;"),
+        Value::Bool(false)
+    ));
+}
+
+#[test]
+fn raises_exponents_from_the_right() {
+    assert!(matches!(value("2 ** 3 ** 2;"), Value::Number(number) if number == 512.0));
+    assert!(matches!(value("(2 ** 3) ** 2;"), Value::Number(number) if number == 64.0));
+    assert!(matches!(value("2 * 2 ** 2 ** 2;"), Value::Number(number) if number == 32.0));
+}
+
+#[test]
+fn short_circuits_logical_operators() {
+    let crash = "fn crash() { 1 + true } let t = true; let f = false;";
+    let code = format!("{} f and crash();", crash);
+    assert!(matches!(value(&code), Value::Bool(false)));
+    let code = format!("{} t or crash();", crash);
+    assert!(matches!(value(&code), Value::Bool(true)));
+    assert!(run(&format!("{} t and crash();", crash), VmOptions::default()).is_err());
+
+    // The result is still the value of the operator
+    assert!(matches!(
+        value("let t = true; t and false;"),
+        Value::Bool(false)
+    ));
+    assert!(matches!(
+        value("let f = false; f or true;"),
+        Value::Bool(true)
+    ));
+    assert!(matches!(
+        value("let f = false; f or f;"),
+        Value::Bool(false)
+    ));
+    assert!(run("let t = true; t and 1;", VmOptions::default()).is_err());
+}
+
+#[test]
+fn compares_in_conditions() {
+    let code = "let s = 0; for (let i = 0; i <= 4; i = i + 1) { if i != 2 { s = s + i; }; }; s;";
+    assert!(matches!(value(code), Value::Number(number) if number == 8.0));
+    let code = "let a = \"x\"; if a == \"x\" { 1 } else { 2 };";
+    assert!(matches!(value(code), Value::Number(number) if number == 1.0));
+    // Operator methods still decide through the jump after the comparison
+    let code = "fn below(other) { false } let v = new { op_lt: below }; if v < 3 { 1 } else { 2 };";
+    assert!(matches!(value(code), Value::Number(number) if number == 2.0));
+    assert!(run("let a = true; if a < 1 { 1 };", VmOptions::default()).is_err());
+}
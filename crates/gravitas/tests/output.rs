@@ -0,0 +1,30 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use gravitas::{Vm, VmOptions};
+
+#[derive(Clone, Default)]
+struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn prints_to_the_output_of_the_options() {
+    let output = Captured::default();
+    let options = VmOptions::new().output(output.clone()).max_call_depth(100);
+    let mut vm = Vm::builder().options(options).build();
+    vm.run(r#"print("first"); print(2);"#)
+        .expect("Program should run");
+    vm.run(r#"print("second run");"#)
+        .expect("Program should run");
+
+    let printed = String::from_utf8(output.0.borrow().clone()).expect("Output should be text");
+    assert_eq!(printed, "first\n2\nsecond run\n");
+}
@@ -0,0 +1,59 @@
+use gravitas::{compile, run, Error, ProgramBytecode, SourceDb, Value, Vm, VmOptions};
+
+#[test]
+fn runs_source_in_one_call() {
+    let result = run("let a = 20; a * 2 + 2;", VmOptions::default()).unwrap();
+    assert!(matches!(result, Value::Number(number) if number == 42.0));
+}
+
+#[test]
+fn reports_compilation_errors_as_diagnostics() {
+    let code = "let a = 1; b;";
+    let diagnostics = compile(code).unwrap_err();
+    let mut sources = SourceDb::new();
+    sources.add("main.gv", code);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics
+        .render(&sources)
+        .contains("Variable was used but it's not defined anywhere"));
+    assert!(matches!(
+        run("b;", VmOptions::default()),
+        Err(Error::Compile(_))
+    ));
+}
+
+#[test]
+fn calls_host_functions() {
+    let mut vm = Vm::builder()
+        .host_function("double", 1, |args| match args[..] {
+            [Value::Number(number)] => Ok(Value::Number(number * 2.0)),
+            _ => Err(gravitas::RuntimeErrorCause::ExpectedNumber),
+        })
+        .build();
+
+    let result = vm.run("double(21);").unwrap();
+    assert!(matches!(result, Value::Number(number) if number == 42.0));
+    assert!(matches!(vm.run("double(\"a\");"), Err(Error::Runtime(_))));
+}
+
+#[test]
+fn exposes_statistics_of_the_last_run() {
+    let mut vm = Vm::builder().build();
+    vm.run("fn f() => 1 f() + f();").unwrap();
+
+    let stats = vm.stats();
+    assert_eq!(stats.calls, 2);
+    assert!(stats.instructions > 0);
+}
+
+#[test]
+fn runs_programs_loaded_from_bytes() {
+    let bytes = compile("fn fib(n) => if n < 2 { n } else { fib(n - 1) + fib(n - 2) } fib(10);")
+        .unwrap()
+        .to_bytes();
+
+    let program = ProgramBytecode::from_bytes(&bytes).unwrap();
+    let result = Vm::builder().build().execute(program).unwrap();
+    assert!(matches!(result, Value::Number(number) if number == 55.0));
+}
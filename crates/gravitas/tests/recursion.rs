@@ -0,0 +1,78 @@
+mod common;
+
+use common::number;
+use gravitas::{run, Error, RuntimeErrorCause, Value, Vm, VmOptions};
+
+fn boolean(code: &str) -> bool {
+    match run(code, VmOptions::default()).expect("Program should run") {
+        Value::Bool(boolean) => boolean,
+        value => panic!("Expected a bool, got {:?}", value),
+    }
+}
+
+#[test]
+fn computes_fibonacci() {
+    let fib = "fn fib(n) { if n < 2 { return n; }; fib(n - 1) + fib(n - 2) }";
+    assert_eq!(number(&format!("{} fib(1);", fib)), 1.0);
+    assert_eq!(number(&format!("{} fib(15);", fib)), 610.0);
+}
+
+#[test]
+fn calls_functions_recursing_into_each_other() {
+    // Functions can't be used before they are declared, so one of them is a global set later
+    let parity = "let is_odd = false; \
+                  fn is_even(n) { if n == 0 { return true; }; is_odd(n - 1) } \
+                  is_odd = |n| { if n == 0 { return false; }; is_even(n - 1) };";
+    assert!(boolean(&format!("{} is_even(10);", parity)));
+    assert!(boolean(&format!("{} is_odd(7);", parity)));
+    assert!(!boolean(&format!("{} is_even(7);", parity)));
+}
+
+#[test]
+fn continues_after_nested_calls() {
+    // Every caller picks up right after its own call, however deep the calls went
+    let code = "fn depth(n) { if n == 0 { return 0; }; 1 + depth(n - 1) } \
+                fn add(a, b) { a + b } \
+                add(depth(3), add(depth(2), depth(4))) * 10 + depth(1);";
+    assert_eq!(number(code), 91.0);
+}
+
+#[test]
+fn recurses_inside_loops() {
+    let code = "fn sum(n) { let total = 0; for (let i = 0; i < n; i = i + 1) { total = total + sum(i) + 1; }; total } \
+                sum(5);";
+    assert_eq!(number(code), 31.0);
+}
+
+#[test]
+fn recurses_through_closures() {
+    let code = "fn make() { let count = 0; fn down(n) { count = count + 1; if n > 0 { down(n - 1); }; count } down } \
+                let down = make(); down(4);";
+    assert_eq!(number(code), 5.0);
+}
+
+#[test]
+fn stops_endless_recursion() {
+    let code = "fn forever(n) { forever(n + 1) } forever(0);";
+    assert!(matches!(
+        run(code, VmOptions::default()),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::RecursionLimit
+    ));
+}
+
+#[test]
+fn limits_the_depth_of_calls() {
+    let depth = "fn depth(n) { if n == 0 { return 0; }; 1 + depth(n - 1) }";
+    // The program takes a frame too
+    let mut vm = Vm::builder()
+        .options(VmOptions::new().max_call_depth(11))
+        .build();
+    let value = vm
+        .run(&format!("{} depth(9);", depth))
+        .expect("Program should run");
+    assert!(matches!(value, Value::Number(number) if number == 9.0));
+    assert!(matches!(
+        vm.run("depth(10);"),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::RecursionLimit
+    ));
+}
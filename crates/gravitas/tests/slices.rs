@@ -0,0 +1,37 @@
+mod common;
+
+use common::{number, string};
+use gravitas::{run, Error, RuntimeErrorCause, VmOptions};
+
+#[test]
+fn slices_arrays() {
+    let code = "let a = [1, 2, 3, 4, 5]; let s = a[1..3]; s[0] * 10 + s[1];";
+    assert_eq!(number(code), 23.0);
+    assert_eq!(number("let a = [1, 2, 3]; let s = a[1..=2]; s[1];"), 3.0);
+    // Missing bounds are the start and the end of the array
+    assert_eq!(number("let a = [1, 2, 3]; let s = a[..2]; s[1];"), 2.0);
+    assert_eq!(number("let a = [1, 2, 3]; let s = a[2..]; s[0];"), 3.0);
+    // The slice is a copy
+    assert_eq!(
+        number("let a = [1, 2]; let s = a[..]; s[0] = 5; a[0];"),
+        1.0
+    );
+}
+
+#[test]
+fn slices_strings_by_characters() {
+    assert_eq!(string("\"héllo\"[1..3];"), "él");
+    assert_eq!(string("let i = 1; \"héllo\"[i + 1..];"), "llo");
+    assert_eq!(string("\"abc\"[3..];"), "");
+}
+
+#[test]
+fn fails_on_bounds_outside_of_the_target() {
+    for code in ["[1][0..3];", "\"ab\"[2..1];", "[1, 2][0.5..];"] {
+        assert!(run(code, VmOptions::default()).is_err(), "{}", code);
+    }
+    assert!(matches!(
+        run("[1][..2];", VmOptions::default()),
+        Err(Error::Runtime(error)) if error.cause == RuntimeErrorCause::IndexOutOfBounds
+    ));
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::number;
+use gravitas::{run, VmOptions};
+
+#[test]
+fn concatenates_arrays() {
+    let code = "let a = [2, 3]; let b = [1, ...a, 4, ...[5]]; \
+                b[0] + b[1] * 10 + b[2] * 100 + b[3] * 1000 + b[4] * 10000;";
+    assert_eq!(number(code), 54321.0);
+    assert_eq!(number("let empty = []; let a = [...empty, 1]; a[0];"), 1.0);
+}
+
+#[test]
+fn forwards_arguments() {
+    let add = "fn add(x, y, z) { x * 100 + y * 10 + z } ";
+    assert_eq!(number(&format!("{} add(...[1, 2, 3]);", add)), 123.0);
+    assert_eq!(number(&format!("{} add(1, ...[2], 3);", add)), 123.0);
+    let code =
+        "fn last(...rest) { rest[2] } fn forward(...args) { last(...args) } forward(1, 2, 3);";
+    assert_eq!(number(code), 3.0);
+}
+
+#[test]
+fn spreads_only_arrays() {
+    assert!(run("let x = [...5];", VmOptions::default()).is_err());
+}
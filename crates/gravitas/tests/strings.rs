@@ -0,0 +1,19 @@
+mod common;
+
+use common::string;
+
+#[test]
+fn interpolates_expressions_into_strings() {
+    assert_eq!(string("let x = 2; \"value is ${x + 1}\";"), "value is 3");
+    assert_eq!(string("\"${1}${true} ${0..2}\";"), "1true 0..2");
+    assert_eq!(string("\"a ${\"b ${[1, 2][1]}\"} c\";"), "a b 2 c");
+}
+
+#[test]
+fn replaces_escape_sequences() {
+    assert_eq!(
+        string(r#""a\tb\n\"c\" \\ \${d} \u{263A}";"#),
+        "a\tb\n\"c\" \\ ${d} \u{263A}"
+    );
+    assert_eq!(string(r#"let x = 1; "\"${x}\"";"#), "\"1\"");
+}
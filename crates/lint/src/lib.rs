@@ -0,0 +1,144 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use common::diagnostics::{Diagnostics, FileId};
+use parser::parse::AstRef;
+
+pub(crate) mod rules;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    // Variables and parameters that are never read
+    UnusedVariables,
+    // Declarations reusing the name of a variable that is still in scope
+    Shadowing,
+    // Code after return, break or continue
+    UnreachableCode,
+    // If and while conditions that are literals
+    ConstantCondition,
+    // Calls whose result is thrown away although the function returns a value
+    UnusedResult,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::UnusedVariables,
+        Rule::Shadowing,
+        Rule::UnreachableCode,
+        Rule::ConstantCondition,
+        Rule::UnusedResult,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnusedVariables => "unused_variables",
+            Self::Shadowing => "shadowing",
+            Self::UnreachableCode => "unreachable_code",
+            Self::ConstantCondition => "constant_condition",
+            Self::UnusedResult => "unused_result",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.name() == name)
+            .ok_or_else(|| format!("Unknown lint rule {}", name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    // Reported as an error, so the lint fails
+    Deny,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => Err(format!("Unknown lint level {}", name)),
+        }
+    }
+}
+
+// Level of every rule, all of them warn unless configured otherwise
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<Rule, Level>,
+}
+
+impl LintConfig {
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels.get(&rule).copied().unwrap_or(Level::Warn)
+    }
+
+    pub fn set(&mut self, rule: Rule, level: Level) {
+        self.levels.insert(rule, level);
+    }
+
+    pub fn with(mut self, rule: Rule, level: Level) -> Self {
+        self.set(rule, level);
+        self
+    }
+}
+
+// One `rule = level` pair per line, # starts a comment:
+//
+// unused_variables = deny
+// shadowing = allow
+impl FromStr for LintConfig {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut config = LintConfig::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (rule, level) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Line {}: expected rule = level", index + 1))?;
+            let rule: Rule = rule.trim().parse()?;
+            let level: Level = level.trim().parse()?;
+            config.set(rule, level);
+        }
+
+        Ok(config)
+    }
+}
+
+// Runs every rule that isn't allowed. Names of declarations aren't nodes, so the code is needed
+// to point at them.
+pub fn lint(code: &str, ast: AstRef, file_id: FileId, config: &LintConfig) -> Diagnostics {
+    rules::Linter::new(code, file_id, config).run(ast)
+}
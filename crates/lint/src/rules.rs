@@ -0,0 +1,486 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use common::diagnostics::{DiagnosticKind, Diagnostics, FileId};
+use parser::parse::{
+    expr::{atom::AtomicValue, Expr, ExprKind, Pattern},
+    stmt::{Stmt, StmtKind},
+    AstRef, Params, Span,
+};
+
+use crate::{Level, LintConfig, Rule};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BindingKind {
+    Variable,
+    Parameter,
+    // Remembers whether the function returns a value, for unused_result
+    Function { returns_value: bool },
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    name: String,
+    span: Option<Span>,
+    kind: BindingKind,
+    used: bool,
+}
+
+// Names starting with an underscore are unused on purpose
+fn is_ignored(name: &str) -> bool {
+    name.starts_with('_')
+}
+
+fn diverges(stmt: &Stmt) -> bool {
+    match &*stmt.kind {
+        StmtKind::Expression { expr } => matches!(
+            &*expr.kind,
+            ExprKind::Return { .. } | ExprKind::Break { .. } | ExprKind::Continue
+        ),
+        _ => false,
+    }
+}
+
+// Searches the function body for a return with a value, without entering nested functions
+fn returns_value(expr: &Expr) -> bool {
+    use ExprKind::*;
+
+    match &*expr.kind {
+        Return { value } => value.is_some(),
+        Block { stmts, return_expr } => {
+            return_expr.is_some()
+                || stmts.iter().any(|stmt| match &*stmt.kind {
+                    StmtKind::Expression { expr }
+                    | StmtKind::VariableDeclaration { expr, .. }
+                    | StmtKind::Destructuring { expr, .. } => {
+                        returns_value(expr)
+                    }
+                    StmtKind::MultipleDeclaration { exprs, .. } => exprs.iter().any(returns_value),
+                    StmtKind::ParallelAssignment { targets, values } => {
+                        targets.iter().chain(values).any(returns_value)
+                    }
+                    StmtKind::FunctionDeclaration { .. }
+                    | StmtKind::Import { .. }
+                    | StmtKind::Export { .. } => false,
+                })
+        }
+        If {
+            body, else_expr, ..
+        } => returns_value(body) || else_expr.as_ref().is_some_and(returns_value),
+        While { body, .. } | Loop { body } | For { body, .. } | ForIn { body, .. } => {
+            returns_value(body)
+        }
+        Match { arms, .. } => arms.iter().any(|arm| returns_value(&arm.body)),
+        _ => false,
+    }
+}
+
+// Arrow functions return their body, blocks only what they end with or pass to return
+fn function_returns_value(body: &Expr) -> bool {
+    !matches!(&*body.kind, ExprKind::Block { .. }) || returns_value(body)
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(
+        &*expr.kind,
+        ExprKind::Atom(AtomicValue::Boolean(_))
+            | ExprKind::Atom(AtomicValue::Number(_))
+            | ExprKind::Atom(AtomicValue::Text(_))
+    )
+}
+
+pub(crate) struct Linter<'a> {
+    code: &'a str,
+    file_id: FileId,
+    config: &'a LintConfig,
+    scopes: Vec<Vec<Binding>>,
+    diagnostics: Diagnostics,
+}
+
+impl<'a> Linter<'a> {
+    pub(crate) fn new(code: &'a str, file_id: FileId, config: &'a LintConfig) -> Self {
+        Self {
+            code,
+            file_id,
+            config,
+            scopes: vec![],
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    pub(crate) fn run(mut self, ast: AstRef) -> Diagnostics {
+        self.scopes.push(vec![]);
+        for stmt in ast {
+            self.visit_stmt(stmt);
+        }
+        self.leave_scope();
+
+        self.diagnostics
+    }
+
+    fn report(&mut self, rule: Rule, message: String, labels: Vec<Label<FileId>>) {
+        let diagnostic = match self.config.level(rule) {
+            Level::Allow => return,
+            Level::Warn => Diagnostic::warning(),
+            Level::Deny => Diagnostic::error(),
+        };
+
+        let level = self.config.level(rule).name();
+        let diagnostic = diagnostic
+            .with_message(message)
+            .with_code(rule.name())
+            .with_labels(labels)
+            .with_notes(vec![format!("{} is set to {}", rule, level)]);
+        self.diagnostics.push(DiagnosticKind::Lint, diagnostic);
+    }
+
+    // Declared names aren't nodes, so they're found in the source right after their keyword
+    fn name_span(&self, stmt_span: &Span, keyword: &str, name: &str) -> Option<Span> {
+        let after_keyword = stmt_span.start + keyword.len();
+        self.code
+            .get(after_keyword..stmt_span.end)
+            .and_then(|text| text.find(name))
+            .map(|offset| after_keyword + offset..after_keyword + offset + name.len())
+    }
+
+    fn find(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|binding| binding.name == name))
+    }
+
+    fn declare(&mut self, name: &str, span: Option<Span>, kind: BindingKind) {
+        if !is_ignored(name) {
+            let shadowed = self.find(name).map(|binding| binding.span.clone());
+            if let Some(shadowed) = shadowed {
+                let mut labels = vec![];
+                if let Some(span) = span.clone() {
+                    labels.push(Label::primary(self.file_id, span));
+                }
+                if let Some(shadowed) = shadowed {
+                    labels.push(
+                        Label::secondary(self.file_id, shadowed)
+                            .with_message("previous declaration"),
+                    );
+                }
+                self.report(
+                    Rule::Shadowing,
+                    format!("{} shadows a previous declaration", name),
+                    labels,
+                );
+            }
+        }
+
+        let binding = Binding {
+            name: name.to_owned(),
+            span,
+            kind,
+            used: false,
+        };
+        self.scopes
+            .last_mut()
+            .expect("There's always a scope")
+            .push(binding);
+    }
+
+    fn leave_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+
+        for binding in scope {
+            if binding.used || is_ignored(&binding.name) {
+                continue;
+            }
+
+            let what = match binding.kind {
+                BindingKind::Variable => "variable",
+                BindingKind::Parameter => "parameter",
+                BindingKind::Function { .. } => continue,
+            };
+            let labels = binding
+                .span
+                .map(|span| vec![Label::primary(self.file_id, span)])
+                .unwrap_or_default();
+            self.report(
+                Rule::UnusedVariables,
+                format!("Unused {} {}", what, binding.name),
+                labels,
+            );
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &*stmt.kind {
+            StmtKind::VariableDeclaration { name, expr, .. } => {
+                self.visit_expr(expr);
+                let span = self.name_span(&stmt.span, "let", name);
+                self.declare(name, span, BindingKind::Variable);
+            }
+            StmtKind::MultipleDeclaration { names, exprs } => {
+                for expr in exprs {
+                    self.visit_expr(expr);
+                }
+                for name in names {
+                    self.declare(&name.kind, Some(name.span.clone()), BindingKind::Variable);
+                }
+            }
+            StmtKind::ParallelAssignment { targets, values } => {
+                // Like in a single assignment, writing to a variable doesn't use it
+                for target in targets {
+                    if !matches!(&*target.kind, ExprKind::Atom(AtomicValue::Identifier { .. })) {
+                        self.visit_expr(target);
+                    }
+                }
+                for value in values {
+                    self.visit_expr(value);
+                }
+            }
+            StmtKind::Destructuring { pattern, expr } => {
+                self.visit_expr(expr);
+                for name in pattern.names() {
+                    self.declare(&name.kind, Some(name.span.clone()), BindingKind::Variable);
+                }
+            }
+            StmtKind::FunctionDeclaration {
+                name, params, body, ..
+            } => {
+                let span = self.name_span(&stmt.span, "fn", name);
+                let kind = BindingKind::Function {
+                    returns_value: function_returns_value(body),
+                };
+                self.declare(name, span, kind);
+                self.visit_function(params, body);
+            }
+            StmtKind::Expression { expr } => {
+                self.check_unused_result(expr);
+                self.visit_expr(expr);
+            }
+            StmtKind::Import { .. } => {}
+            StmtKind::Export { declaration } => {
+                self.visit_stmt(declaration);
+                // Other files use what gets exported
+                for name in declaration.kind.declared_names() {
+                    if let Some(binding) = self.find(&name) {
+                        binding.used = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_unused_result(&mut self, expr: &Expr) {
+        if let ExprKind::Call { callee, .. } = &*expr.kind {
+            if let ExprKind::Atom(AtomicValue::Identifier { name, .. }) = &*callee.kind {
+                let returns_value = matches!(
+                    self.find(name).map(|binding| binding.kind),
+                    Some(BindingKind::Function {
+                        returns_value: true
+                    })
+                );
+
+                if returns_value {
+                    self.report(
+                        Rule::UnusedResult,
+                        format!("Result of {} is never used", name),
+                        vec![Label::primary(self.file_id, expr.span.clone())],
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Expr, keyword: &str) {
+        // while true is the way to write an infinite loop
+        let infinite_loop = keyword == "while"
+            && matches!(&*condition.kind, ExprKind::Atom(AtomicValue::Boolean(true)));
+
+        if is_literal(condition) && !infinite_loop {
+            self.report(
+                Rule::ConstantCondition,
+                format!("Condition of this {} is always the same", keyword),
+                vec![Label::primary(self.file_id, condition.span.clone())],
+            );
+        }
+    }
+
+    fn visit_function(&mut self, params: &Params, body: &Expr) {
+        self.scopes.push(vec![]);
+        for param in params.kind.iter() {
+            self.declare(
+                &param.kind,
+                Some(param.span.clone()),
+                BindingKind::Parameter,
+            );
+        }
+        self.visit_expr(body);
+        self.leave_scope();
+    }
+
+    fn visit_block(&mut self, stmts: &[Stmt], return_expr: &Option<Expr>) {
+        self.scopes.push(vec![]);
+
+        let mut unreachable: Option<Span> = None;
+        let mut diverged = false;
+        for stmt in stmts {
+            if diverged && unreachable.is_none() {
+                unreachable = Some(stmt.span.clone());
+            }
+            self.visit_stmt(stmt);
+            diverged = diverged || diverges(stmt);
+        }
+        if let Some(return_expr) = return_expr {
+            if diverged && unreachable.is_none() {
+                unreachable = Some(return_expr.span.clone());
+            }
+            self.visit_expr(return_expr);
+        }
+
+        if let Some(start) = unreachable {
+            let end = return_expr
+                .as_ref()
+                .map(|expr| expr.span.end)
+                .or_else(|| stmts.last().map(|stmt| stmt.span.end))
+                .unwrap_or(start.end);
+            self.report(
+                Rule::UnreachableCode,
+                "Unreachable code".to_owned(),
+                vec![Label::primary(self.file_id, start.start..end)],
+            );
+        }
+
+        self.leave_scope();
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        use ExprKind::*;
+
+        match &*expr.kind {
+            Atom(AtomicValue::Identifier { name, .. }) => {
+                if let Some(binding) = self.find(name) {
+                    binding.used = true;
+                }
+            }
+            Atom(_) | Continue => {}
+            Binary { lhs, rhs, .. } => {
+                self.visit_expr(lhs);
+                self.visit_expr(rhs);
+            }
+            Unary { rhs, .. } | Spread { value: rhs } => self.visit_expr(rhs),
+            Block { stmts, return_expr } => self.visit_block(stmts, return_expr),
+            If {
+                condition,
+                body,
+                else_expr,
+            } => {
+                self.check_condition(condition, "if");
+                self.visit_expr(condition);
+                self.visit_expr(body);
+                if let Some(else_expr) = else_expr {
+                    self.visit_expr(else_expr);
+                }
+            }
+            While { condition, body } => {
+                self.check_condition(condition, "while");
+                self.visit_expr(condition);
+                self.visit_expr(body);
+            }
+            Loop { body } => self.visit_expr(body),
+            Match { value, arms } => {
+                self.visit_expr(value);
+                for arm in arms {
+                    if let Pattern::Value(pattern) = &arm.pattern {
+                        self.visit_expr(pattern);
+                    }
+                    self.visit_expr(&arm.body);
+                }
+            }
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                self.scopes.push(vec![]);
+                if let Some(init) = init {
+                    self.visit_stmt(init);
+                }
+                if let Some(condition) = condition {
+                    self.check_condition(condition, "for");
+                    self.visit_expr(condition);
+                }
+                if let Some(step) = step {
+                    self.visit_expr(step);
+                }
+                self.visit_expr(body);
+                self.leave_scope();
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                self.visit_expr(iterable);
+                self.scopes.push(vec![]);
+                self.declare(&item.kind, Some(item.span.clone()), BindingKind::Variable);
+                self.visit_expr(body);
+                self.leave_scope();
+            }
+            Break { return_expr: value } | Return { value } => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+            }
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
+                self.visit_expr(callee);
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+                for arg in named_args {
+                    self.visit_expr(&arg.value);
+                }
+            }
+            Array { values } | Interpolation { parts: values } => {
+                for value in values {
+                    self.visit_expr(value);
+                }
+            }
+            Index { target, position } => {
+                self.visit_expr(target);
+                self.visit_expr(position);
+            }
+            Slice {
+                target, start, end, ..
+            } => {
+                self.visit_expr(target);
+                for bound in start.iter().chain(end) {
+                    self.visit_expr(bound);
+                }
+            }
+            GetProperty { target, .. } => self.visit_expr(target),
+            SetProperty { target, value, .. } => {
+                self.visit_expr(target);
+                self.visit_expr(value);
+            }
+            // Writing to a variable doesn't count as using it
+            Assignment { target, value } => {
+                if !matches!(&*target.kind, Atom(AtomicValue::Identifier { .. })) {
+                    self.visit_expr(target);
+                }
+                self.visit_expr(value);
+            }
+            ObjectLiteral { properties } => {
+                for (_, value) in properties {
+                    self.visit_expr(value);
+                }
+            }
+            Map { entries } => {
+                for (_, value) in entries {
+                    self.visit_expr(value);
+                }
+            }
+            Closure { params, body } => self.visit_function(params, body),
+        }
+    }
+}
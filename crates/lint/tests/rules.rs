@@ -0,0 +1,89 @@
+use common::diagnostics::Diagnostics;
+use lint::{lint, Level, LintConfig, Rule};
+use parser::parse;
+
+fn run(code: &str, config: &LintConfig) -> Diagnostics {
+    let ast = parse(code).expect("Program should parse");
+    lint(code, &ast, 0, config)
+}
+
+fn codes(diagnostics: &Diagnostics) -> Vec<String> {
+    diagnostics
+        .iter()
+        .filter_map(|entry| entry.diagnostic.code.clone())
+        .collect()
+}
+
+#[test]
+fn reports_unused_variables_and_parameters() {
+    let code = "let used = 1; let unused = 2; let _ignored = 3; fn f(a, b) { a } f(used, 1);";
+    let diagnostics = run(code, &LintConfig::default());
+    let messages: Vec<&str> = diagnostics
+        .iter()
+        .map(|entry| entry.diagnostic.message.as_str())
+        .filter(|message| message.starts_with("Unused"))
+        .collect();
+
+    assert_eq!(
+        messages,
+        vec!["Unused parameter b", "Unused variable unused"]
+    );
+    let label = &diagnostics.iter().next().unwrap().diagnostic.labels[0];
+    assert_eq!(&code[label.range.clone()], "b");
+}
+
+#[test]
+fn reports_shadowing_unreachable_code_and_constant_conditions() {
+    let code = "fn f(a) { let a = 2; return a; a + 1 } if true { 1 } else { 2 }; f(1);";
+    let diagnostics = run(code, &LintConfig::default());
+
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            "shadowing",
+            "unreachable_code",
+            "unused_variables",
+            "constant_condition",
+            "unused_result"
+        ]
+    );
+}
+
+#[test]
+fn infinite_loops_are_not_constant_conditions() {
+    let diagnostics = run("while true { break; };", &LintConfig::default());
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn levels_come_from_the_config() {
+    let config: LintConfig = "# strict\nunused_variables = deny\nunused_result = allow"
+        .parse()
+        .unwrap();
+    assert_eq!(config.level(Rule::UnusedVariables), Level::Deny);
+    assert_eq!(config.level(Rule::Shadowing), Level::Warn);
+
+    let diagnostics = run("fn f() => 1 let x = f(); f();", &config);
+    assert_eq!(codes(&diagnostics), vec!["unused_variables"]);
+    assert!(diagnostics.has_errors());
+
+    let config = LintConfig::default().with(Rule::UnusedVariables, Level::Allow);
+    let diagnostics = run("fn f() => 1 fn g() { print(1); } f(); g();", &config);
+    assert_eq!(codes(&diagnostics), vec!["unused_result"]);
+
+    assert!("unknown = deny".parse::<LintConfig>().is_err());
+    assert!("shadowing = sometimes".parse::<LintConfig>().is_err());
+}
+
+#[test]
+fn parallel_assignment_does_not_use_its_targets() {
+    let code = "let a, b = 1, 2; a, b = 3, 4; print(b);";
+    let diagnostics = run(code, &LintConfig::default());
+    let messages: Vec<&str> = diagnostics
+        .iter()
+        .map(|entry| entry.diagnostic.message.as_str())
+        .filter(|message| message.starts_with("Unused"))
+        .collect();
+
+    assert_eq!(messages, vec!["Unused variable a"]);
+}
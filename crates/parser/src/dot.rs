@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use common::ProgramText;
+
+use crate::{
+    parse::{
+        expr::{atom::AtomicValue, Expr, ExprKind, Pattern},
+        stmt::{annotated, Stmt, StmtKind},
+        Params, Program,
+    },
+    token::escape_string,
+};
+
+struct Scope {
+    bindings: HashMap<ProgramText, usize>,
+    // How many functions deep the scope is, used to tell captured variables apart
+    function_depth: usize,
+}
+
+// Renders the AST as a Graphviz digraph, optionally linking every identifier to its declaration
+pub(crate) struct DotWriter {
+    nodes: Vec<String>,
+    edges: Vec<String>,
+    references: Vec<String>,
+    show_scopes: bool,
+    scopes: Vec<Scope>,
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl DotWriter {
+    pub(crate) fn new(show_scopes: bool) -> Self {
+        Self {
+            nodes: vec![],
+            edges: vec![],
+            references: vec![],
+            show_scopes,
+            scopes: vec![Scope {
+                bindings: HashMap::new(),
+                function_depth: 0,
+            }],
+        }
+    }
+
+    pub(crate) fn write(mut self, program: &Program) -> String {
+        let root = self.node("Program", None, "");
+        for stmt in program {
+            self.stmt(stmt, root);
+        }
+
+        let mut out =
+            String::from("digraph ast {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for line in self.nodes.iter().chain(&self.edges).chain(&self.references) {
+            out.push_str("    ");
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn node(&mut self, label: &str, parent: Option<usize>, role: &str) -> usize {
+        let id = self.nodes.len();
+        self.nodes
+            .push(format!("n{} [label=\"{}\"];", id, escape(label)));
+
+        if let Some(parent) = parent {
+            self.edges.push(if role.is_empty() {
+                format!("n{} -> n{};", parent, id)
+            } else {
+                format!("n{} -> n{} [label=\"{}\"];", parent, id, escape(role))
+            });
+        }
+
+        id
+    }
+
+    fn function_depth(&self) -> usize {
+        self.scopes.last().map_or(0, |scope| scope.function_depth)
+    }
+
+    fn enter_scope(&mut self, function_depth: usize) {
+        self.scopes.push(Scope {
+            bindings: HashMap::new(),
+            function_depth,
+        });
+    }
+
+    fn bind(&mut self, name: &str, id: usize) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bindings.insert(name.to_owned(), id);
+        }
+    }
+
+    // Variables declared in an enclosing function (but not globals) are captured by closures
+    fn reference(&mut self, name: &str, id: usize) {
+        if !self.show_scopes {
+            return;
+        }
+
+        let current_depth = self.function_depth();
+        let declaration = self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .bindings
+                .get(name)
+                .map(|declaration| (*declaration, scope.function_depth))
+        });
+
+        if let Some((declaration, depth)) = declaration {
+            let style = if depth > 0 && depth < current_depth {
+                "color=red, label=\"captures\""
+            } else {
+                "color=blue"
+            };
+
+            self.references.push(format!(
+                "n{} -> n{} [style=dashed, constraint=false, {}];",
+                id, declaration, style
+            ));
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, parent: usize) {
+        match &*stmt.kind {
+            StmtKind::Expression { expr } => {
+                let id = self.node("Expression", Some(parent), "");
+                self.expr(expr, id, "");
+            }
+            StmtKind::VariableDeclaration {
+                name,
+                annotation,
+                expr,
+                ..
+            } => {
+                let label = format!("let {}{}", name, annotated(annotation));
+                let id = self.node(&label, Some(parent), "");
+                self.expr(expr, id, "value");
+                self.bind(name, id);
+            }
+            StmtKind::Destructuring { pattern, expr } => {
+                let id = self.node(&format!("let {}", pattern), Some(parent), "");
+                self.expr(expr, id, "value");
+                for name in pattern.names() {
+                    self.bind(&name.kind, id);
+                }
+            }
+            StmtKind::MultipleDeclaration { names, exprs } => {
+                let names: Vec<&str> = names.iter().map(|name| name.kind.as_str()).collect();
+                let id = self.node(&format!("let {}", names.join(", ")), Some(parent), "");
+                for expr in exprs {
+                    self.expr(expr, id, "value");
+                }
+                for name in names {
+                    self.bind(name, id);
+                }
+            }
+            StmtKind::ParallelAssignment { targets, values } => {
+                let id = self.node("Parallel assignment", Some(parent), "");
+                for target in targets {
+                    self.expr(target, id, "target");
+                }
+                for value in values {
+                    self.expr(value, id, "value");
+                }
+            }
+            StmtKind::FunctionDeclaration {
+                name, params, body, ..
+            } => {
+                let id = self.node(&format!("fn {}", name), Some(parent), "");
+                // Bound before the body so recursive calls resolve
+                self.bind(name, id);
+                self.function(params, body, id);
+            }
+            StmtKind::Import { path } => {
+                self.node(&format!("import {:?}", path.kind), Some(parent), "");
+            }
+            StmtKind::Export { declaration } => {
+                let id = self.node("export", Some(parent), "");
+                self.stmt(declaration, id);
+            }
+        }
+    }
+
+    fn function(&mut self, params: &Params, body: &Expr, id: usize) {
+        self.enter_scope(self.function_depth() + 1);
+
+        for param in params.kind.iter() {
+            let param_id = self.node(&param.kind, Some(id), "param");
+            self.bind(&param.kind, param_id);
+        }
+
+        self.expr(body, id, "body");
+        self.scopes.pop();
+    }
+
+    fn exprs(&mut self, exprs: &[Expr], parent: usize, role: &str) {
+        for (index, expr) in exprs.iter().enumerate() {
+            self.expr(expr, parent, &format!("{} {}", role, index));
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, parent: usize, role: &str) {
+        use ExprKind::*;
+
+        let parent = Some(parent);
+
+        match &*expr.kind {
+            Atom(AtomicValue::Identifier { name, .. }) => {
+                let id = self.node(name, parent, role);
+                self.reference(name, id);
+            }
+            Atom(AtomicValue::Text(text)) => {
+                self.node(&format!("\"{}\"", escape_string(text)), parent, role);
+            }
+            Atom(value) => {
+                self.node(&value.to_string(), parent, role);
+            }
+            Binary { lhs, op, rhs } => {
+                let id = self.node(&op.kind.to_string(), parent, role);
+                self.expr(lhs, id, "lhs");
+                self.expr(rhs, id, "rhs");
+            }
+            Unary { op, rhs } => {
+                let id = self.node(&op.kind.to_string(), parent, role);
+                self.expr(rhs, id, "");
+            }
+            Block { stmts, return_expr } => {
+                let id = self.node("Block", parent, role);
+                self.enter_scope(self.function_depth());
+                for stmt in stmts {
+                    self.stmt(stmt, id);
+                }
+                if let Some(return_expr) = return_expr {
+                    self.expr(return_expr, id, "return");
+                }
+                self.scopes.pop();
+            }
+            If {
+                condition,
+                body,
+                else_expr,
+            } => {
+                let id = self.node("If", parent, role);
+                self.expr(condition, id, "condition");
+                self.expr(body, id, "then");
+                if let Some(else_expr) = else_expr {
+                    self.expr(else_expr, id, "else");
+                }
+            }
+            While { condition, body } => {
+                let id = self.node("While", parent, role);
+                self.expr(condition, id, "condition");
+                self.expr(body, id, "body");
+            }
+            Loop { body } => {
+                let id = self.node("Loop", parent, role);
+                self.expr(body, id, "body");
+            }
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                let id = self.node("For", parent, role);
+                // Variable declared by the init is only visible inside of the loop
+                self.enter_scope(self.function_depth());
+                if let Some(init) = init {
+                    self.stmt(init, id);
+                }
+                if let Some(condition) = condition {
+                    self.expr(condition, id, "condition");
+                }
+                if let Some(step) = step {
+                    self.expr(step, id, "step");
+                }
+                self.expr(body, id, "body");
+                self.scopes.pop();
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                let id = self.node("ForIn", parent, role);
+                self.expr(iterable, id, "iterable");
+                self.enter_scope(self.function_depth());
+                let item_id = self.node(&item.kind, Some(id), "item");
+                self.bind(&item.kind, item_id);
+                self.expr(body, id, "body");
+                self.scopes.pop();
+            }
+            Match { value, arms } => {
+                let id = self.node("Match", parent, role);
+                self.expr(value, id, "value");
+                for (index, arm) in arms.iter().enumerate() {
+                    let arm_id = self.node(&format!("Arm {}", index), Some(id), "");
+                    match &arm.pattern {
+                        Pattern::Wildcard => {
+                            self.node("_", Some(arm_id), "pattern");
+                        }
+                        Pattern::Value(pattern) => self.expr(pattern, arm_id, "pattern"),
+                    }
+                    self.expr(&arm.body, arm_id, "body");
+                }
+            }
+            Break { return_expr } => {
+                let id = self.node("Break", parent, role);
+                if let Some(return_expr) = return_expr {
+                    self.expr(return_expr, id, "");
+                }
+            }
+            Continue => {
+                self.node("Continue", parent, role);
+            }
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
+                let id = self.node("Call", parent, role);
+                self.expr(callee, id, "callee");
+                self.exprs(args, id, "arg");
+                for arg in named_args {
+                    self.expr(&arg.value, id, &format!("arg {}", arg.name.kind));
+                }
+            }
+            Return { value } => {
+                let id = self.node("Return", parent, role);
+                if let Some(value) = value {
+                    self.expr(value, id, "");
+                }
+            }
+            Array { values } => {
+                let id = self.node("Array", parent, role);
+                self.exprs(values, id, "item");
+            }
+            Spread { value } => {
+                let id = self.node("...", parent, role);
+                self.expr(value, id, "");
+            }
+            Interpolation { parts } => {
+                let id = self.node("Interpolation", parent, role);
+                self.exprs(parts, id, "part");
+            }
+            Index { target, position } => {
+                let id = self.node("Index", parent, role);
+                self.expr(target, id, "target");
+                self.expr(position, id, "position");
+            }
+            Slice {
+                target,
+                start,
+                end,
+                inclusive,
+            } => {
+                let label = if *inclusive { "Slice ..=" } else { "Slice .." };
+                let id = self.node(label, parent, role);
+                self.expr(target, id, "target");
+                if let Some(start) = start {
+                    self.expr(start, id, "start");
+                }
+                if let Some(end) = end {
+                    self.expr(end, id, "end");
+                }
+            }
+            GetProperty {
+                target, identifier, ..
+            } => {
+                let id = self.node(&format!(".{}", identifier.kind), parent, role);
+                self.expr(target, id, "target");
+            }
+            SetProperty {
+                target,
+                value,
+                identifier,
+            } => {
+                let id = self.node(&format!(".{} =", identifier.kind), parent, role);
+                self.expr(target, id, "target");
+                self.expr(value, id, "value");
+            }
+            ObjectLiteral { properties } => {
+                let id = self.node("Object", parent, role);
+                for (key, value) in properties {
+                    self.expr(value, id, key);
+                }
+            }
+            Map { entries } => {
+                let id = self.node("Map", parent, role);
+                for (key, value) in entries {
+                    self.expr(value, id, &key.kind);
+                }
+            }
+            Assignment { target, value } => {
+                let id = self.node("=", parent, role);
+                self.expr(target, id, "target");
+                self.expr(value, id, "value");
+            }
+            Closure { params, body } => {
+                let id = self.node("Closure", parent, role);
+                self.function(params, body, id);
+            }
+        }
+    }
+}
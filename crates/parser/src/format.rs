@@ -0,0 +1,615 @@
+use crate::{
+    parse::{
+        expr::{atom::AtomicValue, Expr, ExprKind, Pattern},
+        operator::{BinaryOperator, UnaryOperator},
+        stmt::{annotated, Stmt, StmtKind},
+        visit::{walk_expr, Visit},
+        Params, Span,
+    },
+    token::{escape_string, is_radix_number, operator::Operator},
+    trivia::{comments, Comment},
+};
+
+const INDENT: &str = "    ";
+const MAX_WIDTH: usize = 80;
+// Binding power of the prefix operators, see Operator::prefix_bp
+const PREFIX_BP: u8 = 7;
+
+enum Item<'a> {
+    Stmt(&'a Stmt),
+    // Last expression of a block which is not followed by a semicolon
+    Expr(&'a Expr),
+}
+
+impl<'a> Item<'a> {
+    fn span(&self) -> Span {
+        match self {
+            Item::Stmt(stmt) => stmt.span.clone(),
+            Item::Expr(expr) => expr.span.clone(),
+        }
+    }
+}
+
+fn pad(indent: usize) -> String {
+    INDENT.repeat(indent)
+}
+
+// How far an expression reaches to the right when it's not wrapped in parentheses.
+// For example unary operators swallow every following operator that binds stronger
+// than they do, so -a + b is in fact -(a + b).
+fn absorbs(expr: &Expr) -> Option<u8> {
+    match &*expr.kind {
+        ExprKind::Unary { .. } => Some(PREFIX_BP),
+        ExprKind::Assignment { .. }
+        | ExprKind::SetProperty { .. }
+        | ExprKind::Closure { .. }
+        | ExprKind::Return { value: Some(_) }
+        | ExprKind::Break {
+            return_expr: Some(_),
+        } => Some(0),
+        _ => None,
+    }
+}
+
+fn has_notation(literal: &str) -> bool {
+    is_radix_number(literal) || literal.contains('_')
+}
+
+fn binding_power(op: BinaryOperator) -> (u8, u8) {
+    Operator::from(op)
+        .infix_bp()
+        .expect("Every binary operator has a binding power")
+}
+
+// Outermost blocks of the item, comments inside of them are printed by the blocks themselves
+#[derive(Default)]
+struct Blocks {
+    spans: Vec<Span>,
+}
+
+impl Visit for Blocks {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &*expr.kind {
+            ExprKind::Block { .. } => self.spans.push(expr.span.clone()),
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+pub(crate) struct Formatter<'c> {
+    code: &'c str,
+    comments: Vec<Comment>,
+}
+
+impl<'c> Formatter<'c> {
+    pub(crate) fn new(code: &'c str) -> Self {
+        Self {
+            code,
+            comments: comments(code),
+        }
+    }
+
+    pub(crate) fn format(&self, program: &[Stmt]) -> String {
+        let items: Vec<Item> = program.iter().map(Item::Stmt).collect();
+        let lines = self.items(&items, 0..self.code.len(), 0);
+
+        if lines.is_empty() {
+            String::new()
+        } else {
+            lines.join("\n") + "\n"
+        }
+    }
+
+    fn comments_in(&self, range: Span) -> impl Iterator<Item = &Comment> {
+        self.comments
+            .iter()
+            .filter(move |comment| range.contains(&comment.span.start))
+    }
+
+    fn has_blank_line(&self, from: usize, to: usize) -> bool {
+        from < to && self.code[from..to].matches('\n').count() > 1
+    }
+
+    // Comments placed inside of the item, but not inside any of its blocks, e.g. in between
+    // call's arguments. There is no good place to keep them at so they end up above the item.
+    fn inner_comments(&self, item: &Item) -> Vec<&Comment> {
+        let mut blocks = Blocks::default();
+        match item {
+            Item::Expr(expr) => blocks.visit_expr(expr),
+            Item::Stmt(stmt) => blocks.visit_stmt(stmt),
+        }
+
+        self.comments_in(item.span())
+            .filter(|comment| !blocks.spans.iter().any(|span| span.contains(&comment.span.start)))
+            .collect()
+    }
+
+    // Comment which starts on the same line the item ends at
+    fn trailing_comment(&self, item_end: usize) -> Option<&Comment> {
+        let line_end = self.code[item_end..]
+            .find('\n')
+            .map_or(self.code.len(), |offset| item_end + offset);
+
+        self.comments_in(item_end..line_end)
+            .find(|comment| self.code[item_end..comment.span.start].trim().is_empty())
+    }
+
+    // Pushes the line, separated by an empty one if there was one in the source
+    fn push_line(
+        &self,
+        lines: &mut Vec<String>,
+        last_end: &mut Option<usize>,
+        span: Span,
+        line: String,
+        indent: usize,
+    ) {
+        if let Some(last_end) = last_end {
+            if self.has_blank_line(*last_end, span.start) {
+                lines.push(String::new());
+            }
+        }
+
+        lines.push(format!("{}{}", pad(indent), line));
+        *last_end = Some(span.end);
+    }
+
+    fn items(&self, items: &[Item], region: Span, indent: usize) -> Vec<String> {
+        let mut lines = vec![];
+        // Position up to which the source has been already printed
+        let mut cursor = region.start;
+        let mut last_end = None;
+
+        for item in items {
+            let span = item.span();
+            for comment in self.comments_in(cursor..span.start) {
+                let text = comment.text.clone();
+                self.push_line(&mut lines, &mut last_end, comment.span.clone(), text, indent);
+            }
+
+            // Inner comments are spaced as if they were placed at the beginning of the item
+            for comment in self.inner_comments(item) {
+                let span = span.start..comment.span.end;
+                let text = comment.text.clone();
+                self.push_line(&mut lines, &mut last_end, span, text, indent);
+            }
+
+            let mut line = match item {
+                Item::Stmt(stmt) => self.stmt(stmt, indent),
+                Item::Expr(expr) => self.expr(expr, indent, None),
+            };
+            cursor = span.end;
+
+            if let Some(comment) = self.trailing_comment(span.end) {
+                line = format!("{} {}", line, comment.text);
+                cursor = comment.span.end;
+            }
+
+            self.push_line(&mut lines, &mut last_end, span.start..cursor, line, indent);
+        }
+
+        for comment in self.comments_in(cursor..region.end) {
+            let text = comment.text.clone();
+            self.push_line(&mut lines, &mut last_end, comment.span.clone(), text, indent);
+        }
+
+        lines
+    }
+
+    fn stmt(&self, stmt: &Stmt, indent: usize) -> String {
+        match &*stmt.kind {
+            StmtKind::Expression { expr } => format!("{};", self.expr(expr, indent, None)),
+            StmtKind::VariableDeclaration {
+                name,
+                annotation,
+                expr,
+                ..
+            } => format!(
+                "let {}{} = {};",
+                name,
+                annotated(annotation),
+                self.expr(expr, indent, None)
+            ),
+            StmtKind::Destructuring { pattern, expr } => {
+                format!("let {} = {};", pattern, self.expr(expr, indent, None))
+            }
+            StmtKind::MultipleDeclaration { names, exprs } => {
+                let names: Vec<&str> = names.iter().map(|name| name.kind.as_str()).collect();
+                format!("let {} = {};", names.join(", "), self.exprs(exprs, indent))
+            }
+            StmtKind::ParallelAssignment { targets, values } => format!(
+                "{} = {};",
+                self.exprs(targets, indent),
+                self.exprs(values, indent)
+            ),
+            StmtKind::FunctionDeclaration {
+                name,
+                params,
+                returns,
+                body,
+                ..
+            } => {
+                let params = self.params(params);
+                let returns = returns
+                    .as_ref()
+                    .map(|returns| format!(" -> {}", returns.kind))
+                    .unwrap_or_default();
+                let arrow = match &*body.kind {
+                    ExprKind::Block { .. } => "",
+                    _ => "=> ",
+                };
+                format!(
+                    "fn {}({}){} {}{}",
+                    name,
+                    params,
+                    returns,
+                    arrow,
+                    self.expr(body, indent, None)
+                )
+            }
+            // The path stays quoted the way it was written
+            StmtKind::Import { path } => format!("import {};", &self.code[path.span.clone()]),
+            StmtKind::Export { declaration } => {
+                format!("export {}", self.stmt(declaration, indent))
+            }
+        }
+    }
+
+    fn params(&self, params: &Params) -> String {
+        let mut names: Vec<String> = params
+            .kind
+            .params
+            .iter()
+            .zip(&params.kind.types)
+            .map(|(param, annotation)| format!("{}{}", param.kind, annotated(annotation)))
+            .collect();
+        if let Some(rest) = &params.kind.rest {
+            names.push(format!("...{}", rest.kind));
+        }
+        names.join(", ")
+    }
+
+    fn exprs(&self, exprs: &[Expr], indent: usize) -> String {
+        exprs
+            .iter()
+            .map(|expr| self.expr(expr, indent, None))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // Prints the expression wrapped in parentheses if the operator
+    // that follows it would otherwise become a part of it.
+    fn operand(&self, expr: &Expr, indent: usize, followed_by: Option<u8>) -> String {
+        match (absorbs(expr), followed_by) {
+            (Some(absorbed), Some(following)) if following >= absorbed => {
+                format!("({})", self.expr(expr, indent, None))
+            }
+            _ => self.expr(expr, indent, followed_by),
+        }
+    }
+
+    // Target of a call, index or property access
+    fn postfix_target(&self, expr: &Expr, indent: usize) -> String {
+        match &*expr.kind {
+            ExprKind::Binary { .. } => format!("({})", self.expr(expr, indent, None)),
+            _ => self.operand(expr, indent, Some(u8::MAX)),
+        }
+    }
+
+    // Comma separated list that is broken into one element per line, each followed by a comma,
+    // if it doesn't fit
+    fn list(&self, open: &str, close: &str, elements: Vec<String>, indent: usize) -> String {
+        let flat = format!("{}{}{}", open, elements.join(", "), close);
+        if flat.contains('\n') || pad(indent).len() + flat.len() <= MAX_WIDTH {
+            return flat;
+        }
+
+        let separator = format!(",\n{}", pad(indent + 1));
+        format!(
+            "{}\n{}{},\n{}{}",
+            open.trim_end(),
+            pad(indent + 1),
+            elements.join(&separator),
+            pad(indent),
+            close.trim_start()
+        )
+    }
+
+    fn expr(&self, expr: &Expr, indent: usize, followed_by: Option<u8>) -> String {
+        use ExprKind::*;
+
+        match &*expr.kind {
+            Atom(value) => match value {
+                AtomicValue::Boolean(value) => value.to_string(),
+                // Hex, binary and octal literals, and digit separators would be lost otherwise
+                AtomicValue::Number(_) if has_notation(&self.code[expr.span.clone()]) => {
+                    self.code[expr.span.clone()].to_owned()
+                }
+                AtomicValue::Number(value) => value.to_string(),
+                // Raw strings are kept the way they were written
+                AtomicValue::Text(_) if self.code[expr.span.clone()].starts_with('r') => {
+                    self.code[expr.span.clone()].to_owned()
+                }
+                AtomicValue::Text(text) => format!("\"{}\"", escape_string(text)),
+                AtomicValue::Identifier { name, .. } => name.to_string(),
+            },
+            Binary { lhs, op, rhs } => {
+                let (l_bp, r_bp) = binding_power(op.kind);
+
+                // The operand would be taken apart by the operator otherwise, both sides of
+                // the powers matter because some operators associate to the right.
+                // Comparisons can't be chained without the parentheses either.
+                let lhs = match &*lhs.kind {
+                    Binary { op: lhs_op, .. }
+                        if binding_power(lhs_op.kind).1 <= l_bp
+                            || lhs_op.kind.is_comparison() && op.kind.is_comparison() =>
+                    {
+                        format!("({})", self.expr(lhs, indent, None))
+                    }
+                    _ => self.operand(lhs, indent, Some(l_bp)),
+                };
+
+                let rhs = match &*rhs.kind {
+                    Binary { op: rhs_op, .. } if binding_power(rhs_op.kind).0 < r_bp => {
+                        format!("({})", self.expr(rhs, indent, None))
+                    }
+                    _ => self.operand(rhs, indent, followed_by),
+                };
+
+                match op.kind {
+                    BinaryOperator::Range | BinaryOperator::RangeInclusive => {
+                        format!("{}{}{}", lhs, op.kind, rhs)
+                    }
+                    _ => format!("{} {} {}", lhs, op.kind, rhs),
+                }
+            }
+            Unary { op, rhs } => {
+                let rhs_expr = rhs;
+                let rhs = match &*rhs.kind {
+                    Binary { op: rhs_op, .. } if binding_power(rhs_op.kind).0 < PREFIX_BP => {
+                        format!("({})", self.expr(rhs, indent, None))
+                    }
+                    _ => self.operand(rhs, indent, followed_by),
+                };
+
+                // Lexer would read -1 + 2 as a negative number literal followed by + 2
+                let number_follows = rhs.starts_with(|c: char| c.is_ascii_digit() || c == '.');
+                match (op.kind, &*rhs_expr.kind) {
+                    (UnaryOperator::Negate, Atom(AtomicValue::Number(_))) => {
+                        format!("{}{}", op.kind, rhs)
+                    }
+                    (UnaryOperator::Negate, _) if number_follows => format!("{}({})", op.kind, rhs),
+                    _ => format!("{}{}", op.kind, rhs),
+                }
+            }
+            Block { stmts, return_expr } => {
+                let mut items: Vec<Item> = stmts.iter().map(Item::Stmt).collect();
+                if let Some(return_expr) = return_expr {
+                    items.push(Item::Expr(return_expr));
+                }
+
+                // Skip the brackets themselves
+                let region = expr.span.start + 1..expr.span.end.saturating_sub(1);
+                let lines = self.items(&items, region, indent + 1);
+
+                if lines.is_empty() {
+                    "{}".to_owned()
+                } else {
+                    format!("{{\n{}\n{}}}", lines.join("\n"), pad(indent))
+                }
+            }
+            If {
+                condition,
+                body,
+                else_expr,
+            } => {
+                let mut printed = format!(
+                    "if {} {}",
+                    self.expr(condition, indent, None),
+                    self.expr(body, indent, None)
+                );
+
+                if let Some(else_expr) = else_expr {
+                    printed = format!("{} else {}", printed, self.expr(else_expr, indent, None));
+                }
+
+                printed
+            }
+            Match { value, arms } => {
+                let value = self.expr(value, indent, None);
+                if arms.is_empty() {
+                    return format!("match {} {{}}", value);
+                }
+
+                // Every arm is placed on its own line
+                let arms: Vec<String> = arms
+                    .iter()
+                    .map(|arm| {
+                        let pattern = match &arm.pattern {
+                            Pattern::Wildcard => "_".to_owned(),
+                            Pattern::Value(pattern) => self.expr(pattern, indent + 1, None),
+                        };
+                        let body = self.expr(&arm.body, indent + 1, None);
+                        format!("{}{} => {},", pad(indent + 1), pattern, body)
+                    })
+                    .collect();
+
+                format!("match {} {{\n{}\n{}}}", value, arms.join("\n"), pad(indent))
+            }
+            While { condition, body } => format!(
+                "while {} {}",
+                self.expr(condition, indent, None),
+                self.expr(body, indent, None)
+            ),
+            Loop { body } => format!("loop {}", self.expr(body, indent, None)),
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                let init = init
+                    .as_ref()
+                    .map_or(";".to_owned(), |init| self.stmt(init, indent));
+                let condition = condition
+                    .as_ref()
+                    .map_or(String::new(), |condition| {
+                        format!(" {}", self.expr(condition, indent, None))
+                    });
+                let step = step
+                    .as_ref()
+                    .map_or(String::new(), |step| format!(" {}", self.expr(step, indent, None)));
+
+                format!(
+                    "for ({}{};{}) {}",
+                    init,
+                    condition,
+                    step,
+                    self.expr(body, indent, None)
+                )
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => format!(
+                "for {} in {} {}",
+                item.kind,
+                self.expr(iterable, indent, None),
+                self.expr(body, indent, None)
+            ),
+            Break { return_expr } => match return_expr {
+                Some(value) => format!("break {}", self.expr(value, indent, followed_by)),
+                None => "break".to_owned(),
+            },
+            Continue => "continue".to_owned(),
+            Return { value } => match value {
+                Some(value) => format!("return {}", self.expr(value, indent, followed_by)),
+                None => "return".to_owned(),
+            },
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
+                let callee = self.postfix_target(callee, indent);
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg, indent + 1, None))
+                    .chain(named_args.iter().map(|arg| {
+                        format!("{}: {}", arg.name.kind, self.expr(&arg.value, indent + 1, None))
+                    }))
+                    .collect();
+
+                self.list(&format!("{}(", callee), ")", args, indent)
+            }
+            Array { values } => {
+                let values = values
+                    .iter()
+                    .map(|value| self.expr(value, indent + 1, None))
+                    .collect();
+
+                self.list("[", "]", values, indent)
+            }
+            Spread { value } => format!("...{}", self.expr(value, indent, followed_by)),
+            Interpolation { parts } => {
+                let parts: String = parts
+                    .iter()
+                    .map(|part| match &*part.kind {
+                        Atom(AtomicValue::Text(text)) => escape_string(text),
+                        _ => format!("${{{}}}", self.expr(part, indent, None)),
+                    })
+                    .collect();
+
+                format!("\"{}\"", parts)
+            }
+            Index { target, position } => format!(
+                "{}[{}]",
+                self.postfix_target(target, indent),
+                self.expr(position, indent, None)
+            ),
+            Slice {
+                target,
+                start,
+                end,
+                inclusive,
+            } => {
+                let bound = |bound: &Option<Expr>| {
+                    bound
+                        .as_ref()
+                        .map(|bound| self.expr(bound, indent, None))
+                        .unwrap_or_default()
+                };
+                format!(
+                    "{}[{}{}{}]",
+                    self.postfix_target(target, indent),
+                    bound(start),
+                    if *inclusive { "..=" } else { ".." },
+                    bound(end)
+                )
+            }
+            GetProperty {
+                target, identifier, ..
+            } => format!(
+                "{}.{}",
+                self.postfix_target(target, indent),
+                identifier.kind
+            ),
+            SetProperty {
+                target,
+                identifier,
+                value,
+            } => format!(
+                "{}.{} = {}",
+                self.postfix_target(target, indent),
+                identifier.kind,
+                self.expr(value, indent, followed_by)
+            ),
+            Assignment { target, value } => format!(
+                "{} = {}",
+                self.expr(target, indent, None),
+                self.expr(value, indent, followed_by)
+            ),
+            Closure { params, body } => format!(
+                "|{}| => {}",
+                self.params(params),
+                self.expr(body, indent, followed_by)
+            ),
+            ObjectLiteral { properties } => {
+                let properties: Vec<String> = properties
+                    .iter()
+                    .map(|(key, value)| match &*value.kind {
+                        Atom(AtomicValue::Identifier { name, .. }) if name == key => key.to_string(),
+                        ObjectLiteral { .. } => {
+                            // Nested literals don't need the new keyword
+                            let nested = self.expr(value, indent + 1, None);
+                            format!("{}: {}", key, nested.trim_start_matches("new "))
+                        }
+                        _ => format!("{}: {}", key, self.expr(value, indent + 1, None)),
+                    })
+                    .collect();
+
+                if properties.is_empty() {
+                    "new {}".to_owned()
+                } else {
+                    self.list("new { ", " }", properties, indent)
+                }
+            }
+            Map { entries } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        // Keys stay quoted or bare, the way they were written
+                        let source = &self.code[key.span.clone()];
+                        let key = if source.starts_with('"') {
+                            format!("\"{}\"", escape_string(&key.kind))
+                        } else {
+                            source.to_owned()
+                        };
+                        format!("{}: {}", key, self.expr(value, indent + 1, None))
+                    })
+                    .collect();
+                self.list("{ ", " }", entries, indent)
+            }
+        }
+    }
+}
@@ -1,8 +1,16 @@
-use crate::parse::{Parser, ParserOutput};
+use crate::{
+    parse::{incremental::Edit, Ast, Parser, ParserOutput, ProgramErrors, Span},
+    token::Lexer,
+};
 use std::{fs, path::Path};
 
+pub use token::{operator::Operator, Token};
+
+pub(crate) mod dot;
+pub(crate) mod format;
 pub mod parse;
 pub(crate) mod token;
+pub mod trivia;
 pub mod utils;
 
 pub fn parse(code: &str) -> ParserOutput {
@@ -10,6 +18,38 @@ pub fn parse(code: &str) -> ParserOutput {
     parser.parse()
 }
 
+// Parses the code after the edit, the statements of the old AST the edit didn't touch are reused
+pub fn reparse(old_ast: Ast, code: &str, edit: &Edit) -> ParserOutput {
+    Parser::reparse(old_ast, code, edit)
+}
+
+// Parses the code and prints it back in the canonical style, keeping the comments
+pub fn format(code: &str) -> Result<String, ProgramErrors> {
+    let program = parse(code)?;
+    Ok(format::Formatter::new(code).format(&program))
+}
+
+// Renders the AST as a Graphviz DOT graph, `show_scopes` links identifiers to their declarations
+pub fn dot(code: &str, show_scopes: bool) -> Result<String, ProgramErrors> {
+    let program = parse(code)?;
+    Ok(dot::DotWriter::new(show_scopes).write(&program))
+}
+
+// Every token the lexer produced, with the span it was found at
+pub fn lex(code: &str) -> Vec<(Token<'_>, Span)> {
+    Lexer::new(code)
+        .map(|lexeme| (lexeme.token, lexeme.span()))
+        .collect()
+}
+
+// Tokens of the code in their Debug notation
+pub fn tokens(code: &str) -> Vec<(String, Span)> {
+    lex(code)
+        .into_iter()
+        .map(|(token, span)| (format!("{:?}", token), span))
+        .collect()
+}
+
 pub fn parse_file<P: AsRef<Path>>(path: P) -> ParserOutput {
     let code = fs::read_to_string(path).unwrap();
     parse(&code)
@@ -1,22 +1,26 @@
 use crate::{
     parse::{
         expr::{Expr, ExprKind},
-        ExprResult, Node, Parser,
+        ExprResult, Node, Parser, Span,
+    },
+    token::{constants::ASSIGN, operator::Operator, radix_number, string_parts, unescape, StringPart, Token},
+    utils::{
+        combine,
+        error::{Expect, ParseErrorCause},
     },
-    token::{constants::ASSIGN, operator::Operator, Token},
-    utils::combine,
 };
-use common::{Number, ProgramText};
+use common::{Number, ProgramText, Symbol};
 use std::fmt;
 
-pub type VariableProperty = Node<String>;
+pub type VariableProperty = Node<Symbol>;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AtomicValue {
     Boolean(bool),
     Number(Number),
     Text(ProgramText),
-    Identifier { name: String, is_assignment: bool },
+    Identifier { name: Symbol, is_assignment: bool },
 }
 
 impl fmt::Display for AtomicValue {
@@ -50,11 +54,19 @@ impl<'t> Parser<'t> {
         let val = match lexeme.token {
             Token::Bool(val) => AtomicValue::Boolean(val),
             Token::Number(val) => AtomicValue::Number(val),
+            Token::RadixNumber(literal) => {
+                AtomicValue::Number(radix_number(literal, atom_span.start)?)
+            }
             // It's safe to unwrap because these strings should be interned during advance()
             // If it panics then we have a bug in our code
-            Token::String(str) => AtomicValue::Text(str.to_owned()),
+            Token::String(str) if str.contains("${") => {
+                return Self::parse_interpolation(str, atom_span);
+            }
+            // The text of the string starts after the opening quote
+            Token::String(str) => AtomicValue::Text(unescape(str, atom_span.start + 1)?),
+            Token::RawString(str) => AtomicValue::Text(str.to_owned()),
             Token::Identifier(identifier) => {
-                let name = identifier.to_owned();
+                let name = Symbol::intern(identifier);
                 let is_assignment = self.peek() == ASSIGN;
 
                 AtomicValue::Identifier {
@@ -70,6 +82,49 @@ impl<'t> Parser<'t> {
         Ok(Expr::boxed(ExprKind::Atom(val), atom_span))
     }
 
+    fn parse_interpolation(text: &str, span: Span) -> ExprResult {
+        // The text of the string starts after the opening quote
+        let text_start = span.start + 1;
+        let mut parts = vec![];
+
+        for part in string_parts(text) {
+            match part {
+                StringPart::Text(text, offset) => {
+                    let start = text_start + offset;
+                    parts.push(Expr::boxed(
+                        ExprKind::Atom(AtomicValue::Text(unescape(text, start)?)),
+                        start..start + text.len(),
+                    ));
+                }
+                StringPart::Code(code, offset) => {
+                    let mut parser = Parser::with_offset(code, text_start + offset);
+                    let expr = parser.parse_expression()?;
+                    if parser.peek() != Token::Eof {
+                        return Err(ParseErrorCause::UnexpectedToken);
+                    }
+                    // Blocks recover from their errors, the nested parser still has them
+                    if let Some(error) = parser.errors.pop() {
+                        return Err(error.cause);
+                    }
+                    parts.push(expr);
+                }
+            }
+        }
+
+        // Backslashes can leave a string without any interpolation
+        if !parts
+            .iter()
+            .any(|part| !matches!(&*part.kind, ExprKind::Atom(AtomicValue::Text(_))))
+        {
+            return Ok(Expr::boxed(
+                ExprKind::Atom(AtomicValue::Text(unescape(text, text_start)?)),
+                span,
+            ));
+        }
+
+        Ok(Expr::boxed(ExprKind::Interpolation { parts }, span))
+    }
+
     pub(super) fn parse_obj_literal(&mut self, nested: bool) -> ExprResult {
         let start = if !nested {
             let new = self.expect(Token::New)?.span();
@@ -87,7 +142,7 @@ impl<'t> Parser<'t> {
         while self.peek() != Token::Operator(Operator::CurlyBracketClose) {
             let (key, key_span) = {
                 let key = self.advance()?;
-                (key.slice.to_owned(), key.span())
+                (Symbol::intern(key.slice), key.span())
             };
             // Shorthand for { key } instead of { key: key }
             let value = if [Token::Comma, Token::Operator(Operator::CurlyBracketClose)]
@@ -95,7 +150,7 @@ impl<'t> Parser<'t> {
             {
                 Expr::boxed(
                     ExprKind::Atom(AtomicValue::Identifier {
-                        name: key.clone(),
+                        name: key,
                         is_assignment: false,
                     }),
                     key_span,
@@ -126,6 +181,42 @@ impl<'t> Parser<'t> {
             combine(&start, &close_bracket),
         ))
     }
+
+    pub(super) fn parse_map_literal(&mut self) -> ExprResult {
+        let start = self
+            .expect(Token::Operator(Operator::CurlyBracketOpen))?
+            .span();
+
+        let mut entries = Vec::new();
+
+        while self.peek() != Token::Operator(Operator::CurlyBracketClose) {
+            let key = self.advance()?;
+            let key_span = key.span();
+            let key = match key.token {
+                Token::String(str) if !str.contains("${") => unescape(str, key_span.start + 1)?,
+                Token::RawString(str) | Token::Identifier(str) => str.to_owned(),
+                _ => return Err(ParseErrorCause::Expected(Expect::Identifier)),
+            };
+            self.expect(Token::Colon)?;
+            let value = self.parse_expression()?;
+
+            entries.push((Node::new(key, key_span), value));
+
+            if self.peek() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+
+        let close_bracket = self
+            .expect(Token::Operator(Operator::CurlyBracketClose))?
+            .span();
+
+        Ok(Expr::boxed(
+            ExprKind::Map { entries },
+            combine(&start, &close_bracket),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -197,7 +288,7 @@ pub(crate) mod test {
                 parsed_identifier,
                 Expr::boxed(
                     ExprKind::Atom(AtomicValue::Identifier {
-                        name: identifier.to_owned(),
+                        name: Symbol::intern(identifier),
                         is_assignment: false
                     }),
                     0..identifier.len(),
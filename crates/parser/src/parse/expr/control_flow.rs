@@ -1,13 +1,15 @@
+use common::Symbol;
+
 use crate::parse::utils::ExprOrStmt;
-use crate::utils::error::ParseErrorCause;
+use crate::utils::error::{Expect, ParseErrorCause};
 use crate::{
     parse::{
-        expr::{Expr, ExprKind},
+        expr::{Expr, ExprKind, MatchArm, Pattern},
         stmt::Stmt,
-        ExprResult, Parser,
+        ExprResult, Param, Parser, Span,
     },
     token::{
-        constants::{CLOSE_BRACKET, OPEN_BRACKET},
+        constants::{CLOSE_BRACKET, CLOSE_PARENTHESIS, OPEN_BRACKET, OPEN_PARENTHESIS},
         Token,
     },
     utils::combine,
@@ -23,15 +25,27 @@ impl<'t> Parser<'t> {
             if next == CLOSE_BRACKET || next == Token::Eof {
                 break;
             }
-            match self.parse_expr_or_stmt()? {
+            let span_start = self.lexer.current_span();
+            let item = match self.parse_expr_or_stmt() {
+                Ok(item) => item,
+                Err(cause) => {
+                    // The rest of the block is still parsed to find more errors
+                    self.recover(cause, span_start);
+                    continue;
+                }
+            };
+            match item {
                 ExprOrStmt::Expr(expr) => {
                     // return_expr must always come last in the block
-                    return_expr = Some(expr);
-
                     if self.peek() != CLOSE_BRACKET {
-                        return Err(ParseErrorCause::ReturnExprMustBeLast);
+                        let cause = self
+                            .misspelled(&expr)
+                            .unwrap_or(ParseErrorCause::ReturnExprMustBeLast);
+                        self.recover(cause, span_start);
+                        continue;
                     }
 
+                    return_expr = Some(expr);
                     break;
                 }
                 ExprOrStmt::Stmt(stmt) => {
@@ -88,6 +102,108 @@ impl<'t> Parser<'t> {
         Ok(Expr::boxed(ExprKind::While { condition, body }, span))
     }
 
+    pub(super) fn parse_loop_expr(&mut self) -> ExprResult {
+        let keyword = self.expect(Token::Loop)?.span();
+        let body = self.parse_block_expr()?;
+        let span = combine(&keyword, &body.span);
+
+        Ok(Expr::boxed(ExprKind::Loop { body }, span))
+    }
+
+    pub(super) fn parse_for_expr(&mut self) -> ExprResult {
+        let keyword = self.expect(Token::For)?.span();
+        if self.peek().is_identifier() {
+            return self.parse_for_in_expr(keyword);
+        }
+        self.expect(OPEN_PARENTHESIS)?;
+
+        // Both kinds of statements consume their own semicolon
+        let init = match self.peek() {
+            Token::Semicolon => {
+                self.expect(Token::Semicolon)?;
+                None
+            }
+            Token::Let => Some(self.parse_variable_declaration()?),
+            _ => Some(self.parse_expression_stmt()?),
+        };
+
+        let condition = match self.peek() {
+            Token::Semicolon => None,
+            _ => Some(self.parse_expression()?),
+        };
+        self.expect(Token::Semicolon)?;
+
+        let step = match self.peek() {
+            CLOSE_PARENTHESIS => None,
+            _ => Some(self.parse_expression()?),
+        };
+        self.expect(CLOSE_PARENTHESIS)?;
+
+        let body = self.parse_block_expr()?;
+        let span = combine(&keyword, &body.span);
+
+        Ok(Expr::boxed(
+            ExprKind::For {
+                init,
+                condition,
+                step,
+                body,
+            },
+            span,
+        ))
+    }
+
+    fn parse_for_in_expr(&mut self, keyword: Span) -> ExprResult {
+        let item = self.expect_identifier()?;
+        let item = Param::new(Symbol::intern(item.slice), item.span());
+        self.expect(Token::In)?;
+
+        let iterable = self.parse_expression()?;
+        let body = self.parse_block_expr()?;
+        let span = combine(&keyword, &body.span);
+
+        Ok(Expr::boxed(
+            ExprKind::ForIn {
+                item,
+                iterable,
+                body,
+            },
+            span,
+        ))
+    }
+
+    pub(super) fn parse_match_expr(&mut self) -> ExprResult {
+        let keyword = self.expect(Token::Match)?.span();
+        let value = self.parse_expression()?;
+        self.expect(OPEN_BRACKET)?;
+
+        let mut arms = vec![];
+        while self.peek() != CLOSE_BRACKET {
+            let pattern = if self.peek() == Token::Identifier("_") {
+                self.advance()?;
+                Pattern::Wildcard
+            } else {
+                Pattern::Value(self.parse_expression()?)
+            };
+            self.expect(Token::Arrow)?;
+            let body = self.parse_expression()?;
+            let ends_with_block = matches!(&*body.kind, ExprKind::Block { .. });
+            arms.push(MatchArm { pattern, body });
+
+            // Neither the last arm nor the ones with a block need a comma
+            if self.peek() == Token::Comma {
+                self.advance()?;
+            } else if self.peek() != CLOSE_BRACKET && !ends_with_block {
+                return Err(ParseErrorCause::Expected(Expect::Token(Token::Comma)));
+            }
+        }
+
+        let close_bracket = self.expect(CLOSE_BRACKET)?.span();
+        let span = combine(&keyword, &close_bracket);
+
+        Ok(Expr::boxed(ExprKind::Match { value, arms }, span))
+    }
+
     pub(super) fn parse_break_expr(&mut self) -> ExprResult {
         let keyword = self.expect(Token::Break)?.span();
         let return_expr = if self.peek().is_expr() {
@@ -1,19 +1,19 @@
-use common::ProgramText;
+use common::{ProgramText, Symbol};
 
 use crate::{
     parse::{
         expr::atom::AtomicValue,
         operator::{BinaryOperator, UnaryOperator},
-        stmt::Stmt,
-        ExprResult, Node, Params, Parser,
+        stmt::{assignment_target, Stmt},
+        ExprResult, Node, Param, Params, Parser, Span,
     },
     token::constants::{
         ASSIGN, CLOSE_PARENTHESIS, CLOSE_SQUARE, DOT, OPEN_PARENTHESIS, OPEN_SQUARE,
     },
-    token::{operator::Operator, Token},
+    token::{escape_string, operator::Operator, Token},
     utils::{
         combine,
-        error::{Expect, Forbidden, ParseErrorCause},
+        error::{Expect, ParseErrorCause},
     },
 };
 use std::fmt;
@@ -27,6 +27,40 @@ pub type Expr = Node<Box<ExprKind>>;
 pub type PathSegment = Node<ProgramText>;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    // _
+    Wildcard,
+    // Any expression, the arm is taken when it's equal to the matched value
+    Value(Expr),
+}
+
+// draw(x: 1, y: 2)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedArg {
+    pub name: Node<Symbol>,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expr,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprKind {
     // 1, false, "foo", foo
     Atom(AtomicValue),
@@ -57,16 +91,44 @@ pub enum ExprKind {
         condition: Expr,
         body: Expr,
     },
+    // loop { break 5 }, only a break ends it
+    Loop {
+        body: Expr,
+    },
+    // for (let i = 0; i < 10; i = i + 1) { }, every part in the parentheses is optional
+    For {
+        init: Option<Stmt>,
+        condition: Option<Expr>,
+        step: Option<Expr>,
+        body: Expr,
+    },
+    // for item in items { }
+    ForIn {
+        item: Param,
+        iterable: Expr,
+        body: Expr,
+    },
+    // match x { 1 => "one", _ => "many" }, arms are checked from the top
+    Match {
+        value: Expr,
+        arms: Vec<MatchArm>,
+    },
     // break, break 5
     Break {
         return_expr: Option<Expr>,
     },
     // continue
     Continue,
-    // foo(), bar(10, 10)
+    // "a ${b} c", the text parts are Text atoms
+    Interpolation {
+        parts: Vec<Expr>,
+    },
+    // foo(), bar(10, 10), baz(10, y: 10)
     Call {
         callee: Expr,
         args: Vec<Expr>,
+        // Come after the positional arguments and get reordered by the compiler
+        named_args: Vec<NamedArg>,
     },
     Return {
         value: Option<Expr>,
@@ -75,11 +137,22 @@ pub enum ExprKind {
     Array {
         values: Vec<Expr>,
     },
+    // ...values, only inside of array literals and arguments of calls
+    Spread {
+        value: Expr,
+    },
     // foo[10]
     Index {
         target: Expr,
         position: Expr,
     },
+    // foo[1..3], foo[..3], foo[1..], missing bounds are the start and the end of the target
+    Slice {
+        target: Expr,
+        start: Option<Expr>,
+        end: Option<Expr>,
+        inclusive: bool,
+    },
     // foo.bar, foo.bar.property
     // The target is an expression because we are not limited
     // only to identifiers. We can also call methods on literals
@@ -87,15 +160,19 @@ pub enum ExprKind {
     GetProperty {
         target: Expr,
         is_method_call: bool,
-        identifier: Node<ProgramText>,
+        identifier: Node<Symbol>,
     },
     SetProperty {
         target: Expr,
         value: Expr,
-        identifier: Node<ProgramText>,
+        identifier: Node<Symbol>,
     },
     ObjectLiteral {
-        properties: Vec<(ProgramText, Expr)>,
+        properties: Vec<(Symbol, Expr)>,
+    },
+    // { "key": value, other: 2 }, keys are strings whether they are quoted or not
+    Map {
+        entries: Vec<(Node<ProgramText>, Expr)>,
     },
     // a = b
     Assignment {
@@ -155,6 +232,43 @@ impl fmt::Display for ExprKind {
             While { condition, body } => {
                 write!(f, "while {} {}", condition, body)?;
             }
+            Loop { body } => {
+                write!(f, "loop {}", body)?;
+            }
+            For {
+                init,
+                condition,
+                step,
+                body,
+            } => {
+                write!(f, "for (")?;
+                match init {
+                    Some(init) => write!(f, "{}", init)?,
+                    None => write!(f, ";")?,
+                }
+                if let Some(condition) = condition {
+                    write!(f, " {}", condition)?;
+                }
+                write!(f, ";")?;
+                if let Some(step) = step {
+                    write!(f, " {}", step)?;
+                }
+                write!(f, ") {}", body)?;
+            }
+            ForIn {
+                item,
+                iterable,
+                body,
+            } => {
+                write!(f, "for {} in {} {}", item, iterable, body)?;
+            }
+            Match { value, arms } => {
+                write!(f, "match {} {{", value)?;
+                for arm in arms {
+                    write!(f, " {} => {},", arm.pattern, arm.body)?;
+                }
+                write!(f, " }}")?;
+            }
             Break { return_expr } => match return_expr {
                 Some(expr) => {
                     write!(f, "break {}", expr)?;
@@ -166,9 +280,22 @@ impl fmt::Display for ExprKind {
             Continue => {
                 write!(f, "continue")?;
             }
-            Call { callee, args } => {
+            Call {
+                callee,
+                args,
+                named_args,
+            } => {
                 write!(f, "{}", callee)?;
                 write!(f, "(")?;
+                let args: Vec<String> = args
+                    .iter()
+                    .map(Expr::to_string)
+                    .chain(
+                        named_args
+                            .iter()
+                            .map(|arg| format!("{}: {}", arg.name.kind, arg.value)),
+                    )
+                    .collect();
                 let count = args.len().saturating_sub(1);
                 for (index, arg) in args.iter().enumerate() {
                     write!(f, "{}", arg)?;
@@ -186,12 +313,41 @@ impl fmt::Display for ExprKind {
                     write!(f, "return")?;
                 }
             },
+            Interpolation { parts } => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match &*part.kind {
+                        Atom(AtomicValue::Text(text)) => write!(f, "{}", escape_string(text))?,
+                        _ => write!(f, "${{{}}}", part)?,
+                    }
+                }
+                write!(f, "\"")?;
+            }
             Index { target, position } => {
                 write!(f, "{}", target)?;
                 write!(f, "[")?;
                 write!(f, "{}", position)?;
                 write!(f, "]")?;
             }
+            Slice {
+                target,
+                start,
+                end,
+                inclusive,
+            } => {
+                write!(f, "{}[", target)?;
+                if let Some(start) = start {
+                    write!(f, "{}", start)?;
+                }
+                write!(f, "{}", if *inclusive { "..=" } else { ".." })?;
+                if let Some(end) = end {
+                    write!(f, "{}", end)?;
+                }
+                write!(f, "]")?;
+            }
+            Spread { value } => {
+                write!(f, "...{}", value)?;
+            }
             Array { values } => {
                 write!(f, "[")?;
                 let count = values.len().saturating_sub(1);
@@ -225,7 +381,7 @@ impl fmt::Display for ExprKind {
                 write!(f, "{} = {}", target, value)?;
             }
             Closure { params, body } => {
-                let params_count = params.kind.len();
+                let params_count = params.kind.iter().count();
                 write!(f, "|{}| => {}", params_count, body)?;
             }
             ObjectLiteral { properties } => {
@@ -235,6 +391,13 @@ impl fmt::Display for ExprKind {
                 }
                 write!(f, " obj")?;
             }
+            Map { entries } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{:?}: {}", key.kind, value))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))?;
+            }
         }
         Ok(())
     }
@@ -253,19 +416,35 @@ impl<'t> Parser<'t> {
         let mut lhs: Expr = match self.peek() {
             Token::If => self.parse_if_expr()?,
             Token::While => self.parse_while_expr()?,
+            Token::Loop => self.parse_loop_expr()?,
+            Token::For => self.parse_for_expr()?,
+            Token::Match => self.parse_match_expr()?,
             Token::Break => self.parse_break_expr()?,
             Token::Continue => self.parse_continue_expr()?,
             Token::Return => self.parse_return_expr()?,
             Token::New => self.parse_obj_literal(false)?,
-            Token::Bar => self.parse_closure_expression()?,
             Token::Operator(Operator::RoundBracketOpen) => {
                 let open_paren = self.expect(OPEN_PARENTHESIS)?.span();
                 let expr = self.parse_expression()?;
                 let close_paren = self.expect(CLOSE_PARENTHESIS)?.span();
                 Expr::new(expr.kind, combine(&open_paren, &close_paren))
             }
-            Token::Operator(Operator::CurlyBracketOpen) => self.parse_block_expr()?,
+            Token::Operator(Operator::CurlyBracketOpen) => {
+                // A key followed by a colon can't start a block
+                let key = self.peek_nth(1);
+                let is_map = matches!(
+                    key,
+                    Token::String(_) | Token::RawString(_) | Token::Identifier(_)
+                ) && self.peek_nth(2) == Token::Colon;
+                if is_map {
+                    self.parse_map_literal()?
+                } else {
+                    self.parse_block_expr()?
+                }
+            }
             Token::Operator(Operator::SquareBracketOpen) => self.parse_array_expr()?,
+            // In front of an expression a bar can only open the parameters of a closure
+            Token::Operator(Operator::Bar) => self.parse_closure_expression()?,
             Token::Operator(op) => {
                 let ((), r_bp) = op
                     .prefix_bp()
@@ -278,6 +457,9 @@ impl<'t> Parser<'t> {
             _ => self.parse_atom_expr()?,
         };
 
+        // Operator of the comparison that is the left side now, a < b < c is most likely a mistake
+        let mut comparison: Option<Span> = None;
+
         while let Token::Operator(operator) = self.peek() {
             if let Some((l_bp, ())) = operator.postfix_bp() {
                 if l_bp < min_bp {
@@ -286,38 +468,53 @@ impl<'t> Parser<'t> {
 
                 // call expr
                 if operator == Operator::RoundBracketOpen {
-                    let open_parenthesis = self.expect(OPEN_PARENTHESIS)?.span();
+                    self.expect(OPEN_PARENTHESIS)?;
                     let mut args: Vec<Expr> = Vec::new();
+                    let mut named_args: Vec<NamedArg> = Vec::new();
                     loop {
                         let next = self.peek();
-                        if next == CLOSE_PARENTHESIS || !next.is_expr() {
+                        if next == CLOSE_PARENTHESIS || !(next.is_expr() || next == Token::Ellipsis)
+                        {
                             break;
                         }
-                        let arg = self.parse_expression()?;
-                        args.push(arg);
+                        if next.is_identifier() && self.peek_nth(1) == Token::Colon {
+                            let name = self.expect_identifier()?;
+                            let name = Node::new(Symbol::intern(name.slice), name.span());
+                            self.expect(Token::Colon)?;
+                            let value = self.parse_expression()?;
+                            named_args.push(NamedArg { name, value });
+                        } else if named_args.is_empty() {
+                            let arg = self.parse_element()?;
+                            args.push(arg);
+                        } else {
+                            return Err(ParseErrorCause::PositionalAfterNamedArgument);
+                        }
 
                         if self.peek() == Token::Comma {
                             self.expect(Token::Comma)?;
                         }
                     }
                     let close_parenthesis = self.expect(CLOSE_PARENTHESIS)?.span();
+                    // Named arguments are placed by the number of positional ones before them
+                    let spreads = args
+                        .iter()
+                        .any(|arg| matches!(&*arg.kind, ExprKind::Spread { .. }));
+                    if spreads && !named_args.is_empty() {
+                        return Err(ParseErrorCause::SpreadWithNamedArguments);
+                    }
+                    let span = combine(&lhs.span, &close_parenthesis);
                     lhs = Expr::boxed(
-                        ExprKind::Call { callee: lhs, args },
-                        combine(&open_parenthesis, &close_parenthesis),
+                        ExprKind::Call {
+                            callee: lhs,
+                            args,
+                            named_args,
+                        },
+                        span,
                     );
                 }
 
                 if operator == Operator::SquareBracketOpen {
-                    let start = self.expect(OPEN_SQUARE)?.span();
-                    let index_position = self.parse_expression()?;
-                    let end = self.expect(CLOSE_SQUARE)?.span();
-                    lhs = Expr::boxed(
-                        ExprKind::Index {
-                            target: lhs,
-                            position: index_position,
-                        },
-                        combine(&start, &end),
-                    );
+                    lhs = self.parse_index(lhs)?;
                 }
                 continue;
             }
@@ -339,7 +536,7 @@ impl<'t> Parser<'t> {
 
                     let identifier = Node {
                         span: combine(&dot, &identifier_lexeme.span()),
-                        kind: identifier_lexeme.slice.to_owned(),
+                        kind: Symbol::intern(identifier_lexeme.slice),
                     };
 
                     let is_assignment = self.peek() == ASSIGN;
@@ -373,16 +570,23 @@ impl<'t> Parser<'t> {
                 continue;
             }
 
+            // The value is a whole expression, so a = b = 1 assigns to b first
             if operator == Operator::Assign {
+                let target = assignment_target(lhs)?;
                 self.expect(ASSIGN)?;
                 let value = self.parse_expression()?;
-                let span = combine(&lhs.span, &value.span);
-                lhs = Expr::boxed(ExprKind::Assignment { target: lhs, value }, span);
+                let span = combine(&target.span, &value.span);
+                lhs = Expr::boxed(ExprKind::Assignment { target, value }, span);
                 continue;
             }
 
+            // A range without the end can only close a slice, foo[1..]
+            if operator == Operator::Range && self.peek_nth(1) == CLOSE_SQUARE {
+                break;
+            }
+
             // Advance and construct spanned operator
-            let op = {
+            let op: Node<BinaryOperator> = {
                 let lexeme = self.advance()?;
                 Node {
                     kind: operator.try_into()?,
@@ -390,6 +594,14 @@ impl<'t> Parser<'t> {
                 }
             };
 
+            if let (Some(first), true) = (&comparison, op.kind.is_comparison()) {
+                return Err(ParseErrorCause::ChainedComparison {
+                    first: first.clone(),
+                    second: op.span,
+                });
+            }
+            comparison = Some(op.span.clone()).filter(|_| op.kind.is_comparison());
+
             let rhs = self.parse_expression_bp(r_bp)?;
             let span = combine(&lhs.span, &rhs.span);
             lhs = Expr::boxed(ExprKind::Binary { lhs, op, rhs }, span);
@@ -398,26 +610,79 @@ impl<'t> Parser<'t> {
         Ok(lhs)
     }
 
+    // foo[position], or a slice when a range is written between the brackets
+    fn parse_index(&mut self, target: Expr) -> ExprResult {
+        self.expect(OPEN_SQUARE)?;
+        let is_range = |token: Token| {
+            matches!(
+                token,
+                Token::Operator(Operator::Range | Operator::RangeInclusive)
+            )
+        };
+
+        let position = if is_range(self.peek()) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+
+        let (start, end, inclusive) = if is_range(self.peek()) {
+            // The range is open on at least one side, so it wasn't parsed as a binary expression
+            let inclusive = self.advance()?.token == Token::Operator(Operator::RangeInclusive);
+            let end = if self.peek() == CLOSE_SQUARE {
+                None
+            } else {
+                Some(self.parse_expression()?)
+            };
+            (position, end, inclusive)
+        } else {
+            let position = position.ok_or(ParseErrorCause::Expected(Expect::Expression))?;
+            match *position.kind {
+                ExprKind::Binary { lhs, op, rhs }
+                    if matches!(
+                        op.kind,
+                        BinaryOperator::Range | BinaryOperator::RangeInclusive
+                    ) =>
+                {
+                    let inclusive = op.kind == BinaryOperator::RangeInclusive;
+                    (Some(lhs), Some(rhs), inclusive)
+                }
+                kind => {
+                    let end = self.expect(CLOSE_SQUARE)?.span();
+                    let span = combine(&target.span, &end);
+                    let position = Expr::boxed(kind, position.span);
+                    return Ok(Expr::boxed(ExprKind::Index { target, position }, span));
+                }
+            }
+        };
+
+        let close = self.expect(CLOSE_SQUARE)?.span();
+        let span = combine(&target.span, &close);
+        Ok(Expr::boxed(
+            ExprKind::Slice {
+                target,
+                start,
+                end,
+                inclusive,
+            },
+            span,
+        ))
+    }
+
     pub(super) fn parse_array_expr(&mut self) -> ExprResult {
         let start = self.expect(OPEN_SQUARE)?.span();
         let mut values: Vec<Expr> = Vec::new();
 
         loop {
             let next = self.peek();
-            if next == CLOSE_SQUARE || !next.is_expr() {
+            if next == CLOSE_SQUARE || !(next.is_expr() || next == Token::Ellipsis) {
                 break;
             }
 
-            let value = self.parse_expression()?;
+            let value = self.parse_element()?;
             values.push(value);
 
-            let next = self.peek();
-            if next != CLOSE_SQUARE {
-                self.expect(Token::Comma)?;
-                if self.peek() == CLOSE_SQUARE {
-                    return Err(ParseErrorCause::NotAllowed(Forbidden::TrailingComma));
-                }
-            }
+            self.expect_separator(CLOSE_SQUARE)?;
         }
 
         let end = self.expect(CLOSE_SQUARE)?.span();
@@ -428,6 +693,18 @@ impl<'t> Parser<'t> {
         ))
     }
 
+    // Element of an array literal or an argument, which can spread an array into its place
+    fn parse_element(&mut self) -> ExprResult {
+        if self.peek() != Token::Ellipsis {
+            return self.parse_expression();
+        }
+
+        let ellipsis = self.expect(Token::Ellipsis)?.span();
+        let value = self.parse_expression()?;
+        let span = combine(&ellipsis, &value.span);
+        Ok(Expr::boxed(ExprKind::Spread { value }, span))
+    }
+
     pub(super) fn parse_return_expr(&mut self) -> ExprResult {
         let return_keyword = self.expect(Token::Return)?.span();
         let value = if self.peek().is_expr() {
@@ -447,7 +724,10 @@ impl<'t> Parser<'t> {
 
     pub(super) fn parse_closure_expression(&mut self) -> ExprResult {
         let params = self.parse_params()?;
-        self.expect(Token::Arrow)?;
+        // The arrow is optional, `|x, y| x + y` is a shorthand for `|x, y| => x + y`
+        if self.peek() == Token::Arrow {
+            self.advance()?;
+        }
         let body = self.parse_expression()?;
         let span = combine(&params.span, &body.span);
         Ok(Expr::boxed(ExprKind::Closure { params, body }, span))
@@ -456,7 +736,7 @@ impl<'t> Parser<'t> {
 
 #[cfg(test)]
 mod test {
-    use crate::utils::error::{Expect, Forbidden, ParseErrorCause};
+    use crate::utils::error::{Expect, ParseErrorCause};
     use crate::utils::test::parser::*;
 
     #[test]
@@ -482,14 +762,9 @@ mod test {
         // logical
         assert_expr("1 or 2 or 3", "(or (or 1 2) 3)");
         assert_expr("1 and 2 and 3", "(and (and 1 2) 3)");
-        // comparison, this will get discarded during static analysis,
-        // but we want to ensure that parser doesn't surprise us
-        assert_expr("1 == 2 == 3", "(== (== 1 2) 3)");
-        assert_expr("1 != 2 != 3", "(!= (!= 1 2) 3)");
-        assert_expr("1 < 2 < 3", "(< (< 1 2) 3)");
-        assert_expr("1 <= 2 <= 3", "(<= (<= 1 2) 3)");
-        assert_expr("1 > 2 > 3", "(> (> 1 2) 3)");
-        assert_expr("1 >= 2 >= 3", "(>= (>= 1 2) 3)");
+        // comparison can only be chained with the parentheses, see rejects_chained_comparisons in tests/expressions.rs
+        assert_expr("(1 == 2) == 3", "(== (== 1 2) 3)");
+        assert_expr("(1 < 2) < 3", "(< (< 1 2) 3)");
         // addition and subtraction
         assert_expr("1 + 2 + 3", "(+ (+ 1 2) 3)");
         assert_expr("1 + 2 + 3 + 4", "(+ (+ (+ 1 2) 3) 4)");
@@ -502,7 +777,7 @@ mod test {
         assert_expr("1 % 2 % 3", "(% (% 1 2) 3)");
         assert_expr("1 * 2 / 3 % 4", "(% (/ (* 1 2) 3) 4)");
         // exponent
-        assert_expr("1 ** 2 ** 3", "(** (** 1 2) 3)");
+        assert_expr("1 ** 2 ** 3", "(** 1 (** 2 3))");
     }
 
     #[test]
@@ -568,6 +843,17 @@ mod test {
         assert_expr("foo(2)", "foo(2)");
         assert_expr("foo(2,3)", "foo(2,3)");
         assert_expr("foo() + bar()", "(+ foo() bar())");
+        assert_expr("foo(1, y: 2, z: 3)", "foo(1,y: 2,z: 3)");
+
+        assert_expr_error(
+            "foo(x: 1, 2)",
+            ParseErrorCause::PositionalAfterNamedArgument,
+        );
+        assert_expr("foo(1, ...args)", "foo(1,...args)");
+        assert_expr_error(
+            "foo(...args, y: 2)",
+            ParseErrorCause::SpreadWithNamedArguments,
+        );
     }
 
     #[test]
@@ -580,11 +866,9 @@ mod test {
     fn parses_array_expression() {
         assert_expr("[]", "[]");
         assert_expr("[1,2,3]", "[1,2,3]");
+        assert_expr("[1, ...foo, 2]", "[1,...foo,2]");
 
-        assert_expr_error(
-            "[1,2,]",
-            ParseErrorCause::NotAllowed(Forbidden::TrailingComma),
-        );
+        assert_expr("[1,2,]", "[1,2]");
     }
 
     #[test]
@@ -613,5 +897,10 @@ mod test {
     fn parses_closure_expression() {
         assert_expr("|| => 10", "|0| => 10");
         assert_expr("|a,b,c| => a + b + c", "|3| => (+ (+ a b) c)");
+        // shorthand without the arrow
+        assert_expr("|| 10", "|0| => 10");
+        assert_expr("|a, b| a * b", "|2| => (* a b)");
+        assert_expr("|a| |b| a - b", "|1| => |1| => (- a b)");
+        assert_expr("foo(|x| x + 1, 2)", "foo(|1| => (+ x 1),2)");
     }
 }
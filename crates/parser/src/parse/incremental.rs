@@ -0,0 +1,245 @@
+use crate::parse::{
+    expr::{Expr, ExprKind, Pattern},
+    stmt::{DestructuringPattern, Stmt, StmtKind},
+    Ast, Params, Parser, ParserOutput, Span,
+};
+
+// Text in `range` of the old code was replaced with `new_len` bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub range: Span,
+    pub new_len: usize,
+}
+
+impl Edit {
+    fn delta(&self) -> isize {
+        self.new_len as isize - self.range.len() as isize
+    }
+}
+
+impl<'t> Parser<'t> {
+    // Parses the edited code reusing the top level statements of the old code the edit didn't
+    // touch. The statement right after the edit is parsed again as well, because its doc comment
+    // could have changed. When the changed part doesn't parse on its own the whole code is parsed,
+    // so the result is always the same as parsing the new code from scratch.
+    pub(crate) fn reparse(old_ast: Ast, code: &'t str, edit: &Edit) -> ParserOutput {
+        let delta = edit.delta();
+        let reused_before = old_ast
+            .iter()
+            .take_while(|stmt| stmt.span.end < edit.range.start)
+            .count();
+        let reused_after = old_ast
+            .iter()
+            .position(|stmt| stmt.span.start > edit.range.end)
+            .map_or(old_ast.len(), |after_edit| after_edit + 1);
+
+        let start = match reused_before {
+            0 => 0,
+            count => old_ast[count - 1].span.end,
+        };
+        // The statements that come later are kept, so their text didn't change
+        let end = if reused_after < old_ast.len() {
+            (old_ast[reused_after - 1].span.end as isize + delta) as usize
+        } else {
+            code.len()
+        };
+        let changed = match code.get(start..end) {
+            Some(changed) if start <= end => changed,
+            _ => return Parser::new(code).parse(),
+        };
+        let reparsed = match Parser::with_offset(changed, start).parse() {
+            Ok(reparsed) => reparsed,
+            Err(_) => return Parser::new(code).parse(),
+        };
+
+        let mut old_ast = old_ast;
+        let mut after = old_ast.split_off(reused_after);
+        old_ast.truncate(reused_before);
+        for stmt in &mut after {
+            shift_stmt(stmt, delta);
+        }
+
+        old_ast.extend(reparsed);
+        old_ast.extend(after);
+        Ok(old_ast)
+    }
+}
+
+fn shift(span: &mut Span, delta: isize) {
+    span.start = (span.start as isize + delta) as usize;
+    span.end = (span.end as isize + delta) as usize;
+}
+
+fn shift_params(params: &mut Params, delta: isize) {
+    shift(&mut params.span, delta);
+    let list = &mut params.kind;
+    for param in list.params.iter_mut().chain(list.rest.iter_mut()) {
+        shift(&mut param.span, delta);
+    }
+    for annotation in list.types.iter_mut().flatten() {
+        shift(&mut annotation.span, delta);
+    }
+}
+
+fn shift_stmt(stmt: &mut Stmt, delta: isize) {
+    use StmtKind::*;
+
+    shift(&mut stmt.span, delta);
+    match &mut *stmt.kind {
+        Expression { expr } => shift_expr(expr, delta),
+        VariableDeclaration {
+            annotation, expr, ..
+        } => {
+            if let Some(annotation) = annotation {
+                shift(&mut annotation.span, delta);
+            }
+            shift_expr(expr, delta);
+        }
+        FunctionDeclaration {
+            params,
+            returns,
+            body,
+            ..
+        } => {
+            shift_params(params, delta);
+            if let Some(returns) = returns {
+                shift(&mut returns.span, delta);
+            }
+            shift_expr(body, delta);
+        }
+        MultipleDeclaration { names, exprs } => {
+            names
+                .iter_mut()
+                .for_each(|name| shift(&mut name.span, delta));
+            exprs.iter_mut().for_each(|expr| shift_expr(expr, delta));
+        }
+        ParallelAssignment { targets, values } => targets
+            .iter_mut()
+            .chain(values)
+            .for_each(|expr| shift_expr(expr, delta)),
+        Destructuring { pattern, expr } => {
+            let (DestructuringPattern::Object(names) | DestructuringPattern::Array(names)) =
+                pattern;
+            names
+                .iter_mut()
+                .for_each(|name| shift(&mut name.span, delta));
+            shift_expr(expr, delta);
+        }
+        Import { path } => shift(&mut path.span, delta),
+        Export { declaration } => shift_stmt(declaration, delta),
+    }
+}
+
+fn shift_expr(expr: &mut Expr, delta: isize) {
+    use ExprKind::*;
+
+    shift(&mut expr.span, delta);
+    let shift_all =
+        |exprs: Vec<&mut Expr>| exprs.into_iter().for_each(|expr| shift_expr(expr, delta));
+    match &mut *expr.kind {
+        Atom(_) | Continue => {}
+        Binary { lhs, op, rhs } => {
+            shift(&mut op.span, delta);
+            shift_all(vec![lhs, rhs]);
+        }
+        Unary { op, rhs } => {
+            shift(&mut op.span, delta);
+            shift_all(vec![rhs]);
+        }
+        Block { stmts, return_expr } => {
+            stmts.iter_mut().for_each(|stmt| shift_stmt(stmt, delta));
+            shift_all(return_expr.iter_mut().collect());
+        }
+        If {
+            condition,
+            body,
+            else_expr,
+        } => {
+            shift_all(vec![condition, body]);
+            shift_all(else_expr.iter_mut().collect());
+        }
+        While { condition, body } => shift_all(vec![condition, body]),
+        Loop { body } => shift_all(vec![body]),
+        For {
+            init,
+            condition,
+            step,
+            body,
+        } => {
+            if let Some(init) = init {
+                shift_stmt(init, delta);
+            }
+            shift_all(condition.iter_mut().chain(step).collect());
+            shift_all(vec![body]);
+        }
+        ForIn {
+            item,
+            iterable,
+            body,
+        } => {
+            shift(&mut item.span, delta);
+            shift_all(vec![iterable, body]);
+        }
+        Match { value, arms } => {
+            shift_all(vec![value]);
+            for arm in arms {
+                if let Pattern::Value(pattern) = &mut arm.pattern {
+                    shift_all(vec![pattern]);
+                }
+                shift_all(vec![&mut arm.body]);
+            }
+        }
+        Break { return_expr: value } | Return { value } => shift_all(value.iter_mut().collect()),
+        Spread { value } => shift_all(vec![value]),
+        Interpolation { parts: values } | Array { values } => {
+            shift_all(values.iter_mut().collect())
+        }
+        Call {
+            callee,
+            args,
+            named_args,
+        } => {
+            shift_all(vec![callee]);
+            shift_all(args.iter_mut().collect());
+            for arg in named_args {
+                shift(&mut arg.name.span, delta);
+                shift_all(vec![&mut arg.value]);
+            }
+        }
+        Index { target, position } => shift_all(vec![target, position]),
+        Slice {
+            target, start, end, ..
+        } => {
+            shift_all(vec![target]);
+            shift_all(start.iter_mut().chain(end).collect());
+        }
+        GetProperty {
+            target, identifier, ..
+        } => {
+            shift(&mut identifier.span, delta);
+            shift_all(vec![target]);
+        }
+        SetProperty {
+            target,
+            value,
+            identifier,
+        } => {
+            shift(&mut identifier.span, delta);
+            shift_all(vec![target, value]);
+        }
+        ObjectLiteral { properties } => {
+            shift_all(properties.iter_mut().map(|(_, value)| value).collect())
+        }
+        Map { entries } => {
+            for (key, value) in entries {
+                shift(&mut key.span, delta);
+                shift_all(vec![value]);
+            }
+        }
+        Assignment { target, value } => shift_all(vec![target, value]),
+        Closure { params, body } => {
+            shift_params(params, delta);
+            shift_all(vec![body]);
+        }
+    }
+}
@@ -1,29 +1,46 @@
-use common::ProgramText;
+use common::{ProgramText, Symbol};
 
 use crate::{
-    parse::{expr::Expr, stmt::Stmt},
+    parse::{
+        expr::Expr,
+        stmt::{Stmt, StmtKind},
+    },
     token::{constants::IDENTIFIER, Lexeme, Lexer, Token},
+    trivia::{comments, doc_comment, Comment},
     utils::error::{Expect, ParseError, ParseErrorCause},
 };
 use std::{fmt, mem::discriminant, ops::Range};
 
 pub mod expr;
+pub mod incremental;
 pub mod operator;
 pub(crate) mod pieces;
+pub(crate) mod recovery;
 pub mod stmt;
 pub mod utils;
+pub mod visit;
 
 pub(crate) struct Parser<'t> {
     lexer: Lexer<'t>,
+    // Errors the parser recovered from, they're reported when the whole input is parsed
+    errors: ProgramErrors,
+    // Doc comments are skipped by the lexer, so they're looked up in the code the parser reads
+    code: &'t str,
+    offset: usize,
+    comments: Vec<Comment>,
+    // Names declared so far, suggested in place of misspelled ones
+    names: Vec<Symbol>,
 }
 
 pub type Ast = Vec<Stmt>;
 pub type Program = Ast;
 pub type AstRef<'a> = &'a [Stmt];
 pub type ProgramErrors = Vec<ParseError>;
-pub type Param = Node<ProgramText>;
-// (a, b, c)
-pub type Params = Node<Vec<Param>>;
+pub type Param = Node<Symbol>;
+// Name of the type after a colon, like Number in `let x: Number = 5;`
+pub type TypeAnnotation = Node<Symbol>;
+// (a, b, ...c)
+pub type Params = Node<ParamList>;
 pub type FunctionBody = Expr;
 pub(crate) type ParserOutput = Result<Ast, ProgramErrors>;
 pub(crate) type ParseResult<'t, T> = Result<T, ParseErrorCause>;
@@ -33,6 +50,7 @@ pub(crate) type StmtResult<'t> = ParseResult<'t, Stmt>;
 pub type Span = Range<usize>;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node<T> {
     pub kind: T,
     pub span: Span,
@@ -72,13 +90,62 @@ where
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamList {
+    pub params: Vec<Param>,
+    // Collects the remaining arguments into an array
+    pub rest: Option<Param>,
+    // Annotations of the params, at the same positions
+    pub types: Vec<Option<TypeAnnotation>>,
+}
+
+impl ParamList {
+    // Every parameter, the rest one comes last
+    pub fn iter(&self) -> impl Iterator<Item = &Param> {
+        self.params.iter().chain(self.rest.iter())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty() && self.rest.is_none()
+    }
+}
+
+impl From<Vec<Param>> for ParamList {
+    fn from(params: Vec<Param>) -> Self {
+        Self {
+            types: vec![None; params.len()],
+            params,
+            rest: None,
+        }
+    }
+}
+
 impl<'t> Parser<'t> {
     pub(crate) fn new(input: &'t str) -> Self {
+        Self::with_offset(input, 0)
+    }
+
+    pub(crate) fn with_offset(input: &'t str, offset: usize) -> Self {
         Self {
-            lexer: Lexer::new(input),
+            lexer: Lexer::with_offset(input, offset),
+            errors: vec![],
+            code: input,
+            offset,
+            comments: comments(input),
+            names: vec![],
         }
     }
 
+    fn declare(&mut self, declaration: &StmtKind) {
+        self.names.extend(declaration.declared_names());
+    }
+
+    // Doc comment written above the declaration starting at `item_start`
+    fn doc_comment(&self, item_start: usize) -> Option<ProgramText> {
+        doc_comment(self.code, &self.comments, item_start - self.offset)
+    }
+
     fn peek(&mut self) -> Token {
         self.lexer
             .peek_nth(0)
@@ -86,6 +153,13 @@ impl<'t> Parser<'t> {
             .unwrap_or(Token::Eof)
     }
 
+    fn peek_nth(&mut self, nth: usize) -> Token {
+        self.lexer
+            .peek_nth(nth)
+            .map(|l| l.token)
+            .unwrap_or(Token::Eof)
+    }
+
     fn peek_eq_consume(&mut self, expected: Token) -> Option<ParseResult<Lexeme>> {
         if let expected = self.peek() {
             Some(self.advance())
@@ -98,59 +172,51 @@ impl<'t> Parser<'t> {
         self.lexer.next().ok_or(ParseErrorCause::EndOfInput)
     }
 
+    // The wrong token is left in place, so errors can point at it
     fn expect(&mut self, expected: Token<'static>) -> ParseResult<Lexeme> {
-        if let Ok(next) = self.advance() {
-            if next.token == expected {
-                return Ok(next);
-            }
+        if self.peek() == expected {
+            return self.advance();
         }
 
         Err(ParseErrorCause::Expected(Expect::Token(expected)))
     }
 
     fn expect_identifier(&mut self) -> ParseResult<Lexeme> {
-        if let Ok(next) = self.advance() {
-            if discriminant(&next.token) == discriminant(&IDENTIFIER) {
-                return Ok(next);
-            }
+        if discriminant(&self.peek()) == discriminant(&IDENTIFIER) {
+            return self.advance();
         }
 
         Err(ParseErrorCause::Expected(Expect::Identifier))
     }
 
+    // Comma between the items of a list, unless the list ends right there
+    fn expect_separator(&mut self, closing_token: Token<'static>) -> ParseResult<()> {
+        match self.peek() {
+            next if next == closing_token => Ok(()),
+            Token::Comma => self.advance().map(|_| ()),
+            _ => Err(ParseErrorCause::ExpectedOneOf(vec![
+                Expect::Token(Token::Comma),
+                Expect::Token(closing_token),
+            ])),
+        }
+    }
+
     pub(crate) fn parse(mut self) -> ParserOutput {
         let mut ast = Vec::new();
-        let mut errors = Vec::new();
 
         while self.peek() != Token::Eof {
             let span_start = self.lexer.current_span();
 
-            match self.parse_stmt() {
+            match self.parse_module_item() {
                 Ok(stmt) => {
                     ast.push(stmt);
                 }
-                Err(cause) => {
-                    let parse_error = ParseError {
-                        cause,
-                        span_start,
-                        span_end: self.lexer.current_span(),
-                    };
-                    errors.push(parse_error);
-
-                    // discard every expression until we encounter a new statement
-                    loop {
-                        let next = self.peek();
-                        if next.is_stmt() || next == Token::Eof {
-                            break;
-                        }
-                        self.advance().unwrap();
-                    }
-                }
+                Err(cause) => self.recover(cause, span_start),
             }
         }
 
-        if !errors.is_empty() {
-            Err(errors)
+        if !self.errors.is_empty() {
+            Err(self.errors)
         } else {
             Ok(ast)
         }
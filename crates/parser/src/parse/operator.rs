@@ -26,6 +26,7 @@ macro_rules! impl_double_ended_conversion {
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOperator {
     // +
     Addition,
@@ -55,6 +56,20 @@ pub enum BinaryOperator {
     Or,
     // and
     And,
+    // ..
+    Range,
+    // ..=
+    RangeInclusive,
+    // &
+    BitAnd,
+    // |
+    BitOr,
+    // ^
+    BitXor,
+    // <<
+    ShiftLeft,
+    // >>
+    ShiftRight,
 }
 
 impl_double_ended_conversion!(
@@ -72,7 +87,14 @@ impl_double_ended_conversion!(
         Operator::Greater => BinaryOperator::GreaterThan,
         Operator::GreaterEqual => BinaryOperator::GreaterEquals,
         Operator::Or => BinaryOperator::Or,
-        Operator::And => BinaryOperator::And
+        Operator::And => BinaryOperator::And,
+        Operator::Range => BinaryOperator::Range,
+        Operator::RangeInclusive => BinaryOperator::RangeInclusive,
+        Operator::Ampersand => BinaryOperator::BitAnd,
+        Operator::Bar => BinaryOperator::BitOr,
+        Operator::Caret => BinaryOperator::BitXor,
+        Operator::ShiftLeft => BinaryOperator::ShiftLeft,
+        Operator::ShiftRight => BinaryOperator::ShiftRight
     ]
 );
 
@@ -84,6 +106,17 @@ impl_double_ended_conversion!(
 //     }
 // }
 
+impl BinaryOperator {
+    pub fn is_comparison(&self) -> bool {
+        use BinaryOperator::*;
+
+        matches!(
+            self,
+            Equals | NotEquals | LesserThan | LesserEquals | GreaterThan | GreaterEquals
+        )
+    }
+}
+
 impl fmt::Display for BinaryOperator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", Operator::from(*self))
@@ -91,15 +124,19 @@ impl fmt::Display for BinaryOperator {
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnaryOperator {
     Negate,
     Not,
+    // ~
+    BitNot,
 }
 
 impl_double_ended_conversion!(
     Operator, UnaryOperator, [
         Operator::Minus => UnaryOperator::Negate,
-        Operator::Bang => UnaryOperator::Not
+        Operator::Bang => UnaryOperator::Not,
+        Operator::Tilde => UnaryOperator::BitNot
     ]
 );
 
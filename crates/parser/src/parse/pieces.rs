@@ -1,13 +1,12 @@
+use common::Symbol;
+
 use crate::{
-    parse::{Param, Params, ParseResult, Parser},
+    parse::{Node, Param, ParamList, Params, ParseResult, Parser, TypeAnnotation},
     token::{
-        constants::{CLOSE_PARENTHESIS, OPEN_PARENTHESIS},
+        constants::{BAR, CLOSE_PARENTHESIS, OPEN_PARENTHESIS},
         Token,
     },
-    utils::{
-        combine,
-        error::{Forbidden, ParseErrorCause},
-    },
+    utils::combine,
 };
 
 impl<'t> Parser<'t> {
@@ -15,8 +14,8 @@ impl<'t> Parser<'t> {
         let (open_parenthesis, closing_token) = {
             // we encountered closure opening so we will have to expect closing bar
             // after the end of the parameters list
-            let (opening_token, closing_token) = if self.peek() == Token::Bar {
-                (Token::Bar, Token::Bar)
+            let (opening_token, closing_token) = if self.peek() == BAR {
+                (BAR, BAR)
             } else {
                 (OPEN_PARENTHESIS, CLOSE_PARENTHESIS)
             };
@@ -25,33 +24,78 @@ impl<'t> Parser<'t> {
         };
 
         let mut args: Vec<Param> = Vec::new();
+        let mut rest = None;
+        let mut types = Vec::new();
 
         loop {
             let next = self.peek();
-            if next == closing_token || !next.is_identifier() {
+            if next == closing_token || !(next.is_identifier() || next == Token::Ellipsis) {
+                break;
+            }
+
+            // The rest parameter has to be the last one
+            if next == Token::Ellipsis {
+                self.advance()?;
+                let rest_lexeme = self.expect_identifier()?;
+                rest = Some(Param::new(Symbol::intern(rest_lexeme.slice), rest_lexeme.span()));
+                if self.peek() == Token::Comma {
+                    self.advance()?;
+                }
                 break;
             }
 
             let arg_lexeme = self.expect_identifier()?;
-            let arg = Param::new(arg_lexeme.slice.to_owned(), arg_lexeme.span());
+            let arg = Param::new(Symbol::intern(arg_lexeme.slice), arg_lexeme.span());
             args.push(arg);
+            types.push(self.parse_type_annotation()?);
 
-            if self.peek() != closing_token {
-                self.expect(Token::Comma)?;
-
-                if !self.peek().is_identifier() {
-                    return Err(ParseErrorCause::NotAllowed(Forbidden::TrailingComma));
-                }
-            }
+            self.expect_separator(closing_token)?;
         }
 
         let close_parenthesis = self.expect(closing_token)?.span();
 
         Ok(Params::new(
-            args,
+            ParamList {
+                params: args,
+                rest,
+                types,
+            },
             combine(&open_parenthesis, &close_parenthesis),
         ))
     }
+
+    // : Number
+    pub(super) fn parse_type_annotation(&mut self) -> ParseResult<Option<TypeAnnotation>> {
+        if self.peek() != Token::Colon {
+            return Ok(None);
+        }
+        self.advance()?;
+        let name = self.expect_identifier()?;
+        Ok(Some(TypeAnnotation::new(Symbol::intern(name.slice), name.span())))
+    }
+
+    // At least one comma separated identifier between the tokens, like {a, b}
+    pub(super) fn parse_names(
+        &mut self,
+        opening_token: Token<'static>,
+        closing_token: Token<'static>,
+    ) -> ParseResult<Vec<Node<Symbol>>> {
+        self.expect(opening_token)?;
+        let mut names = vec![];
+        loop {
+            let name = self.expect_identifier()?;
+            names.push(Node::new(Symbol::intern(name.slice), name.span()));
+            if self.peek() != Token::Comma {
+                break;
+            }
+            self.expect(Token::Comma)?;
+            if self.peek() == closing_token {
+                break;
+            }
+        }
+        self.expect(closing_token)?;
+        Ok(names)
+    }
 }
 
 #[cfg(test)]
@@ -65,19 +109,20 @@ mod test {
 
     #[test]
     fn parser_parses_arguments() {
-        assert_args("()", Params::new(vec![], 0..2));
+        assert_args("()", Params::new(vec![].into(), 0..2));
 
         assert_args(
             "(a)",
-            Params::new(vec![Param::new("a".to_owned(), 2..3)], 0..3),
+            Params::new(vec![Param::new("a".into(), 2..3)].into(), 0..3),
         );
         assert_args(
             "(a, b)",
             Params::new(
                 vec![
-                    Param::new("a".to_owned(), 2..3),
-                    Param::new("b".to_owned(), 4..5),
-                ],
+                    Param::new("a".into(), 2..3),
+                    Param::new("b".into(), 4..5),
+                ]
+                .into(),
                 0..6,
             ),
         );
@@ -85,21 +130,23 @@ mod test {
             "(a, b, c)",
             Params::new(
                 vec![
-                    Param::new("a".to_owned(), 2..3),
-                    Param::new("b".to_owned(), 4..5),
-                    Param::new("c".to_owned(), 6..7),
-                ],
+                    Param::new("a".into(), 2..3),
+                    Param::new("b".into(), 4..5),
+                    Param::new("c".into(), 6..7),
+                ]
+                .into(),
                 0..8,
             ),
         );
     }
 
     #[test]
-    fn parser_doesnt_allow_trailing_comma_while_parsing_args() {
-        let mut parser = Parser::new("(a,)");
-        assert_eq!(
-            parser.parse_params().unwrap_err(),
-            ParseErrorCause::NotAllowed(Forbidden::TrailingComma)
+    fn parser_allows_trailing_comma_while_parsing_args() {
+        assert_args(
+            "(a,)",
+            Params::new(vec![Param::new("a".into(), 1..2)].into(), 0..4),
         );
+        let mut parser = Parser::new("(a, ...b,)");
+        assert!(parser.parse_params().unwrap().kind.rest.is_some());
     }
 }
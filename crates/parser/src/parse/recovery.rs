@@ -0,0 +1,53 @@
+use crate::{
+    parse::{Parser, Span},
+    token::{
+        constants::{CLOSE_BRACKET, OPEN_BRACKET},
+        Token,
+    },
+    utils::error::{Found, ParseError, ParseErrorCause},
+};
+
+impl<'t> Parser<'t> {
+    // Remembers the error and skips to a place where parsing can continue,
+    // so errors that come after it are reported in the same run
+    pub(crate) fn recover(&mut self, cause: ParseErrorCause, span_start: Span) {
+        let found = self.lexer.peek_nth(0).map(|lexeme| Found {
+            text: lexeme.slice.to_owned(),
+            span: lexeme.span(),
+        });
+        self.errors.push(ParseError {
+            cause,
+            span_start: span_start.clone(),
+            span_end: self.lexer.current_span(),
+            found,
+        });
+
+        self.synchronize();
+        // The token that failed would fail again if nothing was skipped
+        if self.lexer.current_span() == span_start && self.peek() != Token::Eof {
+            let _ = self.advance();
+        }
+    }
+
+    // Skips past the next semicolon or up to the next statement keyword or closing brace.
+    // Nested blocks are skipped whole, the statements in them belong to the broken code.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        loop {
+            let next = self.peek();
+            match next {
+                Token::Eof => return,
+                _ if depth == 0 && (next.is_stmt() || next == CLOSE_BRACKET) => return,
+                Token::Semicolon if depth == 0 => {
+                    let _ = self.advance();
+                    return;
+                }
+                _ if next == OPEN_BRACKET => depth += 1,
+                _ if next == CLOSE_BRACKET => depth -= 1,
+                _ => {}
+            }
+            let _ = self.advance();
+        }
+    }
+}
+
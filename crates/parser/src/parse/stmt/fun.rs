@@ -1,7 +1,9 @@
+use common::Symbol;
+
 use crate::{
     parse::{
         stmt::{Stmt, StmtKind},
-        Parser, StmtResult,
+        Parser, StmtResult, TypeAnnotation,
     },
     token::{constants::OPEN_BRACKET, Token},
     utils::combine,
@@ -15,17 +17,29 @@ impl<'t> Parser<'t> {
 
     pub(crate) fn parse_fun_declaration(&mut self) -> StmtResult {
         let fn_keyword = self.expect(Token::Function)?.span();
-        let name = self.expect_identifier()?.slice.to_owned();
+        let name = Symbol::intern(self.expect_identifier()?.slice);
         let params = self.parse_params()?;
+        let returns = if self.peek() == Token::ThinArrow {
+            self.advance()?;
+            let name = self.expect_identifier()?;
+            Some(TypeAnnotation::new(Symbol::intern(name.slice), name.span()))
+        } else {
+            None
+        };
         if self.peek() != OPEN_BRACKET {
             self.expect(Token::Arrow)?;
         }
         let body = self.parse_expression()?;
         let span = combine(&fn_keyword, &body.span);
-        Ok(Stmt::boxed(
-            StmtKind::FunctionDeclaration { name, params, body },
-            span,
-        ))
+        let kind = StmtKind::FunctionDeclaration {
+            name,
+            params,
+            returns,
+            body,
+            doc: self.doc_comment(fn_keyword.start),
+        };
+        self.declare(&kind);
+        Ok(Stmt::boxed(kind, span))
     }
 }
 
@@ -83,9 +97,11 @@ mod test {
             declaration,
             Stmt::boxed(
                 StmtKind::FunctionDeclaration {
-                    name: "foo".to_owned(),
-                    params: Params::new(vec![], 6..8),
-                    body: Expr::boxed(ExprKind::Atom(AtomicValue::Number(2.0)), 12..13)
+                    name: "foo".into(),
+                    params: Params::new(vec![].into(), 6..8),
+                    returns: None,
+                    body: Expr::boxed(ExprKind::Atom(AtomicValue::Number(2.0)), 12..13),
+                    doc: None,
                 },
                 0..13
             )
@@ -95,14 +111,16 @@ mod test {
         let declaration = parser.parse_fun_declaration().unwrap();
         let fun_node = Stmt::boxed(
             StmtKind::FunctionDeclaration {
-                name: "foo".to_owned(),
+                name: "foo".into(),
                 params: Params::new(
                     vec![
-                        Param::new("a".to_owned(), 7..8),
-                        Param::new("b".to_owned(), 9..10),
-                    ],
+                        Param::new("a".into(), 7..8),
+                        Param::new("b".into(), 9..10),
+                    ]
+                    .into(),
                     6..10,
                 ),
+                returns: None,
                 body: Expr::boxed(
                     ExprKind::Block {
                         stmts: vec![],
@@ -113,6 +131,7 @@ mod test {
                     },
                     11..16,
                 ),
+                doc: None,
             },
             0..16,
         );
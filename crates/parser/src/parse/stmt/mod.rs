@@ -1,9 +1,22 @@
 use crate::{
-    parse::{expr::Expr, Node, Params, Parser, StmtResult},
-    token::{operator::Operator, Token},
-    utils::combine,
+    parse::{
+        expr::{atom::AtomicValue, Expr, ExprKind},
+        AstRef, Node, Params, TypeAnnotation, ParseResult, Parser, StmtResult,
+    },
+    token::{
+        constants::{
+            ASSIGN, CLOSE_BRACKET, CLOSE_SQUARE, OPEN_BRACKET, OPEN_SQUARE, STMT_KEYWORDS,
+        },
+        operator::Operator,
+        Token,
+    },
+    utils::{
+        combine,
+        error::{Expect, ParseErrorCause},
+        spelling::closest,
+    },
 };
-use common::ProgramText;
+use common::{ProgramText, Symbol};
 use std::fmt;
 
 use super::FunctionBody;
@@ -11,23 +24,136 @@ use super::FunctionBody;
 pub type Stmt = Node<Box<StmtKind>>;
 
 pub(crate) mod fun;
+pub(crate) mod module;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StmtKind {
     Expression {
         expr: Expr,
     },
     VariableDeclaration {
-        name: ProgramText,
+        name: Symbol,
+        annotation: Option<TypeAnnotation>,
         expr: Expr,
+        // Text of the /// comments above the declaration
+        doc: Option<ProgramText>,
     },
     FunctionDeclaration {
-        name: ProgramText,
+        name: Symbol,
         params: Params,
+        // fn foo() -> Number
+        returns: Option<TypeAnnotation>,
         body: FunctionBody,
+        doc: Option<ProgramText>,
+    },
+    // let a, b = 1, 2;
+    MultipleDeclaration {
+        names: Vec<Node<Symbol>>,
+        exprs: Vec<Expr>,
+    },
+    // a, b = b, a; all values are evaluated before anything is assigned
+    ParallelAssignment {
+        targets: Vec<Expr>,
+        values: Vec<Expr>,
+    },
+    // let {a, b} = obj; let [x, y] = arr;
+    Destructuring {
+        pattern: DestructuringPattern,
+        expr: Expr,
+    },
+    // import "path/module.gv"; binds every name the module exports
+    Import {
+        path: Node<ProgramText>,
+    },
+    // Variable or function declaration visible to the files importing this one
+    Export {
+        declaration: Stmt,
     },
 }
 
+impl StmtKind {
+    // Names of the variables the declaration introduces
+    pub fn declared_names(&self) -> Vec<Symbol> {
+        match self {
+            Self::VariableDeclaration { name, .. } | Self::FunctionDeclaration { name, .. } => {
+                vec![*name]
+            }
+            Self::MultipleDeclaration { names, .. } => names.iter().map(|name| name.kind).collect(),
+            Self::Destructuring { pattern, .. } => {
+                pattern.names().iter().map(|name| name.kind).collect()
+            }
+            Self::Export { declaration } => declaration.kind.declared_names(),
+            Self::Expression { .. } | Self::ParallelAssignment { .. } | Self::Import { .. } => {
+                vec![]
+            }
+        }
+    }
+}
+
+// Names a file makes visible to the files importing it
+pub fn exports(program: AstRef) -> Vec<Symbol> {
+    program
+        .iter()
+        .filter(|stmt| matches!(*stmt.kind, StmtKind::Export { .. }))
+        .flat_map(|stmt| stmt.kind.declared_names())
+        .collect()
+}
+
+// Paths of the files imported by the program, in the order of the imports
+pub fn imports(program: AstRef) -> Vec<&Node<ProgramText>> {
+    program
+        .iter()
+        .filter_map(|stmt| match &*stmt.kind {
+            StmtKind::Import { path } => Some(path),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DestructuringPattern {
+    // Names of the properties, which become the names of the variables
+    Object(Vec<Node<Symbol>>),
+    // Variables bound to the consecutive elements
+    Array(Vec<Node<Symbol>>),
+}
+
+impl DestructuringPattern {
+    pub fn names(&self) -> &[Node<Symbol>] {
+        match self {
+            Self::Object(names) | Self::Array(names) => names,
+        }
+    }
+}
+
+impl fmt::Display for DestructuringPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.names().iter().map(|name| name.kind.as_str()).collect();
+        match self {
+            Self::Object(_) => write!(f, "{{{}}}", names.join(", ")),
+            Self::Array(_) => write!(f, "[{}]", names.join(", ")),
+        }
+    }
+}
+
+fn join(exprs: &[Expr]) -> String {
+    exprs
+        .iter()
+        .map(Expr::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// `: Number` or nothing
+pub fn annotated(annotation: &Option<TypeAnnotation>) -> String {
+    annotation
+        .as_ref()
+        .map(|annotation| format!(": {}", annotation.kind))
+        .unwrap_or_default()
+}
+
 impl fmt::Display for StmtKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use StmtKind::*;
@@ -36,19 +162,50 @@ impl fmt::Display for StmtKind {
             Expression { expr } => {
                 write!(f, "{};", expr)?;
             }
-            VariableDeclaration { expr, name } => {
-                write!(f, "let {} = {};", name, expr)?;
+            VariableDeclaration {
+                expr,
+                name,
+                annotation,
+                ..
+            } => {
+                write!(f, "let {}{} = {};", name, annotated(annotation), expr)?;
+            }
+            MultipleDeclaration { names, exprs } => {
+                let names: Vec<&str> = names.iter().map(|name| name.kind.as_str()).collect();
+                write!(f, "let {} = {};", names.join(", "), join(exprs))?;
             }
-            FunctionDeclaration { params, body, name } => {
+            ParallelAssignment { targets, values } => {
+                write!(f, "{} = {};", join(targets), join(values))?;
+            }
+            Destructuring { pattern, expr } => {
+                write!(f, "let {} = {};", pattern, expr)?;
+            }
+            Import { path } => {
+                write!(f, "import {:?};", path.kind)?;
+            }
+            Export { declaration } => {
+                write!(f, "export {}", declaration.kind)?;
+            }
+            FunctionDeclaration {
+                params,
+                returns,
+                body,
+                name,
+                ..
+            } => {
                 write!(
                     f,
-                    "fn {}({}) {}",
+                    "fn {}({}){} {}",
                     name,
                     if params.kind.is_empty() {
                         "empty"
                     } else {
                         "args"
                     },
+                    returns
+                        .as_ref()
+                        .map(|returns| format!(" -> {}", returns))
+                        .unwrap_or_default(),
                     body
                 )?;
             }
@@ -58,35 +215,195 @@ impl fmt::Display for StmtKind {
     }
 }
 
+// Identifiers read their value unless they're marked as assigned to
+pub(crate) fn assignment_target(target: Expr) -> ParseResult<'static, Expr> {
+    match *target.kind {
+        ExprKind::Atom(AtomicValue::Identifier { name, .. }) => Ok(Expr::boxed(
+            ExprKind::Atom(AtomicValue::Identifier {
+                name,
+                is_assignment: true,
+            }),
+            target.span,
+        )),
+        ExprKind::Index { .. } | ExprKind::GetProperty { .. } => Ok(target),
+        _ => Err(ParseErrorCause::InvalidAssignmentTarget),
+    }
+}
+
 impl<'t> Parser<'t> {
     pub(crate) fn parse_stmt(&mut self) -> StmtResult {
         match self.peek() {
             Token::Let => self.parse_variable_declaration(),
             Token::Function => self.parse_fun_declaration(),
+            Token::Import | Token::Export => Err(ParseErrorCause::NotAtTopLevel),
             _ => self.parse_expression_stmt(),
         }
     }
 
     pub(super) fn parse_expression_stmt(&mut self) -> StmtResult {
         let expr = self.parse_expression()?;
-        let semicolon = self.expect(Token::Semicolon)?.span();
+        if self.peek() == Token::Comma {
+            return self.parse_parallel_assignment(expr);
+        }
+        let semicolon = match self.expect(Token::Semicolon) {
+            Ok(semicolon) => semicolon.span(),
+            Err(cause) => return Err(self.misspelled(&expr).unwrap_or(cause)),
+        };
         let span = combine(&expr.span, &semicolon);
 
         Ok(Stmt::boxed(StmtKind::Expression { expr }, span))
     }
 
+    // A lone word followed by more code, like `wile x {`, is most likely a typo
+    pub(crate) fn misspelled(&self, expr: &Expr) -> Option<ParseErrorCause> {
+        let word = match &*expr.kind {
+            ExprKind::Atom(AtomicValue::Identifier { name, .. }) => name,
+            _ => return None,
+        };
+        if self.names.contains(word) {
+            return None;
+        }
+        let keywords: Vec<String> = STMT_KEYWORDS.iter().map(Token::to_string).collect();
+        let candidates = keywords
+            .iter()
+            .map(String::as_str)
+            .chain(self.names.iter().map(|name| &**name));
+
+        let suggestion = closest(word, candidates)?;
+
+        Some(ParseErrorCause::UnknownWord {
+            word: word.to_string(),
+            suggestion: suggestion.to_owned(),
+        })
+    }
+
     pub(super) fn parse_variable_declaration(&mut self) -> StmtResult {
         let let_keyword = {
             let lexeme = self.expect(Token::Let)?;
             lexeme.span()
         };
-        let name = self.expect_identifier()?.slice.to_owned();
+        let pattern = match self.peek() {
+            OPEN_BRACKET => Some(DestructuringPattern::Object(
+                self.parse_names(OPEN_BRACKET, CLOSE_BRACKET)?,
+            )),
+            OPEN_SQUARE => Some(DestructuringPattern::Array(
+                self.parse_names(OPEN_SQUARE, CLOSE_SQUARE)?,
+            )),
+            _ => None,
+        };
+        let mut names = vec![];
+        let mut annotation = None;
+        if pattern.is_none() {
+            loop {
+                let name = self.expect_identifier()?;
+                names.push(Node::new(Symbol::intern(name.slice), name.span()));
+                if self.peek() != Token::Comma {
+                    break;
+                }
+                self.expect(Token::Comma)?;
+            }
+            // Only a single variable can be annotated
+            if names.len() == 1 {
+                annotation = self.parse_type_annotation()?;
+            }
+        }
         self.expect(Token::Operator(Operator::Assign))?;
-        let expr = self.parse_expression()?;
+        let mut exprs = self.parse_values()?;
+        if pattern.is_none() && names.len() != exprs.len() {
+            return Err(ParseErrorCause::ValueCountMismatch {
+                expected: names.len(),
+                found: exprs.len(),
+            });
+        }
         let semicolon = self.expect(Token::Semicolon)?;
         let span = combine(&let_keyword, &semicolon.span());
+        let kind = match pattern {
+            Some(_) if exprs.len() > 1 => {
+                return Err(ParseErrorCause::Expected(Expect::Token(Token::Semicolon)))
+            }
+            Some(pattern) => StmtKind::Destructuring {
+                pattern,
+                expr: exprs.remove(0),
+            },
+            None if names.len() == 1 => StmtKind::VariableDeclaration {
+                name: names.remove(0).kind,
+                annotation,
+                expr: exprs.remove(0),
+                doc: self.doc_comment(let_keyword.start),
+            },
+            None => StmtKind::MultipleDeclaration { names, exprs },
+        };
+        self.declare(&kind);
+        Ok(Stmt::boxed(kind, span))
+    }
+
+    // Comma separated expressions on the right side of = in declarations and assignments
+    fn parse_values(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut values = vec![self.parse_expression()?];
+        while self.peek() == Token::Comma {
+            self.expect(Token::Comma)?;
+            values.push(self.parse_expression()?);
+        }
+        Ok(values)
+    }
+
+    // The first target was already parsed as an expression, the last one as the assignment
+    // of the first value, like `b = b` in `a, b = b, a;`
+    pub(super) fn parse_parallel_assignment(&mut self, first: Expr) -> StmtResult {
+        let mut targets = vec![first];
+        while self.peek() == Token::Comma {
+            self.expect(Token::Comma)?;
+            let target = self.parse_expression()?;
+            let is_last = matches!(
+                &*target.kind,
+                ExprKind::Assignment { .. } | ExprKind::SetProperty { .. }
+            );
+            targets.push(target);
+            if is_last {
+                break;
+            }
+        }
+
+        let last = targets.pop().expect("There is at least the first target");
+        let (last, first_value) = match *last.kind {
+            ExprKind::Assignment { target, value } => (target, value),
+            ExprKind::SetProperty {
+                target,
+                identifier,
+                value,
+            } => {
+                let span = combine(&target.span, &identifier.span);
+                let target = ExprKind::GetProperty {
+                    target,
+                    identifier,
+                    is_method_call: false,
+                };
+                (Expr::boxed(target, span), value)
+            }
+            _ => return Err(ParseErrorCause::Expected(Expect::Token(ASSIGN))),
+        };
+        targets.push(last);
+        let targets = targets
+            .into_iter()
+            .map(assignment_target)
+            .collect::<ParseResult<Vec<Expr>>>()?;
+
+        let mut values = vec![first_value];
+        while self.peek() == Token::Comma {
+            self.expect(Token::Comma)?;
+            values.push(self.parse_expression()?);
+        }
+        if targets.len() != values.len() {
+            return Err(ParseErrorCause::ValueCountMismatch {
+                expected: targets.len(),
+                found: values.len(),
+            });
+        }
+
+        let semicolon = self.expect(Token::Semicolon)?.span();
+        let span = combine(&targets[0].span, &semicolon);
         Ok(Stmt::boxed(
-            StmtKind::VariableDeclaration { name, expr },
+            StmtKind::ParallelAssignment { targets, values },
             span,
         ))
     }
@@ -0,0 +1,62 @@
+use crate::{
+    parse::{
+        stmt::{Stmt, StmtKind},
+        Node, Parser, StmtResult,
+    },
+    token::{unescape, Token},
+    utils::{
+        combine,
+        error::{Expect, ParseErrorCause},
+    },
+};
+
+impl<'t> Parser<'t> {
+    // import "path/module.gv";
+    // export fn foo() {}
+    pub(crate) fn parse_module_item(&mut self) -> StmtResult {
+        match self.peek() {
+            Token::Import => self.parse_import(),
+            Token::Export => self.parse_export(),
+            _ => self.parse_stmt(),
+        }
+    }
+
+    fn parse_import(&mut self) -> StmtResult {
+        let import_keyword = self.expect(Token::Import)?.span();
+        let path = self.advance()?;
+        let path_span = path.span();
+        let path = match path.token {
+            Token::String(str) if !str.contains("${") => unescape(str, path_span.start + 1)?,
+            Token::RawString(str) => str.to_owned(),
+            _ => return Err(ParseErrorCause::Expected(Expect::Literal)),
+        };
+        let semicolon = self.expect(Token::Semicolon)?.span();
+
+        Ok(Stmt::boxed(
+            StmtKind::Import {
+                path: Node::new(path, path_span),
+            },
+            combine(&import_keyword, &semicolon),
+        ))
+    }
+
+    fn parse_export(&mut self) -> StmtResult {
+        let export_keyword = self.expect(Token::Export)?.span();
+        let mut declaration = match self.peek() {
+            Token::Let => self.parse_variable_declaration()?,
+            Token::Function => self.parse_fun_declaration()?,
+            _ => return Err(ParseErrorCause::ExportNeedsDeclaration),
+        };
+        // Doc comments of exported items are written above the export keyword
+        if let StmtKind::VariableDeclaration { doc, .. }
+        | StmtKind::FunctionDeclaration { doc, .. } = &mut *declaration.kind
+        {
+            if doc.is_none() {
+                *doc = self.doc_comment(export_keyword.start);
+            }
+        }
+        let span = combine(&export_keyword, &declaration.span);
+
+        Ok(Stmt::boxed(StmtKind::Export { declaration }, span))
+    }
+}
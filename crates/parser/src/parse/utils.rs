@@ -21,6 +21,10 @@ impl<'t> Parser<'t> {
 
         let expr = self.parse_expression()?;
 
+        if self.peek() == Token::Comma {
+            return Ok(ExprOrStmt::Stmt(self.parse_parallel_assignment(expr)?));
+        }
+
         if self.peek() == Token::Semicolon {
             let semicolon = self.expect(Token::Semicolon)?.span();
             let span = combine(&expr.span, &semicolon);
@@ -0,0 +1,387 @@
+use crate::parse::{
+    expr::{Expr, ExprKind, MatchArm, NamedArg, Pattern},
+    stmt::{Stmt, StmtKind},
+    Node,
+};
+
+// Read-only traversal of the tree. Every method walks into the children by default,
+// so an implementation overrides only the nodes it cares about and calls the walk_*
+// function itself when it still wants to go deeper.
+pub trait Visit {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+}
+
+pub fn walk_program<V: Visit + ?Sized>(visitor: &mut V, program: &[Stmt]) {
+    for stmt in program {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visit + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match &*stmt.kind {
+        StmtKind::Expression { expr }
+        | StmtKind::VariableDeclaration { expr, .. }
+        | StmtKind::Destructuring { expr, .. }
+        | StmtKind::FunctionDeclaration { body: expr, .. } => visitor.visit_expr(expr),
+        StmtKind::MultipleDeclaration { exprs, .. } => {
+            exprs.iter().for_each(|expr| visitor.visit_expr(expr))
+        }
+        StmtKind::ParallelAssignment { targets, values } => targets
+            .iter()
+            .chain(values)
+            .for_each(|expr| visitor.visit_expr(expr)),
+        StmtKind::Export { declaration } => visitor.visit_stmt(declaration),
+        StmtKind::Import { .. } => {}
+    }
+}
+
+// Children are visited in the order they appear in the code
+pub fn walk_expr<V: Visit + ?Sized>(visitor: &mut V, expr: &Expr) {
+    use ExprKind::*;
+
+    match &*expr.kind {
+        Atom(_) | Continue => {}
+        Binary { lhs, rhs, .. } => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        Unary { rhs, .. } => visitor.visit_expr(rhs),
+        Block { stmts, return_expr } => {
+            stmts.iter().for_each(|stmt| visitor.visit_stmt(stmt));
+            if let Some(return_expr) = return_expr {
+                visitor.visit_expr(return_expr);
+            }
+        }
+        If {
+            condition,
+            body,
+            else_expr,
+        } => {
+            visitor.visit_expr(condition);
+            visitor.visit_expr(body);
+            if let Some(else_expr) = else_expr {
+                visitor.visit_expr(else_expr);
+            }
+        }
+        While { condition, body } => {
+            visitor.visit_expr(condition);
+            visitor.visit_expr(body);
+        }
+        Loop { body } | Closure { body, .. } => visitor.visit_expr(body),
+        For {
+            init,
+            condition,
+            step,
+            body,
+        } => {
+            if let Some(init) = init {
+                visitor.visit_stmt(init);
+            }
+            condition
+                .iter()
+                .chain(step)
+                .for_each(|expr| visitor.visit_expr(expr));
+            visitor.visit_expr(body);
+        }
+        ForIn { iterable, body, .. } => {
+            visitor.visit_expr(iterable);
+            visitor.visit_expr(body);
+        }
+        Match { value, arms } => {
+            visitor.visit_expr(value);
+            for arm in arms {
+                if let Pattern::Value(pattern) = &arm.pattern {
+                    visitor.visit_expr(pattern);
+                }
+                visitor.visit_expr(&arm.body);
+            }
+        }
+        Break { return_expr: value } | Return { value } => {
+            if let Some(value) = value {
+                visitor.visit_expr(value);
+            }
+        }
+        Call {
+            callee,
+            args,
+            named_args,
+        } => {
+            visitor.visit_expr(callee);
+            args.iter().for_each(|arg| visitor.visit_expr(arg));
+            named_args
+                .iter()
+                .for_each(|arg| visitor.visit_expr(&arg.value));
+        }
+        Array { values } | Interpolation { parts: values } => {
+            values.iter().for_each(|value| visitor.visit_expr(value))
+        }
+        Spread { value } => visitor.visit_expr(value),
+        Index { target, position } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(position);
+        }
+        Slice {
+            target, start, end, ..
+        } => {
+            visitor.visit_expr(target);
+            start
+                .iter()
+                .chain(end)
+                .for_each(|bound| visitor.visit_expr(bound));
+        }
+        GetProperty { target, .. } => visitor.visit_expr(target),
+        SetProperty { target, value, .. } | Assignment { target, value } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(value);
+        }
+        ObjectLiteral { properties } => properties
+            .iter()
+            .for_each(|(_, value)| visitor.visit_expr(value)),
+        Map { entries } => entries
+            .iter()
+            .for_each(|(_, value)| visitor.visit_expr(value)),
+    }
+}
+
+// Rewriting traversal, the tree is taken apart and built again from the folded children.
+// Spans of the rebuilt nodes stay the same.
+pub trait Fold {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        fold_stmt(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_expr(self, expr)
+    }
+}
+
+pub fn fold_program<F: Fold + ?Sized>(folder: &mut F, program: Vec<Stmt>) -> Vec<Stmt> {
+    program
+        .into_iter()
+        .map(|stmt| folder.fold_stmt(stmt))
+        .collect()
+}
+
+fn fold_exprs<F: Fold + ?Sized>(folder: &mut F, exprs: Vec<Expr>) -> Vec<Expr> {
+    exprs
+        .into_iter()
+        .map(|expr| folder.fold_expr(expr))
+        .collect()
+}
+
+fn fold_option<F: Fold + ?Sized>(folder: &mut F, expr: Option<Expr>) -> Option<Expr> {
+    expr.map(|expr| folder.fold_expr(expr))
+}
+
+pub fn fold_stmt<F: Fold + ?Sized>(folder: &mut F, stmt: Stmt) -> Stmt {
+    use StmtKind::*;
+
+    let kind = match *stmt.kind {
+        Expression { expr } => Expression {
+            expr: folder.fold_expr(expr),
+        },
+        VariableDeclaration {
+            name,
+            annotation,
+            expr,
+            doc,
+        } => VariableDeclaration {
+            name,
+            annotation,
+            expr: folder.fold_expr(expr),
+            doc,
+        },
+        FunctionDeclaration {
+            name,
+            params,
+            returns,
+            body,
+            doc,
+        } => FunctionDeclaration {
+            name,
+            params,
+            returns,
+            body: folder.fold_expr(body),
+            doc,
+        },
+        MultipleDeclaration { names, exprs } => MultipleDeclaration {
+            names,
+            exprs: fold_exprs(folder, exprs),
+        },
+        ParallelAssignment { targets, values } => ParallelAssignment {
+            targets: fold_exprs(folder, targets),
+            values: fold_exprs(folder, values),
+        },
+        Destructuring { pattern, expr } => Destructuring {
+            pattern,
+            expr: folder.fold_expr(expr),
+        },
+        Import { path } => Import { path },
+        Export { declaration } => Export {
+            declaration: folder.fold_stmt(declaration),
+        },
+    };
+
+    Node::boxed(kind, stmt.span)
+}
+
+pub fn fold_expr<F: Fold + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    use ExprKind::*;
+
+    let kind = match *expr.kind {
+        kind @ Atom(_) | kind @ Continue => kind,
+        Binary { lhs, op, rhs } => Binary {
+            lhs: folder.fold_expr(lhs),
+            op,
+            rhs: folder.fold_expr(rhs),
+        },
+        Unary { op, rhs } => Unary {
+            op,
+            rhs: folder.fold_expr(rhs),
+        },
+        Block { stmts, return_expr } => Block {
+            stmts: fold_program(folder, stmts),
+            return_expr: fold_option(folder, return_expr),
+        },
+        If {
+            condition,
+            body,
+            else_expr,
+        } => If {
+            condition: folder.fold_expr(condition),
+            body: folder.fold_expr(body),
+            else_expr: fold_option(folder, else_expr),
+        },
+        While { condition, body } => While {
+            condition: folder.fold_expr(condition),
+            body: folder.fold_expr(body),
+        },
+        Loop { body } => Loop {
+            body: folder.fold_expr(body),
+        },
+        For {
+            init,
+            condition,
+            step,
+            body,
+        } => For {
+            init: init.map(|init| folder.fold_stmt(init)),
+            condition: fold_option(folder, condition),
+            step: fold_option(folder, step),
+            body: folder.fold_expr(body),
+        },
+        ForIn {
+            item,
+            iterable,
+            body,
+        } => ForIn {
+            item,
+            iterable: folder.fold_expr(iterable),
+            body: folder.fold_expr(body),
+        },
+        Match { value, arms } => Match {
+            value: folder.fold_expr(value),
+            arms: arms
+                .into_iter()
+                .map(|arm| MatchArm {
+                    pattern: match arm.pattern {
+                        Pattern::Value(pattern) => Pattern::Value(folder.fold_expr(pattern)),
+                        Pattern::Wildcard => Pattern::Wildcard,
+                    },
+                    body: folder.fold_expr(arm.body),
+                })
+                .collect(),
+        },
+        Break { return_expr } => Break {
+            return_expr: fold_option(folder, return_expr),
+        },
+        Interpolation { parts } => Interpolation {
+            parts: fold_exprs(folder, parts),
+        },
+        Call {
+            callee,
+            args,
+            named_args,
+        } => Call {
+            callee: folder.fold_expr(callee),
+            args: fold_exprs(folder, args),
+            named_args: named_args
+                .into_iter()
+                .map(|arg| NamedArg {
+                    name: arg.name,
+                    value: folder.fold_expr(arg.value),
+                })
+                .collect(),
+        },
+        Return { value } => Return {
+            value: fold_option(folder, value),
+        },
+        Array { values } => Array {
+            values: fold_exprs(folder, values),
+        },
+        Spread { value } => Spread {
+            value: folder.fold_expr(value),
+        },
+        Index { target, position } => Index {
+            target: folder.fold_expr(target),
+            position: folder.fold_expr(position),
+        },
+        Slice {
+            target,
+            start,
+            end,
+            inclusive,
+        } => Slice {
+            target: folder.fold_expr(target),
+            start: fold_option(folder, start),
+            end: fold_option(folder, end),
+            inclusive,
+        },
+        GetProperty {
+            target,
+            is_method_call,
+            identifier,
+        } => GetProperty {
+            target: folder.fold_expr(target),
+            is_method_call,
+            identifier,
+        },
+        SetProperty {
+            target,
+            value,
+            identifier,
+        } => SetProperty {
+            target: folder.fold_expr(target),
+            value: folder.fold_expr(value),
+            identifier,
+        },
+        ObjectLiteral { properties } => ObjectLiteral {
+            properties: properties
+                .into_iter()
+                .map(|(key, value)| (key, folder.fold_expr(value)))
+                .collect(),
+        },
+        Map { entries } => Map {
+            entries: entries
+                .into_iter()
+                .map(|(key, value)| (key, folder.fold_expr(value)))
+                .collect(),
+        },
+        Assignment { target, value } => Assignment {
+            target: folder.fold_expr(target),
+            value: folder.fold_expr(value),
+        },
+        Closure { params, body } => Closure {
+            params,
+            body: folder.fold_expr(body),
+        },
+    };
+
+    Node::boxed(kind, expr.span)
+}
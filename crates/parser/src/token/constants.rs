@@ -14,3 +14,21 @@ pub(crate) const CLOSE_SQUARE: Token = Token::Operator(Operator::SquareBracketCl
 
 pub(crate) const DOT: Token = Token::Operator(Operator::Dot);
 pub(crate) const ASSIGN: Token = Token::Operator(Operator::Assign);
+// Opens and closes the parameters of closures
+pub(crate) const BAR: Token = Token::Operator(Operator::Bar);
+
+// Keywords that start statements, suggested in place of misspelled words
+pub(crate) const STMT_KEYWORDS: [Token; 12] = [
+    Token::Function,
+    Token::Let,
+    Token::Import,
+    Token::Export,
+    Token::If,
+    Token::While,
+    Token::Loop,
+    Token::Return,
+    Token::For,
+    Token::Match,
+    Token::Break,
+    Token::Continue,
+];
@@ -5,6 +5,8 @@ use logos::Span;
 use logos::{Filter, Logos};
 use regex::Regex;
 
+use crate::utils::error::ParseErrorCause;
+
 use operator::{lex_operator, Operator};
 
 pub(crate) mod constants;
@@ -17,7 +19,9 @@ fn lex_number<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> Result<f64, Token<'t
                 .expect("Couldn't create regex(multiple dots in number)");
     }
 
-    let slice: &str = lex.slice();
+    // Underscores only separate the digits for readability
+    let slice = lex.slice().replace('_', "");
+    let slice = slice.as_str();
 
     if slice == "Infinity" || slice == "inf" {
         return Ok(f64::INFINITY);
@@ -27,16 +31,231 @@ fn lex_number<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> Result<f64, Token<'t
         return Ok(f64::NAN);
     }
 
-    if MULTIPLE_DOTS_IN_NUMBER.is_match(&slice) {
+    if MULTIPLE_DOTS_IN_NUMBER.is_match(slice) {
         Err(Token::Error)
     } else {
         slice.parse::<f64>().map_err(|_| Token::Error)
     }
 }
 
-fn lex_string<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> &'t str {
+pub(crate) fn is_radix_number(literal: &str) -> bool {
+    let digits = literal.strip_prefix('-').unwrap_or(literal);
+    ["0x", "0b", "0o"].iter().any(|prefix| digits.starts_with(prefix))
+}
+
+// Value of a 0x, 0b or 0o literal, which starts at the given offset in the source.
+// Lexer takes any alphanumeric characters after the prefix, so the digits are checked here.
+pub(crate) fn radix_number(literal: &str, start: usize) -> Result<f64, ParseErrorCause> {
+    let (sign, digits_start) = match literal.strip_prefix('-') {
+        Some(_) => (-1.0, 3),
+        None => (1.0, 2),
+    };
+    let radix = match &literal[digits_start - 1..digits_start] {
+        "x" => 16,
+        "b" => 2,
+        _ => 8,
+    };
+
+    let digits = &literal[digits_start..];
+    if digits.chars().all(|char| char == '_') {
+        return Err(ParseErrorCause::MissingDigits(start..start + literal.len()));
+    }
+
+    let mut value = 0.0;
+    for (index, char) in digits.char_indices().filter(|(_, char)| *char != '_') {
+        let digit_start = start + digits_start + index;
+        let digit = char.to_digit(radix).ok_or(ParseErrorCause::InvalidDigit {
+            span: digit_start..digit_start + char.len_utf8(),
+            radix,
+        })?;
+        value = value * radix as f64 + digit as f64;
+    }
+
+    Ok(sign * value)
+}
+
+// Index of the quote closing the string, skipping escaped quotes and the ones of strings nested
+// in interpolated expressions
+fn string_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = 0;
+
+    while let Some(char) = text[index..].chars().next() {
+        match char {
+            '\\' if depth == 0 => {
+                // The escaped character is skipped together with the backslash
+                index += text[index + 1..].chars().next().map_or(0, char::len_utf8);
+            }
+            '"' if depth == 0 => return Some(index),
+            '"' => {
+                index += string_end(&text[index + 1..])? + 2;
+                continue;
+            }
+            '$' if depth == 0 && text[index + 1..].starts_with('{') => {
+                depth = 1;
+                index += 1;
+            }
+            '{' if depth > 0 => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            _ => {}
+        }
+        index += char.len_utf8();
+    }
+
+    None
+}
+
+fn lex_string<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> Option<&'t str> {
+    let end = string_end(lex.remainder())?;
+    lex.bump(end + 1);
+    let slice: &str = lex.slice();
+    Some(&slice[1..slice.len() - 1])
+}
+
+// Length of the raw string r#"..."# at the start of the code, including its quotes and hashes
+pub(crate) fn raw_string_len(code: &str) -> Option<usize> {
+    let hashes = code.strip_prefix('r')?.chars().take_while(|&c| c == '#').count();
+    let text_start = 2 + hashes;
+    if code.get(text_start - 1..text_start) != Some("\"") {
+        return None;
+    }
+
+    let terminator = format!("\"{}", "#".repeat(hashes));
+    let text_len = code[text_start..].find(&terminator)?;
+    Some(text_start + text_len + terminator.len())
+}
+
+fn lex_raw_string<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> Option<&'t str> {
+    // The prefix r#" is already matched, so only the rest of the string is bumped
+    let prefix = lex.slice().len();
+    let len = raw_string_len(&lex.source()[lex.span().start..])?;
+    lex.bump(len - prefix);
     let slice: &str = lex.slice();
-    &slice[1..slice.len() - 1]
+    Some(&slice[prefix..slice.len() - (prefix - 1)])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum StringPart<'t> {
+    // Both parts come with their offset from the start of the string's text
+    Text(&'t str, usize),
+    Code(&'t str, usize),
+}
+
+// Splits the text of a string literal on the ${ } interpolations
+pub(crate) fn string_parts(text: &str) -> Vec<StringPart<'_>> {
+    let mut parts = vec![];
+    let mut text_start = 0;
+    let mut index = 0;
+
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with('\\') {
+            index += rest.chars().take(2).map(char::len_utf8).sum::<usize>();
+        } else if rest.starts_with("${") {
+            // The lexer made sure every interpolation is closed
+            let code_start = index + 2;
+            let code_end = code_start + interpolation_end(&text[code_start..]);
+            if text_start < index {
+                parts.push(StringPart::Text(&text[text_start..index], text_start));
+            }
+            parts.push(StringPart::Code(&text[code_start..code_end], code_start));
+            index = code_end + 1;
+            text_start = index;
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    if text_start < text.len() {
+        parts.push(StringPart::Text(&text[text_start..], text_start));
+    }
+    parts
+}
+
+// Replaces the escape sequences of a string's text, which starts at the given offset in the source
+pub(crate) fn unescape(text: &str, start: usize) -> Result<String, ParseErrorCause> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut index = 0;
+
+    while let Some(char) = text[index..].chars().next() {
+        if char != '\\' {
+            unescaped.push(char);
+            index += char.len_utf8();
+            continue;
+        }
+
+        let escape = &text[index..];
+        let span = |length: usize| start + index..start + index + length;
+        let (escaped, length) = match escape[1..].chars().next() {
+            Some('n') => ('\n', 2),
+            Some('t') => ('\t', 2),
+            Some('"') => ('"', 2),
+            Some('\\') => ('\\', 2),
+            Some('$') => ('$', 2),
+            Some('u') => {
+                let code = escape[2..]
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.find('}').map(|end| &rest[..end]));
+                // Either the whole \u{...} or just the \u when there are no brackets
+                let length = code.map_or(2, |code| code.len() + 4);
+
+                let escaped = code
+                    .filter(|code| !code.is_empty() && code.len() <= 6)
+                    .and_then(|code| u32::from_str_radix(code, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| ParseErrorCause::InvalidUnicodeEscape(span(length)))?;
+                (escaped, length)
+            }
+            Some(char) => return Err(ParseErrorCause::InvalidEscape(span(1 + char.len_utf8()))),
+            None => return Err(ParseErrorCause::InvalidEscape(span(1))),
+        };
+
+        unescaped.push(escaped);
+        index += length;
+    }
+
+    Ok(unescaped)
+}
+
+// Reverse of unescape, so the text can be put back between quotes
+pub(crate) fn escape_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            // Only a dollar followed by a bracket would start an interpolation
+            '$' if chars.peek() == Some(&'{') => escaped.push_str("\\$"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+// Index of the } closing an interpolation, the code is known to be balanced
+fn interpolation_end(code: &str) -> usize {
+    let mut depth = 0;
+    let mut index = 0;
+
+    while let Some(char) = code[index..].chars().next() {
+        match char {
+            '"' => {
+                index += string_end(&code[index + 1..]).map_or(code.len(), |end| end + 2);
+                continue;
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return index,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        index += char.len_utf8();
+    }
+
+    code.len()
 }
 
 fn lex_boolean<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> bool {
@@ -65,20 +284,37 @@ fn lex_error<'t>(lex: &mut logos::Lexer<'t, Token<'t>>) -> Filter<()> {
 }
 
 #[derive(Logos, Debug, PartialEq, Clone, Copy, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token<'t> {
     // DECLARATION KEYWORDS
     #[token("fn")]
+    #[display(fmt = "fn")]
     Function,
     #[token("class")]
+    #[display(fmt = "class")]
     Class,
     #[token("let")]
+    #[display(fmt = "let")]
     Let,
+    #[token("import")]
+    #[display(fmt = "import")]
+    Import,
+    #[token("export")]
+    #[display(fmt = "export")]
+    Export,
     #[token(";")]
     #[display(fmt = ";")]
     Semicolon,
     #[token("=>")]
     #[display(fmt = "=>")]
     Arrow,
+    // Comes before the return type of a function
+    #[token("->")]
+    #[display(fmt = "->")]
+    ThinArrow,
+    #[token("...")]
+    #[display(fmt = "...")]
+    Ellipsis,
     #[token(",")]
     #[display(fmt = ",")]
     Comma,
@@ -88,27 +324,40 @@ pub enum Token<'t> {
     #[token(":")]
     #[display(fmt = ":")]
     Colon,
-    #[token("|")]
-    #[display(fmt = "|")]
-    Bar,
     // EXPRESSION KEYWORDS
     #[token("if")]
+    #[display(fmt = "if")]
     If,
     #[token("else")]
+    #[display(fmt = "else")]
     Else,
     #[token("while")]
+    #[display(fmt = "while")]
     While,
+    #[token("loop")]
+    #[display(fmt = "loop")]
+    Loop,
     #[token("return")]
+    #[display(fmt = "return")]
     Return,
     #[token("for")]
+    #[display(fmt = "for")]
     For,
+    #[token("in")]
+    #[display(fmt = "in")]
+    In,
+    #[token("match")]
+    #[display(fmt = "match")]
+    Match,
     #[token("break")]
+    #[display(fmt = "break")]
     Break,
     #[token("continue")]
+    #[display(fmt = "continue")]
     Continue,
     // OPERATORS
     #[regex(
-        r"\[|\]|\{|\}|\(|\)|\+|\-|\*|/|%|\*\*|==|!=|<|<=|>|>=|or|and|!|\.|=",
+        r"\[|\]|\{|\}|\(|\)|\+|\-|\*|/|%|\*\*|==|!=|<<|>>|<|<=|>|>=|or|and|!|&|\||\^|~|\.\.=|\.\.|\.|=",
         lex_operator
     )]
     Operator(Operator),
@@ -116,10 +365,22 @@ pub enum Token<'t> {
     #[regex("true|false", lex_boolean)]
     Bool(bool),
     #[regex("Infinity|inf|NaN", lex_number)]
-    #[regex("-?[0-9]*\\.?[0-9\\.]+", lex_number)]
+    // A trailing dot would swallow the first dot of a range, so 1. isn't a number
+    // Digits may be separated with underscores, 1_ is a number and not an invalid identifier
+    #[regex(
+        "-?([0-9][0-9_]*|([0-9][0-9_]*)?\\.[0-9][0-9_]*)(\\.[0-9][0-9_]*)*",
+        lex_number,
+        priority = 3
+    )]
     Number(f64),
-    #[regex(r#""(\\"|[^"])*""#, lex_string)]
+    // Digits of 0x, 0b and 0o literals are validated by the parser, which can report them better
+    #[regex("-?0[xbo][0-9a-zA-Z_]*")]
+    RadixNumber(&'t str),
+    #[token("\"", lex_string)]
     String(&'t str),
+    // Text is taken as it is, without escapes or interpolations
+    #[regex("r#*\"", lex_raw_string)]
+    RawString(&'t str),
     #[regex("[a-z_A-Z][a-z_A-Z0-9]*")]
     Identifier(&'t str),
     Eof,
@@ -132,7 +393,7 @@ impl<'t> Token<'t> {
     pub(crate) fn is_stmt(&self) -> bool {
         use Token::*;
 
-        matches!(self, Class | Function | Let)
+        matches!(self, Class | Function | Let | Import | Export)
     }
 
     pub(crate) fn is_expr(&self) -> bool {
@@ -145,16 +406,19 @@ impl<'t> Token<'t> {
             ),
             Token::Identifier(_)
             | Token::String(_)
+            | Token::RawString(_)
             | Token::Bool(_)
             | Token::Number(_)
+            | Token::RadixNumber(_)
             | Token::Break
             | Token::Continue
             | Token::For
             | Token::If
+            | Token::Match
             | Token::Return
             | Token::While
-            | Token::New
-            | Token::Bar => true,
+            | Token::Loop
+            | Token::New => true,
             _ => false,
         }
     }
@@ -166,12 +430,15 @@ impl<'t> Token<'t> {
 
 struct Source<'t> {
     inner: logos::Lexer<'t, Token<'t>>,
+    // Position of the input in the whole program, added to the spans
+    offset: usize,
 }
 
 impl<'t> Source<'t> {
-    pub fn new(input: &'t str) -> Self {
+    pub fn new(input: &'t str, offset: usize) -> Self {
         Self {
             inner: Token::lexer(input),
+            offset,
         }
     }
 }
@@ -201,8 +468,8 @@ impl<'t> Iterator for Source<'t> {
         Some(Lexeme {
             token,
             slice,
-            span_start: span.start,
-            span_end: span.end,
+            span_start: self.offset + span.start,
+            span_end: self.offset + span.end,
         })
     }
 }
@@ -215,8 +482,13 @@ pub(crate) struct Lexer<'t> {
 
 impl<'t> Lexer<'t> {
     pub(crate) fn new(input: &'t str) -> Self {
+        Self::with_offset(input, 0)
+    }
+
+    // Lexes a part of a bigger program, the spans point into the whole program
+    pub(crate) fn with_offset(input: &'t str, offset: usize) -> Self {
         Self {
-            inner: peek_nth(Source::new(input)),
+            inner: peek_nth(Source::new(input, offset)),
             current_span: None,
         }
     }
@@ -324,12 +596,16 @@ mod test {
         // Empty strings
         assert_token(r#""""#, String(""));
         assert_token(r#""    ""#, String("    "));
+        // Interpolated expressions may contain strings of their own
+        assert_token(r#""a ${b} c""#, String("a ${b} c"));
+        assert_token(r#""${ "}" + f("{") }""#, String(r#"${ "}" + f("{") }"#));
+        assert_token(r#""\${b""#, String(r#"\${b"#));
     }
 
     #[quickcheck]
     fn q_lexer_tokenizes_strings(text: String) {
-        // Quickcheck generates strings with quotes, and we don't allow these inside
-        let text = text.replace("\"", "");
+        // Quickcheck generates strings with quotes and interpolations, and we don't allow these inside
+        let text = text.replace("\"", "").replace("${", "");
         assert_token(&format!("\"{}\"", text), Token::String(&text));
     }
 
@@ -362,16 +638,10 @@ mod test {
     fn lexer_tokenizes_numbers_with_trailing_commas() {
         use Token::Number;
         assert_token(".1", Number(0.1));
-        assert_token("1.", Number(1.0));
     }
 
     #[test]
     fn lexer_discards_invalid_numbers() {
-        // more than one dot at the beginning
-        assert_error("..1");
-        // more than one trailing dot
-        assert_error("1..");
-        assert_error("1.1.");
         // more than one dot inside number
         assert_error("1.1.1");
         assert_error("1.1.1.");
@@ -423,11 +693,15 @@ mod test {
         assert_token("fn", Function);
         assert_token("class", Class);
         assert_token("let", Let);
+        assert_token("import", Import);
+        assert_token("export", Export);
         assert_token("if", If);
         assert_token("else", Else);
         assert_token("while", While);
+        assert_token("loop", Loop);
         assert_token("return", Return);
         assert_token("for", For);
+        assert_token("in", In);
         assert_token("break", Break);
         assert_token("continue", Continue);
         assert_token("this", This);
@@ -507,6 +781,7 @@ mod test {
         assert_token(";;;", Token::Semicolon);
         assert_token(",", Token::Comma);
         assert_token("=>", Token::Arrow);
+        assert_token("...", Token::Ellipsis);
         assert_token(":", Token::Inherit);
     }
 }
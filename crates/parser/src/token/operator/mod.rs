@@ -6,6 +6,7 @@ use std::str::FromStr;
 pub(crate) mod precedence;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operator {
     Plus,
     Minus,
@@ -22,8 +23,16 @@ pub enum Operator {
     Or,
     And,
     Bang,
+    Ampersand,
+    Bar,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
     Assign,
     Dot,
+    Range,
+    RangeInclusive,
     RoundBracketOpen,
     RoundBracketClose,
     SquareBracketOpen,
@@ -73,7 +82,15 @@ impl_from_to_str!(
     "or" => Operator::Or,
     "and" => Operator::And,
     "!" => Operator::Bang,
+    "&" => Operator::Ampersand,
+    "|" => Operator::Bar,
+    "^" => Operator::Caret,
+    "~" => Operator::Tilde,
+    "<<" => Operator::ShiftLeft,
+    ">>" => Operator::ShiftRight,
     "." => Operator::Dot,
+    ".." => Operator::Range,
+    "..=" => Operator::RangeInclusive,
     "[" => Operator::SquareBracketOpen,
     "]" => Operator::SquareBracketClose,
     "(" => Operator::RoundBracketOpen,
@@ -114,7 +131,15 @@ mod test {
         assert_token("or", op(Or));
         assert_token("and", op(And));
         assert_token("!", op(Bang));
+        assert_token("&", op(Ampersand));
+        assert_token("|", op(Bar));
+        assert_token("^", op(Caret));
+        assert_token("~", op(Tilde));
+        assert_token("<<", op(ShiftLeft));
+        assert_token(">>", op(ShiftRight));
         assert_token(".", op(Dot));
+        assert_token("..", op(Range));
+        assert_token("..=", op(RangeInclusive));
         assert_token("(", op(RoundBracketOpen));
         assert_token(")", op(RoundBracketClose));
         assert_token("[", op(SquareBracketOpen));
@@ -10,24 +10,30 @@ impl Operator {
             Assign => (0, 1),
             And | Or => (2, 3),
             Less | LessEqual | Greater | GreaterEqual | Compare | BangCompare => (4, 5),
-            Plus | Minus => (6, 7),
-            Multiply | Divide | Modulo => (8, 9),
-            Exponent => (10, 11),
-            Dot => (12, 13),
+            Range | RangeInclusive => (6, 7),
+            Bar => (8, 9),
+            Caret => (10, 11),
+            Ampersand => (12, 13),
+            ShiftLeft | ShiftRight => (14, 15),
+            Plus | Minus => (16, 17),
+            Multiply | Divide | Modulo => (18, 19),
+            // Right associative, 2 ** 3 ** 2 is 2 ** (3 ** 2)
+            Exponent => (21, 20),
+            Dot => (22, 23),
             _ => return None,
         })
     }
 
     pub(crate) fn prefix_bp(&self) -> Option<PrefixBindingPower> {
         Some(match self {
-            Plus | Minus | Bang => ((), 5),
+            Plus | Minus | Bang | Tilde => ((), 7),
             _ => return None,
         })
     }
 
     pub(crate) fn postfix_bp(&self) -> Option<PostfixBindingPower> {
         Some(match self {
-            Operator::RoundBracketOpen | Operator::SquareBracketOpen => (11, ()),
+            Operator::RoundBracketOpen | Operator::SquareBracketOpen => (21, ()),
             _ => return None,
         })
     }
@@ -0,0 +1,94 @@
+use crate::{parse::Span, token::raw_string_len};
+
+// Lexer throws comments away so they are collected on the side, for the tools that need to
+// put them back (formatter) or read them (doc generator)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    // Whole comment including the leading slashes, without the line break
+    pub text: String,
+    pub span: Span,
+}
+
+impl Comment {
+    // `/// text`, but not `//// text` which is a regular comment made to stand out
+    pub fn is_doc(&self) -> bool {
+        self.text.starts_with("///") && !self.text.starts_with("////")
+    }
+}
+
+fn is_identifier_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_'
+}
+
+pub fn comments(code: &str) -> Vec<Comment> {
+    let mut comments = vec![];
+    let mut chars = code.char_indices().peekable();
+    let mut in_string = false;
+
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            // Raw strings can end with a backslash, so they're skipped as a whole
+            'r' if !in_string && !code[..index].ends_with(is_identifier_char) => {
+                if let Some(len) = raw_string_len(&code[index..]) {
+                    while matches!(chars.peek(), Some((next, _)) if *next < index + len) {
+                        chars.next();
+                    }
+                }
+            }
+            '/' if !in_string && matches!(chars.peek(), Some((_, '/'))) => {
+                let end = code[index..]
+                    .find('\n')
+                    .map_or(code.len(), |offset| index + offset);
+
+                comments.push(Comment {
+                    text: code[index..end].trim_end().to_owned(),
+                    span: index..end,
+                });
+
+                while matches!(chars.peek(), Some((next, _)) if *next < end) {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    comments
+}
+
+// Text of the doc comments placed right above the item starting at `item_start`, one line per
+// comment without the slashes. Anything but whitespace in between detaches them from the item.
+pub fn doc_comment(code: &str, comments: &[Comment], item_start: usize) -> Option<String> {
+    let mut lines = vec![];
+    let mut cursor = item_start;
+
+    for comment in comments
+        .iter()
+        .rev()
+        .skip_while(|comment| comment.span.start >= item_start)
+    {
+        if !comment.is_doc() || !code[comment.span.end..cursor].trim().is_empty() {
+            break;
+        }
+        // Doc comment written after some code on the same line belongs to that code
+        let line_start = code[..comment.span.start].rfind('\n').map_or(0, |i| i + 1);
+        if !code[line_start..comment.span.start].trim().is_empty() {
+            break;
+        }
+
+        let text = &comment.text[3..];
+        lines.push(text.strip_prefix(' ').unwrap_or(text).to_owned());
+        cursor = comment.span.start;
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+}
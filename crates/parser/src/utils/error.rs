@@ -1,6 +1,12 @@
-use crate::{token::Token, utils::combine};
+use crate::{
+    token::{
+        constants::{CLOSE_BRACKET, CLOSE_PARENTHESIS, CLOSE_SQUARE},
+        Token,
+    },
+    utils::combine,
+};
 use codespan_reporting::diagnostic::{Diagnostic, Label};
-use common::CompilerDiagnostic;
+use common::{CompilerDiagnostic, ProgramText};
 use logos::Span;
 use std::fmt::{self, Formatter};
 
@@ -18,7 +24,7 @@ impl fmt::Display for Expect {
             Expect::Identifier => "identifier".to_owned(),
             Expect::Literal => "literal".to_owned(),
             Expect::Expression => "expression".to_owned(),
-            Expect::Token(t) => format!("{}", t),
+            Expect::Token(t) => format!("`{}`", t),
         };
 
         write!(f, "{}", str)?;
@@ -26,9 +32,11 @@ impl fmt::Display for Expect {
     }
 }
 
+// Token the parser stopped at
 #[derive(Debug, Clone, PartialEq)]
-pub enum Forbidden {
-    TrailingComma,
+pub struct Found {
+    pub text: ProgramText,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq)]
@@ -36,6 +44,8 @@ pub struct ParseError {
     pub span_start: Span,
     pub span_end: Span,
     pub cause: ParseErrorCause,
+    // None at the end of the input and for errors found after parsing
+    pub found: Option<Found>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,7 +53,8 @@ pub enum ParseErrorCause {
     EndOfInput,
     UnexpectedToken,
     Expected(Expect),
-    NotAllowed(Forbidden),
+    // (a b), either a comma or the closing parenthesis would do
+    ExpectedOneOf(Vec<Expect>),
     UsedBeforeInitialization,
     UsedOutsideLoop,
     UsedOutsideClass,
@@ -52,6 +63,34 @@ pub enum ParseErrorCause {
     NotDefined,
     ReturnExprMustBeLast,
     ReturnUsedOutsideFunction,
+    AlreadyDeclared,
+    // Spans of the escape sequence inside of the string
+    InvalidEscape(Span),
+    InvalidUnicodeEscape(Span),
+    // Prefix of a 0x, 0b or 0o literal without any digits after it
+    MissingDigits(Span),
+    InvalidDigit { span: Span, radix: u32 },
+    PositionalAfterNamedArgument,
+    // f(...args, name: 1)
+    SpreadWithNamedArguments,
+    // let a, b = 1; or a, b = 1, 2, 3;
+    ValueCountMismatch { expected: usize, found: usize },
+    InvalidAssignmentTarget,
+    // import and export can only appear at the top level of a file
+    NotAtTopLevel,
+    // export 1;
+    ExportNeedsDeclaration,
+    // import of a file that wasn't loaded with the program
+    UnknownModule(ProgramText),
+    // Named arguments need to know the parameters of the called function
+    NamedArgumentsNeedFunction,
+    UnknownArgument(ProgramText),
+    DuplicateArgument(ProgramText),
+    MissingArgument(ProgramText),
+    // wile x { }, the suggestion is the closest keyword or declared name
+    UnknownWord { word: ProgramText, suggestion: ProgramText },
+    // a < b < c, spans of both operators
+    ChainedComparison { first: Span, second: Span },
 }
 
 impl CompilerDiagnostic for ParseError {
@@ -68,11 +107,10 @@ impl CompilerDiagnostic for ParseError {
                 .with_labels(vec![
                     Label::primary(file_id, span).with_message("wasn't expected")
                 ]),
-            Expected(e) => Diagnostic::error()
-                .with_message(format!("Expected {}", e))
-                .with_labels(vec![
-                    Label::primary(file_id, span.end..span.end + 1).with_message("but found")
-                ]),
+            Expected(expected) => {
+                expected_diagnostic(file_id, std::slice::from_ref(expected), self, span.end)
+            }
+            ExpectedOneOf(expected) => expected_diagnostic(file_id, expected, self, span.end),
             UsedBeforeInitialization => Diagnostic::error()
                 .with_message("Variable was used before initialization")
                 .with_labels(vec![Label::primary(file_id, span)]),
@@ -101,7 +139,166 @@ impl CompilerDiagnostic for ParseError {
             ReturnUsedOutsideFunction => Diagnostic::error()
                 .with_message("Return expression can only be used inside functions!")
                 .with_labels(vec![Label::primary(file_id, span)]),
-            _ => Diagnostic::error().with_message("TODO"),
+            AlreadyDeclared => Diagnostic::error()
+                .with_message("Name is already declared in this scope")
+                .with_labels(vec![
+                    Label::primary(file_id, span).with_message("declared again here")
+                ]),
+            InvalidEscape(escape) => Diagnostic::error()
+                .with_message("Unknown escape sequence in string")
+                .with_labels(vec![Label::primary(file_id, escape.clone())])
+                .with_notes(vec![
+                    "valid escapes are \\n, \\t, \\\", \\\\, \\$ and \\u{...}".to_owned()
+                ]),
+            InvalidUnicodeEscape(escape) => Diagnostic::error()
+                .with_message("Invalid unicode escape in string")
+                .with_labels(vec![Label::primary(file_id, escape.clone())
+                    .with_message("expected up to 6 hex digits of a code point in brackets")]),
+            MissingDigits(literal) => Diagnostic::error()
+                .with_message("Number literal has no digits after its prefix")
+                .with_labels(vec![Label::primary(file_id, literal.clone())]),
+            InvalidDigit { span, radix } => Diagnostic::error()
+                .with_message(format!("Invalid digit for a base {} number", radix))
+                .with_labels(vec![Label::primary(file_id, span.clone())]),
+            ValueCountMismatch { expected, found } => Diagnostic::error()
+                .with_message(format!("Expected {} values, but got {}", expected, found))
+                .with_labels(vec![Label::primary(file_id, span)]),
+            InvalidAssignmentTarget => Diagnostic::error()
+                .with_message("Only variables, properties and indexes can be assigned to")
+                .with_labels(vec![Label::primary(file_id, span)]),
+            NotAtTopLevel => Diagnostic::error()
+                .with_message("Imports and exports must be at the top level of the file")
+                .with_labels(vec![Label::primary(file_id, span)]),
+            ExportNeedsDeclaration => Diagnostic::error()
+                .with_message("Only variable and function declarations can be exported")
+                .with_labels(vec![Label::primary(file_id, span)]),
+            UnknownModule(path) => Diagnostic::error()
+                .with_message(format!("Module \"{}\" couldn't be found", path))
+                .with_labels(vec![Label::primary(file_id, span)]),
+            SpreadWithNamedArguments => Diagnostic::error()
+                .with_message("Spread arguments can't be mixed with named ones")
+                .with_labels(vec![Label::primary(file_id, span)]),
+            PositionalAfterNamedArgument => Diagnostic::error()
+                .with_message("Positional arguments must come before named ones")
+                .with_labels(vec![Label::primary(file_id, span)]),
+            NamedArgumentsNeedFunction => Diagnostic::error()
+                .with_message("Named arguments can only be passed to declared functions")
+                .with_labels(vec![
+                    Label::primary(file_id, span).with_message("callee isn't known here")
+                ]),
+            UnknownArgument(name) => Diagnostic::error()
+                .with_message(format!("Function has no parameter named '{}'", name))
+                .with_labels(vec![Label::primary(file_id, span)]),
+            DuplicateArgument(name) => Diagnostic::error()
+                .with_message(format!("Argument '{}' is passed more than once", name))
+                .with_labels(vec![Label::primary(file_id, span)]),
+            MissingArgument(name) => Diagnostic::error()
+                .with_message(format!("Missing argument '{}'", name))
+                .with_labels(vec![Label::primary(file_id, span).with_message("in this call")]),
+            // The error spans the whole statement, but only the word is wrong
+            UnknownWord { word, suggestion } => Diagnostic::error()
+                .with_message(format!("Unknown word `{}`", word))
+                .with_labels(vec![Label::primary(file_id, self.span_end.clone())
+                    .with_message(format!("did you mean `{}`?", suggestion))]),
+            ChainedComparison { first, second } => Diagnostic::error()
+                .with_message("Comparisons can't be chained")
+                .with_labels(vec![
+                    Label::primary(file_id, second.clone())
+                        .with_message("compares the result of the comparison before it"),
+                    Label::secondary(file_id, first.clone()).with_message("first comparison"),
+                ])
+                .with_notes(vec![
+                    "help: join the comparisons with `and`, like `a < b and b < c`".to_owned(),
+                    "help: put the first one in parentheses to compare its result".to_owned(),
+                ]),
         }
     }
 }
+
+// `,` or `)`, `a`, `b` or `c`
+fn one_of(expected: &[Expect]) -> String {
+    let names: Vec<String> = expected.iter().map(|expect| expect.to_string()).collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+// `end` is where the last correct token ends, missing tokens belong right after it
+fn expected_diagnostic(
+    file_id: usize,
+    expected: &[Expect],
+    error: &ParseError,
+    end: usize,
+) -> Diagnostic<usize> {
+    let expected_names = one_of(expected);
+    let (message, found_label) = match &error.found {
+        Some(found) => (
+            format!("Expected {}, found `{}`", expected_names, found.text),
+            Label::primary(file_id, found.span.clone()).with_message("unexpected token"),
+        ),
+        None => (
+            format!("Expected {}, found the end of the file", expected_names),
+            Label::primary(file_id, end..end).with_message("the file ends here"),
+        ),
+    };
+
+    let mut labels = vec![found_label];
+    let notes: Vec<String> = suggestion(expected, &error.found).into_iter().collect();
+    // A forgotten semicolon or bracket is missing after the last correct token, not before the next one
+    let terminators = [
+        Token::Semicolon,
+        CLOSE_BRACKET,
+        CLOSE_PARENTHESIS,
+        CLOSE_SQUARE,
+    ];
+    let missing_terminator =
+        matches!(expected, [Expect::Token(token)] if terminators.contains(token));
+    if missing_terminator && error.found.is_some() {
+        labels.push(
+            Label::secondary(file_id, end..end)
+                .with_message(format!("{} belongs here", expected_names)),
+        );
+    }
+
+    Diagnostic::error()
+        .with_message(message)
+        .with_labels(labels)
+        .with_notes(notes)
+}
+
+// Fixes for the mistakes that are easy to make
+fn suggestion(expected: &[Expect], found: &Option<Found>) -> Option<String> {
+    let expects = |token: Token<'static>| expected.contains(&Expect::Token(token));
+    let close = |token: Token<'static>, open: &str| {
+        format!("help: every `{}` needs a matching `{}`", open, token)
+    };
+
+    if expects(Token::Semicolon) {
+        return Some("help: statements end with a `;`".to_owned());
+    }
+    if expects(Token::Comma) {
+        return Some("help: items of a list are separated with `,`".to_owned());
+    }
+    if expects(CLOSE_BRACKET) {
+        return Some(close(CLOSE_BRACKET, "{"));
+    }
+    if expects(CLOSE_PARENTHESIS) {
+        return Some(close(CLOSE_PARENTHESIS, "("));
+    }
+    if expects(CLOSE_SQUARE) {
+        return Some(close(CLOSE_SQUARE, "["));
+    }
+
+    let found = found.as_ref()?;
+    let is_word = found.text.chars().all(|c| c.is_ascii_alphabetic());
+    if expected.contains(&Expect::Identifier) && is_word {
+        // It would have been an identifier otherwise
+        return Some(format!(
+            "help: `{}` is reserved by the language and can't be used as a name",
+            found.text
+        ));
+    }
+    None
+}
@@ -1,6 +1,7 @@
 use crate::parse::Span;
 
 pub mod error;
+pub(crate) mod spelling;
 
 #[cfg(test)]
 pub(crate) mod test {
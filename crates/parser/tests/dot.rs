@@ -0,0 +1,33 @@
+use parser::dot;
+
+#[test]
+fn renders_nodes_and_labelled_edges() {
+    let graph = dot("let a = 1 + b;", false).expect("Program should parse");
+
+    assert!(graph.starts_with("digraph ast {\n"));
+    assert!(graph.contains("n1 [label=\"let a\"];"));
+    assert!(graph.contains("n2 [label=\"+\"];"));
+    assert!(graph.contains("n2 -> n3 [label=\"lhs\"];"));
+    assert!(graph.contains("n2 -> n4 [label=\"rhs\"];"));
+    // References are only drawn when scopes are requested
+    assert!(!graph.contains("dashed"));
+}
+
+#[test]
+fn links_identifiers_to_their_declarations() {
+    let graph = dot(
+        "let g = 1; fn outer() { let a = g; fn inner() { a } inner }",
+        true,
+    )
+    .expect("Program should parse");
+
+    // g is global, so reading it from a function isn't a capture
+    assert!(graph.contains("n6 -> n1 [style=dashed, constraint=false, color=blue];"));
+    assert!(graph.contains("color=red, label=\"captures\""));
+}
+
+#[test]
+fn escapes_quotes_in_labels() {
+    let graph = dot(r#"let s = "a\"b";"#, false).expect("Program should parse");
+    assert!(graph.contains(r#"[label="\"a\\\"b\""]"#));
+}
@@ -0,0 +1,135 @@
+use parser::{
+    parse,
+    parse::{expr::ExprKind, stmt::StmtKind},
+    utils::error::{Expect, ParseErrorCause},
+    Token,
+};
+
+// Expressions are declared as `let value = ...;`, so blocks and maps aren't taken for statements
+fn expr(code: &str) -> String {
+    let ast = parse(&format!("let value = {};", code)).expect("Program should parse");
+    match &*ast[0].kind {
+        StmtKind::VariableDeclaration { expr, .. } => expr.to_string(),
+        kind => panic!("Expected a declaration, got {:?}", kind),
+    }
+}
+
+fn error(code: &str) -> ParseErrorCause {
+    let errors = parse(&format!("let value = {};", code)).expect_err("Program shouldn't parse");
+    errors[0].cause.clone()
+}
+
+#[test]
+fn parses_match_expressions() {
+    assert_eq!(expr("match x { }"), "match x { }");
+    assert_eq!(
+        expr("match x + 1 { 1 => a, 2 => { b }, _ => c }"),
+        "match (+ x 1) { 1 => a, 2 => { b }, _ => c, }"
+    );
+    // The last arm may be followed by a comma
+    assert_eq!(expr("match x { _ => 0, }"), "match x { _ => 0, }");
+    assert_eq!(
+        expr("match x { 1 => { a } _ => 0 }"),
+        "match x { 1 => { a }, _ => 0, }"
+    );
+
+    assert_eq!(
+        error("match x { 1 => 2 3 => 4 }"),
+        ParseErrorCause::Expected(Expect::Token(Token::Comma))
+    );
+    assert_eq!(
+        error("match x { 1 2 }"),
+        ParseErrorCause::Expected(Expect::Token(Token::Arrow))
+    );
+}
+
+#[test]
+fn parses_bitwise_expressions() {
+    assert_eq!(expr("1 & 2"), "(& 1 2)");
+    assert_eq!(expr("1 | 2"), "(| 1 2)");
+    assert_eq!(expr("1 ^ 2"), "(^ 1 2)");
+    assert_eq!(expr("~1"), "(~ 1)");
+    // and precedes xor, which precedes or
+    assert_eq!(expr("1 | 2 ^ 3 & 4"), "(| 1 (^ 2 (& 3 4)))");
+    assert_eq!(expr("1 & 2 ^ 3 | 4"), "(| (^ (& 1 2) 3) 4)");
+    // arithmetic precedes bitwise operators, which precede comparison
+    assert_eq!(expr("1 & 2 + 3"), "(& 1 (+ 2 3))");
+    assert_eq!(expr("1 | 2 == 3"), "(== (| 1 2) 3)");
+    // shifts bind tighter than the rest of bitwise operators, but looser than arithmetic
+    assert_eq!(expr("1 << 2"), "(<< 1 2)");
+    assert_eq!(expr("1 >> 2"), "(>> 1 2)");
+    assert_eq!(expr("1 & 2 << 3"), "(& 1 (<< 2 3))");
+    assert_eq!(expr("1 << 2 + 3"), "(<< 1 (+ 2 3))");
+    assert_eq!(expr("1 << 2 >> 3"), "(>> (<< 1 2) 3)");
+    // a bar in front of an expression opens a closure
+    assert_eq!(expr("a | || => 1"), "(| a |0| => 1)");
+}
+
+#[test]
+fn parses_map_literals() {
+    assert_eq!(
+        expr(r#"{ "key": 1, other: 2 + 2 }"#),
+        r#"{"key": 1, "other": (+ 2 2)}"#
+    );
+    assert_eq!(expr(r#"{ a: 1, }"#), r#"{"a": 1}"#);
+    assert_eq!(
+        error("{ a: 1, 2: 3 }"),
+        ParseErrorCause::Expected(Expect::Identifier)
+    );
+}
+
+#[test]
+fn parses_loop_expressions() {
+    assert_eq!(expr("loop { }"), "loop {  }");
+    assert_eq!(expr("loop { break 5 }"), "loop { break 5 }");
+}
+
+#[test]
+fn parses_slice_expression() {
+    assert_eq!(expr("foo[1..3]"), "foo[1..3]");
+    assert_eq!(expr("foo[..i + 1]"), "foo[..(+ i 1)]");
+    assert_eq!(expr("foo[2..]"), "foo[2..]");
+    assert_eq!(expr("foo[..=2]"), "foo[..=2]");
+    assert_eq!(expr("foo[..]"), "foo[..]");
+}
+
+#[test]
+fn parses_chained_assignment_from_the_right() {
+    assert_eq!(expr("a = b = c = 1"), "a = b = c = 1");
+
+    let ast = parse("a = b[0] = 1;").expect("Program should parse");
+    match &*ast[0].kind {
+        StmtKind::Expression { expr } => match &*expr.kind {
+            ExprKind::Assignment { value, .. } => {
+                assert!(matches!(&*value.kind, ExprKind::Assignment { .. }))
+            }
+            kind => panic!("Expected an assignment, got {:?}", kind),
+        },
+        kind => panic!("Expected an expression, got {:?}", kind),
+    }
+
+    assert_eq!(
+        error("a = b + 1 = 2"),
+        ParseErrorCause::InvalidAssignmentTarget
+    );
+}
+
+#[test]
+fn rejects_chained_comparisons() {
+    // The spans count the `let value = ` in front of the expression
+    assert_eq!(
+        error("1 < 2 <= 3"),
+        ParseErrorCause::ChainedComparison {
+            first: 14..15,
+            second: 18..20,
+        }
+    );
+    assert_eq!(
+        error("a == b + 1 != c"),
+        ParseErrorCause::ChainedComparison {
+            first: 14..16,
+            second: 23..25,
+        }
+    );
+    assert_eq!(expr("1 < 2 and 2 < 3"), "(and (< 1 2) (< 2 3))");
+}
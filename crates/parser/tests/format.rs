@@ -0,0 +1,231 @@
+use parser::{format, parse};
+
+fn assert_formatted(code: &str, expected: &str) {
+    let formatted = format(code).expect("Program should parse");
+    assert_eq!(formatted, expected);
+    // Formatting is stable and doesn't change the meaning of the program
+    assert_eq!(format(&formatted).unwrap(), formatted);
+    assert!(parse(code).unwrap() == parse(&formatted).unwrap());
+}
+
+#[test]
+fn normalizes_spacing_and_indentation() {
+    assert_formatted(
+        "let a=1+2*3;\nfn double(x) { x*2 }",
+        "let a = 1 + 2 * 3;\nfn double(x) {\n    x * 2\n}\n",
+    );
+    assert_formatted(
+        "if a {b;} else if c {d;} else {e};",
+        "if a {\n    b;\n} else if c {\n    d;\n} else {\n    e\n};\n",
+    );
+}
+
+#[test]
+fn keeps_only_necessary_parentheses() {
+    assert_formatted("let a = ((1 + 2)) * 3;", "let a = (1 + 2) * 3;\n");
+    assert_formatted("let a = (1 * 2) + 3;", "let a = 1 * 2 + 3;\n");
+    assert_formatted("let a = 1 - (2 - 3);", "let a = 1 - (2 - 3);\n");
+    // Prefix operators take everything that binds stronger than them
+    assert_formatted("let a = (-b) + c;", "let a = (-b) + c;\n");
+    assert_formatted("let a = -(b + c);", "let a = -b + c;\n");
+    assert_formatted("let a = -(1 + 2);", "let a = -(1 + 2);\n");
+    assert_formatted("let a = !(b and c);", "let a = !(b and c);\n");
+    // Exponents associate to the right
+    assert_formatted("let a = 2 ** (3 ** 2);", "let a = 2 ** 3 ** 2;\n");
+    assert_formatted("let a = (2 ** 3) ** 2;", "let a = (2 ** 3) ** 2;\n");
+    // Comparisons can't be chained
+    assert_formatted("let a = (b < c) == d;", "let a = (b < c) == d;\n");
+}
+
+#[test]
+fn preserves_comments() {
+    assert_formatted(
+        "// header\nlet a = 1;   // trailing\n\n\n// before b\nlet b = 2;\n// end",
+        "// header\nlet a = 1; // trailing\n\n// before b\nlet b = 2;\n// end\n",
+    );
+    assert_formatted(
+        "fn foo() {\n// inside\nlet a = \"// not a comment\";\n// dangling\n}",
+        "fn foo() {\n    // inside\n    let a = \"// not a comment\";\n    // dangling\n}\n",
+    );
+}
+
+#[test]
+fn breaks_long_lists() {
+    assert_formatted(
+        "call_with_many_arguments(first_argument, second_argument, third_argument, fourth);",
+        "call_with_many_arguments(\n    first_argument,\n    second_argument,\n    third_argument,\n    fourth,\n);\n",
+    );
+    assert_formatted(
+        "let point = new { x: 1, y };",
+        "let point = new { x: 1, y };\n",
+    );
+}
+
+#[test]
+fn formats_for_loops() {
+    assert_formatted(
+        "for(let i=0;i<3;i=i+1){ print(i); };\nfor(;;) { break; };",
+        "for (let i = 0; i < 3; i = i + 1) {\n    print(i);\n};\nfor (;;) {\n    break;\n};\n",
+    );
+}
+
+#[test]
+fn formats_slices() {
+    assert_formatted(
+        "let a = b[ 1 .. ];\nlet c = b[..=n+1];",
+        "let a = b[1..];\nlet c = b[..=n + 1];\n",
+    );
+}
+
+#[test]
+fn formats_loops() {
+    assert_formatted(
+        "let x = loop{ break 1; };",
+        "let x = loop {\n    break 1;\n};\n",
+    );
+}
+
+#[test]
+fn formats_for_in_loops() {
+    assert_formatted(
+        "for x in [1,2] { print(x); };",
+        "for x in [1, 2] {\n    print(x);\n};\n",
+    );
+}
+
+#[test]
+fn formats_ranges_without_spaces() {
+    assert_formatted(
+        "let a = 0 .. n+1;\nlet b = -1 ..= 1;",
+        "let a = 0..n + 1;\nlet b = -1..=1;\n",
+    );
+}
+
+#[test]
+fn formats_interpolated_strings() {
+    assert_formatted(
+        "let s = \"a ${ x+1 } b ${f(\"c\")}\";",
+        "let s = \"a ${x + 1} b ${f(\"c\")}\";\n",
+    );
+}
+
+#[test]
+fn keeps_escape_sequences_in_strings() {
+    assert_formatted(
+        r#"let s = "a\tb\n\"c\" \u{48} $d \${e} ${f}";"#,
+        "let s = \"a\\tb\\n\\\"c\\\" H $d \\${e} ${f}\";\n",
+    );
+}
+
+#[test]
+fn keeps_raw_strings_as_written() {
+    assert_formatted(
+        r##"let a=r"C:\dir\";let b = r#"say "hi""#;"##,
+        "let a = r\"C:\\dir\\\";\nlet b = r#\"say \"hi\"\"#;\n",
+    );
+}
+
+#[test]
+fn keeps_notation_of_number_literals() {
+    assert_formatted(
+        "let a=0xFF+0b10 * 0o7;\nlet b = 1_000 + 2.0;",
+        "let a = 0xFF + 0b10 * 0o7;\nlet b = 1_000 + 2;\n",
+    );
+}
+
+#[test]
+fn formats_bitwise_operators() {
+    assert_formatted("let a=~b&c|d^e;", "let a = ~b & c | d ^ e;\n");
+    assert_formatted("let a = (b | c) & d;", "let a = (b | c) & d;\n");
+    assert_formatted(
+        "let a = b<<c+1 >> (d&e);",
+        "let a = b << c + 1 >> (d & e);\n",
+    );
+    assert_formatted("let a = |x| => x|1;", "let a = |x| => x | 1;\n");
+}
+
+#[test]
+fn spells_out_arrows_of_closures() {
+    assert_formatted("let f = |a,b| a+b;", "let f = |a, b| => a + b;\n");
+    assert_formatted("let f = || {1};", "let f = || => {\n    1\n};\n");
+}
+
+#[test]
+fn formats_rest_parameters() {
+    assert_formatted(
+        "fn log(prefix,...args) { args }",
+        "fn log(prefix, ...args) {\n    args\n}\n",
+    );
+    assert_formatted("let f = |...xs| xs;", "let f = |...xs| => xs;\n");
+}
+
+#[test]
+fn formats_named_arguments() {
+    assert_formatted("draw(1,y:2,  z : 3);", "draw(1, y: 2, z: 3);\n");
+}
+
+#[test]
+fn formats_destructuring() {
+    assert_formatted("let {a,b}=c;", "let {a, b} = c;\n");
+    assert_formatted("let [ x , y ] = [1,2];", "let [x, y] = [1, 2];\n");
+}
+
+#[test]
+fn formats_match_arms_on_separate_lines() {
+    assert_formatted(
+        "let name = match n {1=>\"one\", 2 => { print(n); \"two\" } _ => \"many\"};",
+        "let name = match n {\n    1 => \"one\",\n    2 => {\n        print(n);\n        \"two\"\n    },\n    _ => \"many\",\n};\n",
+    );
+}
+
+#[test]
+fn formats_multiple_declarations_and_parallel_assignments() {
+    assert_formatted("let a,b=1,2;", "let a, b = 1, 2;\n");
+    assert_formatted("a ,b=b,a;", "a, b = b, a;\n");
+}
+
+#[test]
+fn formats_map_literals() {
+    assert_formatted(
+        r#"let m = {"key":1,other:2};"#,
+        "let m = { \"key\": 1, other: 2 };\n",
+    );
+}
+
+#[test]
+fn formats_spread_elements() {
+    assert_formatted("let a = [1,... b];", "let a = [1, ...b];\n");
+    assert_formatted("f(... args);", "f(...args);\n");
+}
+
+#[test]
+fn accepts_trailing_commas() {
+    assert_formatted(
+        "fn f(a, b,) { [a, b,] } let {x,} = new { x: f(1, 2,), }; let m = { k: 1, };",
+        "fn f(a, b) {\n    [a, b]\n}\nlet {x} = new { x: f(1, 2) };\nlet m = { k: 1 };\n",
+    );
+}
+
+#[test]
+fn breaks_long_arrays_with_trailing_comma() {
+    assert_formatted(
+        "let numbers = [1000000000, 2000000000, 3000000000, 4000000000, 5000000000, 6000000000, 7000000000];",
+        "let numbers = [\n    1000000000,\n    2000000000,\n    3000000000,\n    4000000000,\n    5000000000,\n    6000000000,\n    7000000000,\n];\n",
+    );
+}
+
+#[test]
+fn formats_imports_and_exports() {
+    assert_formatted(
+        "import   r\"lib/math.gv\" ;export fn square(x){x*x}\nexport let one=1;",
+        "import r\"lib/math.gv\";\nexport fn square(x) {\n    x * x\n}\nexport let one = 1;\n",
+    );
+}
+
+#[test]
+fn formats_type_annotations() {
+    assert_formatted(
+        "let x:Number=5; fn add(a:Number,b)->Number{a+b}\nfn id(a : Any)->Any=>a",
+        "let x: Number = 5;\nfn add(a: Number, b) -> Number {\n    a + b\n}\nfn id(a: Any) -> Any => a\n",
+    );
+}
@@ -0,0 +1,72 @@
+use parser::{parse, parse::incremental::Edit, reparse};
+
+// Replaces the text in the range and checks that reparsing gives the same tree, spans included,
+// as parsing the new code from scratch
+fn assert_reparsed(code: &str, range: std::ops::Range<usize>, text: &str) {
+    let old_ast = parse(code).expect("Old code should parse");
+    let new_code = format!("{}{}{}", &code[..range.start], text, &code[range.end..]);
+    let edit = Edit {
+        range,
+        new_len: text.len(),
+    };
+
+    let reparsed = reparse(old_ast, &new_code, &edit);
+    let parsed = parse(&new_code);
+    assert_eq!(format!("{:?}", reparsed), format!("{:?}", parsed));
+}
+
+const CODE: &str = "let a = 1;\nfn f(x) { x + a }\n/// Doc\nlet b = [f(1), 2];\nlet c = b[0];\n";
+
+#[test]
+fn reparses_edits_inside_of_statements() {
+    // 1 => 100
+    assert_reparsed(CODE, 8..9, "100");
+    // x + a => x
+    assert_reparsed(CODE, 22..27, "");
+    assert_reparsed(CODE, 0..0, "  ");
+}
+
+#[test]
+fn reparses_added_and_removed_statements() {
+    assert_reparsed(CODE, 10..10, "\nlet z = new { k: 1 };");
+    assert_reparsed(CODE, 0..11, "");
+    assert_reparsed(
+        CODE,
+        CODE.len()..CODE.len(),
+        "export fn g(...xs) -> Number => xs",
+    );
+    assert_reparsed("", 0..0, "let a = 1;");
+}
+
+#[test]
+fn reparses_doc_comments_of_the_next_statement() {
+    // Doc => Docs
+    assert_reparsed(CODE, 33..36, "Docs");
+    assert_reparsed(CODE, 29..37, "");
+}
+
+#[test]
+fn reuses_statements_the_edit_didnt_touch() {
+    // The old tree is trusted, so the statements kept from it show where the parsing started
+    let old_ast = parse("let a = 2;\nlet b = 1;\nlet c = 1;\nlet d = 1;").unwrap();
+    let code = "let a = 1;\nlet b = 1;\nlet c = 10;\nlet d = 1;";
+    let edit = Edit {
+        range: 30..31,
+        new_len: 2,
+    };
+
+    let reparsed = reparse(old_ast, code, &edit).unwrap();
+    let texts: Vec<String> = reparsed.iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        texts,
+        vec!["let a = 2;", "let b = 1;", "let c = 10;", "let d = 1;"]
+    );
+    assert_eq!(reparsed[3].span, 34..44);
+}
+
+#[test]
+fn falls_back_to_the_whole_code() {
+    // An unclosed string swallows the statements after it
+    assert_reparsed(CODE, 8..9, "\"1;");
+    assert_reparsed(CODE, 8..9, "");
+}
@@ -0,0 +1,72 @@
+use parser::{lex, Operator, Token};
+
+fn tokens(code: &str) -> Vec<Token<'_>> {
+    lex(code).into_iter().map(|(token, _)| token).collect()
+}
+
+#[test]
+fn tokenizes_ranges() {
+    use Token::Number;
+    assert_eq!(
+        tokens("1..5"),
+        vec![Number(1.0), Token::Operator(Operator::Range), Number(5.0)]
+    );
+    assert_eq!(
+        tokens("0.5..=2"),
+        vec![
+            Number(0.5),
+            Token::Operator(Operator::RangeInclusive),
+            Number(2.0)
+        ]
+    );
+}
+
+#[test]
+fn tokenizes_raw_strings() {
+    use Token::RawString;
+    assert_eq!(tokens(r#"r"C:\dir\""#), vec![RawString(r"C:\dir\")]);
+    assert_eq!(tokens(r#"r"${a} \n""#), vec![RawString(r"${a} \n")]);
+    // Hashes allow quotes inside of the string
+    assert_eq!(
+        tokens(r###"r#"say "hi""#"###),
+        vec![RawString(r#"say "hi""#)]
+    );
+    assert_eq!(
+        tokens(r###"r##"a "# b"##"###),
+        vec![RawString(r##"a "# b"##)]
+    );
+    assert_eq!(tokens("r"), vec![Token::Identifier("r")]);
+    assert_eq!(tokens(r##"r#"never closed""##)[0], Token::Error);
+
+    assert_eq!(lex(r##"a r#"b"# c"##)[1], (RawString("b"), 2..8));
+}
+
+#[test]
+fn tokenizes_radix_numbers() {
+    use Token::RadixNumber;
+    assert_eq!(
+        tokens("0x1F 0b1010 -0o755"),
+        vec![
+            RadixNumber("0x1F"),
+            RadixNumber("0b1010"),
+            RadixNumber("-0o755")
+        ]
+    );
+    // Invalid digits are left for the parser to report
+    assert_eq!(
+        tokens("0b123 0x"),
+        vec![RadixNumber("0b123"), RadixNumber("0x")]
+    );
+}
+
+#[test]
+fn strips_digit_separators() {
+    use Token::Number;
+    assert_eq!(tokens("1_000_000"), vec![Number(1_000_000.0)]);
+    assert_eq!(tokens("-1_0.2_5"), vec![Number(-10.25)]);
+    assert_eq!(tokens("0xFF_FF"), vec![Token::RadixNumber("0xFF_FF")]);
+    assert_eq!(
+        tokens("1_0..2_0"),
+        vec![Number(10.0), Token::Operator(Operator::Range), Number(20.0)]
+    );
+}
@@ -0,0 +1,71 @@
+use parser::{
+    parse,
+    parse::{
+        expr::{atom::AtomicValue, ExprKind},
+        stmt::StmtKind,
+    },
+    utils::error::ParseErrorCause,
+};
+
+fn number(code: &str) -> f64 {
+    let ast = parse(code).expect("Program should parse");
+    match &*ast[0].kind {
+        StmtKind::VariableDeclaration { expr, .. } => match &*expr.kind {
+            ExprKind::Atom(AtomicValue::Number(number)) => *number,
+            kind => panic!("Expected a number, got {:?}", kind),
+        },
+        kind => panic!("Expected a declaration, got {:?}", kind),
+    }
+}
+
+fn error(code: &str) -> ParseErrorCause {
+    let errors = parse(code).expect_err("Program shouldn't parse");
+    errors[0].cause.clone()
+}
+
+#[test]
+fn parses_hex_binary_and_octal_literals() {
+    assert_eq!(number("let n = 0x1F;"), 31.0);
+    assert_eq!(number("let n = 0xff;"), 255.0);
+    assert_eq!(number("let n = 0b1010;"), 10.0);
+    assert_eq!(number("let n = 0o755;"), 493.0);
+    assert_eq!(number("let n = -0x10;"), -16.0);
+}
+
+#[test]
+fn ignores_digit_separators() {
+    assert_eq!(number("let n = 1_000_000;"), 1_000_000.0);
+    assert_eq!(number("let n = 3.141_592;"), 3.141_592);
+    assert_eq!(number("let n = 0xFF_FF;"), 65535.0);
+    assert_eq!(number("let n = 0b_1010_1010;"), 170.0);
+    assert_eq!(
+        error("let n = 0x__;"),
+        ParseErrorCause::MissingDigits(8..12)
+    );
+}
+
+#[test]
+fn reports_malformed_literals() {
+    assert_eq!(error("let n = 0x;"), ParseErrorCause::MissingDigits(8..10));
+    assert_eq!(
+        error("let n = 0b102;"),
+        ParseErrorCause::InvalidDigit {
+            span: 12..13,
+            radix: 2
+        }
+    );
+    assert_eq!(
+        error("let n = 0o78;"),
+        ParseErrorCause::InvalidDigit {
+            span: 11..12,
+            radix: 8
+        }
+    );
+    assert_eq!(
+        error("let n = 0xfg;"),
+        ParseErrorCause::InvalidDigit {
+            span: 11..12,
+            radix: 16
+        }
+    );
+}
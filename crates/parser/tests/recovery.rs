@@ -0,0 +1,126 @@
+use codespan_reporting::diagnostic::Diagnostic;
+use common::CompilerDiagnostic;
+use parser::{
+    parse,
+    utils::error::{Expect, ParseErrorCause},
+    Token,
+};
+
+fn causes(code: &str) -> Vec<ParseErrorCause> {
+    parse(code)
+        .expect_err("Program shouldn't parse")
+        .into_iter()
+        .map(|error| error.cause)
+        .collect()
+}
+
+#[test]
+fn reports_every_broken_statement() {
+    assert_eq!(
+        causes("let = 1; let b = 2; let c = ;"),
+        vec![
+            ParseErrorCause::Expected(Expect::Identifier),
+            ParseErrorCause::Expected(Expect::Expression),
+        ]
+    );
+}
+
+#[test]
+fn keeps_parsing_blocks_after_errors() {
+    // Errors inside of function bodies don't end the function
+    let code = "fn f() { let = 1; let x = 1 let y = 2; } fn g() { 1 2 }";
+    assert_eq!(causes(code).len(), 3);
+    assert_eq!(
+        causes("fn f() { if x { let = 1; }; let = 2; }"),
+        vec![ParseErrorCause::Expected(Expect::Identifier); 2]
+    );
+}
+
+#[test]
+fn skips_stray_closing_braces() {
+    assert_eq!(causes("} let a = 1; }").len(), 2);
+    assert!(parse("fn f() { let a = 1; }").is_ok());
+}
+
+fn diagnostic(code: &str) -> Diagnostic<usize> {
+    parse(code).expect_err("Program shouldn't parse")[0].report(0)
+}
+
+#[test]
+fn names_the_expected_and_found_tokens() {
+    let missing_semicolon = diagnostic("let x = 1 let y = 2;");
+    assert_eq!(missing_semicolon.message, "Expected `;`, found `let`");
+    assert_eq!(missing_semicolon.labels[0].range, 10..13);
+    // The semicolon belongs right after the previous statement
+    assert_eq!(missing_semicolon.labels[1].range, 9..9);
+
+    let missing_comma = diagnostic("fn f(a b) { a }");
+    assert_eq!(missing_comma.message, "Expected `,` or `)`, found `b`");
+
+    let unclosed = diagnostic("fn f() { let a = 1;");
+    assert_eq!(unclosed.message, "Expected `}`, found the end of the file");
+    assert_eq!(
+        unclosed.notes,
+        vec!["help: every `{` needs a matching `}`".to_owned()]
+    );
+}
+
+#[test]
+fn explains_keywords_used_as_names() {
+    let keyword = diagnostic("let while = 2;");
+    assert_eq!(
+        keyword.notes,
+        vec!["help: `while` is reserved by the language and can't be used as a name".to_owned()]
+    );
+}
+
+#[test]
+fn suggests_keywords_and_declared_names_for_misspelled_words() {
+    let unknown = |word: &str, suggestion: &str| ParseErrorCause::UnknownWord {
+        word: word.to_owned(),
+        suggestion: suggestion.to_owned(),
+    };
+    let code = "let counter = 1; fn f() { retrun 5; } wile counter < 3 { }; countr 1;";
+    assert_eq!(
+        causes(code),
+        vec![
+            unknown("retrun", "return"),
+            unknown("wile", "while"),
+            unknown("countr", "counter"),
+        ]
+    );
+
+    let errors = parse("wile x { }").unwrap_err();
+    let diagnostic: Diagnostic<usize> = errors[0].report(0);
+    assert_eq!(diagnostic.message, "Unknown word `wile`");
+    assert_eq!(diagnostic.labels[0].range, 0..4);
+    assert_eq!(diagnostic.labels[0].message, "did you mean `while`?");
+}
+
+#[test]
+fn doesnt_suggest_words_too_far_from_anything() {
+    // Declared names and short words only get the regular error
+    assert_eq!(
+        causes("let counter = 1; counter 1; x 1; banana 1;"),
+        vec![ParseErrorCause::Expected(Expect::Token(Token::Semicolon)); 3]
+    );
+}
+
+#[test]
+fn rejects_chained_comparisons() {
+    let code = "let ok = (1 < 2) == true; let a = 1 < 2 < 3;";
+    let errors = parse(code).unwrap_err();
+    assert_eq!(
+        errors.iter().map(|error| &error.cause).collect::<Vec<_>>(),
+        vec![&ParseErrorCause::ChainedComparison {
+            first: 36..37,
+            second: 40..41
+        }]
+    );
+
+    let diagnostic: Diagnostic<usize> = errors[0].report(0);
+    assert_eq!(diagnostic.message, "Comparisons can't be chained");
+    assert_eq!(diagnostic.labels[0].range, 40..41);
+    assert_eq!(diagnostic.labels[1].range, 36..37);
+    assert!(diagnostic.notes[0].contains("`and`"));
+}
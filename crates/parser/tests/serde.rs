@@ -0,0 +1,47 @@
+#![cfg(feature = "serde")]
+
+use parser::{lex, parse, parse::stmt::Stmt, Operator, Token};
+
+#[test]
+fn serializes_the_syntax_tree() {
+    let program = parse("let x = 1 + y;").unwrap();
+    let json = serde_json::to_string(&program).unwrap();
+
+    assert_eq!(
+        json,
+        concat!(
+            r#"[{"kind":{"VariableDeclaration":{"name":"x","annotation":null,"expr":{"kind":"#,
+            r#"{"Binary":{"lhs":{"kind":{"Atom":{"Number":1.0}},"span":{"start":8,"end":9}},"#,
+            r#""op":{"kind":"Addition","span":{"start":10,"end":11}},"#,
+            r#""rhs":{"kind":{"Atom":{"Identifier":{"name":"y","is_assignment":false}}},"#,
+            r#""span":{"start":12,"end":13}}}},"span":{"start":8,"end":13}},"doc":null}},"span":{"start":0,"end":14}}]"#
+        )
+    );
+}
+
+#[test]
+fn syntax_tree_survives_a_round_trip() {
+    let code = "fn f(a, ...b) { match a { 1 => b[1..], _ => loop { break [a, ...b] } } } export let g = f(1, x: 2);";
+    let program = parse(code).unwrap();
+    let json = serde_json::to_string(&program).unwrap();
+    let deserialized: Vec<Stmt> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized, program);
+    let spans = |program: &[Stmt]| {
+        program
+            .iter()
+            .map(|stmt| stmt.span.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(spans(&deserialized), spans(&program));
+}
+
+#[test]
+fn serializes_tokens() {
+    let tokens = lex("x + \"a\"");
+    let json = serde_json::to_string(&tokens).unwrap();
+    let deserialized: Vec<(Token, std::ops::Range<usize>)> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized, tokens);
+    assert_eq!(deserialized[1].0, Token::Operator(Operator::Plus));
+}
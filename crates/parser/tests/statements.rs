@@ -0,0 +1,124 @@
+use parser::{
+    parse,
+    parse::stmt::StmtKind,
+    utils::error::{Expect, ParseErrorCause},
+    Operator, Token,
+};
+
+fn stmt(code: &str) -> String {
+    let ast = parse(code).expect("Program should parse");
+    ast[0].to_string()
+}
+
+fn error(code: &str) -> ParseErrorCause {
+    let errors = parse(code).expect_err("Program shouldn't parse");
+    errors[0].cause.clone()
+}
+
+#[test]
+fn parses_rest_parameter() {
+    let ast = parse("fn f(a, ...b) => a").expect("Program should parse");
+    let params = match &*ast[0].kind {
+        StmtKind::FunctionDeclaration { params, .. } => params,
+        kind => panic!("Expected a function, got {:?}", kind),
+    };
+    assert_eq!(params.span, 4..13);
+    assert_eq!(params.kind.params.len(), 1);
+    assert_eq!(params.kind.params[0].kind, "a");
+    let rest = params
+        .kind
+        .rest
+        .as_ref()
+        .expect("Function should take the rest");
+    assert_eq!(rest.kind, "b");
+    assert_eq!(rest.span, 11..12);
+
+    // Nothing can follow the rest parameter
+    assert_eq!(
+        error("fn f(...a, b) => a"),
+        ParseErrorCause::Expected(Expect::Token(Token::Operator(Operator::RoundBracketClose)))
+    );
+}
+
+#[test]
+fn parses_destructuring_declaration() {
+    assert_eq!(stmt("let {a, b} = foo;"), "let {a, b} = foo;");
+    assert_eq!(stmt("let [x] = [1, 2];"), "let [x] = [1,2];");
+    // The names may be followed by a comma, like in other lists
+    assert_eq!(stmt("let [x,] = foo;"), "let [x] = foo;");
+
+    assert_eq!(
+        error("let {} = foo;"),
+        ParseErrorCause::Expected(Expect::Identifier)
+    );
+}
+
+#[test]
+fn parses_multiple_declaration() {
+    assert_eq!(stmt("let a, b = 1, 2 + 2;"), "let a, b = 1, (+ 2 2);");
+
+    assert_eq!(
+        error("let a, b = 1;"),
+        ParseErrorCause::ValueCountMismatch {
+            expected: 2,
+            found: 1,
+        }
+    );
+}
+
+#[test]
+fn parses_parallel_assignment() {
+    assert_eq!(stmt("a, b = b, a;"), "a, b = b, a;");
+    assert_eq!(stmt("a[0], a.b = 1, 2;"), "a[0], a.b = 1, 2;");
+
+    assert_eq!(
+        error("a, 2 = 1, 2;"),
+        ParseErrorCause::InvalidAssignmentTarget
+    );
+    assert_eq!(
+        error("a, b = 1, 2, 3;"),
+        ParseErrorCause::ValueCountMismatch {
+            expected: 2,
+            found: 3,
+        }
+    );
+}
+
+#[test]
+fn parses_imports_and_exports() {
+    assert_eq!(stmt(r#"import "lib/math.gv";"#), r#"import "lib/math.gv";"#);
+    assert_eq!(stmt(r#"import r"a.gv";"#), r#"import "a.gv";"#);
+    assert_eq!(stmt("export let a = 1;"), "export let a = 1;");
+
+    assert_eq!(error("export 1;"), ParseErrorCause::ExportNeedsDeclaration);
+}
+
+#[test]
+fn imports_and_exports_must_be_at_top_level() {
+    assert_eq!(
+        error(r#"fn f() { import "a.gv"; }"#),
+        ParseErrorCause::NotAtTopLevel
+    );
+    assert_eq!(
+        error("{ export let a = 1; }"),
+        ParseErrorCause::NotAtTopLevel
+    );
+}
+
+#[test]
+fn parses_type_annotations() {
+    assert_eq!(stmt("let foo: Number = 10;"), "let foo: Number = 10;");
+    assert_eq!(
+        stmt("fn add(a: Number, b) -> Number => a"),
+        "fn add(args) -> Number a"
+    );
+
+    assert_eq!(
+        error("let a: Number, b = 1, 2;"),
+        ParseErrorCause::Expected(Expect::Token(Token::Operator(Operator::Assign)))
+    );
+    assert_eq!(
+        error("let a: 1 = 1;"),
+        ParseErrorCause::Expected(Expect::Identifier)
+    );
+}
@@ -0,0 +1,68 @@
+use parser::{
+    parse,
+    parse::{
+        expr::{atom::AtomicValue, ExprKind},
+        stmt::StmtKind,
+    },
+    tokens,
+    utils::error::ParseErrorCause,
+};
+
+fn text(code: &str) -> String {
+    let ast = parse(code).expect("Program should parse");
+    match &*ast[0].kind {
+        StmtKind::VariableDeclaration { expr, .. } => match &*expr.kind {
+            ExprKind::Atom(AtomicValue::Text(text)) => text.clone(),
+            kind => panic!("Expected a string, got {:?}", kind),
+        },
+        kind => panic!("Expected a declaration, got {:?}", kind),
+    }
+}
+
+fn error(code: &str) -> ParseErrorCause {
+    let errors = parse(code).expect_err("Program shouldn't parse");
+    errors[0].cause.clone()
+}
+
+#[test]
+fn replaces_escape_sequences() {
+    assert_eq!(text(r#"let s = "a\nb\tc";"#), "a\nb\tc");
+    assert_eq!(
+        text(r#"let s = "\"quoted\" \\ \${x}";"#),
+        "\"quoted\" \\ ${x}"
+    );
+    assert_eq!(text(r#"let s = "\u{48}\u{1F600}";"#), "H\u{1F600}");
+}
+
+#[test]
+fn points_at_invalid_escapes() {
+    assert_eq!(
+        error(r#"let s = "a\qb";"#),
+        ParseErrorCause::InvalidEscape(10..12)
+    );
+    assert_eq!(
+        error(r#"let s = "\u{110000}";"#),
+        ParseErrorCause::InvalidUnicodeEscape(9..19)
+    );
+    assert_eq!(
+        error(r#"let s = "\u12";"#),
+        ParseErrorCause::InvalidUnicodeEscape(9..11)
+    );
+    // Text after an interpolation keeps its position in the source
+    assert_eq!(
+        error(r#"let s = "${x} \é";"#),
+        ParseErrorCause::InvalidEscape(14..17)
+    );
+}
+
+#[test]
+fn keeps_raw_strings_verbatim() {
+    assert_eq!(text(r#"let s = r"C:\dir\";"#), r"C:\dir\");
+    assert_eq!(text(r##"let s = r#"\d+ "${x}""#;"##), r#"\d+ "${x}""#);
+
+    let spans: Vec<_> = tokens(r##"let s = r#"a"#;"##)
+        .into_iter()
+        .map(|(_, span)| span)
+        .collect();
+    assert_eq!(spans, vec![0..3, 4..5, 6..7, 8..14, 14..15]);
+}
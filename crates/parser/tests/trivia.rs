@@ -0,0 +1,86 @@
+use parser::{
+    parse,
+    parse::{
+        stmt::{Stmt, StmtKind},
+        visit::{walk_program, walk_stmt, Visit},
+    },
+    trivia::{comments, doc_comment},
+};
+
+fn docs(code: &str) -> Vec<Option<String>> {
+    let comments = comments(code);
+    parse(code)
+        .expect("Program should parse")
+        .iter()
+        .map(|stmt| doc_comment(code, &comments, stmt.span.start))
+        .collect()
+}
+
+#[test]
+fn collects_comments_outside_of_strings() {
+    let code = "let a = \"// not a comment\"; // trailing\n/// doc\nfn f() => 1";
+    let texts: Vec<String> = comments(code).into_iter().map(|c| c.text).collect();
+    assert_eq!(texts, vec!["// trailing", "/// doc"]);
+}
+
+#[test]
+fn skips_raw_strings_ending_with_a_backslash() {
+    let code = "let a = r\"C:\\\"; // trailing\nlet b = r#\"// \"\"#;";
+    let texts: Vec<String> = comments(code).into_iter().map(|c| c.text).collect();
+    assert_eq!(texts, vec!["// trailing"]);
+}
+
+#[test]
+fn attaches_doc_comments_to_the_next_item() {
+    let code = "/// Adds numbers\n///\n/// Second paragraph\nfn add(a, b) { a + b }\n\
+                // regular comment\nfn plain() => 1\n\
+                let x = 1; /// belongs to x\n//// banner\nlet y = 2;";
+    assert_eq!(
+        docs(code),
+        vec![
+            Some("Adds numbers\n\nSecond paragraph".to_owned()),
+            None,
+            None,
+            None
+        ]
+    );
+}
+
+// Docs of every declaration in the program, nested ones included
+#[derive(Default)]
+struct Docs(Vec<(String, Option<String>)>);
+
+impl Visit for Docs {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &*stmt.kind {
+            StmtKind::VariableDeclaration { name, doc, .. }
+            | StmtKind::FunctionDeclaration { name, doc, .. } => {
+                self.0.push((name.to_string(), doc.clone()))
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+fn ast_docs(code: &str) -> Vec<(String, Option<String>)> {
+    let mut docs = Docs::default();
+    walk_program(&mut docs, &parse(code).expect("Program should parse"));
+    docs.0
+}
+
+#[test]
+fn keeps_doc_comments_in_the_declarations() {
+    let code = "/// Outer\nfn outer() {\n    /// Inner\n    let inner = 1;\n    let plain = \"${ 1 }\";\n}\n\
+                /// Exported\nexport fn f() => 1\n\
+                let a, b = 1, 2;";
+    assert_eq!(
+        ast_docs(code),
+        vec![
+            ("outer".to_owned(), Some("Outer".to_owned())),
+            ("inner".to_owned(), Some("Inner".to_owned())),
+            ("plain".to_owned(), None),
+            ("f".to_owned(), Some("Exported".to_owned())),
+        ]
+    );
+}
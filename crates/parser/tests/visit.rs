@@ -0,0 +1,71 @@
+use parser::{
+    parse,
+    parse::{
+        expr::{atom::AtomicValue, Expr, ExprKind},
+        visit::{fold_expr, fold_program, walk_expr, walk_program, Fold, Visit},
+    },
+};
+
+#[derive(Default)]
+struct Identifiers(Vec<String>);
+
+impl Visit for Identifiers {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Atom(AtomicValue::Identifier { name, .. }) = &*expr.kind {
+            self.0.push(name.to_string());
+        }
+        walk_expr(self, expr);
+    }
+}
+
+fn identifiers(code: &str) -> Vec<String> {
+    let program = parse(code).expect("Program should parse");
+    let mut visitor = Identifiers::default();
+    walk_program(&mut visitor, &program);
+    visitor.0
+}
+
+#[test]
+fn visits_nested_expressions_in_order() {
+    let code = "fn f(x) { if a { b[c..d] } else { loop { break e } } } export let g = [h, ...i];";
+    assert_eq!(identifiers(code), vec!["a", "b", "c", "d", "e", "h", "i"]);
+}
+
+#[test]
+fn visits_match_arms_and_named_arguments() {
+    assert_eq!(
+        identifiers("match a { b => c(d, e: f), _ => g };"),
+        vec!["a", "b", "c", "d", "f", "g"]
+    );
+}
+
+struct Rename;
+
+impl Fold for Rename {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let mut expr = fold_expr(self, expr);
+        if let ExprKind::Atom(AtomicValue::Identifier { name, .. }) = &mut *expr.kind {
+            *name = name.to_uppercase().into();
+        }
+        expr
+    }
+}
+
+#[test]
+fn folds_keep_the_shape_of_the_tree() {
+    let program =
+        parse("let x = { a + b(c) }; for (let i = j; k; l) { m };").expect("Program should parse");
+    let spans: Vec<_> = program.iter().map(|stmt| stmt.span.clone()).collect();
+    let folded = fold_program(&mut Rename, program);
+
+    assert_eq!(
+        folded
+            .iter()
+            .map(|stmt| stmt.span.clone())
+            .collect::<Vec<_>>(),
+        spans
+    );
+    let mut visitor = Identifiers::default();
+    walk_program(&mut visitor, &folded);
+    assert_eq!(visitor.0, vec!["A", "B", "C", "J", "K", "L", "M"]);
+}
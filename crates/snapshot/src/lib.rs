@@ -0,0 +1,218 @@
+// Golden-file tests of the whole pipeline. Every `.gv` fixture is compared with the `.snap` file
+// next to it, which holds the tokens, the AST, the bytecode and the result of the program.
+// Setting GRAVITAS_BLESS=1 writes the current output to the `.snap` files instead.
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use gravitas::{compile, Diagnostics, SourceDb, Vm};
+
+pub const BLESS_VAR: &str = "GRAVITAS_BLESS";
+// Unchanged lines shown around every change of a diff
+const CONTEXT: usize = 2;
+
+fn section(out: &mut String, title: &str, body: &str) {
+    out.push_str(&format!("--- {} ---\n", title));
+    out.push_str(body.trim_end());
+    out.push('\n');
+}
+
+fn render(diagnostics: &Diagnostics, name: &str, source: &str) -> String {
+    let mut sources = SourceDb::new();
+    sources.add(name, source);
+    diagnostics.render(&sources)
+}
+
+// Each stage only runs if the previous one succeeded, the errors of the failing one end the snapshot
+pub fn snapshot(name: &str, source: &str) -> String {
+    let mut out = String::new();
+
+    let tokens: Vec<String> = parser::tokens(source)
+        .into_iter()
+        .map(|(token, span)| format!("{} {:?} {:?}", token, span, &source[span.clone()]))
+        .collect();
+    section(&mut out, "tokens", &tokens.join("\n"));
+
+    let ast = match parser::parse(source) {
+        Ok(ast) => ast,
+        Err(_) => {
+            // The facade reports the same errors as diagnostics
+            let diagnostics = compile(source).expect_err("Parsing failed");
+            section(&mut out, "errors", &render(&diagnostics, name, source));
+            return out;
+        }
+    };
+    let stmts: Vec<String> = ast.iter().map(|stmt| stmt.to_string()).collect();
+    section(&mut out, "ast", &stmts.join("\n"));
+
+    let bytecode = match compile(source) {
+        Ok(bytecode) => bytecode,
+        Err(diagnostics) => {
+            section(&mut out, "errors", &render(&diagnostics, name, source));
+            return out;
+        }
+    };
+    let globals: Vec<String> = bytecode
+        .globals
+        .iter()
+        .map(|global| global.to_string())
+        .collect();
+    section(&mut out, "bytecode", &globals.join("\n"));
+
+    let output = match Vm::builder().build().execute(bytecode) {
+        Ok(value) => value.to_string(),
+        Err(error) => format!("runtime error: {:?}", error.cause),
+    };
+    section(&mut out, "output", &output);
+
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// Longest common subsequence of the lines, fixtures are small enough for the quadratic table
+fn changes<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Change<'a>> {
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push(Change::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(Change::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(new[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+// Changed lines prefixed with - and +, with a few unchanged lines around them
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    let changes = changes(&old, &new);
+
+    let near_change = |index: usize| {
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + CONTEXT + 1).min(changes.len());
+        changes[start..end]
+            .iter()
+            .any(|change| !matches!(change, Change::Same(_)))
+    };
+
+    let mut out = String::new();
+    let mut skipped = false;
+    for (index, change) in changes.iter().enumerate() {
+        if !near_change(index) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            out.push_str("...\n");
+            skipped = false;
+        }
+        let line = match change {
+            Change::Same(line) => format!("  {}", line),
+            Change::Removed(line) => format!("- {}", line),
+            Change::Added(line) => format!("+ {}", line),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+fn blessing() -> bool {
+    env::var(BLESS_VAR).is_ok_and(|value| value != "0")
+}
+
+pub fn snapshot_path(fixture: &Path) -> PathBuf {
+    fixture.with_extension("snap")
+}
+
+// Compares the fixture with its snapshot, the error describes the difference
+pub fn check_fixture(fixture: impl AsRef<Path>) -> Result<(), String> {
+    let fixture = fixture.as_ref();
+    let source = fs::read_to_string(fixture)
+        .map_err(|error| format!("Couldn't read {}: {}", fixture.display(), error))?;
+    let name = fixture
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let actual = snapshot(&name, &source);
+
+    let path = snapshot_path(fixture);
+    let expected = fs::read_to_string(&path).ok();
+    if expected.as_deref() == Some(actual.as_str()) {
+        return Ok(());
+    }
+
+    if blessing() {
+        return fs::write(&path, actual)
+            .map_err(|error| format!("Couldn't write {}: {}", path.display(), error));
+    }
+
+    let reason = if expected.is_some() {
+        "doesn't match"
+    } else {
+        "is missing"
+    };
+    Err(format!(
+        "Snapshot {} {}, run with {}=1 to update it\n{}",
+        path.display(),
+        reason,
+        BLESS_VAR,
+        diff(expected.as_deref().unwrap_or_default(), &actual)
+    ))
+}
+
+pub fn assert_fixture(fixture: impl AsRef<Path>) {
+    if let Err(message) = check_fixture(fixture) {
+        panic!("{}", message);
+    }
+}
+
+// Checks every `.gv` file of the directory and reports all the failing ones at once
+pub fn assert_fixtures(dir: impl AsRef<Path>) {
+    let dir = dir.as_ref();
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|error| panic!("Couldn't read {}: {}", dir.display(), error))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "gv"))
+        .collect();
+    fixtures.sort();
+
+    let failures: Vec<String> = fixtures
+        .iter()
+        .filter_map(|fixture| check_fixture(fixture).err())
+        .collect();
+
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} snapshots failed\n\n{}",
+            failures.len(),
+            fixtures.len(),
+            failures.join("\n")
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use snapshot::{assert_fixtures, diff, snapshot};
+
+#[test]
+fn fixtures_match_their_snapshots() {
+    assert_fixtures(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"));
+}
+
+#[test]
+fn stops_at_the_first_failing_stage() {
+    let out = snapshot("main.gv", "let a = ;");
+    assert!(out.contains("--- tokens ---"));
+    assert!(out.contains("--- errors ---"));
+    assert!(!out.contains("--- ast ---"));
+
+    let out = snapshot("main.gv", "1 + 2;");
+    assert!(out.contains("--- bytecode ---"));
+    assert!(out.ends_with("--- output ---\n3\n"));
+}
+
+#[test]
+fn diff_shows_changes_with_context() {
+    let expected = "a\nb\nc\nd\ne\nf\ng\n";
+    let actual = "a\nb\nc\nd\nE\nf\ng\n";
+    assert_eq!(
+        diff(expected, actual),
+        "...\n  c\n  d\n- e\n+ E\n  f\n  g\n"
+    );
+}
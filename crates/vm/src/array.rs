@@ -0,0 +1,120 @@
+use common::Number;
+
+use crate::{
+    gc::{HeapObject, HeapPointer},
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    MachineResult, OperationResult, VM,
+};
+
+impl VM {
+    pub(crate) fn op_create_array(&mut self, amount: usize) -> OperationResult {
+        let mut values = Vec::with_capacity(amount);
+        for _ in 0..amount {
+            values.push(self.pop_operand()?);
+        }
+        // Elements were pushed in order, so the last one is on the top of the stack
+        values.reverse();
+
+        let array_ptr = self.gc.allocate(HeapObject::Array(values));
+        self.push_operand(RuntimeValue::HeapPointer(array_ptr));
+        Ok(())
+    }
+
+    pub(crate) fn op_create_spread_array(&mut self, groups: usize) -> OperationResult {
+        let values = self.pop_groups(groups)?;
+        let array_ptr = self.gc.allocate(HeapObject::Array(values));
+        self.push_operand(RuntimeValue::HeapPointer(array_ptr));
+        Ok(())
+    }
+
+    pub(crate) fn op_spread(&mut self) -> OperationResult {
+        let values = match self.pop_operand()? {
+            RuntimeValue::HeapPointer(ptr) => match self.checked(self.gc.deref(ptr))? {
+                HeapObject::Array(values) => values.clone(),
+                _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+            },
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+
+        let count = values.len();
+        for value in values {
+            self.push_operand(value);
+        }
+        self.push_operand(RuntimeValue::Number(count as Number));
+        Ok(())
+    }
+
+    // Puts the arguments below the callee back on the stack without their group sizes
+    pub(crate) fn spread_args(&mut self, groups: usize) -> MachineResult<usize> {
+        let callee = self.pop_operand()?;
+        let args = self.pop_groups(groups)?;
+        let args_count = args.len();
+        for arg in args {
+            self.push_operand(arg);
+        }
+        self.push_operand(callee);
+        Ok(args_count)
+    }
+
+    // Every group of values is followed by its size, the last group is on the top of the stack
+    fn pop_groups(&mut self, groups: usize) -> MachineResult<Vec<RuntimeValue>> {
+        let mut values = vec![];
+        for _ in 0..groups {
+            let size = self.pop_operand()?;
+            let size = self.expect_number(size)? as usize;
+            for _ in 0..size {
+                values.push(self.pop_operand()?);
+            }
+        }
+        values.reverse();
+        Ok(values)
+    }
+
+    fn array_position(&mut self, array_ptr: HeapPointer, position: Number) -> MachineResult<usize> {
+        let len = match self.checked(self.gc.deref(array_ptr))? {
+            HeapObject::Array(values) => values.len(),
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+
+        if position.fract() != 0.0 {
+            return self.error(RuntimeErrorCause::MismatchedTypes);
+        }
+        if position < 0.0 || position as usize >= len {
+            return self.error(RuntimeErrorCause::IndexOutOfBounds);
+        }
+
+        Ok(position as usize)
+    }
+
+    pub(crate) fn array_index(
+        &mut self,
+        array_ptr: HeapPointer,
+        position: Number,
+    ) -> OperationResult {
+        let position = self.array_position(array_ptr, position)?;
+        let values = self.checked(self.gc.deref(array_ptr).and_then(HeapObject::as_array))?;
+        let value = values[position].clone();
+        self.push_operand(value);
+        Ok(())
+    }
+
+    pub(crate) fn array_set_index(
+        &mut self,
+        array_ptr: HeapPointer,
+        position: Number,
+        value: RuntimeValue,
+    ) -> OperationResult {
+        let position = self.array_position(array_ptr, position)?;
+        let values = self
+            .gc
+            .deref_mut(array_ptr)
+            .and_then(HeapObject::as_array_mut);
+        match values {
+            Ok(values) => values[position] = value.clone(),
+            Err(cause) => return self.error(cause),
+        }
+        self.push_operand(value);
+        Ok(())
+    }
+}
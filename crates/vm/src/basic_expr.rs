@@ -54,6 +54,25 @@ impl RuntimeValue {
         }
     }
 
+    // Shifts work on the integer parts of the numbers and take the amount modulo 64
+    pub(crate) fn shl(self, other: RuntimeValue, vm: &mut VM) -> MachineResult<RuntimeValue> {
+        match (self, other) {
+            (RuntimeValue::Number(a), RuntimeValue::Number(b)) => Ok(RuntimeValue::Number(
+                (a as i64).wrapping_shl(b as i64 as u32) as f64,
+            )),
+            _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+        }
+    }
+
+    pub(crate) fn shr(self, other: RuntimeValue, vm: &mut VM) -> MachineResult<RuntimeValue> {
+        match (self, other) {
+            (RuntimeValue::Number(a), RuntimeValue::Number(b)) => Ok(RuntimeValue::Number(
+                (a as i64).wrapping_shr(b as i64 as u32) as f64,
+            )),
+            _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+        }
+    }
+
     pub(crate) fn and(self, other: RuntimeValue, vm: &mut VM) -> MachineResult<RuntimeValue> {
         match (self, other) {
             (RuntimeValue::Bool(a), RuntimeValue::Bool(b)) => Ok(RuntimeValue::Bool(a && b)),
@@ -87,7 +106,7 @@ impl VM {
     // Start of stuff that doesn't belong to any particular group
 
     pub(crate) fn op_constant(&mut self, index: ConstantIndex) -> OperationResult {
-        let item = self.current_code().chunk.read(index);
+        let item = self.current_code()?.chunk.read(index);
         let value = RuntimeValue::from(item);
         self.push_operand(value);
         Ok(())
@@ -156,6 +175,20 @@ impl VM {
         Ok(())
     }
 
+    pub(crate) fn op_shl(&mut self) -> OperationResult {
+        let (a, b) = self.pop_two_operands()?;
+        let res = a.shl(b, self)?;
+        self.push_operand(res);
+        Ok(())
+    }
+
+    pub(crate) fn op_shr(&mut self) -> OperationResult {
+        let (a, b) = self.pop_two_operands()?;
+        let res = a.shr(b, self)?;
+        self.push_operand(res);
+        Ok(())
+    }
+
     pub(crate) fn op_and(&mut self) -> OperationResult {
         let (a, b) = self.pop_two_operands()?;
         let res = a.and(b, self)?;
@@ -171,6 +204,17 @@ impl VM {
     }
 
     // End of binary expressions
+
+    pub(crate) fn op_concat(&mut self, amount: usize) -> OperationResult {
+        let mut parts = Vec::with_capacity(amount);
+        for _ in 0..amount {
+            parts.push(self.pop_operand()?.to_string());
+        }
+        parts.reverse();
+
+        self.push_operand(RuntimeValue::String(parts.concat()));
+        Ok(())
+    }
 }
 
 #[cfg(test)]
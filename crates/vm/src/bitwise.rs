@@ -0,0 +1,29 @@
+use crate::{
+    runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, MachineResult, OperationResult,
+    VM,
+};
+
+// Bitwise operators work on the integer part of the numbers, the ones that don't fit saturate
+fn to_integer(value: RuntimeValue, vm: &mut VM) -> MachineResult<i64> {
+    match value {
+        RuntimeValue::Number(number) => Ok(number as i64),
+        _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+    }
+}
+
+impl VM {
+    pub(crate) fn op_bitwise(&mut self, operation: fn(i64, i64) -> i64) -> OperationResult {
+        let (a, b) = self.pop_two_operands()?;
+        let a = to_integer(a, self)?;
+        let b = to_integer(b, self)?;
+        self.push_operand(RuntimeValue::Number(operation(a, b) as f64));
+        Ok(())
+    }
+
+    pub(crate) fn op_bit_not(&mut self) -> OperationResult {
+        let value = self.pop_operand()?;
+        let value = to_integer(value, self)?;
+        self.push_operand(RuntimeValue::Number(!value as f64));
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::ops::Range;
+
+use common::Number;
+
+use crate::{
+    gc::{HeapObject, HeapPointer},
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    MachineResult, OperationResult, VM,
+};
+
+impl VM {
+    pub(crate) fn allocate_bytes(&mut self, bytes: Vec<u8>) -> RuntimeValue {
+        RuntimeValue::Bytes(self.gc.allocate(HeapObject::Bytes(bytes)))
+    }
+
+    pub(crate) fn expect_bytes(&mut self, value: RuntimeValue) -> MachineResult<HeapPointer> {
+        match value {
+            RuntimeValue::Bytes(ptr) => Ok(ptr),
+            _ => self.error(RuntimeErrorCause::MismatchedTypes),
+        }
+    }
+
+    // Range of `size` bytes starting at the `offset`, checked against the buffer's length
+    pub(crate) fn byte_range(
+        &mut self,
+        bytes_ptr: HeapPointer,
+        offset: Number,
+        size: usize,
+    ) -> MachineResult<Range<usize>> {
+        if offset.fract() != 0.0 {
+            return self.error(RuntimeErrorCause::MismatchedTypes);
+        }
+
+        let len = self
+            .checked(self.gc.deref(bytes_ptr).and_then(HeapObject::as_bytes))?
+            .len();
+        if offset < 0.0 || offset as usize + size > len {
+            return self.error(RuntimeErrorCause::IndexOutOfBounds);
+        }
+
+        let start = offset as usize;
+        Ok(start..start + size)
+    }
+
+    pub(crate) fn op_index(&mut self) -> OperationResult {
+        let position = self.pop_operand()?;
+        let target = self.pop_operand()?;
+        if let RuntimeValue::Map(map_ptr) = target {
+            return self.map_index(map_ptr, position);
+        }
+        let position = self.expect_number(position)?;
+        match target {
+            RuntimeValue::HeapPointer(array_ptr) => return self.array_index(array_ptr, position),
+            RuntimeValue::Range {
+                start,
+                end,
+                inclusive,
+            } => return self.range_index(start, end, inclusive, position),
+            _ => {}
+        }
+        let bytes_ptr = self.expect_bytes(target)?;
+        let range = self.byte_range(bytes_ptr, position, 1)?;
+
+        let bytes = self.checked(self.gc.deref(bytes_ptr).and_then(HeapObject::as_bytes))?;
+        let byte = bytes[range.start];
+        self.push_operand(RuntimeValue::Number(byte as Number));
+        Ok(())
+    }
+
+    pub(crate) fn op_set_index(&mut self) -> OperationResult {
+        let value = self.pop_operand()?;
+        let position = self.pop_operand()?;
+        let target = self.pop_operand()?;
+        if let RuntimeValue::Map(map_ptr) = target {
+            return self.map_set_index(map_ptr, position, value);
+        }
+        let position = self.expect_number(position)?;
+        if let RuntimeValue::HeapPointer(array_ptr) = target {
+            return self.array_set_index(array_ptr, position, value);
+        }
+        let value = match value {
+            RuntimeValue::Number(value) => value,
+            _ => return self.error(RuntimeErrorCause::ExpectedNumber),
+        };
+        let bytes_ptr = self.expect_bytes(target)?;
+        let range = self.byte_range(bytes_ptr, position, 1)?;
+
+        if value.fract() != 0.0 || !(0.0..=255.0).contains(&value) {
+            return self.error(RuntimeErrorCause::MismatchedTypes);
+        }
+
+        let bytes = self
+            .gc
+            .deref_mut(bytes_ptr)
+            .and_then(HeapObject::as_bytes_mut);
+        match bytes {
+            Ok(bytes) => bytes[range.start] = value as u8,
+            Err(cause) => return self.error(cause),
+        }
+        self.push_operand(RuntimeValue::Number(value));
+        Ok(())
+    }
+}
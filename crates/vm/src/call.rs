@@ -1,15 +1,16 @@
 use crate::{
     gc::{HeapObject, HeapPointer},
     gravitas_std::{FnArgs, NativeFunction, NATIVE_FUNCTIONS},
-    MachineResult, RuntimeErrorCause, RuntimeValue, VM,
+    MachineResult, OperationResult, RuntimeErrorCause, RuntimeValue, VM,
 };
-use common::ProgramText;
+use common::{ProgramText, Symbol};
 
 #[derive(Debug, Clone)]
 pub(crate) struct CallFrame {
     pub(crate) stack_start: usize,
-    pub(crate) name: ProgramText,
-    pub(crate) return_ip: usize,
+    pub(crate) name: Symbol,
+    // Next opcode of the frame's function, callers keep theirs while the frames above them run
+    pub(crate) ip: usize,
     pub(crate) closure_ptr: HeapPointer,
 }
 
@@ -21,7 +22,7 @@ pub(crate) enum CallType {
 pub(crate) type CallOperation = MachineResult<CallType>;
 
 impl VM {
-    fn get_args(&mut self, arity: usize) -> MachineResult<FnArgs> {
+    pub(crate) fn get_args(&mut self, arity: usize) -> MachineResult<FnArgs> {
         let mut args = vec![];
         for _ in 0..arity {
             args.push(self.pop_operand()?);
@@ -30,39 +31,103 @@ impl VM {
         Ok(args)
     }
 
-    pub(crate) fn add_call_frame(&mut self, call_frame: CallFrame) {
+    pub(crate) fn add_call_frame(&mut self, call_frame: CallFrame) -> OperationResult {
+        if self.call_stack.len() >= self.options.max_call_depth {
+            return self.error(RuntimeErrorCause::RecursionLimit);
+        }
+
         self.debug(format!(
-            "[CALL_STACK][NEW FRAME][NAME={}][RETURN_IP={}][STACK_START={}]",
-            call_frame.name, call_frame.return_ip, call_frame.stack_start
+            "[CALL_STACK][NEW FRAME][NAME={}][STACK_START={}]",
+            call_frame.name, call_frame.stack_start
         ));
 
         self.call_stack.push(call_frame);
+        self.stats.peak_call_depth = self.stats.peak_call_depth.max(self.call_stack.len());
+        Ok(())
     }
 
-    pub(crate) fn remove_call_frame(&mut self) {
-        let call_frame = self
-            .call_stack
-            .pop()
-            .expect("Tried to remove the global call frame.");
+    // Makes room for the values the function of the current frame needs at once,
+    // so the stack doesn't grow while it runs
+    pub(crate) fn reserve_frame(&mut self, max_stack: usize) -> OperationResult {
+        let used = self.operands.len() - self.current_frame()?.stack_start;
+        self.operands.reserve(max_stack.saturating_sub(used));
+        Ok(())
+    }
+
+    // Names of the functions on the call stack, starting from the innermost one
+    pub(crate) fn stack_trace(&self) -> ProgramText {
+        self.call_stack
+            .iter()
+            .rev()
+            .map(|frame| format!("at {}", frame.name))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub(crate) fn remove_call_frame(&mut self) -> OperationResult {
+        let call_frame = match self.call_stack.pop() {
+            Some(call_frame) => call_frame,
+            None => return self.error(RuntimeErrorCause::EmptyCallStack),
+        };
 
         self.debug(format!(
-            "[CALL_STACK][REMOVE FRAME][NAME={}][RETURN_IP={}][STACK_START={}]",
-            call_frame.name, call_frame.return_ip, call_frame.stack_start
+            "[CALL_STACK][REMOVE FRAME][NAME={}][IP={}][STACK_START={}]",
+            call_frame.name, call_frame.ip, call_frame.stack_start
         ));
 
-        self.ip = call_frame.return_ip;
+        // Returns can leave from inside of any block, so the frame closes everything it captured
+        self.close_upvalues(call_frame.stack_start)?;
         self.operands.truncate(call_frame.stack_start);
+        Ok(())
     }
 
-    fn closure_call(&mut self, closure_ptr: HeapPointer) -> CallOperation {
-        let closure = self.gc.deref(closure_ptr).as_closure();
+    // Arguments past the arity of a variadic function are collected into an array,
+    // which is passed as the last argument. Calls through values aren't checked by the
+    // compiler, so the other functions have to get exactly as many arguments as they declare.
+    fn collect_rest_args(
+        &mut self,
+        arity: usize,
+        variadic: bool,
+        args_count: usize,
+    ) -> MachineResult<usize> {
+        if !variadic {
+            if args_count != arity {
+                return self.error(RuntimeErrorCause::WrongArgumentCount);
+            }
+            return Ok(arity);
+        }
+        if args_count < arity {
+            return self.error(RuntimeErrorCause::MissingArguments);
+        }
+
+        self.op_create_array(args_count - arity)?;
+        Ok(arity + 1)
+    }
+
+    // -2 because we also count function pushed onto the stack for recursion purposes and "this"
+    // handler. Argument counts are checked before, so a stack too short for them is a bug.
+    fn frame_start(&mut self, arity: usize) -> MachineResult<usize> {
+        match self.operands.len().checked_sub(arity + 2) {
+            Some(stack_start) => Ok(stack_start),
+            None => self.error(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    fn closure_call(&mut self, closure_ptr: HeapPointer, args_count: usize) -> CallOperation {
+        let closure = self.checked(self.gc.deref(closure_ptr).and_then(HeapObject::as_closure))?;
         let function_ptr = closure.function_ptr;
 
-        let (arity, name) = {
-            let function = self.deref_global(function_ptr).as_function();
+        let (arity, variadic, name, max_stack) = {
+            let function = self.deref_global(function_ptr)?.as_function();
 
-            (function.arity, function.name.clone())
+            (
+                function.arity,
+                function.variadic,
+                function.name,
+                function.max_stack,
+            )
         };
+        let arity = self.collect_rest_args(arity, variadic, args_count)?;
 
         self.debug(format!("[VM][CALL][FUNCTION][NAME={}]", &name));
 
@@ -72,41 +137,48 @@ impl VM {
         self.push_operand(RuntimeValue::Null);
 
         let frame = CallFrame {
-            // -2 because we also count function pushed onto the stack
-            // for recursion purposes and "this" handler
-            stack_start: self.operands.len() - arity - 2,
+            stack_start: self.frame_start(arity)?,
             name,
             closure_ptr,
-            return_ip: self.ip,
+            ip: 0,
         };
 
-        self.add_call_frame(frame);
+        self.add_call_frame(frame)?;
+        self.reserve_frame(max_stack)?;
 
         Ok(CallType::EnterFnBody)
     }
 
-    fn bound_method_call(&mut self, method_ptr: HeapPointer) -> CallOperation {
-        let bound_method = self.gc.deref(method_ptr).as_bound_method();
+    fn bound_method_call(&mut self, method_ptr: HeapPointer, args_count: usize) -> CallOperation {
+        let bound_method =
+            self.checked(self.gc.deref(method_ptr).and_then(HeapObject::as_bound_method))?;
         let recursion_handler = RuntimeValue::HeapPointer(bound_method.method_ptr);
         let this_handler = RuntimeValue::HeapPointer(bound_method.receiver);
-        let (arity, name) = {
-            let function = self.deref_global(bound_method.method_ptr).as_function();
-            (function.arity, function.name.clone())
+        let (arity, variadic, name, max_stack) = {
+            let method = self.gc.deref(bound_method.method_ptr);
+            let function_ptr = self.checked(method.and_then(HeapObject::as_closure))?.function_ptr;
+            let function = self.deref_global(function_ptr)?.as_function();
+            (
+                function.arity,
+                function.variadic,
+                function.name,
+                function.max_stack,
+            )
         };
+        let arity = self.collect_rest_args(arity, variadic, args_count)?;
 
         self.push_operand(recursion_handler);
         self.push_operand(this_handler);
 
         let frame = CallFrame {
-            // -2 because we also count function pushed onto the stack
-            // for recursion purposes and "this" handler
-            stack_start: self.operands.len() - arity - 2,
+            stack_start: self.frame_start(arity)?,
             name,
             closure_ptr: method_ptr,
-            return_ip: self.ip,
+            ip: 0,
         };
 
-        self.add_call_frame(frame);
+        self.add_call_frame(frame)?;
+        self.reserve_frame(max_stack)?;
 
         Ok(CallType::EnterFnBody)
     }
@@ -123,43 +195,45 @@ impl VM {
     //     instance_ptr
     // }
 
-    fn native_function_call(&mut self, native_function: &NativeFunction) -> CallOperation {
+    fn native_function_call(
+        &mut self,
+        native_function: &NativeFunction,
+        args_count: usize,
+    ) -> CallOperation {
         let NativeFunction {
             arity,
             fn_body,
             name,
         } = native_function;
+        if args_count != *arity {
+            return self.error(RuntimeErrorCause::WrongArgumentCount);
+        }
 
         self.debug(format!("[VM][CALL][BUILT IN]"));
 
         let args = self.get_args(*arity)?;
-        let result = fn_body(args, self);
+        let result = fn_body(args, self)?;
         self.push_operand(result);
         Ok(CallType::InlineFn)
     }
 
-    pub(crate) fn op_call(&mut self) -> CallOperation {
+    pub(crate) fn op_call(&mut self, args_count: usize) -> CallOperation {
         let callee = self.pop_operand()?;
+        self.stats.calls += 1;
         match callee {
             // RuntimeValue::GlobalPointer(global_ptr) => self.class_call(global_ptr),
-            RuntimeValue::HeapPointer(heap_ptr) => {
-                let result = match self.gc.deref(heap_ptr) {
-                    HeapObject::Closure(_) => self.closure_call(heap_ptr),
-                    HeapObject::BoundMethod(_) => self.bound_method_call(heap_ptr),
-                    d => {
-                        dbg!(d);
-                        unreachable!()
-                    }
-                };
-
-                result
-            }
+            RuntimeValue::HeapPointer(heap_ptr) => match self.checked(self.gc.deref(heap_ptr))? {
+                HeapObject::Closure(_) => self.closure_call(heap_ptr, args_count),
+                HeapObject::BoundMethod(_) => self.bound_method_call(heap_ptr, args_count),
+                _ => self.error(RuntimeErrorCause::NotCallable),
+            },
             RuntimeValue::NativeFunction(built_in_function) => {
-                let fun = NATIVE_FUNCTIONS
-                    .get(&built_in_function)
-                    .expect("We ensured during compilation that this exists.");
-                self.native_function_call(fun)
+                match NATIVE_FUNCTIONS.get(&built_in_function) {
+                    Some(fun) => self.native_function_call(fun, args_count),
+                    None => self.error(RuntimeErrorCause::NotCallable),
+                }
             }
+            RuntimeValue::HostFunction(index) => self.host_function_call(index, args_count),
             _ => self.error(RuntimeErrorCause::NotCallable),
         }
     }
@@ -170,18 +244,20 @@ mod test {
     use bytecode::{callables::Function, chunk::Constant, Opcode};
     use common::MAIN_FUNCTION_NAME;
 
-    use crate::{test::main_fn, Chunk, OperationResult, VM};
+    use crate::{options::VmOptions, test::main_fn, Chunk, OperationResult, VM};
 
     #[test]
     fn grow_callstack() {
         let function = Function {
             arity: 0,
+            variadic: false,
             chunk: Chunk::default(),
-            name: "foo".to_owned(),
+            name: "foo".into(),
+            max_stack: 0,
         };
 
         let code = main_fn(Chunk::new(
-            vec![Opcode::Constant(0), Opcode::Call],
+            vec![Opcode::Constant(0), Opcode::Call(0)],
             vec![Constant::Function(function)],
         ));
     }
@@ -190,26 +266,28 @@ mod test {
     fn change_callframe() -> OperationResult {
         let function = Function {
             arity: 0,
+            variadic: false,
             chunk: Chunk::default(),
-            name: "my_func".to_owned(),
+            name: "my_func".into(),
+            max_stack: 0,
         };
 
         let mut code = main_fn(Chunk::new(
-            vec![Opcode::Constant(0), Opcode::Call],
+            vec![Opcode::Constant(0), Opcode::Call(0)],
             vec![Constant::Function(function)],
         ));
 
-        let mut vm = VM::new();
+        let mut vm = VM::new(VmOptions::default());
 
         // we start with the global callframe which name is "main"
-        let main_fn = vm.current_frame().name.clone();
-        assert_eq!(&main_fn, MAIN_FUNCTION_NAME);
+        let main_fn = vm.current_frame()?.name;
+        assert_eq!(main_fn, MAIN_FUNCTION_NAME);
         // push the constant onto the stack
         vm.tick()?;
         // call the function
         vm.tick()?;
         // now the function's name should be equal to "my_func"
-        let my_func = vm.current_frame().name.clone();
+        let my_func = vm.current_frame()?.name;
         assert_eq!(my_func, "my_func");
 
         Ok(())
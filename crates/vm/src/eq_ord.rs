@@ -1,3 +1,5 @@
+use bytecode::Opcode;
+
 use crate::{
     runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, MachineResult, OperationResult,
     VM,
@@ -9,6 +11,18 @@ impl RuntimeValue {
             (RuntimeValue::Number(a), RuntimeValue::Number(b)) => a == b,
             (RuntimeValue::String(a), RuntimeValue::String(b)) => a == b,
             (RuntimeValue::Bool(a), RuntimeValue::Bool(b)) => a == b,
+            (
+                RuntimeValue::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                RuntimeValue::Range {
+                    start: other_start,
+                    end: other_end,
+                    inclusive: other_inclusive,
+                },
+            ) => start == other_start && end == other_end && inclusive == other_inclusive,
             _ => false,
         })
     }
@@ -51,8 +65,9 @@ impl RuntimeValue {
             RuntimeValue::Number(Number) => true,
             RuntimeValue::String(ProgramText) => true,
             RuntimeValue::Bool(bool) => bool,
+            RuntimeValue::Bytes(_) | RuntimeValue::Map(_) | RuntimeValue::Range { .. } => true,
             RuntimeValue::Null => false,
-            _ => unreachable!(),
+            _ => return vm.error(RuntimeErrorCause::MismatchedTypes),
         })
     }
 }
@@ -104,6 +119,21 @@ impl VM {
         self.push_operand(RuntimeValue::Bool(result));
         Ok(())
     }
+
+    // The result decides the jump right away, so the Jif after the opcode is skipped
+    pub(crate) fn op_jif_compare(&mut self, opcode: Opcode, distance: isize) -> OperationResult {
+        let (a, b) = self.pop_two_operands()?;
+        let holds = match opcode.fused_comparison() {
+            Some(Opcode::Eq) => a.eq(&b, self)?,
+            Some(Opcode::Ne) => a.ne(b, self)?,
+            Some(Opcode::Lt) => a.lt(b, self)?,
+            Some(Opcode::Le) => a.le(b, self)?,
+            Some(Opcode::Gt) => a.gt(b, self)?,
+            Some(Opcode::Ge) => a.ge(b, self)?,
+            _ => return self.error(RuntimeErrorCause::InternalError),
+        };
+        self.move_pointer(if holds { 1 } else { distance })
+    }
 }
 
 #[cfg(test)]
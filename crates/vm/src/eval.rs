@@ -0,0 +1,92 @@
+use bytecode::{
+    chunk::Constant,
+    generate_bytecode_with_globals,
+    stmt::{GlobalItem, GlobalPointer},
+    ProgramBytecode,
+};
+use common::{BuiltInFunction, ProgramText, Symbol};
+
+use crate::{
+    call::CallFrame, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue,
+    MachineResult, TickOutcome, VM,
+};
+
+impl VM {
+    // Appends globals of a freshly compiled program to the ones the VM already knows about.
+    // Pointers inside of the new program are relative to its own globals, so they have
+    // to be moved by the amount of globals that were there before.
+    pub(crate) fn link_program(&mut self, program: ProgramBytecode) -> GlobalPointer {
+        let offset = self.globals.len();
+
+        for mut global in program.globals {
+            match &mut global {
+                GlobalItem::Function(function) => {
+                    for constant in function.chunk.constants.iter_mut() {
+                        if let Constant::GlobalPointer(ptr) = constant {
+                            *ptr += offset;
+                        }
+                    }
+                }
+            }
+
+            self.globals.push(global);
+        }
+
+        program.global_fn_ptr + offset
+    }
+
+    // Compiles the source and runs it inside of a new call frame on top of the current one.
+    // The code shares the heap and the globals with the rest of the program.
+    pub(crate) fn eval(&mut self, source: &str) -> MachineResult<RuntimeValue> {
+        if !self.options.allow_eval {
+            return self.error(RuntimeErrorCause::EvalNotAllowed);
+        }
+
+        // The code can use the top-level variables of the program
+        let globals = self.global_names();
+        let host_functions: Vec<ProgramText> = self
+            .host_functions
+            .iter()
+            .map(|host| host.name.clone())
+            .collect();
+        let generate = |ast| generate_bytecode_with_globals(ast, &host_functions, &globals);
+        let program = match parser::parse(source).map(generate) {
+            Ok(Ok(program)) => program,
+            _ => return self.error(RuntimeErrorCause::EvalCompilationFailed),
+        };
+
+        let fn_ptr = self.link_program(program);
+        let closure_ptr = self.make_closure(fn_ptr);
+        let depth = self.call_stack.len();
+        let name: ProgramText = BuiltInFunction::Eval.into();
+
+        self.add_call_frame(CallFrame {
+            stack_start: self.operands.len(),
+            name: Symbol::intern(&name),
+            closure_ptr,
+            ip: 0,
+        })?;
+
+        loop {
+            // Evaluated code returned explicitly so its frame is already gone
+            if self.call_stack.len() <= depth {
+                return self.pop_operand();
+            }
+
+            if self.tick()? == TickOutcome::FinishProgram {
+                break;
+            }
+        }
+
+        let stack_start = self.current_frame()?.stack_start;
+        let result = if self.operands.len() > stack_start {
+            self.pop_operand()?
+        } else {
+            RuntimeValue::Null
+        };
+
+        self.remove_call_frame()?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    call::CallType,
+    gc::HeapObject,
+    gravitas_std::NATIVE_FUNCTIONS,
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    stats::timed,
+    MachineResult, OperationResult, ProgramOutput, TickOutcome, VM,
+};
+use bytecode::ProgramBytecode;
+
+pub(crate) type TimerId = usize;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Timer {
+    pub(crate) id: TimerId,
+    pub(crate) due: Instant,
+    // Intervals are rescheduled after every run, timeouts fire only once
+    pub(crate) interval: Option<Duration>,
+    pub(crate) callback: RuntimeValue,
+}
+
+impl VM {
+    pub(crate) fn schedule_timer(
+        &mut self,
+        callback: RuntimeValue,
+        delay: Duration,
+        interval: Option<Duration>,
+    ) -> MachineResult<TimerId> {
+        if !self.takes_no_arguments(&callback) {
+            return self.error(RuntimeErrorCause::NotCallable);
+        }
+
+        self.next_timer_id += 1;
+        let id = self.next_timer_id;
+
+        self.debug(format!("[VM][TIMER][NEW][ID={}][DELAY={:?}]", id, delay));
+
+        self.timers.push(Timer {
+            id,
+            due: Instant::now() + delay,
+            interval,
+            callback,
+        });
+
+        Ok(id)
+    }
+
+    pub(crate) fn clear_timer(&mut self, id: TimerId) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    // Callbacks are always called without any arguments
+    fn takes_no_arguments(&self, callee: &RuntimeValue) -> bool {
+        match callee {
+            RuntimeValue::HeapPointer(heap_ptr) => {
+                let fn_ptr = match self.gc.deref(*heap_ptr) {
+                    Ok(HeapObject::Closure(closure)) => closure.function_ptr,
+                    Ok(HeapObject::BoundMethod(bound_method)) => {
+                        let method = self.gc.deref(bound_method.method_ptr);
+                        match method.and_then(HeapObject::as_closure) {
+                            Ok(closure) => closure.function_ptr,
+                            Err(_) => return false,
+                        }
+                    }
+                    _ => return false,
+                };
+
+                self.deref_global(fn_ptr)
+                    .is_ok_and(|global| global.as_function().arity == 0)
+            }
+            RuntimeValue::NativeFunction(built_in_function) => NATIVE_FUNCTIONS
+                .get(built_in_function)
+                .is_some_and(|function| function.arity == 0),
+            _ => false,
+        }
+    }
+
+    // Calls the callee on top of whatever is currently executing and runs it until it returns.
+    fn call_callback(&mut self, callee: RuntimeValue) -> OperationResult {
+        let depth = self.call_stack.len();
+
+        self.push_operand(callee);
+        if let CallType::EnterFnBody = self.op_call(0)? {
+            while self.call_stack.len() > depth {
+                if self.tick()? == TickOutcome::FinishProgram {
+                    break;
+                }
+            }
+        }
+
+        // Result of the callback is not used by anyone
+        self.pop_operand()?;
+        Ok(())
+    }
+
+    fn next_due_timer(&self) -> Option<usize> {
+        self.timers
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, timer)| timer.due)
+            .map(|(index, _)| index)
+    }
+
+    // Runs the program and then keeps firing scheduled callbacks, sleeping in between,
+    // until there are no timers left. Result is the one of the program itself.
+    #[cfg(feature = "native")]
+    pub fn run_event_loop(&mut self, program: ProgramBytecode) -> ProgramOutput {
+        let result = self.run(program)?;
+
+        while let Some(index) = self.next_due_timer() {
+            let now = Instant::now();
+            let timer = &mut self.timers[index];
+
+            if timer.due > now {
+                thread::sleep(timer.due - now);
+            }
+
+            let callback = timer.callback.clone();
+            match timer.interval {
+                Some(interval) => timer.due += interval,
+                None => {
+                    self.timers.remove(index);
+                }
+            }
+
+            self.debug("[VM][TIMER][FIRE]");
+            let (outcome, time) = timed(|| self.call_callback(callback));
+            self.stats.execution_time += time;
+            outcome?;
+        }
+
+        Ok(result)
+    }
+}
@@ -1,3 +1,23 @@
+use bytecode::chunk::{Constant, ConstantIndex, TableKey};
+
+use crate::{runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, OperationResult, VM};
+
+impl VM {
+    // Values other than numbers and strings don't match any literal, so they take the default arm
+    pub(crate) fn op_jump_table(&mut self, index: ConstantIndex) -> OperationResult {
+        let key = match self.pop_operand()? {
+            RuntimeValue::Number(number) => TableKey::from_number(number),
+            RuntimeValue::String(text) => Some(TableKey::String(text)),
+            _ => None,
+        };
+        let distance = match &self.current_code()?.chunk.constants[index] {
+            Constant::JumpTable(table) => table.distance(key.as_ref()),
+            _ => return self.error(RuntimeErrorCause::InternalError),
+        };
+        self.move_pointer(distance)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use bytecode::{
@@ -6,8 +26,8 @@ mod test {
     };
 
     use crate::{
-        runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, test::main_fn,
-        OperationResult, VM,
+        options::VmOptions, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue,
+        test::main_fn, OperationResult, VM,
     };
 
     #[test]
@@ -17,11 +37,11 @@ mod test {
             vec![Constant::Number(127.0)],
         ));
 
-        let mut vm = VM::new();
-        assert_eq!(vm.ip, 0);
-        // opcodes advance the pointer to 0, and 1 and then we have a jump that advances by another 10 so 11
+        let mut vm = VM::new(VmOptions::default());
+        assert_eq!(vm.ip(), 0);
+        // opcodes advance the pointer to 1, and 2 and then we have a jump that advances by another 10 so 12
         assert!(vm.run(code)?.eq(&RuntimeValue::Number(127.0), &mut vm)?);
-        assert_eq!(vm.ip, 11);
+        assert_eq!(vm.ip(), 12);
 
         Ok(())
     }
@@ -33,8 +53,8 @@ mod test {
             vec![Constant::Number(127.0)],
         ));
 
-        let mut vm = VM::new();
-        assert_eq!(vm.ip, 0);
+        let mut vm = VM::new(VmOptions::default());
+        assert_eq!(vm.ip(), 0);
         // opcodes advance the pointer to 0, 1, and 2 and then we have a jump that retreats by 3 so -1
         // and that will cause a stack overflow
         // If we'd like to just test it to come back to a normal value then it would cause an infinite loop
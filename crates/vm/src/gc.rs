@@ -1,12 +1,13 @@
-use core::panic;
 use std::{collections::HashMap, hash::Hash};
 
 use bytecode::stmt::GlobalPointer;
 use common::ProgramText;
 
-use crate::runtime_value::RuntimeValue;
+use crate::{runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue};
 
 pub(crate) type HeapPointer = usize;
+// Pointers to nowhere and objects of another kind than the code expects are bugs of the VM
+pub(crate) type HeapResult<T> = Result<T, RuntimeErrorCause>;
 
 #[derive(Debug)]
 pub(crate) struct Closure {
@@ -54,47 +55,93 @@ impl Object {
     }
 }
 
+// Position of a `for ... in` loop in the value it goes through
+#[derive(Debug)]
+pub(crate) struct Cursor {
+    pub(crate) iterable: RuntimeValue,
+    pub(crate) position: usize,
+}
+
 #[derive(Debug)]
 pub(crate) enum HeapObject {
     Closure(Closure),
     BoundMethod(BoundMethod),
     Value(RuntimeValue),
     Object(Object),
+    Bytes(Vec<u8>),
+    Array(Vec<RuntimeValue>),
+    Map(HashMap<ProgramText, RuntimeValue>),
+    Cursor(Cursor),
 }
 
 impl HeapObject {
-    pub fn as_closure(&self) -> &Closure {
+    pub fn as_closure(&self) -> HeapResult<&Closure> {
+        match self {
+            Self::Closure(closure) => Ok(closure),
+            _ => Err(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    pub fn as_value(&self) -> HeapResult<&RuntimeValue> {
+        match self {
+            Self::Value(value) => Ok(value),
+            _ => Err(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    pub fn as_bound_method(&self) -> HeapResult<&BoundMethod> {
+        match self {
+            Self::BoundMethod(bound_method) => Ok(bound_method),
+            _ => Err(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> HeapResult<&mut Object> {
         match self {
-            Self::Closure(closure) => closure,
-            _ => panic!("Expected closure"),
+            Self::Object(object) => Ok(object),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_value(&self) -> &RuntimeValue {
+    pub fn as_bytes(&self) -> HeapResult<&Vec<u8>> {
         match self {
-            Self::Value(value) => value,
-            _ => panic!("Expected value"),
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_bound_method(&self) -> &BoundMethod {
+    pub fn as_bytes_mut(&mut self) -> HeapResult<&mut Vec<u8>> {
         match self {
-            Self::BoundMethod(bound_method) => bound_method,
-            _ => panic!("Expected bound method"),
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_object(&self) -> &Object {
+    pub fn as_array(&self) -> HeapResult<&Vec<RuntimeValue>> {
         match self {
-            Self::Object(object) => object,
-            _ => panic!("Expected object"),
+            Self::Array(values) => Ok(values),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_object_mut(&mut self) -> &mut Object {
+    pub fn as_array_mut(&mut self) -> HeapResult<&mut Vec<RuntimeValue>> {
         match self {
-            Self::Object(object) => object,
-            _ => panic!("Expected object"),
+            Self::Array(values) => Ok(values),
+            _ => Err(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    pub fn as_map(&self) -> HeapResult<&HashMap<ProgramText, RuntimeValue>> {
+        match self {
+            Self::Map(entries) => Ok(entries),
+            _ => Err(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    pub fn as_map_mut(&mut self) -> HeapResult<&mut HashMap<ProgramText, RuntimeValue>> {
+        match self {
+            Self::Map(entries) => Ok(entries),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 }
@@ -120,25 +167,45 @@ impl From<BoundMethod> for HeapObject {
 #[derive(Debug)]
 pub(crate) struct GC {
     objects: Vec<HeapObject>,
+    // Since the last reset, for the statistics of a run
+    allocations: u64,
 }
 
 impl GC {
     pub fn new() -> Self {
         Self {
             objects: Vec::new(),
+            allocations: 0,
         }
     }
 
     pub fn allocate(&mut self, object: HeapObject) -> HeapPointer {
+        self.allocations += 1;
         self.objects.push(object);
         self.objects.len() - 1
     }
 
-    pub fn deref(&self, pointer: HeapPointer) -> &HeapObject {
-        self.objects.get(pointer).unwrap()
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    pub fn reset_allocations(&mut self) {
+        self.allocations = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn deref(&self, pointer: HeapPointer) -> HeapResult<&HeapObject> {
+        self.objects
+            .get(pointer)
+            .ok_or(RuntimeErrorCause::InternalError)
     }
 
-    pub fn deref_mut(&mut self, pointer: HeapPointer) -> &mut HeapObject {
-        self.objects.get_mut(pointer).unwrap()
+    pub fn deref_mut(&mut self, pointer: HeapPointer) -> HeapResult<&mut HeapObject> {
+        self.objects
+            .get_mut(pointer)
+            .ok_or(RuntimeErrorCause::InternalError)
     }
 }
@@ -0,0 +1,143 @@
+use common::Number;
+
+use crate::{
+    gc::{HeapObject, HeapPointer},
+    gravitas_std::FnArgs,
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    MachineResult, VM,
+};
+
+// All of the multi-byte numbers are stored in little endian order
+
+fn number_arg(arg: Option<RuntimeValue>, vm: &mut VM) -> MachineResult<Number> {
+    match arg {
+        Some(RuntimeValue::Number(number)) => Ok(number),
+        _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+    }
+}
+
+fn bytes_arg(arg: Option<RuntimeValue>, vm: &mut VM) -> MachineResult<HeapPointer> {
+    match arg {
+        Some(value) => vm.expect_bytes(value),
+        None => vm.error(RuntimeErrorCause::MismatchedTypes),
+    }
+}
+
+fn contents(bytes_ptr: HeapPointer, vm: &VM) -> MachineResult<&Vec<u8>> {
+    vm.checked(vm.gc.deref(bytes_ptr).and_then(HeapObject::as_bytes))
+}
+
+// Reads `N` bytes at the offset that is passed as the second argument
+fn read<const N: usize>(args: &mut FnArgs, vm: &mut VM) -> MachineResult<[u8; N]> {
+    let bytes_ptr = bytes_arg(args.pop(), vm)?;
+    let offset = number_arg(args.pop(), vm)?;
+    let range = vm.byte_range(bytes_ptr, offset, N)?;
+
+    let mut buffer = [0; N];
+    buffer.copy_from_slice(&contents(bytes_ptr, vm)?[range]);
+    Ok(buffer)
+}
+
+fn write<const N: usize>(
+    bytes_ptr: HeapPointer,
+    offset: Number,
+    buffer: [u8; N],
+    vm: &mut VM,
+) -> MachineResult<RuntimeValue> {
+    let range = vm.byte_range(bytes_ptr, offset, N)?;
+
+    let bytes = vm
+        .gc
+        .deref_mut(bytes_ptr)
+        .and_then(HeapObject::as_bytes_mut);
+    match bytes {
+        Ok(bytes) => bytes[range].copy_from_slice(&buffer),
+        Err(cause) => return vm.error(cause),
+    }
+    Ok(RuntimeValue::Null)
+}
+
+// Creates a zeroed buffer of the given length
+pub fn bytes(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let len = number_arg(args.pop(), vm)?;
+
+    if len < 0.0 || len.fract() != 0.0 {
+        return vm.error(RuntimeErrorCause::MismatchedTypes);
+    }
+
+    // A length too big for the memory fails here instead of aborting the whole process
+    let mut buffer = Vec::new();
+    if buffer.try_reserve_exact(len as usize).is_err() {
+        return vm.error(RuntimeErrorCause::OutOfMemory);
+    }
+    buffer.resize(len as usize, 0);
+    Ok(vm.allocate_bytes(buffer))
+}
+
+pub fn read_u32(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let buffer = read::<4>(&mut args, vm)?;
+    Ok(RuntimeValue::Number(u32::from_le_bytes(buffer) as Number))
+}
+
+pub fn write_u32(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let bytes_ptr = bytes_arg(args.pop(), vm)?;
+    let offset = number_arg(args.pop(), vm)?;
+    let value = number_arg(args.pop(), vm)?;
+
+    if value.fract() != 0.0 || !(0.0..=u32::MAX as Number).contains(&value) {
+        return vm.error(RuntimeErrorCause::MismatchedTypes);
+    }
+
+    write(bytes_ptr, offset, (value as u32).to_le_bytes(), vm)
+}
+
+pub fn read_f64(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let buffer = read::<8>(&mut args, vm)?;
+    Ok(RuntimeValue::Number(f64::from_le_bytes(buffer)))
+}
+
+pub fn write_f64(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let bytes_ptr = bytes_arg(args.pop(), vm)?;
+    let offset = number_arg(args.pop(), vm)?;
+    let value = number_arg(args.pop(), vm)?;
+
+    write(bytes_ptr, offset, value.to_le_bytes(), vm)
+}
+
+// Copies bytes from the start (inclusive) to the end (exclusive) into a new buffer
+pub fn slice(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let bytes_ptr = bytes_arg(args.pop(), vm)?;
+    let start = number_arg(args.pop(), vm)?;
+    let end = number_arg(args.pop(), vm)?;
+
+    // Like the length of bytes(), the bounds have to be whole numbers
+    if start.fract() != 0.0 || end.fract() != 0.0 {
+        return vm.error(RuntimeErrorCause::MismatchedTypes);
+    }
+    if end < start {
+        return vm.error(RuntimeErrorCause::IndexOutOfBounds);
+    }
+
+    let range = vm.byte_range(bytes_ptr, start, (end - start) as usize)?;
+    let copy = contents(bytes_ptr, vm)?[range].to_vec();
+
+    Ok(vm.allocate_bytes(copy))
+}
+
+pub fn bytes_from_string(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    match args.pop() {
+        Some(RuntimeValue::String(text)) => Ok(vm.allocate_bytes(text.into_bytes())),
+        _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+    }
+}
+
+pub fn bytes_to_string(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let bytes_ptr = bytes_arg(args.pop(), vm)?;
+    let bytes = contents(bytes_ptr, vm)?.clone();
+
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(RuntimeValue::String(text)),
+        Err(_) => vm.error(RuntimeErrorCause::InvalidUtf8),
+    }
+}
@@ -1,19 +1,99 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::Write;
+#[cfg(feature = "native")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::{gravitas_std::FnArgs, runtime_value::RuntimeValue, VM};
+use crate::{
+    gc::{HeapObject, Object},
+    gravitas_std::FnArgs,
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    MachineResult, VM,
+};
 
-pub fn clock(_: FnArgs, _: &mut VM) -> RuntimeValue {
-    RuntimeValue::Number(
+#[cfg(feature = "native")]
+pub fn clock(_: FnArgs, _: &mut VM) -> MachineResult<RuntimeValue> {
+    Ok(RuntimeValue::Number(
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Can't access system time")
             .as_millis() as f64,
-    )
+    ))
 }
 
-pub fn print(args: FnArgs, _: &mut VM) -> RuntimeValue {
+pub fn print(args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
     for arg in args {
-        println!("{}", arg);
+        if writeln!(vm.options.output, "{}", arg).is_err() {
+            return vm.error(RuntimeErrorCause::OutputFailed);
+        }
+    }
+    Ok(RuntimeValue::Null)
+}
+
+// Null once the input has ended, e.g. with Ctrl+D in a terminal
+pub fn read_line(_: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    match vm.options.input.read_line() {
+        Ok(Some(line)) => Ok(RuntimeValue::String(line)),
+        Ok(None) => Ok(RuntimeValue::Null),
+        Err(_) => vm.error(RuntimeErrorCause::InputFailed),
+    }
+}
+
+pub fn eval(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    match args.pop() {
+        Some(RuntimeValue::String(source)) => vm.eval(&source),
+        _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+    }
+}
+
+// Creates an error object carrying the message and the stack trace captured at the call site.
+// Cause is null until the language gets a way of wrapping one error inside of another.
+pub fn error(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let message = match args.pop() {
+        Some(RuntimeValue::String(message)) => message,
+        _ => return vm.error(RuntimeErrorCause::MismatchedTypes),
+    };
+
+    let mut error = Object::default();
+    error.set("message".to_owned(), RuntimeValue::String(message));
+    error.set("cause".to_owned(), RuntimeValue::Null);
+    error.set("stack".to_owned(), RuntimeValue::String(vm.stack_trace()));
+
+    let error_ptr = vm.gc.allocate(HeapObject::Object(error));
+    Ok(RuntimeValue::HeapPointer(error_ptr))
+}
+
+#[cfg(feature = "native")]
+fn timer_args(mut args: FnArgs, vm: &mut VM) -> MachineResult<(RuntimeValue, Duration)> {
+    match (args.pop(), args.pop()) {
+        (Some(callback), Some(RuntimeValue::Number(ms))) if ms >= 0.0 => {
+            Ok((callback, Duration::from_millis(ms as u64)))
+        }
+        _ => vm.error(RuntimeErrorCause::MismatchedTypes),
+    }
+}
+
+// Callbacks are only fired by the event loop, see VM::run_event_loop
+#[cfg(feature = "native")]
+pub fn set_timeout(args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let (callback, delay) = timer_args(args, vm)?;
+    let id = vm.schedule_timer(callback, delay, None)?;
+    Ok(RuntimeValue::Number(id as f64))
+}
+
+#[cfg(feature = "native")]
+pub fn set_interval(args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    let (callback, interval) = timer_args(args, vm)?;
+    let id = vm.schedule_timer(callback, interval, Some(interval))?;
+    Ok(RuntimeValue::Number(id as f64))
+}
+
+#[cfg(feature = "native")]
+pub fn clear_timer(mut args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue> {
+    match args.pop() {
+        Some(RuntimeValue::Number(id)) => {
+            vm.clear_timer(id as usize);
+            Ok(RuntimeValue::Null)
+        }
+        _ => vm.error(RuntimeErrorCause::MismatchedTypes),
     }
-    RuntimeValue::Null
 }
@@ -1,18 +1,24 @@
 use std::{collections::HashMap, fmt};
 
-use crate::{runtime_value::RuntimeValue, VM};
+use crate::{runtime_value::RuntimeValue, MachineResult, VM};
 use common::BuiltInFunction;
 use lazy_static::lazy_static;
 
+pub(crate) mod bytes;
 pub(crate) mod functions;
-use functions::{clock, print};
+use bytes::{
+    bytes, bytes_from_string, bytes_to_string, read_f64, read_u32, slice, write_f64, write_u32,
+};
+use functions::{error, eval, print, read_line};
+#[cfg(feature = "native")]
+use functions::{clear_timer, clock, set_interval, set_timeout};
 
 pub(crate) type FnArgs = Vec<RuntimeValue>;
 #[derive(Clone)]
 pub struct NativeFunction {
     pub arity: usize,
     pub name: BuiltInFunction,
-    pub fn_body: fn(args: FnArgs, vm: &mut VM) -> RuntimeValue,
+    pub fn_body: fn(args: FnArgs, vm: &mut VM) -> MachineResult<RuntimeValue>,
 }
 
 impl fmt::Debug for NativeFunction {
@@ -37,8 +43,32 @@ macro_rules! hashmap {
 }
 
 lazy_static! {
-    pub static ref NATIVE_FUNCTIONS: HashMap<BuiltInFunction, NativeFunction> = hashmap! (
-        BuiltInFunction::Clock => NativeFunction { arity: 0, fn_body: clock, name: BuiltInFunction::Clock },
-        BuiltInFunction::Print => NativeFunction  { arity: 1, fn_body: print, name: BuiltInFunction::Print }
-    );
+    pub static ref NATIVE_FUNCTIONS: HashMap<BuiltInFunction, NativeFunction> = {
+        #[cfg_attr(not(feature = "native"), allow(unused_mut))]
+        let mut natives = hashmap! (
+            BuiltInFunction::Print => NativeFunction  { arity: 1, fn_body: print, name: BuiltInFunction::Print },
+            BuiltInFunction::ReadLine => NativeFunction { arity: 0, fn_body: read_line, name: BuiltInFunction::ReadLine },
+            BuiltInFunction::Eval => NativeFunction { arity: 1, fn_body: eval, name: BuiltInFunction::Eval },
+            BuiltInFunction::Error => NativeFunction { arity: 1, fn_body: error, name: BuiltInFunction::Error },
+            BuiltInFunction::Bytes => NativeFunction { arity: 1, fn_body: bytes, name: BuiltInFunction::Bytes },
+            BuiltInFunction::ReadU32 => NativeFunction { arity: 2, fn_body: read_u32, name: BuiltInFunction::ReadU32 },
+            BuiltInFunction::WriteU32 => NativeFunction { arity: 3, fn_body: write_u32, name: BuiltInFunction::WriteU32 },
+            BuiltInFunction::ReadF64 => NativeFunction { arity: 2, fn_body: read_f64, name: BuiltInFunction::ReadF64 },
+            BuiltInFunction::WriteF64 => NativeFunction { arity: 3, fn_body: write_f64, name: BuiltInFunction::WriteF64 },
+            BuiltInFunction::Slice => NativeFunction { arity: 3, fn_body: slice, name: BuiltInFunction::Slice },
+            BuiltInFunction::BytesFromString => NativeFunction { arity: 1, fn_body: bytes_from_string, name: BuiltInFunction::BytesFromString },
+            BuiltInFunction::BytesToString => NativeFunction { arity: 1, fn_body: bytes_to_string, name: BuiltInFunction::BytesToString }
+        );
+
+        // These need a clock, see the `native` feature
+        #[cfg(feature = "native")]
+        natives.extend(hashmap! (
+            BuiltInFunction::Clock => NativeFunction { arity: 0, fn_body: clock, name: BuiltInFunction::Clock },
+            BuiltInFunction::SetTimeout => NativeFunction { arity: 2, fn_body: set_timeout, name: BuiltInFunction::SetTimeout },
+            BuiltInFunction::SetInterval => NativeFunction { arity: 2, fn_body: set_interval, name: BuiltInFunction::SetInterval },
+            BuiltInFunction::ClearTimer => NativeFunction { arity: 1, fn_body: clear_timer, name: BuiltInFunction::ClearTimer }
+        ));
+
+        natives
+    };
 }
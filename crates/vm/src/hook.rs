@@ -0,0 +1,71 @@
+use std::{fmt, io::Write};
+
+use bytecode::Opcode;
+
+use crate::VM;
+
+// Lets tools observe the execution without changing it
+pub trait ExecutionHook: fmt::Debug {
+    // Called right before the opcode at the current ip is executed
+    fn before_instruction(&mut self, vm: &VM, opcode: &Opcode);
+}
+
+// Writes every executed instruction as a JSON object on its own line (JSON Lines)
+#[derive(Debug)]
+pub struct JsonTrace<W> {
+    sink: W,
+}
+
+impl<W: Write> JsonTrace<W> {
+    pub fn new(sink: W) -> Self {
+        Self { sink }
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+impl<W: Write + fmt::Debug> ExecutionHook for JsonTrace<W> {
+    fn before_instruction(&mut self, vm: &VM, opcode: &Opcode) {
+        let frame = match vm.current_frame() {
+            Ok(frame) => frame,
+            Err(_) => return,
+        };
+        let stack: Vec<String> = vm
+            .operands
+            .iter()
+            .map(|value| json_string(&value.to_string()))
+            .collect();
+
+        let line = format!(
+            "{{\"ip\":{},\"opcode\":{},\"stack\":[{}],\"frame\":{{\"name\":{},\"stack_start\":{},\"depth\":{}}}}}",
+            frame.ip,
+            json_string(&opcode.to_string()),
+            stack.join(","),
+            json_string(&frame.name),
+            frame.stack_start,
+            vm.call_stack.len(),
+        );
+
+        if let Err(e) = writeln!(self.sink, "{}", line) {
+            eprintln!("Couldn't write the trace: {}", e);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use crate::{
+    call::{CallOperation, CallType},
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    VM,
+};
+
+// Arguments are passed in the order they were written in the call
+pub type HostFn = Box<dyn FnMut(Vec<RuntimeValue>) -> Result<RuntimeValue, RuntimeErrorCause>>;
+
+// Function provided by the application embedding the VM
+pub(crate) struct HostFunction {
+    pub(crate) name: String,
+    pub(crate) arity: usize,
+    pub(crate) body: HostFn,
+}
+
+impl fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .field("body", &"<host function>")
+            .finish()
+    }
+}
+
+impl VM {
+    // Returns the index the function is called by, which matches the position of its name
+    // in the list passed to bytecode::generate_bytecode_with_host_functions
+    pub fn register_host_function(
+        &mut self,
+        name: &str,
+        arity: usize,
+        body: impl FnMut(Vec<RuntimeValue>) -> Result<RuntimeValue, RuntimeErrorCause> + 'static,
+    ) -> usize {
+        self.host_functions.push(HostFunction {
+            name: name.to_owned(),
+            arity,
+            body: Box::new(body),
+        });
+        self.host_functions.len() - 1
+    }
+
+    pub(crate) fn host_function_call(&mut self, index: usize, args_count: usize) -> CallOperation {
+        let arity = match self.host_functions.get(index) {
+            Some(host_function) => host_function.arity,
+            None => return self.error(RuntimeErrorCause::NotCallable),
+        };
+        if args_count != arity {
+            return self.error(RuntimeErrorCause::WrongArgumentCount);
+        }
+
+        self.debug(format!("[VM][CALL][HOST][INDEX={}]", index));
+
+        let mut args = self.get_args(arity)?;
+        args.reverse();
+
+        let result = (self.host_functions[index].body)(args);
+        match result {
+            Ok(value) => {
+                self.push_operand(value);
+                Ok(CallType::InlineFn)
+            }
+            Err(cause) => self.error(cause),
+        }
+    }
+}
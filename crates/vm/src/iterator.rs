@@ -0,0 +1,82 @@
+use crate::{
+    gc::{Cursor, HeapObject},
+    range,
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    OperationResult, VM,
+};
+
+impl VM {
+    pub(crate) fn op_iter(&mut self) -> OperationResult {
+        let iterable = self.pop_operand()?;
+
+        let iterable = match iterable {
+            RuntimeValue::Bytes(_) | RuntimeValue::String(_) | RuntimeValue::Range { .. } => {
+                iterable
+            }
+            RuntimeValue::HeapPointer(ptr)
+                if matches!(self.gc.deref(ptr), Ok(HeapObject::Array(_))) =>
+            {
+                iterable
+            }
+            _ => return self.error(RuntimeErrorCause::NotIterable),
+        };
+
+        let cursor_ptr = self.gc.allocate(HeapObject::Cursor(Cursor {
+            iterable,
+            position: 0,
+        }));
+        self.push_operand(RuntimeValue::HeapPointer(cursor_ptr));
+        Ok(())
+    }
+
+    // The iterator stays on the stack, the loop drops it once it's exhausted
+    pub(crate) fn op_iter_next(&mut self, distance: isize) -> OperationResult {
+        let cursor_ptr = match self.operands.last() {
+            Some(RuntimeValue::HeapPointer(ptr)) => *ptr,
+            _ => return self.error(RuntimeErrorCause::NotIterable),
+        };
+
+        let (iterable, position) = match self.checked(self.gc.deref(cursor_ptr))? {
+            HeapObject::Cursor(cursor) => (cursor.iterable.clone(), cursor.position),
+            _ => return self.error(RuntimeErrorCause::NotIterable),
+        };
+
+        let next = match &iterable {
+            RuntimeValue::HeapPointer(ptr) => self
+                .checked(self.gc.deref(*ptr).and_then(HeapObject::as_array))?
+                .get(position)
+                .cloned(),
+            RuntimeValue::Bytes(ptr) => self
+                .checked(self.gc.deref(*ptr).and_then(HeapObject::as_bytes))?
+                .get(position)
+                .map(|byte| RuntimeValue::Number(*byte as f64)),
+            // Strings are walked by characters, the position counts bytes
+            RuntimeValue::String(text) => text[position.min(text.len())..]
+                .chars()
+                .next()
+                .map(|char| RuntimeValue::String(char.to_string())),
+            RuntimeValue::Range {
+                start,
+                end,
+                inclusive,
+            } => range::nth(*start, *end, *inclusive, position).map(RuntimeValue::Number),
+            _ => None,
+        };
+
+        match next {
+            Some(value) => {
+                let step = match &value {
+                    RuntimeValue::String(char) => char.len(),
+                    _ => 1,
+                };
+                if let Ok(HeapObject::Cursor(cursor)) = self.gc.deref_mut(cursor_ptr) {
+                    cursor.position += step;
+                }
+                self.push_operand(value);
+                Ok(())
+            }
+            None => self.move_pointer(distance),
+        }
+    }
+}
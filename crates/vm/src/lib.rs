@@ -1,37 +1,62 @@
 use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
-use std::path::Path;
+use std::panic::{self, AssertUnwindSafe};
 
-use crate::call::CallType;
 use crate::gc::{BoundMethod, HeapObject, Object, Properties};
 use bytecode::callables::Function;
 use bytecode::stmt::{GlobalItem, GlobalPointer};
-use bytecode::{Opcode, ProgramBytecode};
+use bytecode::{MemoryAddress, Opcode, ProgramBytecode};
 use call::CallFrame;
-use common::MAIN_FUNCTION_NAME;
-use gc::{Closure, HeapPointer, GC};
-use runtime_error::{RuntimeError, RuntimeErrorCause};
+use common::ProgramText;
+use event_loop::{Timer, TimerId};
+use gc::{Closure, HeapPointer, HeapResult, GC};
+use hook::ExecutionHook;
+use host::HostFunction;
+use options::VmOptions;
+use runtime_error::{RuntimeError, RuntimeErrorCause, TraceFrame};
 use runtime_value::RuntimeValue;
+use stats::{timed, VmStats};
 
-#[macro_use]
-extern crate prettytable;
-
+pub(crate) mod array;
 pub(crate) mod basic_expr;
+pub(crate) mod bitwise;
+pub(crate) mod bytes;
 pub(crate) mod call;
 pub(crate) mod eq_ord;
+pub(crate) mod eval;
+// Timers are only fired by the event loop, which needs a clock and a way to sleep
+#[cfg_attr(not(feature = "native"), allow(dead_code, unused_imports))]
+pub(crate) mod event_loop;
 pub(crate) mod flow_control;
 pub(crate) mod gc;
 pub mod gravitas_std;
+pub mod hook;
+pub mod host;
+pub(crate) mod iterator;
+pub(crate) mod map;
 pub(crate) mod memory;
-pub(crate) mod runtime_error;
+pub mod options;
+pub(crate) mod overload;
+pub(crate) mod property;
+pub(crate) mod range;
+pub(crate) mod slice;
+pub mod runtime_error;
 pub mod runtime_value;
 pub(crate) mod stack;
+pub mod stats;
+pub mod step;
+#[cfg(feature = "serde")]
+pub mod value_serde;
 
 pub type ProgramOutput = Result<RuntimeValue, RuntimeError>;
 pub type MachineResult<T> = Result<T, RuntimeError>;
 pub type OperationResult = MachineResult<()>;
 
+// Calls nested deeper than this fail instead of growing the call stack without end
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+// Values the operand stack holds at most, locals and temporaries of every frame together
+pub const DEFAULT_STACK_CAPACITY: usize = 1 << 20;
+
 #[derive(PartialEq)]
 pub enum TickOutcome {
     FinishProgram,
@@ -39,114 +64,202 @@ pub enum TickOutcome {
     ContinueExecution,
 }
 
-#[derive(Debug)]
-struct DebugOptions {
-    file: File,
-}
-
-impl DebugOptions {
-    fn new() -> Self {
-        static DEBUG_LOG: &str = "debug.gv";
-
-        if Path::new(DEBUG_LOG).exists() {
-            std::fs::remove_file(DEBUG_LOG).unwrap();
-        }
-
-        let file = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open("debug.gv")
-            .unwrap();
-
-        Self { file }
-    }
-}
-
 #[derive(Debug)]
 pub struct VM {
     pub(crate) operands: Vec<RuntimeValue>,
     pub(crate) call_stack: Vec<CallFrame>,
-    pub(crate) ip: usize,
-    pub(crate) debug: Option<DebugOptions>,
+    pub(crate) options: VmOptions,
+    pub(crate) hook: Option<Box<dyn ExecutionHook>>,
+    pub(crate) timers: Vec<Timer>,
+    pub(crate) next_timer_id: TimerId,
+    pub(crate) host_functions: Vec<HostFunction>,
+    // Stack slots of the variables captured by closures, with the upvalues pointing at them
+    pub(crate) open_upvalues: Vec<(usize, HeapPointer)>,
+    pub(crate) stats: VmStats,
 
     pub(crate) globals: Vec<GlobalItem>,
+    // Top-level variables, they outlive the programs that defined them
+    pub(crate) global_variables: HashMap<ProgramText, RuntimeValue>,
     pub(crate) gc: GC,
 }
 
-pub fn run(bytecode: ProgramBytecode, debug: bool) -> RuntimeValue {
-    let mut vm = VM::new();
+#[cfg(feature = "native")]
+pub fn run(bytecode: ProgramBytecode, debug: bool) -> ProgramOutput {
+    let mut options = VmOptions::new();
 
     if debug {
-        vm = vm.with_debug();
+        options = options
+            .debug_log()
+            .map_err(|_| RuntimeError::new(RuntimeErrorCause::OutputFailed))?;
     }
 
-    vm.run(bytecode).expect("VM went kaboom")
+    VM::new(options).run(bytecode)
 }
 
 impl VM {
-    pub fn new() -> Self {
+    pub fn new(options: VmOptions) -> Self {
         Self {
             operands: Vec::new(),
             call_stack: vec![],
-            ip: 0,
-            debug: None,
+            options,
+            hook: None,
+            timers: vec![],
+            next_timer_id: 0,
+            host_functions: vec![],
+            open_upvalues: vec![],
+            stats: VmStats::default(),
             globals: vec![],
+            global_variables: HashMap::new(),
             gc: GC::new(),
         }
     }
 
-    pub fn with_debug(mut self) -> Self {
-        self.debug = Some(DebugOptions::new());
+    pub fn with_hook(mut self, hook: impl ExecutionHook + 'static) -> Self {
+        self.hook = Some(Box::new(hook));
         self
     }
 
-    fn error<T>(&mut self, cause: RuntimeErrorCause) -> MachineResult<T> {
-        Err(RuntimeError { cause })
+    pub fn options(&self) -> &VmOptions {
+        &self.options
+    }
+
+    // Replaces the options the VM was created with, e.g. between runs
+    pub fn set_options(&mut self, options: VmOptions) {
+        self.options = options;
+    }
+
+    fn error<T>(&self, cause: RuntimeErrorCause) -> MachineResult<T> {
+        Err(RuntimeError {
+            cause,
+            trace: self.trace(),
+        })
+    }
+
+    // Turns the broken state found on the heap or in a value into an error of the running code
+    pub(crate) fn checked<T>(&self, result: HeapResult<T>) -> MachineResult<T> {
+        result.or_else(|cause| self.error(cause))
+    }
+
+    // Every frame has moved past the opcode it runs, so that's the one before its pointer
+    fn trace(&self) -> Vec<TraceFrame> {
+        self.call_stack
+            .iter()
+            .rev()
+            .map(|frame| {
+                let offset = frame.ip.saturating_sub(1);
+                let span = self
+                    .frame_code(frame)
+                    .ok()
+                    .and_then(|function| function.chunk.span(offset).cloned());
+                TraceFrame {
+                    function: frame.name.to_string(),
+                    offset,
+                    span,
+                }
+            })
+            .collect()
     }
 
     // TODO: This probably could be hidden behind a feature flag to not
     // decrease VM's performance but since it's not a language to use
     // in real world scenario then it's fine.
     fn debug<S: std::fmt::Display + AsRef<str>>(&mut self, msg: S) {
-        if let Some(debug_options) = &mut self.debug {
-            if let Err(e) = writeln!(debug_options.file, "{}", msg) {
-                eprintln!("Couldn't write to file: {}", e);
+        for sink in &mut self.options.debug_sinks {
+            if let Err(e) = writeln!(sink, "{}", msg) {
+                eprintln!("Couldn't write the debug log: {}", e);
             }
         }
     }
 
-    pub(crate) fn current_frame(&self) -> &CallFrame {
-        self.call_stack.last().expect("Callstack is empty")
+    pub(crate) fn current_frame(&self) -> MachineResult<&CallFrame> {
+        self.call_stack
+            .last()
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorCause::EmptyCallStack))
+    }
+
+    pub(crate) fn current_frame_mut(&mut self) -> MachineResult<&mut CallFrame> {
+        self.call_stack
+            .last_mut()
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorCause::EmptyCallStack))
     }
 
     // TODO: This slows whole execution down, but it's fine for now
-    pub(crate) fn current_code(&self) -> &Function {
-        let current_frame = self.current_frame();
-
-        let fn_ptr = match self.gc.deref(current_frame.closure_ptr) {
-            HeapObject::Closure(closure) => closure.function_ptr,
-            HeapObject::BoundMethod(bound_method) => {
-                let closure_ptr = bound_method.method_ptr;
-                self.gc.deref(closure_ptr).as_closure().function_ptr
+    pub(crate) fn current_code(&self) -> MachineResult<&Function> {
+        self.frame_code(self.current_frame()?)
+    }
+
+    fn frame_code(&self, frame: &CallFrame) -> MachineResult<&Function> {
+        let fn_ptr = match self.gc.deref(frame.closure_ptr) {
+            Ok(HeapObject::Closure(closure)) => closure.function_ptr,
+            Ok(HeapObject::BoundMethod(bound_method)) => {
+                let method = self.gc.deref(bound_method.method_ptr);
+                let closure = method.and_then(HeapObject::as_closure);
+                closure.map_err(RuntimeError::new)?.function_ptr
             }
-            _ => unreachable!(),
+            _ => return Err(RuntimeError::new(RuntimeErrorCause::InternalError)),
         };
 
-        self.globals.get(fn_ptr).unwrap().as_function()
+        Ok(self.deref_global(fn_ptr)?.as_function())
     }
 
+    // Bugs of the VM end the program with an error instead of taking the application down with it.
+    // Whatever the program changed before the panic is left as it was.
     pub(crate) fn tick(&mut self) -> MachineResult<TickOutcome> {
-        let has_next_opcode = self.ip < self.current_code().chunk.opcodes_len();
+        let outcome = match panic::catch_unwind(AssertUnwindSafe(|| self.execute_next())) {
+            Ok(outcome) => outcome?,
+            Err(_) => return self.error(RuntimeErrorCause::Panicked),
+        };
+        self.check_memory_limits()?;
+        Ok(outcome)
+    }
+
+    // Checked once the opcode that went over a limit finished, so the error points at it
+    fn check_memory_limits(&mut self) -> OperationResult {
+        if self.operands.len() > self.options.stack_capacity {
+            return self.error(RuntimeErrorCause::StackOverflow);
+        }
+        if let Some(max_objects) = self.options.max_heap_objects {
+            if self.gc.len() > max_objects {
+                return self.error(RuntimeErrorCause::HeapLimit);
+            }
+        }
+        Ok(())
+    }
+
+    // The pointer of the frame is moved past the opcode before it runs, so calls return right
+    // after the opcode that made them and jumps are relative to the next opcode
+    fn execute_next(&mut self) -> MachineResult<TickOutcome> {
+        let ip = self.current_frame()?.ip;
+        let has_next_opcode = ip < self.current_code()?.chunk.opcodes_len();
         // we finish the program if no next opcode and callstack is empty
         if !has_next_opcode {
             return Ok(TickOutcome::FinishProgram);
         }
 
-        let next = self.current_code().chunk.read_opcode(self.ip);
+        let next = self.current_code()?.chunk.read_opcode(ip);
         use Opcode::*;
+        self.stats.instructions += 1;
 
         self.debug(format!("[OPCODE][NEXT]: {}", &next));
 
+        // Taken out for the duration of the call, so the hook can look at the whole VM
+        if let Some(mut hook) = self.hook.take() {
+            hook.before_instruction(self, &next);
+            self.hook = Some(hook);
+        }
+
+        self.current_frame_mut()?.ip = ip + 1;
+
+        if let Some(budget) = self.options.instruction_budget {
+            if self.stats.instructions > budget {
+                return self.error(RuntimeErrorCause::InstructionBudget);
+            }
+        }
+
+        if self.call_operator_method(&next)?.is_some() {
+            return Ok(TickOutcome::ContinueExecution);
+        }
+
         match next {
             Constant(index) => self.op_constant(index),
             Add => self.op_add(),
@@ -165,6 +278,14 @@ impl VM {
             Ge => self.op_ge(),
             Or => self.op_or(),
             And => self.op_and(),
+            Range => self.op_range(false),
+            RangeInclusive => self.op_range(true),
+            BitAnd => self.op_bitwise(|a, b| a & b),
+            BitOr => self.op_bitwise(|a, b| a | b),
+            BitXor => self.op_bitwise(|a, b| a ^ b),
+            BitNot => self.op_bit_not(),
+            Shl => self.op_shl(),
+            Shr => self.op_shr(),
             Jif(distance) => {
                 let condition = self.pop_operand()?;
                 if !condition.to_bool(self)? {
@@ -172,34 +293,35 @@ impl VM {
                 }
                 Ok(())
             }
-            Jp(distance) => {
-                self.move_pointer(distance)?;
-                // So we don't increment the IP after jumping
+            Jp(distance) => self.move_pointer(distance),
+            JumpTable(index) => self.op_jump_table(index),
+            JifEq(distance) | JifNe(distance) | JifLt(distance) | JifLe(distance)
+            | JifGt(distance) | JifGe(distance) => self.op_jif_compare(next, distance),
+            Pop(amount) => self.op_pop(amount),
+            Dup => {
+                let value = self.pop_operand()?;
+                self.push_operand(value.clone());
+                self.push_operand(value);
                 Ok(())
             }
-            Pop(amount) => self.op_pop(amount),
             Block(amount) => {
                 let block_result = self.pop_operand()?;
                 self.op_pop(amount)?;
                 self.push_operand(block_result);
                 Ok(())
             }
-            Break(distance) => {
-                self.move_pointer(distance)?;
-                Ok(())
-            }
+            Break(distance) => self.move_pointer(distance),
             Get => self.op_get(),
             Asg => self.op_asg(),
-            Call => match self.op_call()? {
-                CallType::EnterFnBody => {
-                    self.ip = 0;
-                    return Ok(TickOutcome::ContinueExecution);
-                }
-                CallType::InlineFn => Ok(()),
-            },
+            // Functions start at the beginning of their new frame, nothing is left to move
+            Call(args_count) => self.op_call(args_count).map(|_| ()),
+            CallSpread(groups) => {
+                let args_count = self.spread_args(groups)?;
+                self.op_call(args_count).map(|_| ())
+            }
             Return => {
                 let result = self.pop_operand()?;
-                self.remove_call_frame();
+                self.remove_call_frame()?;
                 self.push_operand(result);
                 Ok(())
             }
@@ -211,39 +333,50 @@ impl VM {
                 let mut upvalues = vec![];
 
                 for _ in 0..upvalues_count {
-                    let upvalue_address = self.pop_operand()?.as_address();
-                    let upvalue = self.get_variable(upvalue_address.clone())?;
-                    let upvalue_ptr = self.gc.allocate(HeapObject::Value(upvalue));
+                    let upvalue_ptr = match self.pop_address()? {
+                        // Closures created inside of a closure share the variables it captured
+                        MemoryAddress::Upvalue { index, .. } => self.upvalue_ptr(index)?,
+                        MemoryAddress::Local(local_address) => self.capture_local(local_address)?,
+                        address => {
+                            let upvalue = self.get_variable(address)?;
+                            self.gc.allocate(HeapObject::Value(upvalue))
+                        }
+                    };
                     upvalues.push(upvalue_ptr);
                 }
+                // The addresses were popped starting from the last one
+                upvalues.reverse();
 
                 let fn_ptr = self.pop_operand()?.as_global_pointer();
+                let fn_ptr = self.checked(fn_ptr)?;
                 let closure_ptr = self.make_closure(fn_ptr);
-                let closure_mut = self.gc.deref_mut(closure_ptr);
-                for upvalue in upvalues {
-                    match closure_mut {
-                        HeapObject::Closure(closure) => {
+                match self.gc.deref_mut(closure_ptr) {
+                    Ok(HeapObject::Closure(closure)) => {
+                        for upvalue in upvalues {
                             closure.close_upvalue(upvalue);
                         }
-                        _ => {
-                            todo!();
-                        }
                     }
+                    _ => return self.error(RuntimeErrorCause::InternalError),
                 }
                 self.push_operand(RuntimeValue::HeapPointer(closure_ptr));
                 Ok(())
             }
+            CloseUpvalue(local_address) => self.op_close_upvalue(local_address),
+            DefineGlobal(index) => self.op_define_global(index),
+            GetGlobal(index) => self.op_get_global(index),
+            SetGlobal(index) => self.op_set_global(index),
             CreateObject(amount) => {
                 let mut properties: Properties = HashMap::new();
 
                 let obj_ptr = self.gc.allocate(HeapObject::Object(Object::default()));
 
                 for _ in 0..amount {
-                    let name = self.pop_operand()?.as_string().clone();
+                    let name = self.pop_operand()?.as_string();
+                    let name = self.checked(name)?;
                     let mut value = self.pop_operand()?;
 
                     if let RuntimeValue::HeapPointer(method_ptr) = value {
-                        if let HeapObject::Closure(closure) = self.gc.deref(method_ptr) {
+                        if let Ok(HeapObject::Closure(_)) = self.gc.deref(method_ptr) {
                             let bound_method_ptr =
                                 self.gc.allocate(HeapObject::BoundMethod(BoundMethod {
                                     receiver: obj_ptr,
@@ -256,39 +389,35 @@ impl VM {
                     properties.insert(name, value);
                 }
 
-                let obj_mut = self.gc.deref_mut(obj_ptr).as_object_mut();
-
-                obj_mut.properties = properties;
+                match self.gc.deref_mut(obj_ptr).and_then(HeapObject::as_object_mut) {
+                    Ok(object) => object.properties = properties,
+                    Err(cause) => return self.error(cause),
+                }
 
                 self.push_operand(RuntimeValue::HeapPointer(obj_ptr));
                 Ok(())
             }
-            SetProperty(_) => {
-                let value = self.pop_operand()?;
-                let name = self.pop_operand()?.as_string().clone();
-                let obj_ptr = self.pop_operand()?.as_heap_pointer();
-                let obj = self.gc.deref_mut(obj_ptr).as_object_mut();
-                obj.set(name, value);
-                Ok(())
-            }
-            GetProperty { .. } => {
-                let name = self.pop_operand()?.as_string().clone();
-                let obj_ptr = self.pop_operand()?.as_heap_pointer();
-                let obj = self.gc.deref(obj_ptr).as_object();
-                let property = obj.get(&name).cloned().unwrap_or(RuntimeValue::Null);
-                self.push_operand(property);
-
-                Ok(())
-            }
+            SetProperty(_) => self.op_set_property(),
+            Index => self.op_index(),
+            SetIndex => self.op_set_index(),
+            Slice { inclusive } => self.op_slice(inclusive),
+            CreateArray(amount) => self.op_create_array(amount),
+            CreateSpreadArray(groups) => self.op_create_spread_array(groups),
+            Spread => self.op_spread(),
+            CreateMap(amount) => self.op_create_map(amount),
+            Iter => self.op_iter(),
+            IterNext(distance) => self.op_iter_next(distance),
+            Concat(amount) => self.op_concat(amount),
+            GetProperty { .. } => self.op_get_property(),
         }?;
 
-        self.move_pointer(1)?;
-
         Ok(TickOutcome::ContinueExecution)
     }
 
-    pub(crate) fn deref_global(&self, ptr: GlobalPointer) -> &GlobalItem {
-        self.globals.get(ptr).unwrap()
+    pub(crate) fn deref_global(&self, ptr: GlobalPointer) -> MachineResult<&GlobalItem> {
+        self.globals
+            .get(ptr)
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorCause::InternalError))
     }
 
     pub(crate) fn make_closure(&mut self, function_ptr: GlobalPointer) -> HeapPointer {
@@ -300,34 +429,30 @@ impl VM {
         self.gc.allocate(closure.into())
     }
 
-    pub(crate) fn run(&mut self, program: ProgramBytecode) -> ProgramOutput {
-        for global in &program.globals {
-            self.debug(format!("[GLOBAL][NAME={}]", global.name()));
-            self.debug(format!("{}", global));
+    // Runs the program, and its timers too when the options ask for the event loop
+    pub fn execute(&mut self, program: ProgramBytecode) -> ProgramOutput {
+        #[cfg(feature = "native")]
+        if self.options.event_loop {
+            return self.run_event_loop(program);
         }
 
-        self.globals = program.globals;
-        let closure_ptr = self.make_closure(program.global_fn_ptr);
-        let initial_frame = CallFrame {
-            stack_start: 0,
-            name: MAIN_FUNCTION_NAME.to_string(),
-            closure_ptr,
-            return_ip: 0,
-        };
-
-        self.add_call_frame(initial_frame);
+        self.run(program)
+    }
 
-        self.debug(format!(
-            "[VM][START OF EXECUTION][NAME={}]",
-            self.current_frame().name
-        ));
+    pub fn run(&mut self, program: ProgramBytecode) -> ProgramOutput {
+        self.reset_stats();
+        let (loaded, load_time) = timed(|| self.load(program));
+        self.stats.load_time = load_time;
+        loaded?;
 
-        loop {
+        let (outcome, execution_time) = timed(|| loop {
             if self.tick()? == TickOutcome::FinishProgram {
-                break;
+                break Ok(());
             }
             self.debug("[VM] TICK");
-        }
+        });
+        self.stats.execution_time += execution_time;
+        outcome?;
 
         self.debug("[VM][END OF EXECUTION]");
         let result = self.pop_operand();
@@ -339,23 +464,22 @@ impl VM {
     pub(crate) fn move_pointer(&mut self, distance: isize) -> OperationResult {
         use std::ops::Neg;
 
+        let ip = self.current_frame()?.ip;
         self.debug(format!(
             "[VM][MOVE_POINTER][IP_NOW = {}][DISTANCE = {}]",
-            self.ip, distance
+            ip, distance
         ));
 
-        if distance.is_positive() {
-            self.ip += distance as usize;
-            Ok(())
+        let new_ip = if distance.is_positive() {
+            ip + distance as usize
         } else {
-            match self.ip.checked_sub(distance.neg() as usize) {
-                Some(new_ip) => {
-                    self.ip = new_ip;
-                    Ok(())
-                }
-                None => self.error(RuntimeErrorCause::StackOverflow),
+            match ip.checked_sub(distance.neg() as usize) {
+                Some(new_ip) => new_ip,
+                None => return self.error(RuntimeErrorCause::StackOverflow),
             }
-        }
+        };
+        self.current_frame_mut()?.ip = new_ip;
+        Ok(())
     }
 }
 
@@ -366,18 +490,20 @@ mod test {
         callables::{Class, Function},
         chunk::Constant,
     };
-    use common::CONSTRUCTOR_NAME;
+    use common::{CONSTRUCTOR_NAME, MAIN_FUNCTION_NAME};
 
     pub(crate) fn main_fn(chunk: Chunk) -> Function {
         Function {
             arity: 0,
+            variadic: false,
             chunk,
-            name: MAIN_FUNCTION_NAME.to_owned(),
+            name: MAIN_FUNCTION_NAME.into(),
+            max_stack: 0,
         }
     }
 
     pub fn assert_program(code: Chunk, expected_outcome: RuntimeValue) {
-        let mut vm = VM::new();
+        let mut vm = VM::new(VmOptions::default());
         assert!(vm
             .run(main_fn(code))
             .unwrap()
@@ -389,7 +515,7 @@ mod test {
         opcode: Opcode,
     ) -> impl Fn(Constant, Constant, RuntimeErrorCause) {
         move |a: Constant, b: Constant, expected: RuntimeErrorCause| {
-            let mut vm = VM::new();
+            let mut vm = VM::new(VmOptions::default());
             let code = main_fn(Chunk::new(
                 vec![Opcode::Constant(0), Opcode::Constant(1), opcode],
                 vec![a, b],
@@ -403,7 +529,7 @@ mod test {
         opcode: Opcode,
     ) -> impl Fn(Constant, Constant, RuntimeValue) {
         move |a: Constant, b: Constant, expected: RuntimeValue| {
-            let mut vm = VM::new();
+            let mut vm = VM::new(VmOptions::default());
 
             let code = main_fn(Chunk::new(
                 vec![Opcode::Constant(0), Opcode::Constant(1), opcode],
@@ -421,8 +547,10 @@ mod test {
             name: "dummy".to_owned(),
             constructor: Function {
                 arity: 0,
+                variadic: false,
                 chunk: Chunk::default(),
-                name: CONSTRUCTOR_NAME.to_owned(),
+                name: CONSTRUCTOR_NAME.into(),
+                max_stack: 0,
             },
             super_class: None,
             methods: vec![],
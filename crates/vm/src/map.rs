@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use crate::{
+    gc::{HeapObject, HeapPointer},
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
+    OperationResult, VM,
+};
+
+impl VM {
+    pub(crate) fn op_create_map(&mut self, amount: usize) -> OperationResult {
+        let mut entries = Vec::with_capacity(amount);
+        for _ in 0..amount {
+            let key = self.pop_operand()?.as_string();
+            let key = self.checked(key)?;
+            let value = self.pop_operand()?;
+            entries.push((key, value));
+        }
+        // Entries were pushed in order, so when a key repeats the last one wins
+        let entries: HashMap<_, _> = entries.into_iter().rev().collect();
+
+        let map_ptr = self.gc.allocate(HeapObject::Map(entries));
+        self.push_operand(RuntimeValue::Map(map_ptr));
+        Ok(())
+    }
+
+    // Keys that aren't in the map read as null, like missing properties of objects
+    pub(crate) fn map_index(&mut self, map_ptr: HeapPointer, key: RuntimeValue) -> OperationResult {
+        let key = match key {
+            RuntimeValue::String(key) => key,
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+        let value = self
+            .checked(self.gc.deref(map_ptr).and_then(HeapObject::as_map))?
+            .get(&key)
+            .cloned()
+            .unwrap_or(RuntimeValue::Null);
+        self.push_operand(value);
+        Ok(())
+    }
+
+    pub(crate) fn map_set_index(
+        &mut self,
+        map_ptr: HeapPointer,
+        key: RuntimeValue,
+        value: RuntimeValue,
+    ) -> OperationResult {
+        let key = match key {
+            RuntimeValue::String(key) => key,
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+        match self.gc.deref_mut(map_ptr).and_then(HeapObject::as_map_mut) {
+            Ok(entries) => entries.insert(key, value.clone()),
+            Err(cause) => return self.error(cause),
+        };
+        self.push_operand(value);
+        Ok(())
+    }
+}
@@ -1,7 +1,13 @@
-use bytecode::MemoryAddress;
+use bytecode::{
+    chunk::{Constant, ConstantIndex},
+    MemoryAddress,
+};
+use common::ProgramText;
 
 use crate::{
-    gravitas_std::NATIVE_FUNCTIONS, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue,
+    gc::{HeapObject, HeapPointer},
+    runtime_error::RuntimeErrorCause,
+    runtime_value::RuntimeValue,
     MachineResult, OperationResult, VM,
 };
 
@@ -19,7 +25,7 @@ impl VM {
         value: RuntimeValue,
         address: MemoryAddress,
     ) -> OperationResult {
-        let stack_start = self.current_frame().stack_start;
+        let stack_start = self.current_frame()?.stack_start;
 
         self.debug(format!(
             "[STACK][ASSIGN][ADDRESS={}][VALUE={}]",
@@ -28,38 +34,41 @@ impl VM {
 
         match address {
             MemoryAddress::Local(local_address) => {
-                self.operands[stack_start + local_address as usize] = value;
+                self.set_stack_value(stack_start + local_address, value)
             }
             MemoryAddress::Upvalue { index, is_ref } => {
-                let current_closure_ptr = self
-                    .call_stack
-                    .last()
-                    .map(|frame| frame.closure_ptr)
-                    .unwrap();
-
-                let closure = self.gc.deref(current_closure_ptr).as_closure();
-
-                let mut upvalue_ptr = closure.upvalues.get(index).cloned().unwrap();
+                let mut upvalue_ptr = self.upvalue_ptr(index)?;
 
                 if is_ref {
                     while let RuntimeValue::HeapPointer(new_upvalue_ptr) =
-                        self.gc.deref(upvalue_ptr).as_value()
+                        self.checked(self.gc.deref(upvalue_ptr).and_then(HeapObject::as_value))?
                     {
                         upvalue_ptr = *new_upvalue_ptr;
                     }
                 }
 
-                *self.gc.deref_mut(upvalue_ptr) = value.into();
+                match self.open_upvalue_slot(upvalue_ptr) {
+                    Some(slot) => self.set_stack_value(slot, value),
+                    None => match self.gc.deref_mut(upvalue_ptr) {
+                        Ok(upvalue) => {
+                            *upvalue = value.into();
+                            Ok(())
+                        }
+                        Err(cause) => self.error(cause),
+                    },
+                }
             }
-            _ => unreachable!(),
+            // Functions are only read, the compiler never assigns to them
+            _ => self.error(RuntimeErrorCause::InternalError),
         }
-        Ok(())
     }
 
     pub(crate) fn op_asg(&mut self) -> OperationResult {
         let to_assign = self.pop_operand()?;
         let address = self.pop_address()?;
-        self.assign_value(to_assign, address)?;
+        // Assignment is an expression evaluating to the assigned value
+        self.assign_value(to_assign.clone(), address)?;
+        self.push_operand(to_assign);
 
         Ok(())
     }
@@ -68,7 +77,7 @@ impl VM {
         &mut self,
         local_address: usize,
     ) -> MachineResult<RuntimeValue> {
-        let stack_start = self.current_frame().stack_start;
+        let stack_start = self.current_frame()?.stack_start;
         let stack_address = stack_start + local_address as usize;
 
         match self.operands.get(stack_address).cloned() {
@@ -83,24 +92,98 @@ impl VM {
         }
     }
 
+    // Variables and open upvalues point at slots of the stack, which have to be there
+    fn stack_value(&self, slot: usize) -> MachineResult<RuntimeValue> {
+        match self.operands.get(slot) {
+            Some(value) => Ok(value.clone()),
+            None => self.error(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    fn set_stack_value(&mut self, slot: usize, value: RuntimeValue) -> OperationResult {
+        match self.operands.get_mut(slot) {
+            Some(variable) => {
+                *variable = value;
+                Ok(())
+            }
+            None => self.error(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    // Heap value holding the variable captured by the closure that is running
+    pub(crate) fn upvalue_ptr(&mut self, upvalue_index: usize) -> MachineResult<HeapPointer> {
+        let current_closure_ptr = self.current_frame()?.closure_ptr;
+
+        let closure = self
+            .gc
+            .deref(current_closure_ptr)
+            .and_then(HeapObject::as_closure);
+        let closure = self.checked(closure)?;
+        match closure.upvalues.get(upvalue_index).cloned() {
+            Some(upvalue_ptr) => Ok(upvalue_ptr),
+            None => self.error(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    // Open upvalues are read from the stack slot of the variable, until the variable leaves the stack
+    fn open_upvalue_slot(&self, upvalue_ptr: HeapPointer) -> Option<usize> {
+        self.open_upvalues
+            .iter()
+            .find(|(_, open_ptr)| *open_ptr == upvalue_ptr)
+            .map(|(slot, _)| *slot)
+    }
+
+    // Closures capturing the same variable share its upvalue, so they see each other's assignments
+    pub(crate) fn capture_local(&mut self, local_address: usize) -> MachineResult<HeapPointer> {
+        let slot = self.current_frame()?.stack_start + local_address;
+        if let Some((_, upvalue_ptr)) = self.open_upvalues.iter().find(|(open, _)| *open == slot) {
+            return Ok(*upvalue_ptr);
+        }
+
+        let value = self.stack_value(slot)?;
+        let upvalue_ptr = self.gc.allocate(HeapObject::Value(value));
+        self.open_upvalues.push((slot, upvalue_ptr));
+        Ok(upvalue_ptr)
+    }
+
+    // Copies the variables from the stack slot onwards into their upvalues
+    pub(crate) fn close_upvalues(&mut self, from_slot: usize) -> OperationResult {
+        let (closed, open) = std::mem::take(&mut self.open_upvalues)
+            .into_iter()
+            .partition(|(slot, _)| *slot >= from_slot);
+        self.open_upvalues = open;
+
+        for (slot, upvalue_ptr) in closed {
+            let value = self.stack_value(slot)?;
+            match self.gc.deref_mut(upvalue_ptr) {
+                Ok(upvalue) => *upvalue = value.into(),
+                Err(cause) => return self.error(cause),
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn op_close_upvalue(&mut self, local_address: usize) -> OperationResult {
+        let from_slot = self.current_frame()?.stack_start + local_address;
+        self.close_upvalues(from_slot)
+    }
+
     pub(crate) fn get_upvalue(
         &mut self,
         upvalue_index: usize,
         is_ref: bool,
     ) -> MachineResult<RuntimeValue> {
-        let current_closure_ptr = self
-            .call_stack
-            .last()
-            .map(|frame| frame.closure_ptr)
-            .unwrap();
-
-        let closure = self.gc.deref(current_closure_ptr).as_closure();
-        let upvalue_ptr = closure.upvalues.get(upvalue_index).cloned().unwrap();
-        let mut upvalue = self.gc.deref(upvalue_ptr).as_value();
+        let upvalue_ptr = self.upvalue_ptr(upvalue_index)?;
+        if let Some(slot) = self.open_upvalue_slot(upvalue_ptr) {
+            return self.stack_value(slot);
+        }
+        let mut upvalue =
+            self.checked(self.gc.deref(upvalue_ptr).and_then(HeapObject::as_value))?;
 
         if is_ref {
             while let RuntimeValue::HeapPointer(upvalue_ptr) = upvalue {
-                upvalue = self.gc.deref(*upvalue_ptr).as_value();
+                upvalue =
+                    self.checked(self.gc.deref(*upvalue_ptr).and_then(HeapObject::as_value))?;
             }
         }
 
@@ -114,7 +197,50 @@ impl VM {
             MemoryAddress::BuiltInFunction(built_in_function) => {
                 Ok(RuntimeValue::NativeFunction(built_in_function))
             }
+            MemoryAddress::HostFunction(index) => Ok(RuntimeValue::HostFunction(index)),
+        }
+    }
+
+    fn global_name(&mut self, index: ConstantIndex) -> MachineResult<ProgramText> {
+        match self.current_code()?.chunk.read(index) {
+            Constant::String(name) => Ok(name),
+            _ => self.error(RuntimeErrorCause::InternalError),
+        }
+    }
+
+    pub(crate) fn op_define_global(&mut self, index: ConstantIndex) -> OperationResult {
+        let value = self.pop_operand()?;
+        let name = self.global_name(index)?;
+        self.global_variables.insert(name, value);
+        Ok(())
+    }
+
+    pub(crate) fn op_get_global(&mut self, index: ConstantIndex) -> OperationResult {
+        let name = self.global_name(index)?;
+        match self.global_variables.get(&name).cloned() {
+            Some(value) => {
+                self.push_operand(value);
+                Ok(())
+            }
+            None => self.error(RuntimeErrorCause::UndefinedGlobal),
+        }
+    }
+
+    pub(crate) fn op_set_global(&mut self, index: ConstantIndex) -> OperationResult {
+        let name = self.global_name(index)?;
+        let value = self.pop_operand()?;
+        match self.global_variables.get_mut(&name) {
+            Some(global) => *global = value.clone(),
+            None => return self.error(RuntimeErrorCause::UndefinedGlobal),
         }
+        // Assignment is an expression evaluating to the assigned value
+        self.push_operand(value);
+        Ok(())
+    }
+
+    // Names of the top-level variables defined by the programs that ran so far
+    pub fn global_names(&self) -> Vec<ProgramText> {
+        self.global_variables.keys().cloned().collect()
     }
 
     pub(crate) fn op_get(&mut self) -> OperationResult {
@@ -127,7 +253,9 @@ impl VM {
 
 #[cfg(test)]
 mod test {
-    use crate::{runtime_value::RuntimeValue, test::main_fn, OperationResult, VM};
+    use crate::{
+        options::VmOptions, runtime_value::RuntimeValue, test::main_fn, OperationResult, VM,
+    };
     use bytecode::{
         chunk::{Chunk, Constant},
         MemoryAddress, Opcode,
@@ -149,7 +277,7 @@ mod test {
             ],
         ));
 
-        let mut vm = VM::new();
+        let mut vm = VM::new(VmOptions::default());
 
         // let's push the constants onto the stack
         vm.tick()?;
@@ -169,7 +297,7 @@ mod test {
 
     #[test]
     fn op_get() -> OperationResult {
-        let mut vm = VM::new();
+        let mut vm = VM::new(VmOptions::default());
         let mut code = main_fn(Chunk::new(
             vec![Opcode::Constant(0), Opcode::Constant(1), Opcode::Get],
             vec![
@@ -195,7 +323,7 @@ mod test {
 
     #[test]
     fn op_asg() -> OperationResult {
-        let mut vm = VM::new();
+        let mut vm = VM::new(VmOptions::default());
         let mut code = main_fn(Chunk::new(
             vec![
                 Opcode::Constant(0),
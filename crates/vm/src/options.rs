@@ -0,0 +1,163 @@
+#[cfg(feature = "native")]
+use std::fs::File;
+use std::{
+    fmt,
+    io::{self, BufRead, Cursor, Write},
+};
+
+use crate::{DEFAULT_MAX_CALL_DEPTH, DEFAULT_STACK_CAPACITY};
+
+// Limits, capabilities and debugging of a VM,
+// e.g. VM::new(VmOptions::new().instruction_budget(1_000_000))
+pub struct VmOptions {
+    pub(crate) allow_eval: bool,
+    #[cfg(feature = "native")]
+    pub(crate) event_loop: bool,
+    pub(crate) stack_capacity: usize,
+    pub(crate) max_call_depth: usize,
+    pub(crate) max_heap_objects: Option<usize>,
+    pub(crate) instruction_budget: Option<u64>,
+    pub(crate) debug_sinks: Vec<Box<dyn Write>>,
+    pub(crate) output: Box<dyn Write>,
+    pub(crate) input: Input,
+}
+
+// Where read_line() takes the lines from
+pub(crate) enum Input {
+    Stdin,
+    Reader(Box<dyn BufRead>),
+    // Called for every line, None ends the input
+    Callback(Box<dyn FnMut() -> Option<String>>),
+}
+
+impl Input {
+    // The next line without its line break, None once the input has ended
+    pub(crate) fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = match self {
+            Self::Stdin => io::stdin().read_line(&mut line)?,
+            Self::Reader(reader) => reader.read_line(&mut line)?,
+            Self::Callback(callback) => return Ok(callback()),
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+}
+
+impl Default for VmOptions {
+    fn default() -> Self {
+        Self {
+            allow_eval: false,
+            #[cfg(feature = "native")]
+            event_loop: false,
+            stack_capacity: DEFAULT_STACK_CAPACITY,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_heap_objects: None,
+            instruction_budget: None,
+            debug_sinks: vec![],
+            output: Box::new(io::stdout()),
+            input: Input::Stdin,
+        }
+    }
+}
+
+impl fmt::Debug for VmOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut options = f.debug_struct("VmOptions");
+        options.field("allow_eval", &self.allow_eval);
+        #[cfg(feature = "native")]
+        options.field("event_loop", &self.event_loop);
+        options
+            .field("stack_capacity", &self.stack_capacity)
+            .field("max_call_depth", &self.max_call_depth)
+            .field("max_heap_objects", &self.max_heap_objects)
+            .field("instruction_budget", &self.instruction_budget)
+            .field("debug_sinks", &self.debug_sinks.len())
+            .field("output", &"<output>")
+            .field("input", &"<input>")
+            .finish()
+    }
+}
+
+impl VmOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Allows the code to compile and run other code with eval()
+    pub fn allow_eval(mut self) -> Self {
+        self.allow_eval = true;
+        self
+    }
+
+    // VM::execute keeps running until every timer fired, instead of returning after the program
+    #[cfg(feature = "native")]
+    pub fn event_loop(mut self) -> Self {
+        self.event_loop = true;
+        self
+    }
+
+    // Most values the operand stack can hold, pushing past it is a stack overflow
+    pub fn stack_capacity(mut self, capacity: usize) -> Self {
+        self.stack_capacity = capacity;
+        self
+    }
+
+    // Most frames the call stack can hold, the program itself takes one of them
+    pub fn max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    // Most objects the heap can hold, it's not limited by default
+    pub fn max_heap_objects(mut self, objects: usize) -> Self {
+        self.max_heap_objects = Some(objects);
+        self
+    }
+
+    // Most opcodes a single run can execute, so programs that never end can be stopped
+    pub fn instruction_budget(mut self, instructions: u64) -> Self {
+        self.instruction_budget = Some(instructions);
+        self
+    }
+
+    // Every executed opcode and call is logged to the sink, one line each
+    pub fn debug_sink(mut self, sink: impl Write + 'static) -> Self {
+        self.debug_sinks.push(Box::new(sink));
+        self
+    }
+
+    // Where print() writes to, the standard output by default
+    pub fn output(mut self, sink: impl Write + 'static) -> Self {
+        self.output = Box::new(sink);
+        self
+    }
+
+    // read_line() returns the lines of the text instead of reading the standard input
+    pub fn input_text(mut self, text: impl Into<String>) -> Self {
+        self.input = Input::Reader(Box::new(Cursor::new(text.into())));
+        self
+    }
+
+    // read_line() asks the callback for every line, returning None ends the input
+    pub fn input_callback(mut self, callback: impl FnMut() -> Option<String> + 'static) -> Self {
+        self.input = Input::Callback(Box::new(callback));
+        self
+    }
+
+    // Logs the execution to a debug.gv file in the working directory, fails when it can't be created
+    #[cfg(feature = "native")]
+    pub fn debug_log(self) -> io::Result<Self> {
+        let file = File::create("debug.gv")?;
+        Ok(self.debug_sink(file))
+    }
+}
@@ -0,0 +1,79 @@
+use bytecode::Opcode;
+
+use crate::{
+    call::CallType, gc::HeapObject, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue,
+    MachineResult, VM,
+};
+
+// Method an object can define to decide what the operator does with it
+fn method_name(opcode: &Opcode) -> Option<&'static str> {
+    Some(match opcode {
+        Opcode::Add => "op_add",
+        Opcode::Sub => "op_sub",
+        Opcode::Mul => "op_mul",
+        Opcode::Div => "op_div",
+        Opcode::Mod => "op_mod",
+        Opcode::Pow => "op_pow",
+        Opcode::Eq => "op_eq",
+        Opcode::Ne => "op_ne",
+        Opcode::Lt => "op_lt",
+        Opcode::Le => "op_le",
+        Opcode::Gt => "op_gt",
+        Opcode::Ge => "op_ge",
+        // Fused jumps call the method of their comparison and let the Jif after them decide
+        _ => return opcode.fused_comparison().and_then(|comparison| method_name(&comparison)),
+    })
+}
+
+impl VM {
+    // The left operand decides, so `obj + 1` calls obj.op_add(1) while `1 + obj` doesn't.
+    // Returns None when there's no such method and the operator should run as usual.
+    pub(crate) fn call_operator_method(
+        &mut self,
+        opcode: &Opcode,
+    ) -> MachineResult<Option<CallType>> {
+        let name = match method_name(opcode) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let lhs = match self.operands.len().checked_sub(2) {
+            Some(index) => &self.operands[index],
+            None => return Ok(None),
+        };
+        let method = match lhs {
+            RuntimeValue::HeapPointer(ptr) => match self.checked(self.gc.deref(*ptr))? {
+                HeapObject::Object(object) => object.get_method(name).cloned(),
+                _ => None,
+            },
+            _ => None,
+        };
+        let method = match method {
+            Some(method) => method,
+            None => return Ok(None),
+        };
+
+        // Checked before the operands are taken, so the error points at the operator
+        if !self.takes_one_argument(&method)? {
+            return self.error(RuntimeErrorCause::WrongArgumentCount);
+        }
+
+        let (_, rhs) = self.pop_two_operands()?;
+        self.push_operand(rhs);
+        self.push_operand(method);
+        self.op_call(1).map(Some)
+    }
+
+    // Operator methods get the right operand only. Values that aren't closures are left
+    // for the call to reject.
+    fn takes_one_argument(&mut self, method: &RuntimeValue) -> MachineResult<bool> {
+        let function_ptr = match method {
+            RuntimeValue::HeapPointer(ptr) => match self.checked(self.gc.deref(*ptr))? {
+                HeapObject::Closure(closure) => closure.function_ptr,
+                _ => return Ok(true),
+            },
+            _ => return Ok(true),
+        };
+        let function = self.deref_global(function_ptr)?.as_function();
+        Ok(function.arity == 1 || (function.variadic && function.arity <= 1))
+    }
+}
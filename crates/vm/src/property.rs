@@ -0,0 +1,45 @@
+use crate::{
+    gc::HeapObject, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, OperationResult,
+    VM,
+};
+
+impl VM {
+    // Objects and maps have properties, `map.key` reads the same entry as `map["key"]`
+    pub(crate) fn op_get_property(&mut self) -> OperationResult {
+        let name = self.pop_operand()?;
+        let target = self.pop_operand()?;
+        let obj_ptr = match target {
+            RuntimeValue::Map(map_ptr) => return self.map_index(map_ptr, name),
+            RuntimeValue::HeapPointer(obj_ptr) => obj_ptr,
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+        let property = match (self.checked(self.gc.deref(obj_ptr))?, name) {
+            (HeapObject::Object(obj), RuntimeValue::String(name)) => {
+                obj.get(&name).cloned().unwrap_or(RuntimeValue::Null)
+            }
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+        self.push_operand(property);
+        Ok(())
+    }
+
+    pub(crate) fn op_set_property(&mut self) -> OperationResult {
+        let value = self.pop_operand()?;
+        let name = self.pop_operand()?;
+        let target = self.pop_operand()?;
+        let obj_ptr = match target {
+            RuntimeValue::Map(map_ptr) => return self.map_set_index(map_ptr, name, value),
+            RuntimeValue::HeapPointer(obj_ptr) => obj_ptr,
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+        match (self.gc.deref_mut(obj_ptr), name) {
+            (Ok(HeapObject::Object(obj)), RuntimeValue::String(name)) => {
+                obj.set(name, value.clone())
+            }
+            (Err(cause), _) => return self.error(cause),
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        }
+        self.push_operand(value);
+        Ok(())
+    }
+}
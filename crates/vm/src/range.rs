@@ -0,0 +1,51 @@
+use common::Number;
+
+use crate::{runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, OperationResult, VM};
+
+// Number at the `position` of the range, `None` once the range is over
+pub(crate) fn nth(start: Number, end: Number, inclusive: bool, position: usize) -> Option<Number> {
+    let value = start + position as Number;
+    let in_range = if inclusive { value <= end } else { value < end };
+    in_range.then_some(value)
+}
+
+impl VM {
+    pub(crate) fn op_range(&mut self, inclusive: bool) -> OperationResult {
+        let (start, end) = match self.pop_two_operands()? {
+            (RuntimeValue::Number(start), RuntimeValue::Number(end)) => (start, end),
+            _ => return self.error(RuntimeErrorCause::ExpectedNumber),
+        };
+
+        self.push_operand(RuntimeValue::Range {
+            start,
+            end,
+            inclusive,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn range_index(
+        &mut self,
+        start: Number,
+        end: Number,
+        inclusive: bool,
+        position: Number,
+    ) -> OperationResult {
+        if position.fract() != 0.0 {
+            return self.error(RuntimeErrorCause::MismatchedTypes);
+        }
+
+        let value = if position < 0.0 {
+            None
+        } else {
+            nth(start, end, inclusive, position as usize)
+        };
+        match value {
+            Some(value) => {
+                self.push_operand(RuntimeValue::Number(value));
+                Ok(())
+            }
+            None => self.error(RuntimeErrorCause::IndexOutOfBounds),
+        }
+    }
+}
@@ -1,6 +1,62 @@
-#[derive(Clone, Copy, Debug, PartialEq)]
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use bytecode::VerificationError;
+use common::{CompilerDiagnostic, ProgramText};
+use parser::parse::Span;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct RuntimeError {
     pub cause: RuntimeErrorCause,
+    // Functions that were running when the error happened, starting from the innermost one
+    pub trace: Vec<TraceFrame>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceFrame {
+    pub function: ProgramText,
+    // Opcode the function was running, for the callers it's the call
+    pub offset: usize,
+    // Code the opcode was generated from, None when the bytecode has no debug info
+    pub span: Option<Span>,
+}
+
+impl TraceFrame {
+    fn location(&self, source: Option<&str>) -> String {
+        match (&self.span, source) {
+            (Some(span), Some(source)) if span.start <= source.len() => {
+                let line = source[..span.start].matches('\n').count() + 1;
+                format!("at line {}", line)
+            }
+            _ => format!("at opcode {}", self.offset),
+        }
+    }
+}
+
+impl RuntimeError {
+    pub fn new(cause: RuntimeErrorCause) -> Self {
+        Self {
+            cause,
+            trace: vec![],
+        }
+    }
+
+    // One line for every function, e.g. "in foo at line 3" followed by "called from main at
+    // line 12". Lines are only known with the source of the program, opcodes are shown otherwise.
+    pub fn backtrace(&self, source: Option<&str>) -> String {
+        self.trace
+            .iter()
+            .enumerate()
+            .map(|(depth, frame)| {
+                let prefix = if depth == 0 { "in" } else { "called from" };
+                format!("{} {} {}", prefix, frame.function, frame.location(source))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Code of the opcode that failed, None when the bytecode has no debug info
+    pub fn span(&self) -> Option<&Span> {
+        self.trace.first().and_then(|frame| frame.span.as_ref())
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -8,7 +64,68 @@ pub enum RuntimeErrorCause {
     PoppedFromEmptyStack,
     MismatchedTypes,
     StackOverflow,
+    // Calls were nested deeper than the VM allows
+    RecursionLimit,
+    // The heap holds more objects than the VM allows
+    HeapLimit,
+    // The run executed more opcodes than the VM allows
+    InstructionBudget,
     ExpectedNumber,
     ExpectedAddress,
     NotCallable,
+    // A variadic function got fewer arguments than it has positional parameters
+    MissingArguments,
+    // A function that isn't variadic got more or fewer arguments than it has parameters
+    WrongArgumentCount,
+    // Only arrays, strings, bytes and ranges can be looped over with `for ... in`
+    NotIterable,
+    EvalNotAllowed,
+    EvalCompilationFailed,
+    IndexOutOfBounds,
+    // Memory for a new value couldn't be allocated, e.g. for a buffer of a billion gigabytes
+    OutOfMemory,
+    InvalidUtf8,
+    // A function registered by the embedding application reported an error
+    HostFunctionFailed,
+    // print() couldn't write to the output of the VM, e.g. because the pipe was closed
+    OutputFailed,
+    // read_line() couldn't read the input of the VM, e.g. because it wasn't UTF-8
+    InputFailed,
+    // Read or assigned a top-level variable before its declaration ran
+    UndefinedGlobal,
+    // The program was rejected before any of its code ran
+    InvalidBytecode(VerificationError),
+    // Code ran without a program loaded, or after the frame of the program was removed
+    EmptyCallStack,
+    // The VM got into a state verified bytecode never leads to, e.g. a global pointer to nowhere
+    InternalError,
+    // A bug of the VM caused a panic, which was caught before it reached the embedding application
+    Panicked,
+}
+
+// The failing opcode is marked along with the calls that led to it, a recursion marks every call
+// only once. Without spans the functions that were running are listed in a note instead.
+impl CompilerDiagnostic for RuntimeError {
+    fn report(&self, file_id: usize) -> Diagnostic<usize> {
+        let diagnostic =
+            Diagnostic::error().with_message(format!("Runtime error: {:?}", self.cause));
+        let mut labels: Vec<Label<usize>> = vec![];
+        if let Some(span) = self.span() {
+            labels.push(Label::primary(file_id, span.clone()));
+        }
+        for (callee, caller) in self.trace.iter().zip(self.trace.iter().skip(1)) {
+            match &caller.span {
+                Some(span) if !labels.iter().any(|label| label.range == *span) => labels.push(
+                    Label::secondary(file_id, span.clone())
+                        .with_message(format!("{} was called here", callee.function)),
+                ),
+                _ => {}
+            }
+        }
+        let diagnostic = diagnostic.with_labels(labels);
+        if self.trace.is_empty() || self.span().is_some() {
+            return diagnostic;
+        }
+        diagnostic.with_notes(vec![self.backtrace(None)])
+    }
 }
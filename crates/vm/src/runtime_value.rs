@@ -1,7 +1,7 @@
 use bytecode::{chunk::Constant, stmt::GlobalPointer, MemoryAddress};
 use common::{BuiltInFunction, Number, ProgramText};
 
-use crate::gc::HeapPointer;
+use crate::{gc::HeapPointer, runtime_error::RuntimeErrorCause};
 use std::fmt;
 
 #[derive(Debug, Clone)]
@@ -12,37 +12,49 @@ pub enum RuntimeValue {
     MemoryAddress(MemoryAddress),
     GlobalPointer(GlobalPointer),
     HeapPointer(HeapPointer),
+    // Binary buffer living on the heap, so writes are visible through every copy of the value
+    Bytes(HeapPointer),
+    // String keyed hash map on the heap, shared by every copy of the value like the bytes
+    Map(HeapPointer),
+    // start..end or start..=end, numbers are counted from the start by one
+    Range {
+        start: Number,
+        end: Number,
+        inclusive: bool,
+    },
     NativeFunction(BuiltInFunction),
+    // Index of a function registered with VM::register_host_function
+    HostFunction(usize),
     // This will be an object instance of an Option in the future
     Null,
 }
 
 impl RuntimeValue {
-    pub fn as_global_pointer(self) -> GlobalPointer {
+    pub fn as_global_pointer(self) -> Result<GlobalPointer, RuntimeErrorCause> {
         match self {
-            RuntimeValue::GlobalPointer(ptr) => ptr,
-            x => panic!("Expected pointer, got {}", x),
+            RuntimeValue::GlobalPointer(ptr) => Ok(ptr),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_heap_pointer(self) -> HeapPointer {
+    pub fn as_heap_pointer(self) -> Result<HeapPointer, RuntimeErrorCause> {
         match self {
-            RuntimeValue::HeapPointer(ptr) => ptr,
-            x => panic!("Expected pointer, got {}", x),
+            RuntimeValue::HeapPointer(ptr) => Ok(ptr),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_address(self) -> MemoryAddress {
+    pub fn as_address(self) -> Result<MemoryAddress, RuntimeErrorCause> {
         match self {
-            RuntimeValue::MemoryAddress(address) => address,
-            x => panic!("Expected address, got {}", x),
+            RuntimeValue::MemoryAddress(address) => Ok(address),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 
-    pub fn as_string(self) -> ProgramText {
+    pub fn as_string(self) -> Result<ProgramText, RuntimeErrorCause> {
         match self {
-            RuntimeValue::String(text) => text,
-            x => panic!("Expected string, got {}", x),
+            RuntimeValue::String(text) => Ok(text),
+            _ => Err(RuntimeErrorCause::InternalError),
         }
     }
 }
@@ -58,7 +70,15 @@ impl fmt::Display for RuntimeValue {
             Null => write!(f, "null"),
             GlobalPointer(ptr) => write!(f, "global ptr: {}", ptr),
             HeapPointer(ptr) => write!(f, "heap ptr: {}", ptr),
+            Bytes(ptr) => write!(f, "bytes ptr: {}", ptr),
+            Map(ptr) => write!(f, "map ptr: {}", ptr),
+            Range {
+                start,
+                end,
+                inclusive,
+            } => write!(f, "{}..{}{}", start, if *inclusive { "=" } else { "" }, end),
             NativeFunction(_) => write!(f, "native function"),
+            HostFunction(_) => write!(f, "host function"),
         }
     }
 }
@@ -71,6 +91,7 @@ impl From<Constant> for RuntimeValue {
             Constant::Bool(bl) => RuntimeValue::Bool(bl),
             Constant::MemoryAddress(address) => RuntimeValue::MemoryAddress(address),
             Constant::GlobalPointer(ptr) => RuntimeValue::GlobalPointer(ptr),
+            Constant::JumpTable(_) => unreachable!("Jump tables are only read by JumpTable"),
         }
     }
 }
@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+use common::Number;
+
+use crate::{
+    gc::HeapObject, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, MachineResult,
+    OperationResult, VM,
+};
+
+impl VM {
+    pub(crate) fn op_slice(&mut self, inclusive: bool) -> OperationResult {
+        let end = self.pop_operand()?;
+        let start = self.pop_operand()?;
+        let target = self.pop_operand()?;
+
+        let sliced = match target {
+            RuntimeValue::String(text) => {
+                // Strings are sliced by characters, like they are iterated
+                let chars: Vec<char> = text.chars().collect();
+                let range = self.slice_range(chars.len(), start, end, inclusive)?;
+                RuntimeValue::String(chars[range].iter().collect())
+            }
+            RuntimeValue::HeapPointer(ptr) => {
+                let len = match self.checked(self.gc.deref(ptr))? {
+                    HeapObject::Array(values) => values.len(),
+                    _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+                };
+                let range = self.slice_range(len, start, end, inclusive)?;
+                let values = self.checked(self.gc.deref(ptr).and_then(HeapObject::as_array))?;
+                let values = values[range].to_vec();
+                RuntimeValue::HeapPointer(self.gc.allocate(HeapObject::Array(values)))
+            }
+            _ => return self.error(RuntimeErrorCause::MismatchedTypes),
+        };
+
+        self.push_operand(sliced);
+        Ok(())
+    }
+
+    // Missing bounds are null and stand for the start and the end of the sliced value
+    fn slice_range(
+        &mut self,
+        len: usize,
+        start: RuntimeValue,
+        end: RuntimeValue,
+        inclusive: bool,
+    ) -> MachineResult<Range<usize>> {
+        let start = self.slice_bound(start, 0.0)?;
+        let end = self.slice_bound(end, len as Number)? + if inclusive { 1.0 } else { 0.0 };
+
+        if start < 0.0 || start > end || end > len as Number {
+            return self.error(RuntimeErrorCause::IndexOutOfBounds);
+        }
+
+        Ok(start as usize..end as usize)
+    }
+
+    fn slice_bound(&mut self, bound: RuntimeValue, missing: Number) -> MachineResult<Number> {
+        let bound = match bound {
+            RuntimeValue::Null => return Ok(missing),
+            bound => self.expect_number(bound)?,
+        };
+        if bound.fract() != 0.0 {
+            return self.error(RuntimeErrorCause::MismatchedTypes);
+        }
+        Ok(bound)
+    }
+}
@@ -1,27 +1,27 @@
 use bytecode::MemoryAddress;
 use common::Number;
-use prettytable::{Cell, Row, Table};
 
 use crate::{runtime_error::RuntimeErrorCause, MachineResult, RuntimeValue, VM};
 
 impl VM {
     fn debug_stack(&mut self) {
-        let mut table = Table::new();
-
-        table.add_row(row!["INDEX", "STACK VALUE"]);
-
-        for (index, value) in self.operands.iter().enumerate() {
-            table.add_row(row![index, value]);
+        if self.options.debug_sinks.is_empty() {
+            return;
         }
 
-        self.debug("");
-        self.debug(table.to_string());
+        let listing: Vec<String> = self
+            .operands
+            .iter()
+            .enumerate()
+            .map(|(index, value)| format!("{:>5}  {}", index, value))
+            .collect();
+        self.debug(format!("[STACK]\n{}", listing.join("\n")));
     }
 
-    pub(crate) fn pop_number(&mut self) -> MachineResult<Number> {
-        match self.pop_operand()? {
+    pub(crate) fn expect_number(&mut self, value: RuntimeValue) -> MachineResult<Number> {
+        match value {
             RuntimeValue::Number(num) => Ok(num),
-            _ => return self.error(RuntimeErrorCause::ExpectedNumber),
+            _ => self.error(RuntimeErrorCause::ExpectedNumber),
         }
     }
 
@@ -37,6 +37,7 @@ impl VM {
         self.debug_stack();
 
         self.operands.push(operand);
+        self.stats.peak_operands = self.stats.peak_operands.max(self.operands.len());
     }
 
     pub(crate) fn pop_operand(&mut self) -> MachineResult<RuntimeValue> {
@@ -62,11 +63,11 @@ mod test {
 
     use bytecode::chunk::Chunk;
 
-    use crate::{runtime_value::RuntimeValue, VM};
+    use crate::{options::VmOptions, runtime_value::RuntimeValue, VM};
 
     #[test]
     fn pop_operand() {
-        let mut vm = VM::new();
+        let mut vm = VM::new(VmOptions::default());
         vm.operands = vec![
             RuntimeValue::Number(10.0),
             RuntimeValue::String("foo".to_owned()),
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use crate::VM;
+
+// Counters of the last `run`, cheap enough to always be collected
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VmStats {
+    pub instructions: u64,
+    // Calls of closures, methods, native and host functions
+    pub calls: u64,
+    pub peak_operands: usize,
+    pub peak_call_depth: usize,
+    pub allocations: u64,
+    // Heap objects are never freed yet, so there are no cycles to count
+    pub gc_cycles: u64,
+    // Setting up the globals and the main frame
+    pub load_time: Duration,
+    // Running the program and, in the event loop, the timer callbacks
+    pub execution_time: Duration,
+}
+
+// Wall clock is only read natively, wasm32-unknown-unknown has none and reports zero times
+#[cfg(feature = "native")]
+pub(crate) fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = std::time::Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+#[cfg(not(feature = "native"))]
+pub(crate) fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    (f(), Duration::ZERO)
+}
+
+impl VM {
+    pub fn stats(&self) -> VmStats {
+        VmStats {
+            allocations: self.gc.allocations(),
+            ..self.stats
+        }
+    }
+
+    pub(crate) fn reset_stats(&mut self) {
+        self.stats = VmStats::default();
+        self.gc.reset_allocations();
+    }
+}
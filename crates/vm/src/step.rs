@@ -0,0 +1,91 @@
+use bytecode::{callables::Function, disassemble, verify, ProgramBytecode};
+use common::{ProgramText, Symbol, MAIN_FUNCTION_NAME};
+
+use crate::{
+    call::CallFrame, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, MachineResult,
+    TickOutcome, VM,
+};
+
+// Snapshot of a call frame for tools that inspect the running program
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    pub name: ProgramText,
+    pub stack_start: usize,
+    // Next opcode the frame runs, for callers it's the one after their call
+    pub ip: usize,
+}
+
+impl VM {
+    // Prepares the program for execution without running any of its code. Programs that fail
+    // verification are rejected before the VM changes.
+    pub fn load(&mut self, program: ProgramBytecode) -> MachineResult<()> {
+        if let Err(error) = verify(&program) {
+            return self.error(RuntimeErrorCause::InvalidBytecode(error));
+        }
+
+        for global in &program.globals {
+            self.debug(format!("[GLOBAL][NAME={}]", global.name()));
+            self.debug(disassemble(global.as_function()));
+        }
+
+        // Globals defined by the programs that ran before can hold closures of their functions,
+        // so the new functions are added after them. Nothing else is left from those programs.
+        self.close_upvalues(0)?;
+        self.operands.clear();
+        self.call_stack.clear();
+        let global_fn_ptr = self.link_program(program);
+        let closure_ptr = self.make_closure(global_fn_ptr);
+        let initial_frame = CallFrame {
+            stack_start: 0,
+            name: Symbol::intern(MAIN_FUNCTION_NAME),
+            closure_ptr,
+            ip: 0,
+        };
+
+        self.add_call_frame(initial_frame)?;
+        let max_stack = self.deref_global(global_fn_ptr)?.as_function().max_stack;
+        self.reserve_frame(max_stack)?;
+
+        self.debug(format!(
+            "[VM][START OF EXECUTION][NAME={}]",
+            MAIN_FUNCTION_NAME
+        ));
+        Ok(())
+    }
+
+    // Executes a single opcode of the loaded program
+    pub fn step(&mut self) -> MachineResult<TickOutcome> {
+        self.tick()
+    }
+
+    // Next opcode of the innermost frame
+    pub fn ip(&self) -> usize {
+        self.call_stack.last().map_or(0, |frame| frame.ip)
+    }
+
+    pub fn operands(&self) -> &[RuntimeValue] {
+        &self.operands
+    }
+
+    // Objects are never freed, so this is also the most the program ever allocated
+    pub fn heap_objects(&self) -> usize {
+        self.gc.len()
+    }
+
+    // Frames from the outermost to the innermost one
+    pub fn frames(&self) -> Vec<FrameInfo> {
+        self.call_stack
+            .iter()
+            .map(|frame| FrameInfo {
+                name: frame.name.to_string(),
+                stack_start: frame.stack_start,
+                ip: frame.ip,
+            })
+            .collect()
+    }
+
+    // None until a program is loaded
+    pub fn current_function(&self) -> Option<&Function> {
+        self.current_code().ok()
+    }
+}
@@ -0,0 +1,179 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeSeq},
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    gc::{HeapObject, Object},
+    runtime_value::RuntimeValue,
+    VM,
+};
+
+// Runtime value together with the heap it points into. Objects and maps are serialized as maps,
+// arrays as sequences and byte buffers as bytes, functions and addresses can't be serialized.
+pub struct SerializeValue<'vm> {
+    vm: &'vm VM,
+    value: &'vm RuntimeValue,
+}
+
+impl Serialize for SerializeValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let vm = self.vm;
+
+        match self.value {
+            RuntimeValue::Number(number) => serializer.serialize_f64(*number),
+            RuntimeValue::String(text) => serializer.serialize_str(text),
+            RuntimeValue::Bool(value) => serializer.serialize_bool(*value),
+            RuntimeValue::Null => serializer.serialize_unit(),
+            // Bytes and maps point at heap objects of their own kind
+            RuntimeValue::Bytes(ptr) | RuntimeValue::Map(ptr) | RuntimeValue::HeapPointer(ptr) => {
+                let object = vm.gc.deref(*ptr).map_err(|_| {
+                    ser::Error::custom("value points outside of the heap of the VM")
+                })?;
+                match object {
+                    HeapObject::Value(value) => vm.serializable(value).serialize(serializer),
+                    HeapObject::Object(object) => {
+                        serialize_entries(vm, &object.properties, serializer)
+                    }
+                    HeapObject::Map(entries) => serialize_entries(vm, entries, serializer),
+                    HeapObject::Closure(_) | HeapObject::BoundMethod(_) => {
+                        Err(ser::Error::custom("functions can't be serialized"))
+                    }
+                    HeapObject::Bytes(bytes) => serializer.serialize_bytes(bytes),
+                    HeapObject::Array(values) => {
+                        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                        for value in values {
+                            seq.serialize_element(&vm.serializable(value))?;
+                        }
+                        seq.end()
+                    }
+                    HeapObject::Cursor(_) => {
+                        Err(ser::Error::custom("iterators can't be serialized"))
+                    }
+                }
+            }
+            value => Err(ser::Error::custom(format!("{} can't be serialized", value))),
+        }
+    }
+}
+
+fn serialize_entries<S: Serializer>(
+    vm: &VM,
+    entries: &HashMap<String, RuntimeValue>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    // Entries are kept in a hash map, sorting them keeps the output stable
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by_key(|(name, _)| *name);
+
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (name, value) in entries {
+        map.serialize_entry(name, &vm.serializable(value))?;
+    }
+    map.end()
+}
+
+// Allocates the objects and byte buffers it reads on the heap of the VM
+pub struct ValueSeed<'vm> {
+    vm: &'vm mut VM,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = RuntimeValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_> {
+    type Value = RuntimeValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a number, string, bool, null, map or bytes")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::Number(value as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::Number(value as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::Number(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::String(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::String(value))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(RuntimeValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(self.vm.allocate_bytes(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(self.vm.allocate_bytes(bytes))
+    }
+
+    // Formats like JSON write byte buffers as arrays of numbers, so sequences are read back as
+    // byte buffers rather than arrays
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Ok(self.vm.allocate_bytes(bytes))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let vm = self.vm;
+        let mut object = Object::default();
+
+        while let Some(name) = map.next_key::<String>()? {
+            let value = map.next_value_seed(ValueSeed { vm: &mut *vm })?;
+            object.set(name, value);
+        }
+
+        Ok(RuntimeValue::HeapPointer(
+            vm.gc.allocate(HeapObject::Object(object)),
+        ))
+    }
+}
+
+impl VM {
+    pub fn serializable<'vm>(&'vm self, value: &'vm RuntimeValue) -> SerializeValue<'vm> {
+        SerializeValue { vm: self, value }
+    }
+
+    pub fn deserialize_value<'de, D: Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<RuntimeValue, D::Error> {
+        ValueSeed { vm: self }.deserialize(deserializer)
+    }
+}
@@ -0,0 +1,78 @@
+use bytecode::{generate_bytecode, generate_bytecode_with_options, Options};
+use parser::parse;
+use vm::{
+    options::VmOptions,
+    runtime_error::{RuntimeError, RuntimeErrorCause},
+    VM,
+};
+
+const NESTED: &str = "fn inner(x) {
+    x + true
+}
+fn outer() {
+    inner(1)
+}
+outer();";
+
+fn fail(code: &str, options: Options) -> RuntimeError {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode_with_options(ast, options).expect("Program should compile");
+    VM::new(VmOptions::default()).run(bytecode).unwrap_err()
+}
+
+#[test]
+fn records_the_functions_that_were_running() {
+    let error = fail(NESTED, Options::default());
+    assert_eq!(error.cause, RuntimeErrorCause::MismatchedTypes);
+    let functions: Vec<_> = error
+        .trace
+        .iter()
+        .map(|frame| &frame.function[..])
+        .collect();
+    assert_eq!(functions, ["inner", "outer", "main"]);
+}
+
+#[test]
+fn renders_lines_of_the_source() {
+    let error = fail(NESTED, Options::default());
+    assert_eq!(
+        error.backtrace(Some(NESTED)),
+        "in inner at line 2\ncalled from outer at line 5\ncalled from main at line 7"
+    );
+}
+
+#[test]
+fn renders_opcodes_without_lines() {
+    let error = fail(NESTED, Options::default());
+    let backtrace = error.backtrace(None);
+    let offsets: Vec<_> = error.trace.iter().map(|frame| frame.offset).collect();
+    assert_eq!(
+        backtrace,
+        format!(
+            "in inner at opcode {}\ncalled from outer at opcode {}\ncalled from main at opcode {}",
+            offsets[0], offsets[1], offsets[2]
+        )
+    );
+
+    // Without debug info there are no spans to find the lines with
+    let options = Options {
+        debug_info: false,
+        ..Options::default()
+    };
+    let error = fail(NESTED, options);
+    assert!(error.trace.iter().all(|frame| frame.span.is_none()));
+    assert!(error
+        .backtrace(Some(NESTED))
+        .starts_with("in inner at opcode"));
+}
+
+#[test]
+fn errors_outside_of_functions_only_list_the_program() {
+    let ast = parse("let a = 1;\na + false;").expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    let error = VM::new(VmOptions::default()).run(bytecode).unwrap_err();
+    assert_eq!(
+        error.backtrace(Some("let a = 1;\na + false;")),
+        "in main at line 2"
+    );
+}
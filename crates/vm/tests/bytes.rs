@@ -0,0 +1,91 @@
+use bytecode::generate_bytecode;
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, ProgramOutput, VM};
+
+fn run(code: &str) -> ProgramOutput {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    VM::new(VmOptions::default()).run(bytecode)
+}
+
+#[test]
+fn new_buffer_is_zeroed() {
+    let result = run("let b = bytes(4); b[3];").unwrap();
+    assert_eq!(result.to_string(), "0");
+}
+
+#[test]
+fn index_assignment_writes_a_byte() {
+    let result = run("let b = bytes(2); b[1] = 255; b[1];").unwrap();
+    assert_eq!(result.to_string(), "255");
+}
+
+#[test]
+fn numbers_round_trip() {
+    let result = run("let b = bytes(12); write_u32(b, 0, 4000000000); read_u32(b, 0);").unwrap();
+    assert_eq!(result.to_string(), "4000000000");
+
+    let result = run("let b = bytes(12); write_f64(b, 4, 3.5); read_f64(b, 4);").unwrap();
+    assert_eq!(result.to_string(), "3.5");
+}
+
+#[test]
+fn numbers_are_little_endian() {
+    let result = run("let b = bytes(4); write_u32(b, 0, 258); b[0] * 1000 + b[1];").unwrap();
+    assert_eq!(result.to_string(), "2001");
+}
+
+#[test]
+fn slice_copies_the_range() {
+    let result = run(
+        r#"
+        let b = bytes_from_string("gravitas");
+        let part = slice(b, 2, 5);
+        part[0] = 65;
+        bytes_to_string(part);
+    "#,
+    )
+    .unwrap();
+    assert_eq!(result.to_string(), "Avi");
+
+    let result = run(
+        r#"
+        let b = bytes_from_string("gravitas");
+        let part = slice(b, 2, 5);
+        part[0] = 65;
+        bytes_to_string(b);
+    "#,
+    )
+    .unwrap();
+    assert_eq!(result.to_string(), "gravitas");
+}
+
+#[test]
+fn out_of_bounds_access_fails() {
+    let error = run("let b = bytes(2); b[2];").unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::IndexOutOfBounds);
+
+    let error = run("let b = bytes(7); read_f64(b, 0);").unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::IndexOutOfBounds);
+}
+
+#[test]
+fn invalid_utf8_cant_become_a_string() {
+    let error = run("let b = bytes(1); b[0] = 255; bytes_to_string(b);").unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::InvalidUtf8);
+}
+
+#[test]
+fn slice_bounds_must_be_whole_numbers() {
+    let error = run("let b = bytes(4); slice(b, 0, 1.5);").unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::MismatchedTypes);
+
+    let error = run("let b = bytes(4); slice(b, 0.5, 2);").unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::MismatchedTypes);
+}
+
+#[test]
+fn huge_buffers_fail_instead_of_aborting() {
+    let error = run("bytes(1000000000000000000);").unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::OutOfMemory);
+}
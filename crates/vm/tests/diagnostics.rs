@@ -0,0 +1,68 @@
+use bytecode::{generate_bytecode_with_options, Options};
+use codespan_reporting::diagnostic::LabelStyle;
+use common::CompilerDiagnostic;
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeError, VM};
+
+const NESTED: &str = "fn inner(x) {
+    x + true
+}
+fn outer() {
+    inner(1)
+}
+outer();";
+
+fn fail(code: &str, options: Options) -> RuntimeError {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode_with_options(ast, options).expect("Program should compile");
+    VM::new(VmOptions::default()).run(bytecode).unwrap_err()
+}
+
+#[test]
+fn marks_the_failing_code() {
+    let error = fail(NESTED, Options::default());
+    let span = error.span().cloned().expect("Error should have a span");
+    assert!(NESTED[span].contains("x + true"));
+
+    let diagnostic = error.report(0);
+    assert_eq!(diagnostic.message, "Runtime error: MismatchedTypes");
+    assert_eq!(diagnostic.labels[0].style, LabelStyle::Primary);
+    assert!(diagnostic.notes.is_empty());
+}
+
+#[test]
+fn marks_the_calls_that_led_to_the_error() {
+    let diagnostic = fail(NESTED, Options::default()).report(0);
+    let calls: Vec<_> = diagnostic.labels[1..]
+        .iter()
+        .map(|label| (&NESTED[label.range.clone()], &label.message[..]))
+        .collect();
+    assert_eq!(
+        calls,
+        [
+            ("inner(1)", "inner was called here"),
+            ("outer()", "outer was called here")
+        ]
+    );
+}
+
+#[test]
+fn marks_recursive_calls_once() {
+    let code = "fn down(n) { if n == 0 { return n + true; }; down(n - 1) } down(5);";
+    let diagnostic = fail(code, Options::default()).report(0);
+    assert_eq!(diagnostic.labels.len(), 3);
+}
+
+#[test]
+fn lists_functions_without_debug_info() {
+    let options = Options {
+        debug_info: false,
+        ..Options::default()
+    };
+    let error = fail(NESTED, options);
+    assert!(error.span().is_none());
+
+    let diagnostic = error.report(0);
+    assert!(diagnostic.labels.is_empty());
+    assert_eq!(diagnostic.notes, [error.backtrace(None)]);
+}
@@ -0,0 +1,29 @@
+use bytecode::generate_bytecode;
+use parser::parse;
+use vm::{options::VmOptions, runtime_value::RuntimeValue, VM};
+
+fn run(code: &str) -> RuntimeValue {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    VM::new(VmOptions::default())
+        .run(bytecode)
+        .expect("Program should run")
+}
+
+#[test]
+fn error_carries_message() {
+    let result = run(r#"let e = Error("went wrong"); e.message;"#);
+    assert_eq!(result.to_string(), "went wrong");
+}
+
+#[test]
+fn error_has_no_cause_by_default() {
+    let result = run(r#"let e = Error("went wrong"); e.cause;"#);
+    assert_eq!(result.to_string(), "null");
+}
+
+#[test]
+fn error_captures_stack_trace() {
+    let result = run(r#"fn fail() { Error("went wrong") } let e = fail(); e.stack;"#);
+    assert_eq!(result.to_string(), "at fail\nat main");
+}
@@ -0,0 +1,58 @@
+use bytecode::generate_bytecode;
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, ProgramOutput, VM};
+
+fn run(code: &str, mut vm: VM) -> ProgramOutput {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    vm.run(bytecode)
+}
+
+#[test]
+fn eval_returns_value_of_the_code() {
+    let result = run(r#"eval("2 + 3;");"#, VM::new(VmOptions::new().allow_eval())).unwrap();
+    assert_eq!(result.to_string(), "5");
+
+    let result = run(
+        r#"eval("let x = 10; x * 2;");"#,
+        VM::new(VmOptions::new().allow_eval()),
+    )
+    .unwrap();
+    assert_eq!(result.to_string(), "20");
+}
+
+#[test]
+fn eval_doesnt_disturb_surrounding_code() {
+    let result = run(
+        r#"let a = 1; let b = eval("let c = 5; c + 5;"); a + b;"#,
+        VM::new(VmOptions::new().allow_eval()),
+    )
+    .unwrap();
+    assert_eq!(result.to_string(), "11");
+}
+
+#[test]
+fn eval_can_declare_functions() {
+    let result = run(
+        r#"eval("fn double(x) { x * 2 } double(21);");"#,
+        VM::new(VmOptions::new().allow_eval()),
+    )
+    .unwrap();
+    assert_eq!(result.to_string(), "42");
+}
+
+#[test]
+fn eval_must_be_allowed() {
+    let error = run(r#"eval("2 + 3;");"#, VM::new(VmOptions::default())).unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::EvalNotAllowed);
+}
+
+#[test]
+fn eval_reports_invalid_code() {
+    let error = run(
+        r#"eval("let = ;");"#,
+        VM::new(VmOptions::new().allow_eval()),
+    )
+    .unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::EvalCompilationFailed);
+}
@@ -0,0 +1,46 @@
+use std::{cell::Cell, rc::Rc};
+
+use bytecode::generate_bytecode;
+use parser::parse;
+use vm::{options::VmOptions, ProgramOutput, VM};
+
+fn run(code: &str, options: VmOptions) -> ProgramOutput {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    VM::new(options).run(bytecode)
+}
+
+#[test]
+fn reads_lines_of_the_text() {
+    let options = VmOptions::new().input_text("Ada\r\nLovelace\n");
+    let result = run(
+        "read_line() == \"Ada\" and read_line() == \"Lovelace\";",
+        options,
+    );
+    assert_eq!(result.unwrap().to_string(), "true");
+}
+
+#[test]
+fn returns_null_after_the_last_line() {
+    let options = VmOptions::new().input_text("only line");
+    let result = run("read_line(); read_line();", options);
+    assert_eq!(result.unwrap().to_string(), "null");
+}
+
+#[test]
+fn asks_the_callback_for_every_line() {
+    let asked = Rc::new(Cell::new(0));
+    let counter = asked.clone();
+    let mut lines = vec!["first", "second"].into_iter();
+    let options = VmOptions::new().input_callback(move || {
+        counter.set(counter.get() + 1);
+        lines.next().map(str::to_owned)
+    });
+
+    let result = run(
+        "let a = read_line(); let b = read_line(); read_line(); b;",
+        options,
+    );
+    assert_eq!(result.unwrap().to_string(), "second");
+    assert_eq!(asked.get(), 3);
+}
@@ -0,0 +1,39 @@
+use bytecode::modules::{link_modules, Module};
+use parser::parse;
+use vm::{options::VmOptions, VM};
+
+fn module(path: &str, code: &str) -> Module {
+    Module {
+        path: path.to_owned(),
+        program: parse(code).expect("Module should parse"),
+    }
+}
+
+fn run(modules: Vec<Module>, code: &str) -> String {
+    let program = parse(code).expect("Program should parse");
+    let bytecode = link_modules(modules, program, &[]).expect("Program should link");
+    VM::new(VmOptions::default())
+        .run(bytecode)
+        .expect("Program should run")
+        .to_string()
+}
+
+#[test]
+fn modules_can_be_linked_in_any_order() {
+    let modules = vec![
+        module("c.gv", r#"import "b.gv"; export fn c() { b() + 1 }"#),
+        module("b.gv", r#"import "a.gv"; export fn b() { a() + 1 }"#),
+        module("a.gv", "export fn a() { 1 }"),
+    ];
+    assert_eq!(run(modules, r#"import "c.gv"; c();"#), "3");
+}
+
+#[test]
+fn modules_run_after_their_imports_in_the_given_order() {
+    let modules = vec![
+        module("b.gv", r#"import "log.gv"; log[0] = log[0] * 10 + 2;"#),
+        module("a.gv", r#"import "log.gv"; log[0] = log[0] * 10 + 1;"#),
+        module("log.gv", r#"export let log = [0];"#),
+    ];
+    assert_eq!(run(modules, r#"import "log.gv"; log[0];"#), "21");
+}
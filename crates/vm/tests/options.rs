@@ -0,0 +1,100 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use bytecode::{generate_bytecode, ProgramBytecode};
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, ProgramOutput, VM};
+
+fn compile(code: &str) -> ProgramBytecode {
+    let ast = parse(code).expect("Program should parse");
+    generate_bytecode(ast).expect("Program should compile")
+}
+
+fn run(code: &str, options: VmOptions) -> ProgramOutput {
+    VM::new(options).run(compile(code))
+}
+
+fn cause(output: ProgramOutput) -> Option<RuntimeErrorCause> {
+    output.err().map(|error| error.cause)
+}
+
+// The VM owns its sinks, the test keeps a handle to read what was written
+#[derive(Clone, Default)]
+struct SharedLog(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn stops_after_the_instruction_budget() {
+    let endless = "while true { 1; };";
+    assert_eq!(
+        cause(run(endless, VmOptions::new().instruction_budget(1_000))),
+        Some(RuntimeErrorCause::InstructionBudget)
+    );
+
+    let mut vm = VM::new(VmOptions::new().instruction_budget(1_000));
+    vm.run(compile("1 + 2;")).expect("Program should run");
+    // Every run gets the whole budget
+    vm.run(compile("3 + 4;")).expect("Program should run");
+}
+
+#[test]
+fn limits_objects_on_the_heap() {
+    let code = "let all = []; for (let i = 0; i < 100; i = i + 1) { all = [all]; }; 1;";
+    assert_eq!(
+        cause(run(code, VmOptions::new().max_heap_objects(10))),
+        Some(RuntimeErrorCause::HeapLimit)
+    );
+    assert!(run(code, VmOptions::new()).is_ok());
+}
+
+#[test]
+fn limits_values_on_the_operand_stack() {
+    let code = "[1, 2, 3, 4, 5, 6, 7, 8];";
+    assert_eq!(
+        cause(run(code, VmOptions::new().stack_capacity(4))),
+        Some(RuntimeErrorCause::StackOverflow)
+    );
+    assert!(run(code, VmOptions::new().stack_capacity(16)).is_ok());
+}
+
+#[test]
+fn limits_the_depth_of_calls() {
+    let code = "fn depth(n) { if n == 0 { return 0; }; 1 + depth(n - 1) } depth(20);";
+    assert_eq!(
+        cause(run(code, VmOptions::new().max_call_depth(10))),
+        Some(RuntimeErrorCause::RecursionLimit)
+    );
+    assert!(run(code, VmOptions::new().max_call_depth(30)).is_ok());
+}
+
+#[test]
+fn logs_the_execution_to_debug_sinks() {
+    let log = SharedLog::default();
+    run("1 + 2;", VmOptions::new().debug_sink(log.clone())).expect("Program should run");
+
+    let log = String::from_utf8(log.0.borrow().clone()).expect("Log should be text");
+    assert!(log.contains("[OPCODE][NEXT]"));
+    assert!(log.contains("[VM][END OF EXECUTION]"));
+}
+
+#[test]
+fn options_can_change_between_runs() {
+    let mut vm = VM::new(VmOptions::new().instruction_budget(5));
+    let code = "let total = 0; for (let i = 0; i < 10; i = i + 1) { total = total + i; }; total;";
+    assert_eq!(
+        cause(vm.run(compile(code))),
+        Some(RuntimeErrorCause::InstructionBudget)
+    );
+
+    vm.set_options(VmOptions::new());
+    let value = vm.run(compile("2 * 3;")).expect("Program should run");
+    assert_eq!(value.to_string(), "6");
+}
@@ -0,0 +1,72 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use bytecode::generate_bytecode;
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, ProgramOutput, VM};
+
+#[derive(Clone, Default)]
+struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Captured {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).expect("Output should be text")
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Every write fails, like a pipe nobody reads from anymore
+struct Closed;
+
+impl Write for Closed {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run(code: &str, output: impl Write + 'static) -> ProgramOutput {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    VM::new(VmOptions::new().output(output)).run(bytecode)
+}
+
+#[test]
+fn print_writes_to_the_output() {
+    let output = Captured::default();
+    run(
+        r#"print("hello"); print(1 + 2); print(true);"#,
+        output.clone(),
+    )
+    .expect("Program should run");
+    assert_eq!(output.text(), "hello\n3\ntrue\n");
+}
+
+#[test]
+fn output_keeps_the_order_of_calls() {
+    let output = Captured::default();
+    let code = "fn count(n) { for (let i = 0; i < n; i = i + 1) { print(i); }; } count(3); print(\"done\");";
+    run(code, output.clone()).expect("Program should run");
+    assert_eq!(output.text(), "0\n1\n2\ndone\n");
+}
+
+#[test]
+fn failed_writes_are_errors() {
+    let error = run(r#"print("lost");"#, Closed).unwrap_err();
+    assert_eq!(error.cause, RuntimeErrorCause::OutputFailed);
+}
@@ -0,0 +1,58 @@
+use bytecode::{generate_bytecode, generate_bytecode_with_host_functions, ProgramBytecode};
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, runtime_value::RuntimeValue, VM};
+
+fn compile(code: &str) -> ProgramBytecode {
+    let ast = parse(code).expect("Program should parse");
+    generate_bytecode(ast).expect("Program should compile")
+}
+
+#[test]
+fn stepping_without_a_program_is_an_error() {
+    let mut vm = VM::new(VmOptions::default());
+    assert_eq!(
+        vm.step().err().map(|error| error.cause),
+        Some(RuntimeErrorCause::EmptyCallStack)
+    );
+    assert!(vm.current_function().is_none());
+}
+
+#[test]
+fn objects_are_not_conditions() {
+    let result = VM::new(VmOptions::default()).run(compile("if [1] { 1 } else { 2 };"));
+    assert_eq!(
+        result.unwrap_err().cause,
+        RuntimeErrorCause::MismatchedTypes
+    );
+}
+
+#[test]
+fn panics_become_runtime_errors() {
+    // The verifier rejects broken bytecode before it runs, a host function can still panic
+    let mut vm = VM::new(VmOptions::default());
+    vm.register_host_function("explode", 0, |_| panic!("Host function exploded"));
+    let ast = parse("explode();").expect("Program should parse");
+    let bytecode = generate_bytecode_with_host_functions(ast, &["explode".to_owned()])
+        .expect("Program should compile");
+
+    assert_eq!(
+        vm.run(bytecode).unwrap_err().cause,
+        RuntimeErrorCause::Panicked
+    );
+    // The VM can still run other programs
+    let value = vm.run(compile("2;")).expect("Program should run");
+    assert!(matches!(value, RuntimeValue::Number(number) if number == 2.0));
+}
+
+#[test]
+fn calls_with_too_few_arguments_are_not_panics() {
+    for code in [
+        "let f = |a, b| 1; f(1);",
+        "let v = new { op_add: |a, b| 42 }; v + 1;",
+    ] {
+        let error = VM::new(VmOptions::default())
+            .run(compile(code))
+            .unwrap_err();
+        assert_eq!(error.cause, RuntimeErrorCause::WrongArgumentCount);
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "serde")]
+
+use bytecode::{chunk::Constant, generate_bytecode, MemoryAddress};
+use parser::parse;
+use vm::{options::VmOptions, runtime_value::RuntimeValue, VM};
+
+fn run(vm: &mut VM, code: &str) -> RuntimeValue {
+    let bytecode = generate_bytecode(parse(code).unwrap()).unwrap();
+    vm.run(bytecode).unwrap()
+}
+
+#[test]
+fn serializes_objects_as_maps() {
+    let mut vm = VM::new(VmOptions::default());
+    let value = run(
+        &mut vm,
+        "new { name: \"gravitas\", version: 1, nested: new { done: true } };",
+    );
+
+    let json = serde_json::to_string(&vm.serializable(&value)).unwrap();
+    assert_eq!(
+        json,
+        r#"{"name":"gravitas","nested":{"done":true},"version":1.0}"#
+    );
+}
+
+#[test]
+fn deserializes_into_the_heap() {
+    let mut vm = VM::new(VmOptions::default());
+    let json = r#"{"data":[1,2,255],"empty":null,"score":2.5}"#;
+    let value = vm
+        .deserialize_value(&mut serde_json::Deserializer::from_str(json))
+        .unwrap();
+
+    assert!(matches!(value, RuntimeValue::HeapPointer(_)));
+    assert_eq!(
+        serde_json::to_string(&vm.serializable(&value)).unwrap(),
+        json
+    );
+}
+
+#[test]
+fn functions_cant_be_serialized() {
+    let mut vm = VM::new(VmOptions::default());
+    let value = run(&mut vm, "fn add(a, b) { a + b } add;");
+
+    assert!(serde_json::to_string(&vm.serializable(&value)).is_err());
+}
+
+#[test]
+fn constants_round_trip() {
+    let constants = vec![
+        Constant::Number(4.0),
+        Constant::String("text".to_owned()),
+        Constant::MemoryAddress(MemoryAddress::Local(2)),
+    ];
+
+    let json = serde_json::to_string(&constants).unwrap();
+    let decoded: Vec<Constant> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, constants);
+}
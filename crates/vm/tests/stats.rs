@@ -0,0 +1,40 @@
+use bytecode::generate_bytecode;
+use parser::parse;
+use vm::{options::VmOptions, stats::VmStats, VM};
+
+fn run(code: &str, vm: &mut VM) -> VmStats {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    vm.run(bytecode).expect("Program should run");
+    vm.stats()
+}
+
+#[test]
+fn counts_instructions_calls_and_allocations() {
+    let mut vm = VM::new(VmOptions::default());
+    let stats = run(
+        "fn inc(x) { x + 1 } fn twice(x) { inc(inc(x)) } twice(1); new { a: 1 };",
+        &mut vm,
+    );
+
+    // twice, inc and inc again
+    assert_eq!(stats.calls, 3);
+    assert_eq!(stats.peak_call_depth, 3);
+    assert!(stats.instructions > 10);
+    assert!(stats.peak_operands > 0);
+    // main, the two function closures and the object
+    assert_eq!(stats.allocations, 4);
+    assert_eq!(stats.gc_cycles, 0);
+}
+
+#[test]
+fn describes_only_the_last_run() {
+    let mut vm = VM::new(VmOptions::default());
+    let first = run("fn f() => 1 f(); f(); f();", &mut vm);
+    let second = run("1 + 2;", &mut vm);
+
+    assert_eq!(first.calls, 3);
+    assert_eq!(second.calls, 0);
+    assert_eq!(second.allocations, 1);
+    assert!(second.instructions < first.instructions);
+}
@@ -0,0 +1,73 @@
+// The event loop needs a clock and a way to sleep
+#![cfg(feature = "native")]
+
+use bytecode::{generate_bytecode, generate_bytecode_with_globals};
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, ProgramOutput, VM};
+
+fn run_event_loop(code: &str) -> ProgramOutput {
+    let ast = parse(code).expect("Program should parse");
+    let bytecode = generate_bytecode(ast).expect("Program should compile");
+    VM::new(VmOptions::default()).run_event_loop(bytecode)
+}
+
+#[test]
+fn timeouts_fire_in_order_of_their_delay() {
+    // second() blows up if it runs before first()
+    let result = run_event_loop(
+        r#"
+        let state = new { first_done: false };
+        fn first() { state.first_done = true; }
+        fn second() {
+            if !state.first_done {
+                let not_a_function = 0;
+                not_a_function();
+            }
+        }
+        set_timeout(second, 20);
+        set_timeout(first, 5);
+    "#,
+    );
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn interval_runs_until_cleared() {
+    let mut vm = VM::new(VmOptions::default());
+    let program = parse(
+        r#"
+        let state = new { count: 0, id: 0 };
+        fn tick() {
+            state.count = state.count + 1;
+            if state.count == 3 {
+                clear_timer(state.id);
+            }
+        }
+        state.id = set_interval(tick, 1);
+    "#,
+    )
+    .expect("Program should parse");
+    let bytecode = generate_bytecode(program).expect("Program should compile");
+    vm.run_event_loop(bytecode).expect("Program should run");
+
+    // The program returns before any timer fires, so the count is read by another one
+    let ast = parse("state.count;").expect("Program should parse");
+    let bytecode = generate_bytecode_with_globals(ast, &[], &vm.global_names())
+        .expect("Program should compile");
+    let count = vm.run(bytecode).expect("Program should run");
+    assert_eq!(count.to_string(), "3");
+}
+
+#[test]
+fn callbacks_cant_take_arguments() {
+    let error = run_event_loop(
+        r#"
+        fn add(a, b) { a + b }
+        set_timeout(add, 0);
+    "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(error.cause, RuntimeErrorCause::NotCallable);
+}
@@ -0,0 +1,71 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use bytecode::{generate_bytecode_with_options, Opcode, Options};
+use parser::parse;
+use vm::{hook::ExecutionHook, hook::JsonTrace, options::VmOptions, VM};
+
+// Keeps the trace readable after the VM took ownership of the sink
+#[derive(Debug, Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct OpcodeCounter(Rc<RefCell<Vec<Opcode>>>);
+
+impl ExecutionHook for OpcodeCounter {
+    fn before_instruction(&mut self, _: &VM, opcode: &Opcode) {
+        self.0.borrow_mut().push(opcode.clone());
+    }
+}
+
+fn run_with_hook(code: &str, hook: impl ExecutionHook + 'static) {
+    let ast = parse(code).expect("Program should parse");
+    // Without folding `1 + 2` still adds at runtime
+    let options = Options {
+        fold_constants: false,
+        ..Options::default()
+    };
+    let bytecode = generate_bytecode_with_options(ast, options).expect("Program should compile");
+    VM::new(VmOptions::default())
+        .with_hook(hook)
+        .run(bytecode)
+        .expect("Program should run");
+}
+
+#[test]
+fn hook_sees_every_executed_instruction() {
+    let counter = OpcodeCounter::default();
+    run_with_hook("1 + 2;", counter.clone());
+
+    let opcodes = counter.0.borrow();
+    assert_eq!(opcodes.len(), 3);
+    assert_eq!(opcodes[2], Opcode::Add);
+}
+
+#[test]
+fn json_trace_writes_one_object_per_instruction() {
+    let buffer = SharedBuffer::default();
+    run_with_hook("1 + 2;", JsonTrace::new(buffer.clone()));
+
+    let trace = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[2],
+        r#"{"ip":2,"opcode":"ADD","stack":["1","2"],"frame":{"name":"main","stack_start":0,"depth":1}}"#
+    );
+}
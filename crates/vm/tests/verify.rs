@@ -0,0 +1,23 @@
+use bytecode::{generate_bytecode, stmt::GlobalItem, Opcode, VerificationErrorCause};
+use parser::parse;
+use vm::{options::VmOptions, runtime_error::RuntimeErrorCause, VM};
+
+#[test]
+fn invalid_bytecode_is_rejected_before_running() {
+    let ast = parse("let a = 1; a;").expect("Program should parse");
+    let mut bytecode = generate_bytecode(ast).expect("Program should compile");
+    let GlobalItem::Function(main) = &mut bytecode.globals[bytecode.global_fn_ptr];
+    main.chunk.opcodes.insert(0, Opcode::Add);
+
+    let mut vm = VM::new(VmOptions::default());
+    let error = vm.run(bytecode).unwrap_err();
+    match error.cause {
+        RuntimeErrorCause::InvalidBytecode(error) => {
+            assert_eq!(error.index, 0);
+            assert_eq!(error.cause, VerificationErrorCause::StackUnderflow);
+        }
+        cause => panic!("Expected invalid bytecode, got {:?}", cause),
+    }
+    // Nothing ran, so the global was never defined
+    assert!(vm.global_names().is_empty());
+}
@@ -0,0 +1,54 @@
+use analyzer::analyze;
+use bytecode::{generate_bytecode, ProgramBytecode};
+use common::diagnostics::{DiagnosticKind, Diagnostics, FileId, SourceDb};
+use parser::parse;
+use vm::{options::VmOptions, VM};
+use wasm_bindgen::prelude::*;
+
+// Programs are compiled from a single file, the first one added to a SourceDb
+const FILE_ID: FileId = 0;
+
+// Compiled program that can be run any number of times
+#[wasm_bindgen]
+pub struct Program {
+    bytecode: ProgramBytecode,
+}
+
+fn compile_bytecode(code: &str) -> Result<ProgramBytecode, Diagnostics> {
+    let ast = parse(code)
+        .map_err(|errors| Diagnostics::from_errors(DiagnosticKind::Parser, FILE_ID, errors))?;
+    analyze(&ast)
+        .map_err(|errors| Diagnostics::from_errors(DiagnosticKind::Analyzer, FILE_ID, errors))?;
+    generate_bytecode(ast)
+        .map_err(|error| Diagnostics::from_errors(DiagnosticKind::Codegen, FILE_ID, [error]))
+}
+
+fn json(diagnostics: Diagnostics, code: &str) -> String {
+    let mut sources = SourceDb::new();
+    sources.add("main.gv", code);
+    diagnostics.to_json(&sources).to_string()
+}
+
+// JSON array of the problems found in the code, each with a message, notes and labels
+// pointing at byte ranges of the code. Empty when the program compiles.
+#[wasm_bindgen]
+pub fn diagnostics(code: &str) -> String {
+    json(compile_bytecode(code).err().unwrap_or_default(), code)
+}
+
+// Fails with the same JSON as `diagnostics`
+#[wasm_bindgen]
+pub fn compile(code: &str) -> Result<Program, JsValue> {
+    compile_bytecode(code)
+        .map(|bytecode| Program { bytecode })
+        .map_err(|diagnostics| JsValue::from_str(&json(diagnostics, code)))
+}
+
+// Returns the result of the program, printed as it is in the REPL
+#[wasm_bindgen]
+pub fn run(program: &Program) -> Result<String, JsValue> {
+    VM::new(VmOptions::default())
+        .run(program.bytecode.clone())
+        .map(|result| result.to_string())
+        .map_err(|error| JsValue::from_str(&format!("Runtime error: {:?}", error.cause)))
+}
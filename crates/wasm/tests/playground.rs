@@ -0,0 +1,23 @@
+use serde_json::Value;
+use wasm::{compile, diagnostics, run};
+
+#[test]
+fn compiles_and_runs_programs() {
+    let program = compile("let a = 2; a * 21;").expect("Program should compile");
+
+    assert_eq!(run(&program).ok(), Some("42".to_owned()));
+    // Programs can be rerun
+    assert_eq!(run(&program).ok(), Some("42".to_owned()));
+    assert_eq!(diagnostics("let a = 2; a * 21;"), "[]");
+}
+
+#[test]
+fn reports_diagnostics_with_ranges() {
+    let diagnostics: Value = serde_json::from_str(&diagnostics("let a = 1; b;")).unwrap();
+    let diagnostic = &diagnostics[0];
+
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(diagnostic["message"], "Variable was used but it's not defined anywhere");
+    assert_eq!(diagnostic["labels"][0]["start"], 11);
+    assert_eq!(diagnostic["labels"][0]["end"], 12);
+}